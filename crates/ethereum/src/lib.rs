@@ -1,7 +1,29 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
 use anyhow::Context;
-use pathfinder_common::{BlockHash, BlockNumber, EthereumChain, StateCommitment};
+use pathfinder_common::transaction::L1HandlerTransaction;
+use pathfinder_common::{
+    BlockHash,
+    BlockNumber,
+    CallParam,
+    ContractAddress,
+    EntryPoint,
+    EthereumAddress,
+    EthereumChain,
+    L1ToL2MessageNonce,
+    L1ToL2MessagePayloadElem,
+    L2ToL1MessagePayloadElem,
+    StateCommitment,
+    TransactionNonce,
+};
 use pathfinder_crypto::Felt;
 use primitive_types::{H160, H256, U256};
+use reqwest::Url;
+use tokio::sync::mpsc;
+
+mod ws;
 
 pub mod core_addr {
     use const_decoder::Decoder;
@@ -20,46 +42,410 @@ pub struct EthereumStateUpdate {
     pub block_hash: BlockHash,
 }
 
+/// A message sent from L1 to L2, as logged by the core contract's
+/// `LogMessageToL2` event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct L1ToL2Message {
+    pub from_address: EthereumAddress,
+    pub to_address: ContractAddress,
+    pub selector: EntryPoint,
+    pub payload: Vec<L1ToL2MessagePayloadElem>,
+    pub nonce: L1ToL2MessageNonce,
+}
+
+impl L1ToL2Message {
+    /// The L1 handler transaction which executes this message on L2.
+    pub fn l1_handler_transaction(&self) -> L1HandlerTransaction {
+        let from_address = Felt::from_be_slice(self.from_address.0.as_bytes())
+            .expect("Ethereum address fits into a felt");
+        let calldata = std::iter::once(CallParam(from_address))
+            .chain(self.payload.iter().map(|elem| CallParam(elem.0)))
+            .collect();
+
+        L1HandlerTransaction {
+            contract_address: self.to_address,
+            entry_point_selector: self.selector,
+            nonce: TransactionNonce(self.nonce.0),
+            calldata,
+        }
+    }
+
+    pub fn message_hash(&self) -> H256 {
+        self.l1_handler_transaction().calculate_message_hash()
+    }
+}
+
+/// A message sent from L2 to L1 which was consumed on L1, as logged by the
+/// core contract's `ConsumedMessageToL1` event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsumedL2ToL1Message {
+    pub from_address: ContractAddress,
+    pub to_address: EthereumAddress,
+    pub payload: Vec<L2ToL1MessagePayloadElem>,
+}
+
+impl ConsumedL2ToL1Message {
+    pub fn message_hash(&self) -> H256 {
+        let mut data = Vec::with_capacity(32 * (3 + self.payload.len()));
+        data.extend_from_slice(self.from_address.0.as_be_bytes());
+        data.extend_from_slice(H256::from(self.to_address.0).as_bytes());
+        data.extend_from_slice(H256::from_low_u64_be(self.payload.len() as u64).as_bytes());
+        for elem in &self.payload {
+            data.extend_from_slice(elem.0.as_be_bytes());
+        }
+
+        let mut output = [0u8; 32];
+        keccak_hash::keccak_256(&data, &mut output);
+        H256(output)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StarknetMessage {
+    L1ToL2(L1ToL2Message),
+    ConsumedL2ToL1(ConsumedL2ToL1Message),
+}
+
+impl StarknetMessage {
+    pub fn message_hash(&self) -> H256 {
+        match self {
+            StarknetMessage::L1ToL2(message) => message.message_hash(),
+            StarknetMessage::ConsumedL2ToL1(message) => message.message_hash(),
+        }
+    }
+}
+
+/// A Starknet message event emitted by the core contract.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageLog {
+    pub l1_block_number: u64,
+    pub l1_transaction_hash: H256,
+    pub log_index: u64,
+    pub message: StarknetMessage,
+}
+
+/// The L1 block at which the core contract's Starknet state is read. Only
+/// the state at the finalized block is safe from L1 reorgs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum L1Depth {
+    Latest,
+    /// The given number of blocks below the latest block.
+    Confirmations(u64),
+    Safe,
+    Finalized,
+}
+
+impl L1Depth {
+    /// Identifies the depth independently of its number of confirmations.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            L1Depth::Latest => "latest",
+            L1Depth::Confirmations(_) => "confirmations",
+            L1Depth::Safe => "safe",
+            L1Depth::Finalized => "finalized",
+        }
+    }
+}
+
+/// A notification that L1 may have changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum L1Notification {
+    NewHead {
+        number: u64,
+    },
+    /// The core contract emitted a log, e.g. a state update or a message.
+    CoreContractLog {
+        l1_block_number: u64,
+    },
+}
+
 #[async_trait::async_trait]
 pub trait EthereumApi {
+    /// Returns the Starknet state at the finalized L1 block.
     async fn get_starknet_state(&self, address: &H160) -> anyhow::Result<EthereumStateUpdate>;
+    /// Returns the Starknet state at the given L1 depth, along with the number
+    /// of the L1 block it was read at.
+    async fn get_starknet_state_at(
+        &self,
+        address: &H160,
+        depth: L1Depth,
+    ) -> anyhow::Result<(u64, EthereumStateUpdate)>;
     async fn get_chain(&self) -> anyhow::Result<EthereumChain>;
+    /// Returns the number of the latest finalized L1 block.
+    async fn get_finalized_block_number(&self) -> anyhow::Result<u64>;
+    /// Returns the number of the latest L1 block.
+    async fn get_latest_block_number(&self) -> anyhow::Result<u64>;
+    /// Returns the `LogMessageToL2` and `ConsumedMessageToL1` events emitted by
+    /// the core contract in the inclusive L1 block range, ordered as they
+    /// appear on L1. Logs which were removed by an L1 reorg are skipped.
+    async fn get_messages(
+        &self,
+        address: &H160,
+        from_block: u64,
+        to_block: u64,
+    ) -> anyhow::Result<Vec<MessageLog>>;
+    /// Subscribes to new L1 heads and to the logs of the core contract.
+    ///
+    /// Returns [None] if subscriptions are not supported, in which case L1
+    /// has to be polled.
+    async fn subscribe(&self, _address: &H160) -> Option<mpsc::Receiver<L1Notification>> {
+        None
+    }
 }
 
+/// An Ethereum JSON-RPC client for one or more interchangeable endpoints.
+///
+/// Requests go to the endpoint which last responded successfully, and fail
+/// over to the next endpoint on errors. Both HTTP and WebSocket endpoints
+/// are supported, the latter also provide
+/// [subscriptions](EthereumApi::subscribe).
 #[derive(Clone, Debug)]
 pub struct EthereumClient {
-    http: reqwest::Client,
-    url: reqwest::Url,
+    endpoints: Arc<[Endpoint]>,
+    active: Arc<AtomicUsize>,
+    /// The highest finalized L1 block seen on any endpoint. An endpoint which
+    /// is behind it is skipped, so that switching endpoints never moves the
+    /// finalized block backwards.
+    finalized: Arc<AtomicU64>,
+}
+
+#[derive(Debug)]
+enum Endpoint {
+    Http { http: reqwest::Client, url: Url },
+    WebSocket(ws::WebSocket),
 }
 
 const HTTP_OK: u16 = 200;
 
+const LOG_MESSAGE_TO_L2: &str = "LogMessageToL2(address,uint256,uint256,uint256[],uint256,uint256)";
+const CONSUMED_MESSAGE_TO_L1: &str = "ConsumedMessageToL1(uint256,address,uint256[])";
+
+/// Delay before renewing subscriptions after all WebSocket endpoints failed.
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(5);
+
 impl EthereumClient {
-    pub fn with_password(mut url: reqwest::Url, password: &str) -> anyhow::Result<Self> {
-        url.set_password(Some(password))
-            .map_err(|_| anyhow::anyhow!("Setting password failed"))?;
-        Self::new(url)
+    pub fn with_password(url: Url, password: &str) -> anyhow::Result<Self> {
+        Self::with_endpoints(vec![url], Some(password))
+    }
+
+    pub fn new(url: Url) -> anyhow::Result<Self> {
+        Self::with_endpoints(vec![url], None)
     }
 
-    pub fn new(url: reqwest::Url) -> anyhow::Result<Self> {
+    /// Creates a client for the given HTTP(S) and WS(S) endpoints, in order
+    /// of preference. The password, if any, is used for all of them.
+    pub fn with_endpoints(urls: Vec<Url>, password: Option<&str>) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            !urls.is_empty(),
+            "At least one Ethereum endpoint is required"
+        );
+
+        let http = reqwest::ClientBuilder::new().build()?;
+        let endpoints = urls
+            .into_iter()
+            .map(|mut url| {
+                if let Some(password) = password {
+                    url.set_password(Some(password))
+                        .map_err(|_| anyhow::anyhow!("Setting password failed"))?;
+                }
+                match url.scheme() {
+                    "http" | "https" => Ok(Endpoint::Http {
+                        http: http.clone(),
+                        url,
+                    }),
+                    "ws" | "wss" => Ok(Endpoint::WebSocket(ws::WebSocket::new(url))),
+                    scheme => anyhow::bail!("Unsupported Ethereum endpoint scheme {scheme}"),
+                }
+            })
+            .collect::<anyhow::Result<_>>()?;
+
         Ok(Self {
-            http: reqwest::ClientBuilder::new().build()?,
-            url,
+            endpoints,
+            active: Default::default(),
+            finalized: Default::default(),
         })
     }
 
-    async fn get_finalized_block_hash(&self) -> anyhow::Result<H256> {
-        self.call_ethereum(serde_json::json!({
-            "jsonrpc": "2.0",
-            "method": "eth_getBlockByNumber",
-            "params": [
-                "finalized",
-                false
-            ],
-            "id": 0
-        }))
-        .await
-        .and_then(|value| get_h256(&value["hash"]))
+    /// Runs `f` against the active endpoint, and on failure against each of
+    /// the other endpoints in turn. The first endpoint to succeed becomes the
+    /// active one.
+    async fn with_failover<'a, T, F, Fut>(&'a self, f: F) -> anyhow::Result<T>
+    where
+        F: Fn(&'a Endpoint) -> Fut,
+        Fut: std::future::Future<Output = anyhow::Result<T>>,
+    {
+        let active = self.active.load(Ordering::Relaxed);
+        let mut error = None;
+
+        for i in 0..self.endpoints.len() {
+            let index = (active + i) % self.endpoints.len();
+            let endpoint = &self.endpoints[index];
+
+            match f(endpoint).await {
+                Ok(value) => {
+                    if index != active {
+                        tracing::info!(endpoint=%endpoint, "Switched Ethereum endpoint");
+                        self.active.store(index, Ordering::Relaxed);
+                    }
+                    return Ok(value);
+                }
+                Err(e) => {
+                    tracing::debug!(endpoint=%endpoint, error=%e, "Ethereum request failed");
+                    error = Some(e);
+                }
+            }
+        }
+
+        Err(error.expect("There is at least one endpoint"))
+    }
+
+    /// Returns the number and hash of the endpoint's finalized block, and
+    /// fails if it is behind the highest finalized block seen so far.
+    async fn finalized_block(&self, endpoint: &Endpoint) -> anyhow::Result<(u64, H256)> {
+        let (number, hash) = endpoint.block("finalized").await?;
+
+        let highest = self.finalized.fetch_max(number, Ordering::Relaxed);
+        anyhow::ensure!(
+            number >= highest,
+            "Finalized block {number} is behind {highest}"
+        );
+
+        Ok((number, hash))
+    }
+
+    /// Returns the number and hash of the L1 block at the given depth.
+    async fn block_at(&self, endpoint: &Endpoint, depth: L1Depth) -> anyhow::Result<(u64, H256)> {
+        let block = match depth {
+            L1Depth::Latest => endpoint.block("latest").await?,
+            L1Depth::Confirmations(confirmations) => {
+                let latest = endpoint.block_number().await?;
+                let number = latest.saturating_sub(confirmations);
+                endpoint.block(&format!("0x{number:x}")).await?
+            }
+            L1Depth::Safe => endpoint.block("safe").await?,
+            L1Depth::Finalized => self.finalized_block(endpoint).await?,
+        };
+        Ok(block)
+    }
+
+    async fn get_starknet_state_from(
+        &self,
+        endpoint: &Endpoint,
+        address: &H160,
+        depth: L1Depth,
+    ) -> anyhow::Result<(u64, EthereumStateUpdate)> {
+        let (number, hash) = self.block_at(endpoint, depth).await?;
+        let hash = format!("0x{}", hex::encode(hash.as_bytes()));
+        let addr = format!("0x{}", hex::encode(address.as_bytes()));
+        let state = EthereumStateUpdate {
+            state_root: endpoint
+                .call_starknet_contract(&hash, &addr, "stateRoot()")
+                .await
+                .and_then(|value| get_h256(&value))
+                .and_then(get_felt)
+                .map(StateCommitment)?,
+            block_hash: endpoint
+                .call_starknet_contract(&hash, &addr, "stateBlockHash()")
+                .await
+                .and_then(|value| get_h256(&value))
+                .and_then(get_felt)
+                .map(BlockHash)?,
+            block_number: endpoint
+                .call_starknet_contract(&hash, &addr, "stateBlockNumber()")
+                .await
+                .and_then(|value| get_u256(&value))
+                .and_then(get_number)?,
+        };
+        Ok((number, state))
+    }
+
+    async fn get_messages_from(
+        &self,
+        endpoint: &Endpoint,
+        address: &H160,
+        from_block: u64,
+        to_block: u64,
+    ) -> anyhow::Result<Vec<MessageLog>> {
+        // Some providers return no logs for blocks they don't have yet.
+        let latest = endpoint.block_number().await?;
+        anyhow::ensure!(
+            latest >= to_block,
+            "Latest block {latest} is behind requested block {to_block}"
+        );
+
+        let logs = endpoint
+            .call(
+                "eth_getLogs",
+                serde_json::json!([
+                    {
+                        "address": format!("0x{}", hex::encode(address.as_bytes())),
+                        "fromBlock": format!("0x{from_block:x}"),
+                        "toBlock": format!("0x{to_block:x}"),
+                        "topics": [[
+                            event_topic(LOG_MESSAGE_TO_L2),
+                            event_topic(CONSUMED_MESSAGE_TO_L1),
+                        ]]
+                    }
+                ]),
+            )
+            .await?;
+
+        logs.as_array()
+            .context("Expected an array of logs")?
+            .iter()
+            .filter(|log| !log["removed"].as_bool().unwrap_or_default())
+            .map(parse_message_log)
+            .collect()
+    }
+}
+
+impl Endpoint {
+    async fn call(
+        &self,
+        method: &'static str,
+        params: serde_json::Value,
+    ) -> anyhow::Result<serde_json::Value> {
+        match self {
+            Endpoint::Http { http, url } => {
+                let request = serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "method": method,
+                    "params": params,
+                    "id": 0
+                });
+                let res = http.post(url.clone()).json(&request).send().await?;
+
+                let status = res.status();
+                let (code, message) = (status.as_u16(), status.as_str());
+                if code != HTTP_OK {
+                    tracing::error!(code, message, "Ethereum call failed");
+                    anyhow::bail!(code);
+                }
+
+                let response: serde_json::Value = res.json().await?;
+                ws::into_result(response)
+            }
+            Endpoint::WebSocket(ws) => ws.call(method, params).await,
+        }
+    }
+
+    /// Returns the number of the latest block.
+    async fn block_number(&self) -> anyhow::Result<u64> {
+        let number = self
+            .call("eth_blockNumber", serde_json::json!([]))
+            .await
+            .and_then(|value| get_u256(&value))?;
+        Ok(number.as_u64())
+    }
+
+    /// Returns the number and hash of the block with the given number or tag.
+    async fn block(&self, block: &str) -> anyhow::Result<(u64, H256)> {
+        let block = self
+            .call("eth_getBlockByNumber", serde_json::json!([block, false]))
+            .await?;
+        let number = get_u256(&block["number"])?.as_u64();
+        let hash = get_h256(&block["hash"])?;
+        Ok((number, hash))
     }
 
     async fn call_starknet_contract(
@@ -69,72 +455,53 @@ impl EthereumClient {
         signature: &str,
     ) -> anyhow::Result<serde_json::Value> {
         let data = encode_ethereum_call_data(signature.as_bytes());
-        self.call_ethereum(serde_json::json!({
-            "jsonrpc": "2.0",
-            "method": "eth_call",
-            "params": [
+        self.call(
+            "eth_call",
+            serde_json::json!([
                 {
                     "to": address,
                     "value": "0x0",
                     "data": data
                 },
                 {"blockHash": block_hash}
-            ],
-            "id": 0
-        }))
+            ]),
+        )
         .await
     }
+}
 
-    async fn call_ethereum(&self, value: serde_json::Value) -> anyhow::Result<serde_json::Value> {
-        let res = self.http.post(self.url.clone()).json(&value).send().await?;
-
-        let status = res.status();
-        let (code, message) = (status.as_u16(), status.as_str());
-        if code != HTTP_OK {
-            tracing::error!(code, message, "Ethereum call failed");
-            anyhow::bail!(code);
-        }
-
-        let response: serde_json::Value = res.json().await?;
-        Ok(response["result"].clone())
+impl std::fmt::Display for Endpoint {
+    /// Displays the URL without its password.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut url = match self {
+            Endpoint::Http { url, .. } => url.clone(),
+            Endpoint::WebSocket(ws) => ws.url().clone(),
+        };
+        let _ = url.set_password(None);
+        write!(f, "{url}")
     }
 }
 
 #[async_trait::async_trait]
 impl EthereumApi for EthereumClient {
     async fn get_starknet_state(&self, address: &H160) -> anyhow::Result<EthereumStateUpdate> {
-        let hash = self.get_finalized_block_hash().await?;
-        let hash = format!("0x{}", hex::encode(hash.as_bytes()));
-        let addr = format!("0x{}", hex::encode(address.as_bytes()));
-        Ok(EthereumStateUpdate {
-            state_root: self
-                .call_starknet_contract(&hash, &addr, "stateRoot()")
-                .await
-                .and_then(|value| get_h256(&value))
-                .and_then(get_felt)
-                .map(StateCommitment)?,
-            block_hash: self
-                .call_starknet_contract(&hash, &addr, "stateBlockHash()")
-                .await
-                .and_then(|value| get_h256(&value))
-                .and_then(get_felt)
-                .map(BlockHash)?,
-            block_number: self
-                .call_starknet_contract(&hash, &addr, "stateBlockNumber()")
-                .await
-                .and_then(|value| get_u256(&value))
-                .and_then(get_number)?,
-        })
+        self.get_starknet_state_at(address, L1Depth::Finalized)
+            .await
+            .map(|(_, state)| state)
+    }
+
+    async fn get_starknet_state_at(
+        &self,
+        address: &H160,
+        depth: L1Depth,
+    ) -> anyhow::Result<(u64, EthereumStateUpdate)> {
+        self.with_failover(|endpoint| self.get_starknet_state_from(endpoint, address, depth))
+            .await
     }
 
     async fn get_chain(&self) -> anyhow::Result<EthereumChain> {
         let id = self
-            .call_ethereum(serde_json::json!({
-                "jsonrpc": "2.0",
-                "method": "eth_chainId",
-                "params": [],
-                "id": 0
-            }))
+            .with_failover(|endpoint| endpoint.call("eth_chainId", serde_json::json!([])))
             .await
             .and_then(|value| get_u256(&value))?;
         Ok(match id {
@@ -143,6 +510,188 @@ impl EthereumApi for EthereumClient {
             x => EthereumChain::Other(x),
         })
     }
+
+    async fn get_finalized_block_number(&self) -> anyhow::Result<u64> {
+        self.with_failover(|endpoint| async move {
+            self.finalized_block(endpoint)
+                .await
+                .map(|(number, _)| number)
+        })
+        .await
+    }
+
+    async fn get_latest_block_number(&self) -> anyhow::Result<u64> {
+        self.with_failover(|endpoint| endpoint.block_number()).await
+    }
+
+    async fn get_messages(
+        &self,
+        address: &H160,
+        from_block: u64,
+        to_block: u64,
+    ) -> anyhow::Result<Vec<MessageLog>> {
+        self.with_failover(|endpoint| {
+            self.get_messages_from(endpoint, address, from_block, to_block)
+        })
+        .await
+    }
+
+    /// Keeps the subscriptions alive on one of the WebSocket endpoints,
+    /// moving on to the next one whenever the connection drops.
+    async fn subscribe(&self, address: &H160) -> Option<mpsc::Receiver<L1Notification>> {
+        if !self
+            .endpoints
+            .iter()
+            .any(|endpoint| matches!(endpoint, Endpoint::WebSocket(_)))
+        {
+            return None;
+        }
+
+        let (tx, rx) = mpsc::channel(1);
+        let endpoints = self.endpoints.clone();
+        let address = *address;
+
+        tokio::spawn(async move {
+            let websockets = endpoints.iter().filter_map(|endpoint| match endpoint {
+                Endpoint::WebSocket(ws) => Some((endpoint, ws)),
+                Endpoint::Http { .. } => None,
+            });
+
+            for (endpoint, ws) in websockets.cycle() {
+                if tx.is_closed() {
+                    return;
+                }
+
+                match forward_notifications(ws, &address, &tx).await {
+                    Ok(()) => return,
+                    Err(error) => {
+                        tracing::debug!(%endpoint, %error, "Ethereum subscription ended, renewing");
+                        tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+                    }
+                }
+            }
+        });
+
+        Some(rx)
+    }
+}
+
+/// Subscribes to new heads and the core contract's logs, and forwards
+/// them until the connection drops or `tx` is closed.
+async fn forward_notifications(
+    ws: &ws::WebSocket,
+    address: &H160,
+    tx: &mpsc::Sender<L1Notification>,
+) -> anyhow::Result<()> {
+    let mut heads = ws.subscribe(serde_json::json!(["newHeads"])).await?;
+    let mut logs = ws
+        .subscribe(serde_json::json!([
+            "logs",
+            {"address": format!("0x{}", hex::encode(address.as_bytes()))}
+        ]))
+        .await?;
+
+    loop {
+        let notification = tokio::select! {
+            head = heads.recv() => L1Notification::NewHead {
+                number: get_u256(&head.context("Connection closed")?["number"])?.as_u64(),
+            },
+            log = logs.recv() => L1Notification::CoreContractLog {
+                l1_block_number: get_u256(&log.context("Connection closed")?["blockNumber"])?
+                    .as_u64(),
+            },
+            _ = tx.closed() => return Ok(()),
+        };
+
+        // A notification is only a trigger, so one which is still queued
+        // makes another one redundant.
+        let _ = tx.try_send(notification);
+    }
+}
+
+fn event_topic(signature: &str) -> String {
+    let mut output: [u8; 32] = Default::default();
+    keccak_hash::keccak_256(signature.as_bytes(), &mut output[..]);
+    format!("0x{}", hex::encode(output))
+}
+
+fn parse_message_log(log: &serde_json::Value) -> anyhow::Result<MessageLog> {
+    let topics = log["topics"]
+        .as_array()
+        .context("Missing log topics")?
+        .iter()
+        .map(get_h256)
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let data = log["data"].as_str().context("Missing log data")?;
+    let data = hex::decode(data.strip_prefix("0x").unwrap_or(data)).context("Decoding log data")?;
+    anyhow::ensure!(data.len() % 32 == 0, "Log data is not word aligned");
+    let words = data.chunks(32).map(H256::from_slice).collect::<Vec<_>>();
+
+    let signature = topics.first().context("Missing event signature")?;
+    let signature = format!("0x{}", hex::encode(signature.as_bytes()));
+
+    let message = if signature == event_topic(LOG_MESSAGE_TO_L2) {
+        // topics: [signature, from_address, to_address, selector]
+        // data: [payload offset, nonce, fee, payload...]
+        anyhow::ensure!(topics.len() == 4, "Expected 4 topics for LogMessageToL2");
+        let nonce = words.get(1).context("Missing message nonce")?;
+        StarknetMessage::L1ToL2(L1ToL2Message {
+            from_address: EthereumAddress(H160::from(topics[1])),
+            to_address: ContractAddress(get_felt(topics[2])?),
+            selector: EntryPoint(get_felt(topics[3])?),
+            payload: get_dynamic_array(&words, 0)?
+                .into_iter()
+                .map(L1ToL2MessagePayloadElem)
+                .collect(),
+            nonce: L1ToL2MessageNonce(get_felt(*nonce)?),
+        })
+    } else if signature == event_topic(CONSUMED_MESSAGE_TO_L1) {
+        // topics: [signature, from_address, to_address]
+        // data: [payload offset, payload...]
+        anyhow::ensure!(
+            topics.len() == 3,
+            "Expected 3 topics for ConsumedMessageToL1"
+        );
+        StarknetMessage::ConsumedL2ToL1(ConsumedL2ToL1Message {
+            from_address: ContractAddress(get_felt(topics[1])?),
+            to_address: EthereumAddress(H160::from(topics[2])),
+            payload: get_dynamic_array(&words, 0)?
+                .into_iter()
+                .map(L2ToL1MessagePayloadElem)
+                .collect(),
+        })
+    } else {
+        anyhow::bail!("Unexpected event signature {signature}");
+    };
+
+    Ok(MessageLog {
+        l1_block_number: get_u256(&log["blockNumber"])?.as_u64(),
+        l1_transaction_hash: get_h256(&log["transactionHash"])?,
+        log_index: get_u256(&log["logIndex"])?.as_u64(),
+        message,
+    })
+}
+
+/// Decodes the ABI encoded `uint256[]` whose offset is stored in the `head`th
+/// word.
+fn get_dynamic_array(words: &[H256], head: usize) -> anyhow::Result<Vec<Felt>> {
+    let offset = words.get(head).context("Missing array offset")?;
+    let offset = U256::from_big_endian(offset.as_bytes());
+    anyhow::ensure!(
+        offset % 32 == U256::zero(),
+        "Array offset is not word aligned"
+    );
+    let start = (offset / 32).as_usize();
+
+    let len = words.get(start).context("Missing array length")?;
+    let len = U256::from_big_endian(len.as_bytes()).as_usize();
+
+    words
+        .get(start + 1..start + 1 + len)
+        .context("Array exceeds log data")?
+        .iter()
+        .map(|word| get_felt(*word))
+        .collect()
 }
 
 fn encode_ethereum_call_data(signature: &[u8]) -> String {
@@ -194,8 +743,6 @@ mod tests {
     use std::str::FromStr;
 
     use httpmock::prelude::*;
-    use primitive_types::H160;
-    use reqwest::Url;
 
     use super::*;
 
@@ -308,6 +855,227 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_get_messages() -> anyhow::Result<()> {
+        let server = MockServer::start_async().await;
+
+        let word = |value: u64| format!("{:064x}", value);
+        let log_message_to_l2 = serde_json::json!({
+            "address": "0xc662c410c0ecf747543f5ba90660f6abebd9c8c4",
+            "topics": [
+                event_topic(LOG_MESSAGE_TO_L2),
+                format!("0x{}", word(0xabcd)),
+                format!("0x{}", word(0x1234)),
+                format!("0x{}", word(0x5678)),
+            ],
+            "data": format!("0x{}{}{}{}{}{}", word(0x60), word(5), word(0x10), word(2), word(1), word(2)),
+            "blockNumber": "0x10",
+            "transactionHash": format!("0x{}", word(0xaa)),
+            "logIndex": "0x1",
+            "removed": false
+        });
+        let consumed_message_to_l1 = serde_json::json!({
+            "address": "0xc662c410c0ecf747543f5ba90660f6abebd9c8c4",
+            "topics": [
+                event_topic(CONSUMED_MESSAGE_TO_L1),
+                format!("0x{}", word(0x1234)),
+                format!("0x{}", word(0xabcd)),
+            ],
+            "data": format!("0x{}{}{}", word(0x20), word(1), word(3)),
+            "blockNumber": "0x11",
+            "transactionHash": format!("0x{}", word(0xbb)),
+            "logIndex": "0x0",
+            "removed": false
+        });
+        let response = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "result": [log_message_to_l2, consumed_message_to_l1]
+        });
+
+        let mock = server.mock(|when, then| {
+            when.path("/")
+                .method(POST)
+                .header("Content-type", "application/json")
+                .body_contains(r#""method":"eth_getLogs""#)
+                .body_contains(r#""fromBlock":"0x10","toBlock":"0x20""#);
+            then.status(200)
+                .header("Content-type", "application/json")
+                .body(response.to_string());
+        });
+
+        let url = Url::parse(&server.url("/"))?;
+        let eth = EthereumClient::new(url)?;
+        let addr = H160::from_slice(&core_addr::MAINNET);
+        let messages = eth.get_messages(&addr, 0x10, 0x20).await?;
+
+        mock.assert();
+        assert_eq!(
+            messages,
+            vec![
+                MessageLog {
+                    l1_block_number: 0x10,
+                    l1_transaction_hash: H256::from_low_u64_be(0xaa),
+                    log_index: 1,
+                    message: StarknetMessage::L1ToL2(L1ToL2Message {
+                        from_address: EthereumAddress(H160::from_low_u64_be(0xabcd)),
+                        to_address: ContractAddress(Felt::from_u64(0x1234)),
+                        selector: EntryPoint(Felt::from_u64(0x5678)),
+                        payload: vec![
+                            L1ToL2MessagePayloadElem(Felt::from_u64(1)),
+                            L1ToL2MessagePayloadElem(Felt::from_u64(2)),
+                        ],
+                        nonce: L1ToL2MessageNonce(Felt::from_u64(5)),
+                    }),
+                },
+                MessageLog {
+                    l1_block_number: 0x11,
+                    l1_transaction_hash: H256::from_low_u64_be(0xbb),
+                    log_index: 0,
+                    message: StarknetMessage::ConsumedL2ToL1(ConsumedL2ToL1Message {
+                        from_address: ContractAddress(Felt::from_u64(0x1234)),
+                        to_address: EthereumAddress(H160::from_low_u64_be(0xabcd)),
+                        payload: vec![L2ToL1MessagePayloadElem(Felt::from_u64(3))],
+                    }),
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    fn finalized_block_mock(server: &MockServer, number: u64) -> httpmock::Mock<'_> {
+        server.mock(|when, then| {
+            when.path("/")
+                .method(POST)
+                .body_contains(r#""method":"eth_getBlockByNumber""#);
+            then.status(200)
+                .header("Content-type", "application/json")
+                .json_body(serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": {"number": format!("0x{number:x}"), "hash": format!("0x{number:064x}")}
+                }));
+        })
+    }
+
+    #[tokio::test]
+    async fn fails_over_to_next_endpoint() -> anyhow::Result<()> {
+        let failing = MockServer::start_async().await;
+        let working = MockServer::start_async().await;
+
+        failing.mock(|when, then| {
+            when.path("/").method(POST);
+            then.status(503);
+        });
+        let mock = working.mock(|when, then| {
+            when.path("/").method(POST).body_contains("eth_chainId");
+            then.status(200)
+                .header("Content-type", "application/json")
+                .body(r#"{"jsonrpc":"2.0","id":0,"result":"0xaa36a7"}"#);
+        });
+
+        let eth = EthereumClient::with_endpoints(
+            vec![
+                Url::parse(&failing.url("/"))?,
+                Url::parse(&working.url("/"))?,
+            ],
+            None,
+        )?;
+
+        assert_eq!(eth.get_chain().await?, EthereumChain::Sepolia);
+        // The working endpoint is tried first from now on.
+        assert_eq!(eth.get_chain().await?, EthereumChain::Sepolia);
+        assert_eq!(eth.active.load(Ordering::Relaxed), 1);
+        mock.assert_hits(2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn finalized_block_does_not_move_backwards_on_failover() -> anyhow::Result<()> {
+        let ahead = MockServer::start_async().await;
+        let behind = MockServer::start_async().await;
+
+        let mut ahead_mock = finalized_block_mock(&ahead, 0x20);
+        finalized_block_mock(&behind, 0x10);
+
+        let eth = EthereumClient::with_endpoints(
+            vec![Url::parse(&ahead.url("/"))?, Url::parse(&behind.url("/"))?],
+            None,
+        )?;
+        assert_eq!(eth.get_finalized_block_number().await?, 0x20);
+
+        ahead_mock.delete_async().await;
+        eth.get_finalized_block_number().await.unwrap_err();
+
+        finalized_block_mock(&ahead, 0x21);
+        assert_eq!(eth.get_finalized_block_number().await?, 0x21);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn block_at_confirmation_depth() -> anyhow::Result<()> {
+        let server = MockServer::start_async().await;
+
+        server.mock(|when, then| {
+            when.path("/").method(POST).body_contains("eth_blockNumber");
+            then.status(200)
+                .header("Content-type", "application/json")
+                .body(r#"{"jsonrpc":"2.0","id":0,"result":"0x20"}"#);
+        });
+        let mock = server.mock(|when, then| {
+            when.path("/")
+                .method(POST)
+                .body_contains(r#""params":["0x1a",false]"#);
+            then.status(200)
+                .header("Content-type", "application/json")
+                .json_body(serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": {"number": "0x1a", "hash": format!("0x{:064x}", 0x1a)}
+                }));
+        });
+
+        let eth = EthereumClient::new(Url::parse(&server.url("/"))?)?;
+        let hash = eth
+            .block_at(&eth.endpoints[0], L1Depth::Confirmations(6))
+            .await?;
+
+        mock.assert();
+        assert_eq!(hash, H256::from_low_u64_be(0x1a));
+        Ok(())
+    }
+
+    #[test]
+    fn unsupported_scheme() {
+        EthereumClient::new(Url::parse("ftp://localhost").unwrap()).unwrap_err();
+    }
+
+    #[test]
+    fn l1_to_l2_message_to_l1_handler() {
+        let message = L1ToL2Message {
+            from_address: EthereumAddress(H160::from_low_u64_be(0xabcd)),
+            to_address: ContractAddress(Felt::from_u64(0x1234)),
+            selector: EntryPoint(Felt::from_u64(0x5678)),
+            payload: vec![
+                L1ToL2MessagePayloadElem(Felt::from_u64(1)),
+                L1ToL2MessagePayloadElem(Felt::from_u64(2)),
+            ],
+            nonce: L1ToL2MessageNonce(Felt::from_u64(5)),
+        };
+
+        let expected = L1HandlerTransaction {
+            contract_address: ContractAddress(Felt::from_u64(0x1234)),
+            entry_point_selector: EntryPoint(Felt::from_u64(0x5678)),
+            nonce: TransactionNonce(Felt::from_u64(5)),
+            calldata: vec![
+                CallParam(Felt::from_u64(0xabcd)),
+                CallParam(Felt::from_u64(1)),
+                CallParam(Felt::from_u64(2)),
+            ],
+        };
+        assert_eq!(message.l1_handler_transaction(), expected);
+    }
+
     #[test]
     fn test_h256() {
         assert!(H256::from_str(
@@ -0,0 +1,358 @@
+//! Ethereum JSON-RPC over a WebSocket connection, including `eth_subscribe`
+//! notifications.
+//!
+//! The connection is established lazily and re-established by the next
+//! request after it drops. Subscriptions end when their connection drops, and
+//! have to be renewed by the subscriber.
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::Context;
+use futures::{SinkExt, StreamExt};
+use reqwest::Url;
+use tokio::sync::{mpsc, oneshot};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::Message;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// Notifications are dropped if the subscriber falls this far behind.
+const NOTIFICATION_BUFFER: usize = 64;
+
+#[derive(Debug)]
+pub(crate) struct WebSocket {
+    url: Url,
+    commands: tokio::sync::Mutex<Option<mpsc::Sender<Command>>>,
+}
+
+#[derive(Debug)]
+enum Command {
+    Call {
+        method: &'static str,
+        params: serde_json::Value,
+        reply: oneshot::Sender<anyhow::Result<serde_json::Value>>,
+    },
+    Subscribe {
+        params: serde_json::Value,
+        notifications: mpsc::Sender<serde_json::Value>,
+        reply: oneshot::Sender<anyhow::Result<()>>,
+    },
+}
+
+impl WebSocket {
+    pub fn new(url: Url) -> Self {
+        Self {
+            url,
+            commands: Default::default(),
+        }
+    }
+
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    pub async fn call(
+        &self,
+        method: &'static str,
+        params: serde_json::Value,
+    ) -> anyhow::Result<serde_json::Value> {
+        let (reply, response) = oneshot::channel();
+        self.send(Command::Call {
+            method,
+            params,
+            reply,
+        })
+        .await?;
+
+        tokio::time::timeout(REQUEST_TIMEOUT, response)
+            .await
+            .context("Request timed out")?
+            .context("Connection closed")?
+    }
+
+    /// Calls `eth_subscribe` with the given parameters. The returned channel
+    /// is closed when the connection drops.
+    pub async fn subscribe(
+        &self,
+        params: serde_json::Value,
+    ) -> anyhow::Result<mpsc::Receiver<serde_json::Value>> {
+        let (notifications, rx) = mpsc::channel(NOTIFICATION_BUFFER);
+        let (reply, response) = oneshot::channel();
+        self.send(Command::Subscribe {
+            params,
+            notifications,
+            reply,
+        })
+        .await?;
+
+        tokio::time::timeout(REQUEST_TIMEOUT, response)
+            .await
+            .context("Subscription timed out")?
+            .context("Connection closed")??;
+
+        Ok(rx)
+    }
+
+    async fn send(&self, command: Command) -> anyhow::Result<()> {
+        let mut commands = self.commands.lock().await;
+
+        let sender = match commands.as_ref().filter(|sender| !sender.is_closed()) {
+            Some(sender) => sender.clone(),
+            None => {
+                let sender = self.connect().await?;
+                *commands = Some(sender.clone());
+                sender
+            }
+        };
+
+        sender
+            .send(command)
+            .await
+            .map_err(|_| anyhow::anyhow!("Connection closed"))
+    }
+
+    async fn connect(&self) -> anyhow::Result<mpsc::Sender<Command>> {
+        let mut request = self
+            .url
+            .as_str()
+            .into_client_request()
+            .context("Creating WebSocket request")?;
+        if let Some(password) = self.url.password() {
+            let credentials = base64::encode(format!("{}:{password}", self.url.username()));
+            request.headers_mut().insert(
+                http::header::AUTHORIZATION,
+                format!("Basic {credentials}")
+                    .parse()
+                    .context("Creating authorization header")?,
+            );
+        }
+
+        let (stream, _) =
+            tokio::time::timeout(REQUEST_TIMEOUT, tokio_tungstenite::connect_async(request))
+                .await
+                .context("Connecting timed out")?
+                .context("Connecting")?;
+
+        let (sender, commands) = mpsc::channel(NOTIFICATION_BUFFER);
+        tokio::spawn(run(stream, commands));
+
+        Ok(sender)
+    }
+}
+
+enum Pending {
+    Call(oneshot::Sender<anyhow::Result<serde_json::Value>>),
+    Subscribe {
+        notifications: mpsc::Sender<serde_json::Value>,
+        reply: oneshot::Sender<anyhow::Result<()>>,
+    },
+}
+
+impl Pending {
+    /// Whether the caller stopped waiting for the response, e.g. because the
+    /// request timed out.
+    fn is_closed(&self) -> bool {
+        match self {
+            Pending::Call(reply) => reply.is_closed(),
+            Pending::Subscribe { reply, .. } => reply.is_closed(),
+        }
+    }
+}
+
+/// Drives a connection until it drops, or until all handles to it are gone.
+async fn run<S>(mut stream: S, mut commands: mpsc::Receiver<Command>)
+where
+    S: futures::Stream<Item = Result<Message, tokio_tungstenite::tungstenite::Error>>
+        + futures::Sink<Message, Error = tokio_tungstenite::tungstenite::Error>
+        + Unpin,
+{
+    let mut next_id = 0u64;
+    let mut pending = HashMap::new();
+    let mut subscriptions: HashMap<String, mpsc::Sender<serde_json::Value>> = HashMap::new();
+
+    loop {
+        tokio::select! {
+            command = commands.recv() => {
+                let Some(command) = command else {
+                    break;
+                };
+
+                next_id += 1;
+                let (method, params, entry) = match command {
+                    Command::Call { method, params, reply } => (method, params, Pending::Call(reply)),
+                    Command::Subscribe { params, notifications, reply } => {
+                        ("eth_subscribe", params, Pending::Subscribe { notifications, reply })
+                    }
+                };
+                let request = serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "method": method,
+                    "params": params,
+                    "id": next_id,
+                });
+
+                if let Err(error) = stream.send(Message::Text(request.to_string())).await {
+                    tracing::debug!(%error, "Sending WebSocket request failed");
+                    break;
+                }
+                // Requests which are never answered would otherwise be kept
+                // for as long as the connection lives.
+                pending.retain(|_, entry: &mut Pending| !entry.is_closed());
+                pending.insert(next_id, entry);
+            }
+            message = stream.next() => {
+                let text = match message {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | None => break,
+                    Some(Ok(_)) => continue,
+                    Some(Err(error)) => {
+                        tracing::debug!(%error, "WebSocket connection failed");
+                        break;
+                    }
+                };
+
+                let message: serde_json::Value = match serde_json::from_str(&text) {
+                    Ok(message) => message,
+                    Err(error) => {
+                        tracing::debug!(%error, "Invalid WebSocket message");
+                        continue;
+                    }
+                };
+
+                if message["method"] == "eth_subscription" {
+                    let params = &message["params"];
+                    let Some(id) = params["subscription"].as_str() else {
+                        continue;
+                    };
+                    if let Some(subscriber) = subscriptions.get(id) {
+                        match subscriber.try_send(params["result"].clone()) {
+                            Ok(()) => {}
+                            Err(mpsc::error::TrySendError::Full(_)) => {
+                                tracing::trace!(subscription=%id, "Subscriber is lagging, dropping notification");
+                            }
+                            Err(mpsc::error::TrySendError::Closed(_)) => {
+                                subscriptions.remove(id);
+                            }
+                        }
+                    }
+                    continue;
+                }
+
+                let Some(entry) = message["id"].as_u64().and_then(|id| pending.remove(&id)) else {
+                    continue;
+                };
+                let result = into_result(message);
+                match entry {
+                    Pending::Call(reply) => {
+                        let _ = reply.send(result);
+                    }
+                    Pending::Subscribe { notifications, reply } => {
+                        let result = result.and_then(|id| {
+                            id.as_str()
+                                .map(ToOwned::to_owned)
+                                .context("Expected a subscription id")
+                        });
+                        match result {
+                            Ok(id) => {
+                                subscriptions.insert(id, notifications);
+                                let _ = reply.send(Ok(()));
+                            }
+                            Err(error) => {
+                                let _ = reply.send(Err(error));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Dropping the pending replies and subscribers notifies them that the
+    // connection is gone.
+}
+
+/// Extracts the result of a JSON-RPC response.
+pub(crate) fn into_result(mut response: serde_json::Value) -> anyhow::Result<serde_json::Value> {
+    let error = response["error"].take();
+    if !error.is_null() {
+        anyhow::bail!("Ethereum call failed: {error}");
+    }
+    Ok(response["result"].take())
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    /// Serves a single connection, answering each request with its method
+    /// name and sending a notification for each subscription.
+    async fn server() -> Url {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+
+            while let Some(Ok(Message::Text(text))) = ws.next().await {
+                let request: serde_json::Value = serde_json::from_str(&text).unwrap();
+                let id = request["id"].clone();
+
+                if request["method"] == "eth_subscribe" {
+                    let response =
+                        serde_json::json!({"jsonrpc": "2.0", "id": id, "result": "0xsub"});
+                    ws.send(Message::Text(response.to_string())).await.unwrap();
+
+                    let notification = serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "method": "eth_subscription",
+                        "params": {"subscription": "0xsub", "result": {"number": "0x10"}}
+                    });
+                    ws.send(Message::Text(notification.to_string()))
+                        .await
+                        .unwrap();
+                } else if request["method"] == "close" {
+                    break;
+                } else {
+                    let response = serde_json::json!({"jsonrpc": "2.0", "id": id, "result": request["method"]});
+                    ws.send(Message::Text(response.to_string())).await.unwrap();
+                }
+            }
+        });
+
+        Url::parse(&format!("ws://{addr}")).unwrap()
+    }
+
+    #[tokio::test]
+    async fn call() {
+        let ws = WebSocket::new(server().await);
+
+        let result = ws.call("eth_chainId", serde_json::json!([])).await.unwrap();
+        assert_eq!(result, "eth_chainId");
+    }
+
+    #[tokio::test]
+    async fn subscription_ends_with_connection() {
+        let ws = WebSocket::new(server().await);
+
+        let mut notifications = ws.subscribe(serde_json::json!(["newHeads"])).await.unwrap();
+        assert_eq!(
+            notifications.recv().await.unwrap(),
+            serde_json::json!({"number": "0x10"})
+        );
+
+        ws.call("close", serde_json::json!([])).await.unwrap_err();
+        assert_eq!(notifications.recv().await, None);
+    }
+
+    #[test]
+    fn error_response() {
+        let response = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "error": {"code": -32000, "message": "header not found"}
+        });
+        into_result(response).unwrap_err();
+    }
+}
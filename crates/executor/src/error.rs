@@ -1,10 +1,16 @@
+use blockifier::blockifier::stateful_validator::StatefulValidatorError;
+use blockifier::blockifier::transaction_executor::TransactionExecutorError;
 use blockifier::execution::errors::{
     ConstructorEntryPointExecutionError,
     EntryPointExecutionError as BlockifierEntryPointExecutionError,
     PreExecutionError,
 };
 use blockifier::state::errors::StateError;
-use blockifier::transaction::errors::TransactionExecutionError as BlockifierTransactionExecutionError;
+use blockifier::transaction::errors::{
+    TransactionExecutionError as BlockifierTransactionExecutionError,
+    TransactionFeeError,
+    TransactionPreValidationError,
+};
 
 #[derive(Debug)]
 pub enum CallError {
@@ -112,6 +118,75 @@ impl TransactionExecutionError {
     }
 }
 
+#[derive(Debug)]
+pub enum TransactionValidationError {
+    InvalidTransactionNonce,
+    InsufficientMaxFee,
+    InsufficientAccountBalance,
+    ValidationFailure(String),
+    Internal(anyhow::Error),
+    Custom(anyhow::Error),
+}
+
+impl From<BlockifierTransactionExecutionError> for TransactionValidationError {
+    fn from(value: BlockifierTransactionExecutionError) -> Self {
+        use BlockifierTransactionExecutionError::TransactionPreValidationError as PreValidation;
+        match value {
+            PreValidation(TransactionPreValidationError::InvalidNonce { .. }) => {
+                Self::InvalidTransactionNonce
+            }
+            PreValidation(TransactionPreValidationError::TransactionFeeError(
+                TransactionFeeError::MaxFeeExceedsBalance { .. }
+                | TransactionFeeError::L1GasBoundsExceedBalance { .. },
+            )) => Self::InsufficientAccountBalance,
+            PreValidation(TransactionPreValidationError::TransactionFeeError(
+                TransactionFeeError::MaxFeeTooLow { .. }
+                | TransactionFeeError::MaxL1GasAmountTooLow { .. }
+                | TransactionFeeError::MaxL1GasPriceTooLow { .. },
+            )) => Self::InsufficientMaxFee,
+            PreValidation(TransactionPreValidationError::StateError(e))
+            | BlockifierTransactionExecutionError::StateError(e) => e.into(),
+            e => Self::ValidationFailure(e.to_string()),
+        }
+    }
+}
+
+impl From<StatefulValidatorError> for TransactionValidationError {
+    fn from(value: StatefulValidatorError) -> Self {
+        use BlockifierTransactionExecutionError::TransactionPreValidationError as PreValidation;
+        match value {
+            StatefulValidatorError::StateError(e)
+            | StatefulValidatorError::TransactionExecutorError(
+                TransactionExecutorError::StateError(e),
+            ) => e.into(),
+            StatefulValidatorError::TransactionExecutionError(e)
+            | StatefulValidatorError::TransactionExecutorError(
+                TransactionExecutorError::TransactionExecutionError(e),
+            ) => e.into(),
+            StatefulValidatorError::TransactionPreValidationError(e) => PreValidation(e).into(),
+            StatefulValidatorError::TransactionFeeError(e) => {
+                PreValidation(TransactionPreValidationError::TransactionFeeError(e)).into()
+            }
+            e => Self::ValidationFailure(e.to_string()),
+        }
+    }
+}
+
+impl From<StateError> for TransactionValidationError {
+    fn from(e: StateError) -> Self {
+        match e {
+            StateError::StateReadError(_) => Self::Internal(e.into()),
+            _ => Self::Custom(anyhow::anyhow!("State error: {}", e)),
+        }
+    }
+}
+
+impl From<anyhow::Error> for TransactionValidationError {
+    fn from(value: anyhow::Error) -> Self {
+        Self::Internal(value)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -16,6 +16,8 @@ use pathfinder_common::{
 };
 use starknet_api::core::PatriciaKey;
 
+pub use self::versioned_constants::VersionedConstantsMap;
+use super::overrides::{BlockOverrides, OverrideStateReader, StateOverrides};
 use super::pending::PendingStateReader;
 use super::state_reader::PathfinderStateReader;
 use crate::IntoStarkFelt;
@@ -28,8 +30,11 @@ pub const STRK_FEE_TOKEN_ADDRESS: ContractAddress =
 
 mod versioned_constants {
     use std::borrow::Cow;
-    use std::sync::LazyLock;
+    use std::ops::Range;
+    use std::path::Path;
+    use std::sync::{Arc, LazyLock};
 
+    use anyhow::Context;
     use pathfinder_common::StarknetVersion;
 
     use super::VersionedConstants;
@@ -49,6 +54,10 @@ mod versioned_constants {
 
     const STARKNET_VERSION_0_13_2: StarknetVersion = StarknetVersion::new(0, 13, 2, 0);
 
+    /// Used as the exclusive upper bound of ranges without an end.
+    const STARKNET_VERSION_MAX: StarknetVersion =
+        StarknetVersion::new(u8::MAX, u8::MAX, u8::MAX, u8::MAX);
+
     pub static BLOCKIFIER_VERSIONED_CONSTANTS_0_13_0: LazyLock<VersionedConstants> =
         LazyLock::new(|| {
             serde_json::from_slice(BLOCKIFIER_VERSIONED_CONSTANTS_JSON_0_13_0).unwrap()
@@ -64,10 +73,117 @@ mod versioned_constants {
             serde_json::from_slice(BLOCKIFIER_VERSIONED_CONSTANTS_JSON_0_13_1_1).unwrap()
         });
 
-    pub(super) fn for_version(
+    /// Custom versioned constants keyed by the range of Starknet versions they
+    /// apply to.
+    ///
+    /// Versions not covered by any range use the versioned constants built into
+    /// pathfinder.
+    #[derive(Clone, Debug, Default)]
+    pub struct VersionedConstantsMap(Vec<(Range<StarknetVersion>, Arc<VersionedConstants>)>);
+
+    impl VersionedConstantsMap {
+        /// Creates a map from a set of version ranges, which must be non-empty
+        /// and must not overlap.
+        pub fn new(
+            entries: impl IntoIterator<Item = (Range<StarknetVersion>, VersionedConstants)>,
+        ) -> anyhow::Result<Self> {
+            let mut entries = entries
+                .into_iter()
+                .map(|(range, constants)| (range, Arc::new(constants)))
+                .collect::<Vec<_>>();
+            entries.sort_by_key(|(range, _)| range.start);
+
+            for (range, _) in &entries {
+                anyhow::ensure!(
+                    range.start < range.end,
+                    "Empty version range {}..{}",
+                    range.start,
+                    range.end
+                );
+            }
+            for pair in entries.windows(2) {
+                let (first, second) = (&pair[0].0, &pair[1].0);
+                anyhow::ensure!(
+                    first.end <= second.start,
+                    "Version range {}..{} overlaps with {}..{}",
+                    first.start,
+                    first.end,
+                    second.start,
+                    second.end
+                );
+            }
+
+            Ok(Self(entries))
+        }
+
+        /// Uses the given versioned constants for all Starknet versions
+        /// starting from 0.13.2.
+        pub fn latest(constants: VersionedConstants) -> Self {
+            Self(vec![(
+                STARKNET_VERSION_0_13_2..STARKNET_VERSION_MAX,
+                Arc::new(constants),
+            )])
+        }
+
+        /// Loads versioned constants from all files in a directory.
+        ///
+        /// Files are named after the range of Starknet versions they apply to,
+        /// either `<start>-<end>.json` for the half-open range `start..end`,
+        /// or `<start>.json` for all versions starting from `start`. For
+        /// example, `0.13.1-0.13.2.json` and `0.13.2.json`.
+        pub fn from_dir(path: &Path) -> anyhow::Result<Self> {
+            let mut entries = Vec::new();
+
+            for entry in std::fs::read_dir(path)
+                .with_context(|| format!("Reading directory {}", path.display()))?
+            {
+                let path = entry.context("Reading directory entry")?.path();
+
+                let range = path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .and_then(|name| name.strip_suffix(".json"))
+                    .with_context(|| format!("{} is not a JSON file", path.display()))
+                    .and_then(parse_version_range)
+                    .with_context(|| format!("Parsing file name of {}", path.display()))?;
+
+                let file = std::fs::File::open(&path)
+                    .with_context(|| format!("Opening {}", path.display()))?;
+                let constants = serde_json::from_reader(std::io::BufReader::new(file))
+                    .with_context(|| format!("Parsing {}", path.display()))?;
+
+                entries.push((range, constants));
+            }
+
+            Self::new(entries)
+        }
+
+        fn get(&self, version: &StarknetVersion) -> Option<&VersionedConstants> {
+            self.0
+                .iter()
+                .find(|(range, _)| range.contains(version))
+                .map(|(_, constants)| constants.as_ref())
+        }
+    }
+
+    fn parse_version_range(s: &str) -> anyhow::Result<Range<StarknetVersion>> {
+        let (start, end) = match s.split_once('-') {
+            Some((start, end)) => (start, end.parse().context("Parsing end version")?),
+            None => (s, STARKNET_VERSION_MAX),
+        };
+        let start = start.parse().context("Parsing start version")?;
+
+        Ok(start..end)
+    }
+
+    pub(super) fn for_version<'a>(
         version: &StarknetVersion,
-        custom_versioned_constants: Option<VersionedConstants>,
-    ) -> Cow<'static, VersionedConstants> {
+        custom_versioned_constants: &'a VersionedConstantsMap,
+    ) -> Cow<'a, VersionedConstants> {
+        if let Some(constants) = custom_versioned_constants.get(version) {
+            return Cow::Borrowed(constants);
+        }
+
         // We use 0.13.0 for all blocks _before_ 0.13.1.
         if version < &STARKNET_VERSION_0_13_1 {
             Cow::Borrowed(&BLOCKIFIER_VERSIONED_CONSTANTS_0_13_0)
@@ -76,9 +192,76 @@ mod versioned_constants {
         } else if version < &STARKNET_VERSION_0_13_2 {
             Cow::Borrowed(&BLOCKIFIER_VERSIONED_CONSTANTS_0_13_1_1)
         } else {
-            custom_versioned_constants
-                .map(Cow::Owned)
-                .unwrap_or_else(|| Cow::Borrowed(VersionedConstants::latest_constants()))
+            Cow::Borrowed(VersionedConstants::latest_constants())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn constants() -> VersionedConstants {
+            VersionedConstants::latest_constants().clone()
+        }
+
+        #[test]
+        fn version_range_file_names() {
+            assert_eq!(
+                parse_version_range("0.13.1-0.13.2").unwrap(),
+                STARKNET_VERSION_0_13_1..STARKNET_VERSION_0_13_2
+            );
+            assert_eq!(
+                parse_version_range("0.13.1.1").unwrap(),
+                STARKNET_VERSION_0_13_1_1..STARKNET_VERSION_MAX
+            );
+            assert!(parse_version_range("0.13").is_err());
+            assert!(parse_version_range("0.13.1-latest").is_err());
+        }
+
+        #[test]
+        fn overlapping_ranges_are_rejected() {
+            VersionedConstantsMap::new([
+                (
+                    STARKNET_VERSION_0_13_1..STARKNET_VERSION_0_13_2,
+                    constants(),
+                ),
+                (STARKNET_VERSION_0_13_1_1..STARKNET_VERSION_MAX, constants()),
+            ])
+            .unwrap_err();
+
+            VersionedConstantsMap::new([(
+                STARKNET_VERSION_0_13_2..STARKNET_VERSION_0_13_1,
+                constants(),
+            )])
+            .unwrap_err();
+
+            VersionedConstantsMap::new([
+                (STARKNET_VERSION_0_13_2..STARKNET_VERSION_MAX, constants()),
+                (
+                    STARKNET_VERSION_0_13_1..STARKNET_VERSION_0_13_2,
+                    constants(),
+                ),
+            ])
+            .unwrap();
+        }
+
+        #[test]
+        fn for_version_prefers_custom_constants() {
+            let map = VersionedConstantsMap::new([(
+                STARKNET_VERSION_0_13_1..STARKNET_VERSION_0_13_1_1,
+                constants(),
+            )])
+            .unwrap();
+            let custom = map.get(&STARKNET_VERSION_0_13_1).unwrap();
+
+            let selected = for_version(&STARKNET_VERSION_0_13_1, &map);
+            assert!(std::ptr::eq(selected.as_ref(), custom));
+
+            let selected = for_version(&STARKNET_VERSION_0_13_1_1, &map);
+            assert!(std::ptr::eq(
+                selected.as_ref(),
+                &*BLOCKIFIER_VERSIONED_CONSTANTS_0_13_1_1
+            ));
         }
     }
 }
@@ -90,14 +273,16 @@ pub struct ExecutionState<'tx> {
     execute_on_parent_state: bool,
     pending_state: Option<Arc<StateUpdate>>,
     allow_use_kzg_data: bool,
-    custom_versioned_constants: Option<VersionedConstants>,
+    custom_versioned_constants: VersionedConstantsMap,
+    state_overrides: StateOverrides,
+    block_overrides: BlockOverrides,
 }
 
 impl<'tx> ExecutionState<'tx> {
     pub(super) fn starknet_state(
         self,
     ) -> anyhow::Result<(
-        CachedState<PendingStateReader<PathfinderStateReader<'tx>>>,
+        CachedState<OverrideStateReader<PendingStateReader<PathfinderStateReader<'tx>>>>,
         BlockContext,
     )> {
         let block_number = if self.execute_on_parent_state {
@@ -112,7 +297,9 @@ impl<'tx> ExecutionState<'tx> {
             self.pending_state.is_some(),
         );
         let pending_state_reader = PendingStateReader::new(raw_reader, self.pending_state.clone());
-        let mut cached_state = CachedState::new(pending_state_reader);
+        let override_state_reader =
+            OverrideStateReader::new(pending_state_reader, self.state_overrides.clone());
+        let mut cached_state = CachedState::new(override_state_reader);
 
         let chain_info = self.chain_info()?;
         let block_info = self.block_info()?;
@@ -139,15 +326,19 @@ impl<'tx> ExecutionState<'tx> {
             None
         };
 
-        let versioned_constants = versioned_constants::for_version(
-            &self.header.starknet_version,
-            self.custom_versioned_constants,
-        );
+        let starknet_version = self
+            .block_overrides
+            .starknet_version
+            .unwrap_or(self.header.starknet_version);
+        let versioned_constants =
+            versioned_constants::for_version(&starknet_version, &self.custom_versioned_constants);
 
+        // The historical block hash is that of the block executed on, even if
+        // the block number is overridden.
         pre_process_block(
             &mut cached_state,
             old_block_number_and_hash,
-            block_info.block_number,
+            starknet_api::block::BlockNumber(self.header.number.get()),
         )?;
 
         let block_context = BlockContext::new(
@@ -195,49 +386,51 @@ impl<'tx> ExecutionState<'tx> {
     }
 
     fn block_info(&self) -> anyhow::Result<BlockInfo> {
+        let header = self.block_overrides.apply(&self.header);
+
         Ok(BlockInfo {
-            block_number: starknet_api::block::BlockNumber(self.header.number.get()),
-            block_timestamp: starknet_api::block::BlockTimestamp(self.header.timestamp.get()),
+            block_number: starknet_api::block::BlockNumber(header.number.get()),
+            block_timestamp: starknet_api::block::BlockTimestamp(header.timestamp.get()),
             sequencer_address: starknet_api::core::ContractAddress(
-                PatriciaKey::try_from(self.header.sequencer_address.0.into_starkfelt())
+                PatriciaKey::try_from(header.sequencer_address.0.into_starkfelt())
                     .expect("Sequencer address overflow"),
             ),
             gas_prices: blockifier::blockifier::block::GasPrices {
-                eth_l1_gas_price: if self.header.eth_l1_gas_price.0 == 0 {
+                eth_l1_gas_price: if header.eth_l1_gas_price.0 == 0 {
                     // Bad API design - the genesis block has 0 gas price, but
                     // blockifier doesn't allow for it. This isn't critical for
                     // consensus, so we just use 1.
                     1.try_into().unwrap()
                 } else {
-                    self.header.eth_l1_gas_price.0.try_into().unwrap()
+                    header.eth_l1_gas_price.0.try_into().unwrap()
                 },
-                strk_l1_gas_price: if self.header.strk_l1_gas_price.0 == 0 {
+                strk_l1_gas_price: if header.strk_l1_gas_price.0 == 0 {
                     // Bad API design - the genesis block has 0 gas price, but
                     // blockifier doesn't allow for it. This isn't critical for
                     // consensus, so we just use 1.
                     1.try_into().unwrap()
                 } else {
-                    self.header.strk_l1_gas_price.0.try_into().unwrap()
+                    header.strk_l1_gas_price.0.try_into().unwrap()
                 },
-                eth_l1_data_gas_price: if self.header.eth_l1_data_gas_price.0 == 0 {
+                eth_l1_data_gas_price: if header.eth_l1_data_gas_price.0 == 0 {
                     // Bad API design - pre-v0.13.1 blocks have 0 data gas price, but
                     // blockifier doesn't allow for it. This value is ignored for those
                     // transactions.
                     1.try_into().unwrap()
                 } else {
-                    self.header.eth_l1_data_gas_price.0.try_into().unwrap()
+                    header.eth_l1_data_gas_price.0.try_into().unwrap()
                 },
-                strk_l1_data_gas_price: if self.header.strk_l1_data_gas_price.0 == 0 {
+                strk_l1_data_gas_price: if header.strk_l1_data_gas_price.0 == 0 {
                     // Bad API design - pre-v0.13.1 blocks have 0 data gas price, but
                     // blockifier doesn't allow for it. This value is ignored for those
                     // transactions.
                     1.try_into().unwrap()
                 } else {
-                    self.header.strk_l1_data_gas_price.0.try_into().unwrap()
+                    header.strk_l1_data_gas_price.0.try_into().unwrap()
                 },
             },
             use_kzg_da: self.allow_use_kzg_data
-                && self.header.l1_da_mode == L1DataAvailabilityMode::Blob,
+                && header.l1_da_mode == L1DataAvailabilityMode::Blob,
         })
     }
 
@@ -246,7 +439,7 @@ impl<'tx> ExecutionState<'tx> {
         chain_id: ChainId,
         header: BlockHeader,
         pending_state: Option<Arc<StateUpdate>>,
-        custom_versioned_constants: Option<VersionedConstants>,
+        custom_versioned_constants: VersionedConstantsMap,
     ) -> Self {
         Self {
             transaction,
@@ -256,6 +449,8 @@ impl<'tx> ExecutionState<'tx> {
             execute_on_parent_state: true,
             allow_use_kzg_data: true,
             custom_versioned_constants,
+            state_overrides: Default::default(),
+            block_overrides: Default::default(),
         }
     }
 
@@ -265,7 +460,7 @@ impl<'tx> ExecutionState<'tx> {
         header: BlockHeader,
         pending_state: Option<Arc<StateUpdate>>,
         l1_blob_data_availability: L1BlobDataAvailability,
-        custom_versioned_constants: Option<VersionedConstants>,
+        custom_versioned_constants: VersionedConstantsMap,
     ) -> Self {
         Self {
             transaction,
@@ -275,6 +470,24 @@ impl<'tx> ExecutionState<'tx> {
             execute_on_parent_state: false,
             allow_use_kzg_data: l1_blob_data_availability == L1BlobDataAvailability::Enabled,
             custom_versioned_constants,
+            state_overrides: Default::default(),
+            block_overrides: Default::default(),
+        }
+    }
+
+    /// Executes on top of the given changes to the state.
+    pub fn with_state_overrides(self, state_overrides: StateOverrides) -> Self {
+        Self {
+            state_overrides,
+            ..self
+        }
+    }
+
+    /// Executes in a block context with the given changes.
+    pub fn with_block_overrides(self, block_overrides: BlockOverrides) -> Self {
+        Self {
+            block_overrides,
+            ..self
         }
     }
 }
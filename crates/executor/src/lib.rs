@@ -5,11 +5,13 @@ pub(crate) mod estimate;
 pub(crate) mod execution_state;
 pub(crate) mod felt;
 pub(crate) mod lru_cache;
+pub(crate) mod overrides;
 pub(crate) mod pending;
 pub(crate) mod simulate;
 pub(crate) mod state_reader;
 pub(crate) mod transaction;
 pub mod types;
+pub(crate) mod validate;
 
 // re-export blockifier transaction type since it's exposed on our API
 pub use blockifier::execution::contract_class::ClassInfo;
@@ -18,14 +20,17 @@ pub use blockifier::transaction::transaction_execution::Transaction;
 pub use blockifier::versioned_constants::VersionedConstants;
 pub use call::call;
 pub use class::{parse_casm_definition, parse_deprecated_class_definition};
-pub use error::{CallError, TransactionExecutionError};
+pub use error::{CallError, TransactionExecutionError, TransactionValidationError};
 pub use estimate::estimate;
 pub use execution_state::{
     ExecutionState,
     L1BlobDataAvailability,
+    VersionedConstantsMap,
     ETH_FEE_TOKEN_ADDRESS,
     STRK_FEE_TOKEN_ADDRESS,
 };
 pub use felt::{IntoFelt, IntoStarkFelt};
+pub use overrides::{BlockOverrides, ContractOverride, StateOverrides};
 pub use simulate::{simulate, trace, TraceCache};
 pub use transaction::transaction_hash;
+pub use validate::validate;
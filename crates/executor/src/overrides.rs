@@ -0,0 +1,336 @@
+use std::collections::HashMap;
+
+use blockifier::state::errors::StateError;
+use blockifier::state::state_api::StateReader;
+use pathfinder_common::{
+    BlockHeader,
+    BlockNumber,
+    BlockTimestamp,
+    ClassHash,
+    ContractAddress,
+    ContractNonce,
+    GasPrice,
+    SequencerAddress,
+    StarknetVersion,
+    StorageAddress,
+    StorageValue,
+};
+use starknet_api::state::StorageKey;
+use starknet_api::StarknetApiError;
+
+use super::felt::{IntoFelt, IntoStarkFelt};
+
+/// Changes applied on top of the state which a call or simulation executes
+/// on, without being persisted.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StateOverrides(HashMap<ContractAddress, ContractOverride>);
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ContractOverride {
+    pub nonce: Option<ContractNonce>,
+    /// The class must be declared at the block that is executed on.
+    pub class_hash: Option<ClassHash>,
+    pub storage: HashMap<StorageAddress, StorageValue>,
+}
+
+impl StateOverrides {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn contract(&mut self, address: ContractAddress) -> &mut ContractOverride {
+        self.0.entry(address).or_default()
+    }
+
+    /// Sets the balance of `account` in the given ERC20 fee token contract.
+    pub fn set_balance(
+        &mut self,
+        fee_token: ContractAddress,
+        account: ContractAddress,
+        balance: u128,
+    ) {
+        let low = StorageAddress::from_map_name_and_key(b"ERC20_balances", account.0);
+        let high = StorageAddress(low.0 + pathfinder_crypto::Felt::ONE);
+
+        let storage = &mut self.contract(fee_token).storage;
+        storage.insert(low, StorageValue(balance.into()));
+        storage.insert(high, StorageValue::ZERO);
+    }
+
+    fn get(
+        &self,
+        contract_address: &starknet_api::core::ContractAddress,
+    ) -> Option<&ContractOverride> {
+        if self.0.is_empty() {
+            return None;
+        }
+
+        let contract_address = ContractAddress::new_or_panic(contract_address.0.key().into_felt());
+        self.0.get(&contract_address)
+    }
+}
+
+/// Changes to the block context which a call or simulation executes in. The
+/// state is still read from the block that is executed on.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BlockOverrides {
+    pub number: Option<BlockNumber>,
+    pub timestamp: Option<BlockTimestamp>,
+    pub sequencer_address: Option<SequencerAddress>,
+    pub eth_l1_gas_price: Option<GasPrice>,
+    pub strk_l1_gas_price: Option<GasPrice>,
+    pub eth_l1_data_gas_price: Option<GasPrice>,
+    pub strk_l1_data_gas_price: Option<GasPrice>,
+    /// Selects the versioned constants.
+    pub starknet_version: Option<StarknetVersion>,
+}
+
+impl BlockOverrides {
+    /// Returns the header with the overridden fields replaced.
+    pub(super) fn apply(&self, header: &BlockHeader) -> BlockHeader {
+        BlockHeader {
+            number: self.number.unwrap_or(header.number),
+            timestamp: self.timestamp.unwrap_or(header.timestamp),
+            sequencer_address: self.sequencer_address.unwrap_or(header.sequencer_address),
+            eth_l1_gas_price: self.eth_l1_gas_price.unwrap_or(header.eth_l1_gas_price),
+            strk_l1_gas_price: self.strk_l1_gas_price.unwrap_or(header.strk_l1_gas_price),
+            eth_l1_data_gas_price: self
+                .eth_l1_data_gas_price
+                .unwrap_or(header.eth_l1_data_gas_price),
+            strk_l1_data_gas_price: self
+                .strk_l1_data_gas_price
+                .unwrap_or(header.strk_l1_data_gas_price),
+            starknet_version: self.starknet_version.unwrap_or(header.starknet_version),
+            ..header.clone()
+        }
+    }
+}
+
+pub(super) struct OverrideStateReader<S: StateReader> {
+    state: S,
+    overrides: StateOverrides,
+}
+
+impl<S: StateReader> OverrideStateReader<S> {
+    pub(super) fn new(state: S, overrides: StateOverrides) -> Self {
+        Self { state, overrides }
+    }
+}
+
+impl<S: StateReader> StateReader for OverrideStateReader<S> {
+    fn get_storage_at(
+        &self,
+        contract_address: starknet_api::core::ContractAddress,
+        key: StorageKey,
+    ) -> blockifier::state::state_api::StateResult<starknet_types_core::felt::Felt> {
+        if let Some(contract) = self.overrides.get(&contract_address) {
+            let storage_key = StorageAddress::new(key.0.key().into_felt()).ok_or_else(|| {
+                StateError::StarknetApiError(StarknetApiError::OutOfRange {
+                    string: "Storage key out of range".to_owned(),
+                })
+            })?;
+
+            if let Some(value) = contract.storage.get(&storage_key) {
+                return Ok(value.0.into_starkfelt());
+            }
+        }
+
+        self.state.get_storage_at(contract_address, key)
+    }
+
+    fn get_nonce_at(
+        &self,
+        contract_address: starknet_api::core::ContractAddress,
+    ) -> blockifier::state::state_api::StateResult<starknet_api::core::Nonce> {
+        match self
+            .overrides
+            .get(&contract_address)
+            .and_then(|contract| contract.nonce)
+        {
+            Some(nonce) => Ok(starknet_api::core::Nonce(nonce.0.into_starkfelt())),
+            None => self.state.get_nonce_at(contract_address),
+        }
+    }
+
+    fn get_class_hash_at(
+        &self,
+        contract_address: starknet_api::core::ContractAddress,
+    ) -> blockifier::state::state_api::StateResult<starknet_api::core::ClassHash> {
+        match self
+            .overrides
+            .get(&contract_address)
+            .and_then(|contract| contract.class_hash)
+        {
+            Some(class_hash) => Ok(starknet_api::core::ClassHash(class_hash.0.into_starkfelt())),
+            None => self.state.get_class_hash_at(contract_address),
+        }
+    }
+
+    fn get_compiled_contract_class(
+        &self,
+        class_hash: starknet_api::core::ClassHash,
+    ) -> blockifier::state::state_api::StateResult<
+        blockifier::execution::contract_class::ContractClass,
+    > {
+        self.state.get_compiled_contract_class(class_hash)
+    }
+
+    fn get_compiled_class_hash(
+        &self,
+        class_hash: starknet_api::core::ClassHash,
+    ) -> blockifier::state::state_api::StateResult<starknet_api::core::CompiledClassHash> {
+        self.state.get_compiled_class_hash(class_hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use blockifier::state::state_api::StateReader;
+    use pathfinder_common::macro_prelude::*;
+    use starknet_types_core::felt::Felt as CoreFelt;
+
+    use super::*;
+
+    /// Reads the value of every storage slot, nonce and class hash as `0x1`.
+    /// No classes are declared.
+    struct OnesStateReader;
+
+    impl StateReader for OnesStateReader {
+        fn get_storage_at(
+            &self,
+            _contract_address: starknet_api::core::ContractAddress,
+            _key: StorageKey,
+        ) -> blockifier::state::state_api::StateResult<CoreFelt> {
+            Ok(CoreFelt::ONE)
+        }
+
+        fn get_nonce_at(
+            &self,
+            _contract_address: starknet_api::core::ContractAddress,
+        ) -> blockifier::state::state_api::StateResult<starknet_api::core::Nonce> {
+            Ok(starknet_api::core::Nonce(CoreFelt::ONE))
+        }
+
+        fn get_class_hash_at(
+            &self,
+            _contract_address: starknet_api::core::ContractAddress,
+        ) -> blockifier::state::state_api::StateResult<starknet_api::core::ClassHash> {
+            Ok(starknet_api::core::ClassHash(CoreFelt::ONE))
+        }
+
+        fn get_compiled_contract_class(
+            &self,
+            class_hash: starknet_api::core::ClassHash,
+        ) -> blockifier::state::state_api::StateResult<
+            blockifier::execution::contract_class::ContractClass,
+        > {
+            Err(StateError::UndeclaredClassHash(class_hash))
+        }
+
+        fn get_compiled_class_hash(
+            &self,
+            class_hash: starknet_api::core::ClassHash,
+        ) -> blockifier::state::state_api::StateResult<starknet_api::core::CompiledClassHash>
+        {
+            Err(StateError::UndeclaredClassHash(class_hash))
+        }
+    }
+
+    fn address(value: u8) -> starknet_api::core::ContractAddress {
+        starknet_api::core::ContractAddress(
+            starknet_api::core::PatriciaKey::try_from(CoreFelt::from(value)).unwrap(),
+        )
+    }
+
+    fn key(value: u8) -> StorageKey {
+        StorageKey(starknet_api::core::PatriciaKey::try_from(CoreFelt::from(value)).unwrap())
+    }
+
+    #[test]
+    fn overridden_values_take_precedence() {
+        let mut overrides = StateOverrides::default();
+        let contract = overrides.contract(contract_address!("0x2"));
+        contract.nonce = Some(contract_nonce!("0x3"));
+        contract.class_hash = Some(class_hash!("0x4"));
+        contract
+            .storage
+            .insert(storage_address!("0x5"), storage_value!("0x6"));
+
+        let uut = OverrideStateReader::new(OnesStateReader, overrides);
+
+        assert_eq!(
+            uut.get_nonce_at(address(2)).unwrap(),
+            starknet_api::core::Nonce(CoreFelt::from(3u8))
+        );
+        assert_eq!(
+            uut.get_class_hash_at(address(2)).unwrap(),
+            starknet_api::core::ClassHash(CoreFelt::from(4u8))
+        );
+        assert_eq!(
+            uut.get_storage_at(address(2), key(5)).unwrap(),
+            CoreFelt::from(6u8)
+        );
+
+        // Values which are not overridden are read from the underlying state.
+        assert_eq!(
+            uut.get_storage_at(address(2), key(7)).unwrap(),
+            CoreFelt::ONE
+        );
+        assert_eq!(
+            uut.get_nonce_at(address(1)).unwrap(),
+            starknet_api::core::Nonce(CoreFelt::ONE)
+        );
+    }
+
+    #[test]
+    fn block_overrides_replace_only_given_fields() {
+        let header = BlockHeader::builder()
+            .with_number(BlockNumber::new_or_panic(5))
+            .with_timestamp(BlockTimestamp::new_or_panic(100))
+            .with_eth_l1_gas_price(GasPrice(1))
+            .finalize_with_hash(block_hash!("0x5"));
+
+        let overrides = BlockOverrides {
+            timestamp: Some(BlockTimestamp::new_or_panic(200)),
+            eth_l1_gas_price: Some(GasPrice(7)),
+            ..Default::default()
+        };
+
+        let expected = BlockHeader {
+            timestamp: BlockTimestamp::new_or_panic(200),
+            eth_l1_gas_price: GasPrice(7),
+            ..header.clone()
+        };
+        assert_eq!(overrides.apply(&header), expected);
+    }
+
+    #[test]
+    fn balance() {
+        let mut overrides = StateOverrides::default();
+        overrides.set_balance(
+            crate::STRK_FEE_TOKEN_ADDRESS,
+            contract_address!("0x2"),
+            1_000,
+        );
+
+        let uut = OverrideStateReader::new(OnesStateReader, overrides);
+
+        let token = starknet_api::core::ContractAddress(
+            starknet_api::core::PatriciaKey::try_from(
+                crate::STRK_FEE_TOKEN_ADDRESS.0.into_starkfelt(),
+            )
+            .unwrap(),
+        );
+        let low = StorageAddress::from_map_name_and_key(b"ERC20_balances", felt!("0x2"));
+        let high = low.0 + pathfinder_crypto::Felt::ONE;
+        let [low, high] = [low.0, high].map(|key| {
+            StorageKey(starknet_api::core::PatriciaKey::try_from(key.into_starkfelt()).unwrap())
+        });
+
+        assert_eq!(
+            uut.get_storage_at(token, low).unwrap(),
+            CoreFelt::from(1_000u16)
+        );
+        assert_eq!(uut.get_storage_at(token, high).unwrap(), CoreFelt::ZERO);
+    }
+}
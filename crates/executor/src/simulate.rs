@@ -1,4 +1,5 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::num::NonZeroU64;
 use std::sync::{Arc, Mutex};
 
 use anyhow::Context;
@@ -18,12 +19,15 @@ use pathfinder_common::{
     StorageValue,
     TransactionHash,
 };
+use pathfinder_storage::Storage;
 
 use super::error::TransactionExecutionError;
 use super::execution_state::ExecutionState;
 use super::types::{FeeEstimate, TransactionSimulation, TransactionTrace};
 use crate::transaction::transaction_hash;
 use crate::types::{
+    AccessList,
+    AccessSet,
     DataAvailabilityResources,
     DeclareTransactionTrace,
     DeclaredSierraClass,
@@ -62,14 +66,101 @@ impl From<ExecutionError> for TransactionExecutionError {
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct TraceCache(Arc<Mutex<SizedCache<BlockHash, CacheItem>>>);
+#[derive(Clone)]
+pub struct TraceCache {
+    memory: Arc<Mutex<SizedCache<BlockHash, CacheItem>>>,
+    store: Option<TraceStore>,
+}
 
 type Traces = Vec<(TransactionHash, TransactionTrace)>;
 
 impl Default for TraceCache {
     fn default() -> Self {
-        Self(Arc::new(Mutex::new(SizedCache::with_size(128))))
+        Self {
+            memory: Arc::new(Mutex::new(SizedCache::with_size(128))),
+            store: None,
+        }
+    }
+}
+
+impl std::fmt::Debug for TraceCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TraceCache")
+            .field("memory", &self.memory)
+            .field("persistent", &self.store.is_some())
+            .finish()
+    }
+}
+
+impl TraceCache {
+    /// Additionally keeps the traces of up to `max_blocks` blocks in the
+    /// database, so that they survive restarts. Traces of the blocks which are
+    /// reorged away are deleted together with the blocks.
+    ///
+    /// The storage must not be read-only.
+    pub fn with_store(self, storage: Storage, max_blocks: NonZeroU64) -> Self {
+        Self {
+            store: Some(TraceStore {
+                storage,
+                max_blocks,
+            }),
+            ..self
+        }
+    }
+}
+
+/// The version of the serialized [Traces] in the persistent cache. Has to be
+/// bumped whenever their serialization changes, so that traces stored by
+/// earlier versions are re-executed instead of misread.
+const TRACE_FORMAT_VERSION: u32 = 1;
+
+#[derive(Clone)]
+struct TraceStore {
+    storage: Storage,
+    max_blocks: NonZeroU64,
+}
+
+impl TraceStore {
+    /// Errors are logged and treated as a miss, since the traces can always be
+    /// re-executed.
+    fn get(&self, block_hash: BlockHash) -> Option<Traces> {
+        let result = || -> anyhow::Result<Option<Traces>> {
+            let mut db = self.storage.connection()?;
+            let db = db.transaction()?;
+
+            let Some((format_version, traces)) = db.block_traces(block_hash)? else {
+                return Ok(None);
+            };
+            if format_version != TRACE_FORMAT_VERSION {
+                tracing::debug!(block=%block_hash, %format_version, "Ignoring traces persisted in another format");
+                return Ok(None);
+            }
+
+            serde_json::from_slice(&traces)
+                .context("Deserializing block traces")
+                .map(Some)
+        };
+
+        result().unwrap_or_else(|error| {
+            tracing::debug!(block=%block_hash, %error, "Failed to read persisted traces");
+            None
+        })
+    }
+
+    fn insert(&self, block_hash: BlockHash, traces: &Traces) {
+        let result = || -> anyhow::Result<()> {
+            let traces = serde_json::to_vec(traces).context("Serializing block traces")?;
+
+            let mut db = self.storage.connection()?;
+            let db = db.transaction()?;
+            db.insert_block_traces(block_hash, TRACE_FORMAT_VERSION, &traces)?;
+            db.prune_block_traces(self.max_blocks.get())?;
+            db.commit().context("Committing block traces")
+        };
+
+        if let Err(error) = result() {
+            tracing::warn!(block=%block_hash, %error, "Failed to persist traces");
+        }
     }
 }
 
@@ -78,6 +169,7 @@ pub fn simulate(
     transactions: Vec<Transaction>,
     skip_validate: bool,
     skip_fee_charge: bool,
+    return_access_lists: bool,
 ) -> Result<Vec<TransactionSimulation>, TransactionExecutionError> {
     let block_number = execution_state.header.number;
 
@@ -110,6 +202,12 @@ pub fn simulate(
             !skip_validate,
         );
         let state_diff = to_state_diff(&mut tx_state, transaction_declared_deprecated_class_hash)?;
+        // Reads have to be collected before they are merged into the parent state.
+        let reads = if return_access_lists {
+            Some(to_read_set(&tx_state)?)
+        } else {
+            None
+        };
         tx_state.commit();
 
         match tx_info {
@@ -120,6 +218,20 @@ pub fn simulate(
 
                 tracing::trace!(actual_fee=%tx_info.transaction_receipt.fee.0, actual_resources=?tx_info.transaction_receipt.resources, "Transaction simulation finished");
 
+                let access_list = reads.map(|reads| {
+                    AccessList::new(
+                        reads,
+                        &state_diff,
+                        [
+                            &tx_info.validate_call_info,
+                            &tx_info.execute_call_info,
+                            &tx_info.fee_transfer_call_info,
+                        ]
+                        .into_iter()
+                        .flatten(),
+                    )
+                });
+
                 simulations.push(TransactionSimulation {
                     fee_estimation: FeeEstimate::from_tx_info_and_gas_price(
                         &tx_info,
@@ -128,6 +240,7 @@ pub fn simulate(
                         &minimal_l1_gas_amount_vector,
                     ),
                     trace: to_trace(transaction_type, tx_info, state_diff),
+                    access_list,
                 });
             }
             Err(error) => {
@@ -148,7 +261,7 @@ pub fn trace(
     let (mut state, block_context) = execution_state.starknet_state()?;
 
     let sender = {
-        let mut cache = cache.0.lock().unwrap();
+        let mut cache = cache.memory.lock().unwrap();
         match cache.cache_get(&block_hash) {
             Some(CacheItem::CachedOk(cached)) => {
                 tracing::trace!(block=%block_hash, "trace cache hit: ok");
@@ -175,6 +288,14 @@ pub fn trace(
         }
     };
 
+    if let Some(traces) = cache.store.as_ref().and_then(|store| store.get(block_hash)) {
+        tracing::trace!(block=%block_hash, "persisted trace cache hit");
+        let mut cache = cache.memory.lock().unwrap();
+        let _ = sender.send(Ok(traces.clone()));
+        cache.cache_set(block_hash, CacheItem::CachedOk(traces.clone()));
+        return Ok(traces);
+    }
+
     let mut traces = Vec::with_capacity(transactions.len());
     for (transaction_idx, tx) in transactions.into_iter().enumerate() {
         let hash = transaction_hash(&tx);
@@ -193,7 +314,7 @@ pub fn trace(
                     transaction_index: transaction_idx,
                     error: e.to_string(),
                 };
-                let mut cache = cache.0.lock().unwrap();
+                let mut cache = cache.memory.lock().unwrap();
                 let _ = sender.send(Err(err.clone()));
                 cache.cache_set(block_hash, CacheItem::CachedErr(err.clone()));
                 err
@@ -201,7 +322,7 @@ pub fn trace(
         let state_diff =
             to_state_diff(&mut tx_state, tx_declared_deprecated_class_hash).map_err(|e| {
                 // Remove the cache entry so it's no longer inflight.
-                let mut cache = cache.0.lock().unwrap();
+                let mut cache = cache.memory.lock().unwrap();
                 cache.cache_remove(&block_hash);
                 e
             })?;
@@ -211,9 +332,13 @@ pub fn trace(
         traces.push((hash, trace));
     }
 
+    if let Some(store) = &cache.store {
+        store.insert(block_hash, &traces);
+    }
+
     // Lock the cache before sending to avoid race conditions between senders and
     // receivers.
-    let mut cache = cache.0.lock().unwrap();
+    let mut cache = cache.memory.lock().unwrap();
     let _ = sender.send(Ok(traces.clone()));
     cache.cache_set(block_hash, CacheItem::CachedOk(traces.clone()));
     Ok(traces)
@@ -335,6 +460,36 @@ fn to_state_diff<S: blockifier::state::state_api::StateReader>(
     })
 }
 
+/// The state which the transaction read from its parent state.
+fn to_read_set<S: blockifier::state::state_api::StateReader>(
+    state: &blockifier::state::cached_state::CachedState<S>,
+) -> Result<AccessSet, StateError> {
+    let initial_reads = state.get_initial_reads()?;
+
+    let mut storage: BTreeMap<ContractAddress, BTreeSet<StorageAddress>> = BTreeMap::new();
+    for (address, key) in initial_reads.storage.into_keys() {
+        storage
+            .entry(ContractAddress::new_or_panic(address.0.key().into_felt()))
+            .or_default()
+            .insert(StorageAddress::new_or_panic(key.0.key().into_felt()));
+    }
+
+    Ok(AccessSet {
+        storage,
+        nonces: initial_reads
+            .nonces
+            .into_keys()
+            .map(|address| ContractAddress::new_or_panic(address.0.key().into_felt()))
+            .collect(),
+        class_hashes: initial_reads
+            .class_hashes
+            .into_keys()
+            .map(|address| ContractAddress::new_or_panic(address.0.key().into_felt()))
+            .collect(),
+        ..Default::default()
+    })
+}
+
 fn to_trace(
     transaction_type: TransactionType,
     execution_info: blockifier::transaction::objects::TransactionExecutionInfo,
@@ -399,3 +554,65 @@ fn to_trace(
         }),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::{BlockHeader, BlockNumber};
+
+    use super::*;
+
+    #[test]
+    fn trace_store_round_trip() {
+        let storage = pathfinder_storage::StorageBuilder::in_memory().unwrap();
+        let header = BlockHeader::builder()
+            .with_number(BlockNumber::GENESIS)
+            .finalize_with_hash(block_hash!("0xb10c"));
+        {
+            let mut db = storage.connection().unwrap();
+            let db = db.transaction().unwrap();
+            db.insert_block_header(&header).unwrap();
+            db.commit().unwrap();
+        }
+
+        let store = TraceStore {
+            storage,
+            max_blocks: NonZeroU64::new(10).unwrap(),
+        };
+        assert!(store.get(header.hash).is_none());
+
+        let traces: Traces = vec![(
+            transaction_hash!("0x1"),
+            TransactionTrace::Declare(DeclareTransactionTrace {
+                validate_invocation: None,
+                fee_transfer_invocation: None,
+                state_diff: StateDiff {
+                    nonces: [(contract_address!("0x2"), contract_nonce!("0x3"))].into(),
+                    ..Default::default()
+                },
+                execution_resources: Default::default(),
+            }),
+        )];
+        store.insert(header.hash, &traces);
+
+        let stored = store.get(header.hash).unwrap();
+        assert_eq!(
+            serde_json::to_value(stored).unwrap(),
+            serde_json::to_value(&traces).unwrap()
+        );
+
+        // Traces persisted in another format are not read.
+        {
+            let mut db = store.storage.connection().unwrap();
+            let db = db.transaction().unwrap();
+            db.insert_block_traces(
+                header.hash,
+                TRACE_FORMAT_VERSION + 1,
+                &serde_json::to_vec(&traces).unwrap(),
+            )
+            .unwrap();
+            db.commit().unwrap();
+        }
+        assert!(store.get(header.hash).is_none());
+    }
+}
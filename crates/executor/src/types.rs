@@ -1,4 +1,4 @@
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 
 use blockifier::blockifier::block::BlockInfo;
 use blockifier::execution::call_info::OrderedL2ToL1Message;
@@ -96,7 +96,7 @@ impl From<FeeType> for PriceUnit {
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum EntryPointType {
     Constructor,
     External,
@@ -107,6 +107,8 @@ pub enum EntryPointType {
 pub struct TransactionSimulation {
     pub trace: TransactionTrace,
     pub fee_estimation: FeeEstimate,
+    /// Only collected when requested.
+    pub access_list: Option<AccessList>,
 }
 
 impl TransactionSimulation {
@@ -115,7 +117,215 @@ impl TransactionSimulation {
     }
 }
 
-#[derive(Debug, Clone)]
+/// The state a transaction accessed, e.g. for detecting conflicts between
+/// transactions.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AccessList {
+    /// State read by the transaction before writing it, including state which
+    /// was written by preceding transactions of the simulation.
+    pub reads: AccessSet,
+    /// State changed by the transaction. Writes of unchanged values are not
+    /// included.
+    pub writes: AccessSet,
+    /// The validate, execute or constructor, and fee transfer calls.
+    pub calls: Vec<CallAccess>,
+}
+
+impl AccessList {
+    /// Attributes the accesses of the transaction to the `calls` it made.
+    ///
+    /// The nonce of the account is checked and incremented outside of any
+    /// call, and is attributed to the first call on the account, which is
+    /// its validation unless validation was skipped. Likewise, an account is
+    /// deployed outside of any call, and its deployment is attributed to its
+    /// constructor.
+    pub(crate) fn new<'a>(
+        reads: AccessSet,
+        state_diff: &StateDiff,
+        calls: impl IntoIterator<Item = &'a blockifier::execution::call_info::CallInfo>,
+    ) -> Self {
+        let writes = AccessSet::from(state_diff);
+        let replaced = state_diff
+            .replaced_classes
+            .iter()
+            .map(|class| class.contract_address)
+            .collect::<BTreeSet<_>>();
+
+        let mut calls = calls
+            .into_iter()
+            .map(|call_info| {
+                let mut call = CallAccess::new(call_info, &reads, &writes, &replaced);
+                if call_info.call.entry_point_type
+                    == starknet_api::deprecated_contract_class::EntryPointType::Constructor
+                {
+                    call.class_hash_writes.insert(call.contract_address);
+                }
+                call
+            })
+            .collect::<Vec<_>>();
+
+        if let Some(call) = calls.iter_mut().find(|call| {
+            reads.nonces.contains(&call.contract_address)
+                || writes.nonces.contains(&call.contract_address)
+        }) {
+            let account = call.contract_address;
+            if reads.nonces.contains(&account) {
+                call.nonce_reads.insert(account);
+            }
+            if writes.nonces.contains(&account) {
+                call.nonce_writes.insert(account);
+            }
+        }
+
+        Self {
+            reads,
+            writes,
+            calls,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct AccessSet {
+    pub storage: BTreeMap<ContractAddress, BTreeSet<StorageAddress>>,
+    pub nonces: BTreeSet<ContractAddress>,
+    pub class_hashes: BTreeSet<ContractAddress>,
+    /// Sierra classes with their compiled class hash. Only declared, so these
+    /// are never read.
+    pub declared_classes: BTreeMap<SierraHash, CasmHash>,
+    /// Only declared, so these are never read.
+    pub deprecated_declared_classes: BTreeSet<ClassHash>,
+}
+
+/// The state accessed by a call itself, but not by its inner calls.
+///
+/// Blockifier only records which storage keys a call accessed. A key is a
+/// write of the call if the transaction changed it, and a read if the call
+/// did not change it or the transaction read it before changing it. Writes
+/// of unchanged values are therefore reads, and a key changed by one of
+/// several calls on the same contract is a write of each of them which
+/// accessed it.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CallAccess {
+    pub contract_address: ContractAddress,
+    pub class_hash: Option<ClassHash>,
+    /// Storage of `contract_address` read by the call.
+    pub storage_reads: BTreeSet<StorageAddress>,
+    /// Storage of `contract_address` changed by the call.
+    pub storage_writes: BTreeSet<StorageAddress>,
+    pub nonce_reads: BTreeSet<ContractAddress>,
+    pub nonce_writes: BTreeSet<ContractAddress>,
+    /// The class hash of `contract_address` is read by every call, and the
+    /// class hash of a deployed contract by the call deploying it.
+    pub class_hash_reads: BTreeSet<ContractAddress>,
+    /// Contracts deployed by the call, or `contract_address` if its class was
+    /// replaced.
+    pub class_hash_writes: BTreeSet<ContractAddress>,
+    pub inner_calls: Vec<CallAccess>,
+}
+
+impl CallAccess {
+    fn new(
+        call_info: &blockifier::execution::call_info::CallInfo,
+        reads: &AccessSet,
+        writes: &AccessSet,
+        replaced: &BTreeSet<ContractAddress>,
+    ) -> Self {
+        use starknet_api::deprecated_contract_class::EntryPointType;
+
+        let contract_address =
+            ContractAddress::new_or_panic(call_info.call.storage_address.0.key().into_felt());
+        let accessed = call_info
+            .accessed_storage_keys
+            .iter()
+            .map(|key| StorageAddress::new_or_panic(key.0.key().into_felt()));
+
+        let no_keys = BTreeSet::new();
+        let read_first = reads.storage.get(&contract_address).unwrap_or(&no_keys);
+        let changed = writes.storage.get(&contract_address).unwrap_or(&no_keys);
+        let (storage_writes, storage_reads): (BTreeSet<_>, BTreeSet<_>) =
+            accessed.partition(|key| changed.contains(key));
+        let storage_reads = storage_reads
+            .into_iter()
+            .chain(storage_writes.intersection(read_first).copied())
+            .collect();
+
+        let inner_calls = call_info
+            .inner_calls
+            .iter()
+            .map(|inner| Self::new(inner, reads, writes, replaced))
+            .collect::<Vec<_>>();
+
+        // The constructor of a deployed contract is an inner call of the call
+        // deploying it.
+        let deployed = call_info
+            .inner_calls
+            .iter()
+            .filter(|inner| inner.call.entry_point_type == EntryPointType::Constructor)
+            .map(|inner| {
+                ContractAddress::new_or_panic(inner.call.storage_address.0.key().into_felt())
+            })
+            .collect::<BTreeSet<_>>();
+
+        let mut class_hash_reads = deployed.clone();
+        class_hash_reads.insert(contract_address);
+        let mut class_hash_writes = deployed;
+        if replaced.contains(&contract_address) {
+            class_hash_writes.insert(contract_address);
+        }
+
+        Self {
+            contract_address,
+            class_hash: call_info
+                .call
+                .class_hash
+                .map(|class_hash| ClassHash(class_hash.0.into_felt())),
+            storage_reads,
+            storage_writes,
+            nonce_reads: Default::default(),
+            nonce_writes: Default::default(),
+            class_hash_reads,
+            class_hash_writes,
+            inner_calls,
+        }
+    }
+}
+
+impl From<&StateDiff> for AccessSet {
+    fn from(state_diff: &StateDiff) -> Self {
+        Self {
+            storage: state_diff
+                .storage_diffs
+                .iter()
+                .map(|(address, diffs)| (*address, diffs.iter().map(|diff| diff.key).collect()))
+                .collect(),
+            nonces: state_diff.nonces.keys().copied().collect(),
+            class_hashes: state_diff
+                .deployed_contracts
+                .iter()
+                .map(|contract| contract.address)
+                .chain(
+                    state_diff
+                        .replaced_classes
+                        .iter()
+                        .map(|class| class.contract_address),
+                )
+                .collect(),
+            declared_classes: state_diff
+                .declared_classes
+                .iter()
+                .map(|class| (class.class_hash, class.compiled_class_hash))
+                .collect(),
+            deprecated_declared_classes: state_diff
+                .deprecated_declared_classes
+                .iter()
+                .copied()
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum TransactionTrace {
     Declare(DeclareTransactionTrace),
     DeployAccount(DeployAccountTransactionTrace),
@@ -133,9 +343,27 @@ impl TransactionTrace {
             _ => None,
         }
     }
+
+    pub fn state_diff(&self) -> &StateDiff {
+        match self {
+            TransactionTrace::Declare(trace) => &trace.state_diff,
+            TransactionTrace::DeployAccount(trace) => &trace.state_diff,
+            TransactionTrace::Invoke(trace) => &trace.state_diff,
+            TransactionTrace::L1Handler(trace) => &trace.state_diff,
+        }
+    }
+
+    pub fn execution_resources(&self) -> &ExecutionResources {
+        match self {
+            TransactionTrace::Declare(trace) => &trace.execution_resources,
+            TransactionTrace::DeployAccount(trace) => &trace.execution_resources,
+            TransactionTrace::Invoke(trace) => &trace.execution_resources,
+            TransactionTrace::L1Handler(trace) => &trace.execution_resources,
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct DeclareTransactionTrace {
     pub validate_invocation: Option<FunctionInvocation>,
     pub fee_transfer_invocation: Option<FunctionInvocation>,
@@ -143,7 +371,7 @@ pub struct DeclareTransactionTrace {
     pub execution_resources: ExecutionResources,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct DeployAccountTransactionTrace {
     pub validate_invocation: Option<FunctionInvocation>,
     pub constructor_invocation: Option<FunctionInvocation>,
@@ -152,13 +380,13 @@ pub struct DeployAccountTransactionTrace {
     pub execution_resources: ExecutionResources,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum ExecuteInvocation {
     FunctionInvocation(Option<FunctionInvocation>),
     RevertedReason(String),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct InvokeTransactionTrace {
     pub validate_invocation: Option<FunctionInvocation>,
     pub execute_invocation: ExecuteInvocation,
@@ -167,27 +395,27 @@ pub struct InvokeTransactionTrace {
     pub execution_resources: ExecutionResources,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct L1HandlerTransactionTrace {
     pub function_invocation: Option<FunctionInvocation>,
     pub state_diff: StateDiff,
     pub execution_resources: ExecutionResources,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum CallType {
     Call,
     Delegate,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Event {
     pub order: i64,
     pub data: Vec<Felt>,
     pub keys: Vec<Felt>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct FunctionInvocation {
     pub calldata: Vec<Felt>,
     pub contract_address: ContractAddress,
@@ -203,7 +431,7 @@ pub struct FunctionInvocation {
     pub computation_resources: ComputationResources,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct MsgToL1 {
     pub order: usize,
     pub payload: Vec<Felt>,
@@ -211,7 +439,7 @@ pub struct MsgToL1 {
     pub from_address: Felt,
 }
 
-#[derive(Debug, Default, Clone, Eq, PartialEq)]
+#[derive(Debug, Default, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct StateDiff {
     pub storage_diffs: BTreeMap<ContractAddress, Vec<StorageDiff>>,
     pub deployed_contracts: Vec<DeployedContract>,
@@ -221,37 +449,37 @@ pub struct StateDiff {
     pub replaced_classes: Vec<ReplacedClass>,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct StorageDiff {
     pub key: StorageAddress,
     pub value: StorageValue,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct DeployedContract {
     pub address: ContractAddress,
     pub class_hash: ClassHash,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct DeclaredSierraClass {
     pub class_hash: SierraHash,
     pub compiled_class_hash: CasmHash,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct ReplacedClass {
     pub contract_address: ContractAddress,
     pub class_hash: ClassHash,
 }
 
-#[derive(Debug, Default, Clone, Eq, PartialEq)]
+#[derive(Debug, Default, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct ExecutionResources {
     pub computation_resources: ComputationResources,
     pub data_availability: DataAvailabilityResources,
 }
 
-#[derive(Debug, Default, Clone, Eq, PartialEq)]
+#[derive(Debug, Default, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct ComputationResources {
     pub steps: usize,
     pub memory_holes: usize,
@@ -291,7 +519,7 @@ impl std::ops::Add for ComputationResources {
     }
 }
 
-#[derive(Debug, Default, Clone, Eq, PartialEq)]
+#[derive(Debug, Default, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct DataAvailabilityResources {
     pub l1_gas: u128,
     pub l1_data_gas: u128,
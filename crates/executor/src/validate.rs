@@ -0,0 +1,60 @@
+use std::cmp::Ordering;
+
+use blockifier::blockifier::stateful_validator::StatefulValidator;
+use blockifier::state::state_api::StateReader;
+use blockifier::transaction::transaction_execution::Transaction;
+use starknet_api::core::Nonce;
+use starknet_types_core::felt::Felt as CoreFelt;
+
+use super::error::TransactionValidationError;
+use super::execution_state::ExecutionState;
+
+/// Checks whether the sequencer would accept a submitted transaction.
+///
+/// The transaction is validated on top of the given state using the
+/// blockifier's validate-only path: the nonce and fee checks and the
+/// `__validate__` entry point are run, but invoke transactions are not
+/// executed. Deploy account transactions are executed in full, as the
+/// account must exist before `__validate_deploy__` can be called.
+///
+/// A transaction with a nonce ahead of the account's nonce depends on
+/// transactions which are not part of the state yet, so only its nonce is
+/// checked.
+pub fn validate(
+    execution_state: ExecutionState<'_>,
+    transaction: Transaction,
+) -> Result<(), TransactionValidationError> {
+    let block_number = execution_state.header.number;
+
+    let transaction_hash = super::transaction::transaction_hash(&transaction);
+    let Transaction::AccountTransaction(account_transaction) = transaction else {
+        return Err(TransactionValidationError::Custom(anyhow::anyhow!(
+            "Only account transactions can be submitted"
+        )));
+    };
+
+    let _span = tracing::debug_span!("validate", %transaction_hash, %block_number).entered();
+
+    let (state, block_context) = execution_state.starknet_state()?;
+
+    let tx_info = account_transaction.create_tx_info();
+    let account_nonce = state.get_nonce_at(tx_info.sender_address())?;
+    match tx_info.nonce().cmp(&account_nonce) {
+        Ordering::Less => return Err(TransactionValidationError::InvalidTransactionNonce),
+        Ordering::Greater => {
+            tracing::trace!(nonce=?tx_info.nonce(), ?account_nonce, "Nonce ahead of account nonce, skipping validation");
+            return Ok(());
+        }
+        Ordering::Equal => {}
+    }
+
+    // Validation is never skipped based on the nonce, since we don't pass the
+    // hash of a pending deploy account transaction.
+    let mut validator = StatefulValidator::create(state, block_context, Nonce(CoreFelt::ONE));
+    validator
+        .perform_validations(account_transaction, None)
+        .map_err(|error| {
+            tracing::debug!(%error, "Transaction validation failed");
+            error.into()
+        })
+}
@@ -0,0 +1,380 @@
+//! Recording of gateway and feeder gateway responses to an on-disk archive,
+//! and their replay without network access.
+//!
+//! An archive is a sequence of zstd frames, each holding one recorded response
+//! as JSON, with the body encoded as base64. Responses are keyed by their
+//! request's method and query, e.g.
+//! `get_block?blockNumber=latest&headerOnly=true`, and are replayed in the
+//! order they were recorded. Once all responses to a request have been
+//! replayed, the last one is repeated.
+//!
+//! Replayed responses are parsed by the regular [Client](crate::Client), so
+//! a replaying client behaves like one talking to the recorded feeder gateway.
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Context;
+use reqwest::StatusCode;
+use starknet_gateway_types::error::SequencerError;
+
+const COMPRESSION_LEVEL: i32 = 10;
+
+#[derive(Debug, Clone)]
+pub(crate) enum Mode {
+    Record(Arc<Recorder>),
+    Replay(Arc<Replay>),
+}
+
+impl Mode {
+    /// Sends the request, or answers it from the archive when replaying.
+    pub async fn send(
+        &self,
+        key: &str,
+        request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, SequencerError> {
+        match self {
+            Mode::Record(recorder) => {
+                let response = request.send().await?;
+                recorder.record(key, response).await
+            }
+            Mode::Replay(replay) => Ok(replay.respond(key).await),
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Entry {
+    request: String,
+    status: u16,
+    /// Base64 encoded, so that binary responses are archived unchanged.
+    body: String,
+}
+
+impl Entry {
+    fn into_response(self) -> anyhow::Result<(String, Response)> {
+        let body = base64::decode(&self.body).context("Decoding body")?;
+        Ok((
+            self.request,
+            Response {
+                status: StatusCode::from_u16(self.status).unwrap_or(StatusCode::NOT_FOUND),
+                body: body.into(),
+            },
+        ))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Response {
+    status: StatusCode,
+    body: bytes::Bytes,
+}
+
+impl From<Response> for reqwest::Response {
+    fn from(response: Response) -> Self {
+        http::Response::builder()
+            .status(response.status)
+            .body(response.body)
+            .expect("Status is valid")
+            .into()
+    }
+}
+
+/// Appends every successful gateway and feeder gateway response, and every
+/// Starknet error, to an archive.
+///
+/// Transient failures such as rate limiting are not recorded, use
+/// [Replay::inject_error] to reproduce them.
+#[derive(Debug)]
+pub(crate) struct Recorder {
+    file: Mutex<std::fs::File>,
+}
+
+impl Recorder {
+    /// Creates the archive, or appends to it if it already exists.
+    pub fn create(path: &Path) -> anyhow::Result<Self> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Opening gateway archive {}", path.display()))?;
+
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+
+    async fn record(
+        &self,
+        key: &str,
+        response: reqwest::Response,
+    ) -> Result<reqwest::Response, SequencerError> {
+        let status = response.status();
+        let body = response.bytes().await?;
+
+        let response = Response { status, body };
+
+        if matches!(
+            status,
+            StatusCode::OK | StatusCode::BAD_REQUEST | StatusCode::INTERNAL_SERVER_ERROR
+        ) {
+            if let Err(error) = self.append(key, &response) {
+                tracing::warn!(%error, request=%key, "Failed to record gateway response");
+            }
+        }
+
+        Ok(response.into())
+    }
+
+    fn append(&self, key: &str, response: &Response) -> anyhow::Result<()> {
+        let entry = Entry {
+            request: key.to_owned(),
+            status: response.status.as_u16(),
+            body: base64::encode(&response.body),
+        };
+        let mut json = serde_json::to_vec(&entry).context("Serializing entry")?;
+        json.push(b'\n');
+
+        // Each entry is a separate frame, so that an interrupted recording
+        // remains readable.
+        let frame = zstd::encode_all(json.as_slice(), COMPRESSION_LEVEL).context("Compressing")?;
+
+        let mut file = self.file.lock().unwrap();
+        file.write_all(&frame).context("Writing entry")?;
+        file.flush().context("Flushing archive")
+    }
+}
+
+/// Serves the responses from an archive, in the order they were recorded.
+///
+/// Delays, errors and reorgs can be injected to drive sync through specific
+/// scenarios.
+#[derive(Debug, Default)]
+pub struct Replay {
+    responses: Mutex<HashMap<String, VecDeque<Response>>>,
+    errors: Mutex<HashMap<String, VecDeque<StatusCode>>>,
+    delay: Duration,
+}
+
+impl Replay {
+    /// Loads all responses of an archive created by
+    /// [Client::with_recording](crate::Client::with_recording).
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let file = std::fs::File::open(path)
+            .with_context(|| format!("Opening gateway archive {}", path.display()))?;
+        let decoder = zstd::Decoder::new(file).context("Creating decoder")?;
+
+        let mut responses: HashMap<_, VecDeque<_>> = HashMap::new();
+        for line in BufReader::new(decoder).lines() {
+            let line = line.context("Reading archive")?;
+            let entry: Entry = serde_json::from_str(&line).context("Parsing archive entry")?;
+            let (request, response) = entry.into_response()?;
+            responses.entry(request).or_default().push_back(response);
+        }
+
+        Ok(Self {
+            responses: Mutex::new(responses),
+            ..Default::default()
+        })
+    }
+
+    /// Delays each response by the given duration.
+    pub fn with_delay(self, delay: Duration) -> Self {
+        Self { delay, ..self }
+    }
+
+    /// Fails the next request for the given method, e.g. `get_block`, with the
+    /// given status code. Injected errors for the same method are returned in
+    /// order, before any recorded response.
+    pub fn inject_error(&self, method: &str, status: StatusCode) {
+        self.errors
+            .lock()
+            .unwrap()
+            .entry(method.to_owned())
+            .or_default()
+            .push_back(status);
+    }
+
+    /// Switches to the responses of another archive, e.g. one recorded on a
+    /// fork of the chain. Requests which are not part of the fork continue to
+    /// be served from this archive.
+    pub fn reorg(&self, fork: Replay) {
+        let fork = fork.responses.into_inner().unwrap();
+        self.responses.lock().unwrap().extend(fork);
+    }
+
+    async fn respond(&self, key: &str) -> Response {
+        if !self.delay.is_zero() {
+            tokio::time::sleep(self.delay).await;
+        }
+
+        let method = key.split_once('?').map_or(key, |(method, _)| method);
+        let error = self
+            .errors
+            .lock()
+            .unwrap()
+            .get_mut(method)
+            .and_then(VecDeque::pop_front);
+        if let Some(status) = error {
+            return Response {
+                status,
+                body: Default::default(),
+            };
+        }
+
+        let mut responses = self.responses.lock().unwrap();
+        match responses.get_mut(key) {
+            Some(queue) if queue.len() > 1 => queue.pop_front().expect("Queue is not empty"),
+            Some(queue) if !queue.is_empty() => queue[0].clone(),
+            _ => {
+                tracing::warn!(request=%key, "Request not found in gateway archive");
+                Response {
+                    status: StatusCode::NOT_FOUND,
+                    body: Default::default(),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gateway_test_utils::GATEWAY_TIMEOUT;
+    use httpmock::prelude::*;
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::{BlockId, BlockNumber};
+    use serde_json::json;
+
+    use super::*;
+    use crate::{Client, GatewayApi};
+
+    /// Records the `latest` block headers served by a gateway.
+    async fn record(path: &Path, headers: &[(u64, &str)]) {
+        let server = MockServer::start_async().await;
+        let client = Client::with_base_url(server.base_url().parse().unwrap(), GATEWAY_TIMEOUT)
+            .unwrap()
+            .with_recording(path)
+            .unwrap();
+
+        for (number, hash) in headers {
+            let mut mock = server.mock(|when, then| {
+                when.path("/feeder_gateway/get_block");
+                then.status(200)
+                    .json_body(json!({"block_number": number, "block_hash": hash}));
+            });
+            client.block_header(BlockId::Latest).await.unwrap();
+            mock.delete_async().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn replays_in_recorded_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("archive");
+        record(&path, &[(1, "0x1"), (2, "0x2")]).await;
+
+        let client = Client::replay(Arc::new(Replay::load(&path).unwrap()));
+
+        let expected_1 = (BlockNumber::new_or_panic(1), block_hash!("0x1"));
+        let expected_2 = (BlockNumber::new_or_panic(2), block_hash!("0x2"));
+        assert_eq!(client.head().await.unwrap(), expected_1);
+        assert_eq!(client.head().await.unwrap(), expected_2);
+        // The last response is repeated.
+        assert_eq!(client.head().await.unwrap(), expected_2);
+    }
+
+    #[tokio::test]
+    async fn injected_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("archive");
+        record(&path, &[(1, "0x1")]).await;
+
+        let replay = Arc::new(Replay::load(&path).unwrap());
+        let client = Client::replay(replay.clone()).disable_retry_for_tests();
+
+        replay.inject_error("get_block", StatusCode::SERVICE_UNAVAILABLE);
+        client.head().await.unwrap_err();
+        client.head().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn reorg() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("archive");
+        let fork_path = dir.path().join("fork");
+        record(&path, &[(1, "0x1")]).await;
+        record(&fork_path, &[(1, "0xf1")]).await;
+
+        let replay = Arc::new(Replay::load(&path).unwrap());
+        let client = Client::replay(replay.clone());
+
+        assert_eq!(client.head().await.unwrap().1, block_hash!("0x1"));
+        replay.reorg(Replay::load(&fork_path).unwrap());
+        assert_eq!(client.head().await.unwrap().1, block_hash!("0xf1"));
+    }
+
+    #[tokio::test]
+    async fn replays_binary_and_post_responses() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("archive");
+        let binary = vec![0xff, 0x00, 0x80];
+
+        let server = MockServer::start_async().await;
+        server.mock(|when, then| {
+            when.path("/feeder_gateway/get_class_by_hash");
+            then.status(200).body(binary.clone());
+        });
+        server.mock(|when, then| {
+            when.method(POST).path("/gateway/add_transaction");
+            then.status(200)
+                .json_body(json!({"transaction_hash": "0x1"}));
+        });
+        let client = Client::with_base_url(server.base_url().parse().unwrap(), GATEWAY_TIMEOUT)
+            .unwrap()
+            .with_recording(&path)
+            .unwrap();
+
+        let get = |client: Client| async move {
+            client
+                .feeder_gateway_request()
+                .get_class_by_hash()
+                .with_class_hash(class_hash!("0x1"))
+                .with_retry(false)
+                .get_as_bytes()
+                .await
+                .unwrap()
+        };
+        let post = |client: Client| async move {
+            client
+                .gateway_request()
+                .add_transaction()
+                .with_retry(false)
+                .post_with_json::<serde_json::Value, _>(&json!({}), None)
+                .await
+                .unwrap()
+        };
+        get(client.clone()).await;
+        post(client).await;
+
+        let client = Client::replay(Arc::new(Replay::load(&path).unwrap()));
+        assert_eq!(get(client.clone()).await, binary);
+        assert_eq!(post(client).await, json!({"transaction_hash": "0x1"}));
+    }
+
+    #[tokio::test]
+    async fn unrecorded_request() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("archive");
+        record(&path, &[(1, "0x1")]).await;
+
+        let client =
+            Client::replay(Arc::new(Replay::load(&path).unwrap())).disable_retry_for_tests();
+
+        client
+            .block_header(BlockId::Number(BlockNumber::GENESIS))
+            .await
+            .unwrap_err();
+    }
+}
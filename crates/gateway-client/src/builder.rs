@@ -12,9 +12,14 @@
 //!   3. [Params](stage::Params) where you select the retry behavior.
 //!   4. [Final](stage::Final) where you select the REST operation type, which
 //!      is then executed.
+//!
+//! `GET` requests fail over to the other [Endpoints] on errors caused by the
+//! endpoint itself, see [is_failover_error].
 use pathfinder_common::{BlockId, ClassHash, TransactionHash};
 use starknet_gateway_types::error::SequencerError;
 
+use crate::archive;
+use crate::endpoints::{is_failover_error, Endpoints};
 use crate::metrics::{with_metrics, BlockTag, RequestMetadata};
 
 const X_THROTTLING_BYPASS: &str = "X-Throttling-Bypass";
@@ -22,9 +27,13 @@ const X_THROTTLING_BYPASS: &str = "X-Throttling-Bypass";
 /// A Sequencer Request builder.
 pub struct Request<'a, S: RequestState> {
     state: S,
+    /// The request url for the [primary](Endpoints::primary) endpoint.
     url: reqwest::Url,
+    endpoints: &'a Endpoints,
     api_key: Option<String>,
     client: &'a reqwest::Client,
+    /// Records or replays `GET` requests.
+    archive: Option<&'a archive::Mode>,
 }
 
 pub mod stage {
@@ -60,6 +69,7 @@ pub mod stage {
     /// - [get](super::Request::get)
     /// - [get_as_bytes](super::Request::get_as_bytes)
     /// - [post_with_json](super::Request::post_with_json)
+    /// - [broadcast_with_json](super::Request::broadcast_with_json)
     pub struct Final {
         pub meta: RequestMetadata,
         pub retry: bool,
@@ -73,15 +83,18 @@ pub mod stage {
 
 impl<'a> Request<'a, stage::Init> {
     /// Initialize a [Request] builder.
-    pub fn builder(
+    pub(crate) fn builder(
         client: &'a reqwest::Client,
-        url: reqwest::Url,
+        endpoints: &'a Endpoints,
         api_key: Option<String>,
+        archive: Option<&'a archive::Mode>,
     ) -> Request<'a, stage::Method> {
         Request {
-            url,
+            url: endpoints.primary().clone(),
+            endpoints,
             client,
             api_key,
+            archive,
             state: stage::Method,
         }
     }
@@ -131,7 +144,10 @@ mod request_macros {
         };
     }
 
-    pub(super) use {method, method_defs, method_names, methods};
+    pub(super) use method;
+    pub(super) use method_defs;
+    pub(super) use method_names;
+    pub(super) use methods;
 }
 
 impl<'a> Request<'a, stage::Method> {
@@ -158,8 +174,10 @@ impl<'a> Request<'a, stage::Method> {
 
         Request {
             url: self.url,
+            endpoints: self.endpoints,
             client: self.client,
             api_key: self.api_key,
+            archive: self.archive,
             state: stage::Params {
                 meta: RequestMetadata::new(method),
             },
@@ -216,8 +234,10 @@ impl<'a> Request<'a, stage::Params> {
     pub fn with_retry(self, retry: bool) -> Request<'a, stage::Final> {
         Request {
             url: self.url,
+            endpoints: self.endpoints,
             client: self.client,
             api_key: self.api_key,
+            archive: self.archive,
             state: stage::Final {
                 meta: self.state.meta,
                 retry,
@@ -227,6 +247,55 @@ impl<'a> Request<'a, stage::Params> {
 }
 
 impl<'a> Request<'a, stage::Final> {
+    /// Returns the request url for the given endpoint.
+    fn url_for(&self, endpoint: &reqwest::Url) -> reqwest::Url {
+        let mut url = endpoint.clone();
+        url.path_segments_mut()
+            .expect("Base URL is valid")
+            .push(self.state.meta.method);
+        url.set_query(self.url.query());
+        url
+    }
+
+    /// Identifies the request in an archive, independent of the endpoint.
+    fn archive_key(&self) -> String {
+        match self.url.query() {
+            Some(query) => format!("{}?{query}", self.state.meta.method),
+            None => self.state.meta.method.to_owned(),
+        }
+    }
+
+    /// Sends the request to the [candidate](Endpoints::candidates) endpoints
+    /// in turn, until one of them does not fail with a [failover
+    /// error](is_failover_error).
+    async fn with_failover<T, F, Fut>(&self, mut send: F) -> Result<T, SequencerError>
+    where
+        F: FnMut(reqwest::Url) -> Fut,
+        Fut: futures::Future<Output = Result<T, SequencerError>>,
+    {
+        let mut candidates = self.endpoints.candidates().into_iter().peekable();
+        loop {
+            let index = candidates.next().expect("There is at least one endpoint");
+            let url = self.url_for(self.endpoints.url(index));
+
+            let started = std::time::Instant::now();
+            match send(url).await {
+                Err(e) if is_failover_error(&e) => {
+                    self.endpoints.report_failure(index);
+
+                    if candidates.peek().is_none() {
+                        return Err(e);
+                    }
+                    tracing::debug!(reason=%e, endpoint=%self.endpoints.url(index), "Request failed, trying next endpoint");
+                }
+                result => {
+                    self.endpoints.report_success(index, started.elapsed());
+                    return result;
+                }
+            }
+        }
+    }
+
     /// Sends the Sequencer request as a REST `GET` operation and parses the
     /// response into `T`.
     pub async fn get<T>(self) -> Result<T, SequencerError>
@@ -238,6 +307,7 @@ impl<'a> Request<'a, stage::Final> {
             api_key: Option<String>,
             client: &reqwest::Client,
             meta: RequestMetadata,
+            archive: Option<(&archive::Mode, &str)>,
         ) -> Result<T, SequencerError> {
             with_metrics(meta, async move {
                 tracing::trace!(%url, "Fetching data from feeder gateway");
@@ -246,25 +316,29 @@ impl<'a> Request<'a, stage::Final> {
                     Some(api_key) => request.header(X_THROTTLING_BYPASS, api_key),
                     None => request,
                 };
-                let response = request.send().await?;
+                let response = dispatch(request, archive).await?;
                 parse::<T>(response).await
             })
             .await
         }
 
-        match self.state.retry {
-            false => send_request(self.url, self.api_key, self.client, self.state.meta).await,
-            true => {
-                retry0(
-                    || async {
-                        let url = self.url.clone();
-                        let api_key = self.api_key.clone();
-                        send_request(url, api_key, self.client, self.state.meta).await
-                    },
-                    retry_condition,
+        let key = self.archive_key();
+        let archive = self.archive.map(|archive| (archive, key.as_str()));
+        let send = || {
+            self.with_failover(|url| {
+                send_request(
+                    url,
+                    self.api_key.clone(),
+                    self.client,
+                    self.state.meta,
+                    archive,
                 )
-                .await
-            }
+            })
+        };
+
+        match self.state.retry {
+            false => send().await,
+            true => retry0(send, retry_condition).await,
         }
     }
 
@@ -276,6 +350,7 @@ impl<'a> Request<'a, stage::Final> {
             api_key: Option<String>,
             client: &reqwest::Client,
             meta: RequestMetadata,
+            archive: Option<(&archive::Mode, &str)>,
         ) -> Result<bytes::Bytes, SequencerError> {
             with_metrics(meta, async {
                 tracing::trace!(%url, "Fetching binary data from feeder gateway");
@@ -284,7 +359,7 @@ impl<'a> Request<'a, stage::Final> {
                     Some(api_key) => request.header(X_THROTTLING_BYPASS, api_key),
                     None => request,
                 };
-                let response = request.send().await?;
+                let response = dispatch(request, archive).await?;
                 let response = parse_raw(response).await?;
                 let bytes = response.bytes().await?;
                 Ok(bytes)
@@ -292,24 +367,29 @@ impl<'a> Request<'a, stage::Final> {
             .await
         }
 
-        match self.state.retry {
-            false => get_as_bytes_inner(self.url, self.api_key, self.client, self.state.meta).await,
-            true => {
-                retry0(
-                    || async {
-                        let url = self.url.clone();
-                        let api_key = self.api_key.clone();
-                        get_as_bytes_inner(url, api_key, self.client, self.state.meta).await
-                    },
-                    retry_condition,
+        let key = self.archive_key();
+        let archive = self.archive.map(|archive| (archive, key.as_str()));
+        let send = || {
+            self.with_failover(|url| {
+                get_as_bytes_inner(
+                    url,
+                    self.api_key.clone(),
+                    self.client,
+                    self.state.meta,
+                    archive,
                 )
-                .await
-            }
+            })
+        };
+
+        match self.state.retry {
+            false => send().await,
+            true => retry0(send, retry_condition).await,
         }
     }
 
-    /// Sends the Sequencer request as a REST `POST` operation, in addition to
-    /// the specified JSON body. The response is parsed as type `T`.
+    /// Sends the Sequencer request as a REST `POST` operation to the
+    /// [primary](Endpoints::primary) endpoint, in addition to the specified
+    /// JSON body. The response is parsed as type `T`.
     ///
     /// Can specify an optional timeout which will override the client's
     /// timeout.
@@ -329,6 +409,7 @@ impl<'a> Request<'a, stage::Final> {
             meta: RequestMetadata,
             json: &J,
             timeout: Option<std::time::Duration>,
+            archive: Option<(&archive::Mode, &str)>,
         ) -> Result<T, SequencerError>
         where
             T: serde::de::DeserializeOwned,
@@ -344,12 +425,14 @@ impl<'a> Request<'a, stage::Final> {
                     Some(timeout) => request.timeout(timeout),
                     None => request,
                 };
-                let response = request.json(json).send().await?;
+                let response = dispatch(request.json(json), archive).await?;
                 parse::<T>(response).await
             })
             .await
         }
 
+        let key = self.archive_key();
+        let archive = self.archive.map(|archive| (archive, key.as_str()));
         match self.state.retry {
             false => {
                 post_with_json_inner(
@@ -359,6 +442,7 @@ impl<'a> Request<'a, stage::Final> {
                     self.state.meta,
                     json,
                     timeout,
+                    archive,
                 )
                 .await
             }
@@ -375,6 +459,7 @@ impl<'a> Request<'a, stage::Final> {
                             self.state.meta,
                             json,
                             timeout,
+                            archive,
                         )
                         .await
                     },
@@ -384,6 +469,85 @@ impl<'a> Request<'a, stage::Final> {
             }
         }
     }
+
+    /// Sends the Sequencer request as a REST `POST` operation with the
+    /// specified JSON body to all endpoints concurrently. The first successful
+    /// response is parsed as type `T`, while the requests to the remaining
+    /// endpoints are left to complete in the background. If all requests fail,
+    /// the first error is returned.
+    ///
+    /// The request is never retried. When recording or replaying an
+    /// [archive](archive::Mode), it is only sent to the
+    /// [primary](Endpoints::primary) endpoint, so that a single response is
+    /// archived.
+    ///
+    /// Can specify an optional timeout which will override the client's
+    /// timeout.
+    pub async fn broadcast_with_json<T, J>(
+        self,
+        json: &J,
+        timeout: Option<std::time::Duration>,
+    ) -> Result<T, SequencerError>
+    where
+        T: serde::de::DeserializeOwned + Send + 'static,
+        J: serde::Serialize + ?Sized,
+    {
+        if self.archive.is_some() {
+            return self.post_with_json(json, timeout).await;
+        }
+
+        let mut requests = tokio::task::JoinSet::new();
+
+        for endpoint in self.endpoints.urls() {
+            let url = self.url_for(endpoint);
+            tracing::trace!(%url, "Broadcasting data to gateway");
+
+            let request = self.client.post(url);
+            let request = match &self.api_key {
+                Some(api_key) => request.header(X_THROTTLING_BYPASS, api_key),
+                None => request,
+            };
+            let request = match timeout {
+                Some(timeout) => request.timeout(timeout),
+                None => request,
+            };
+            let request = request.json(json).build()?;
+
+            let client = self.client.clone();
+            requests.spawn(with_metrics(self.state.meta, async move {
+                let response = client.execute(request).await?;
+                parse::<T>(response).await
+            }));
+        }
+
+        let mut first_error = None;
+        while let Some(result) = requests.join_next().await {
+            match result.expect("Broadcast request task panicked") {
+                Ok(response) => {
+                    requests.detach_all();
+                    return Ok(response);
+                }
+                Err(e) => {
+                    tracing::debug!(reason=%e, "Broadcast request failed");
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+
+        Err(first_error.expect("There is at least one endpoint"))
+    }
+}
+
+/// Sends the request, or lets the archive [record or replay](archive::Mode)
+/// it.
+async fn dispatch(
+    request: reqwest::RequestBuilder,
+    archive: Option<(&archive::Mode, &str)>,
+) -> Result<reqwest::Response, SequencerError> {
+    match archive {
+        Some((archive, key)) => archive.send(key, request).await,
+        None => Ok(request.send().await?),
+    }
 }
 
 async fn parse<T>(response: reqwest::Response) -> Result<T, SequencerError>
@@ -787,4 +951,140 @@ mod tests {
             Ok(())
         }
     }
+
+    mod failover {
+        use gateway_test_utils::GATEWAY_TIMEOUT;
+        use httpmock::prelude::*;
+        use serde_json::json;
+        use starknet_gateway_types::error::SequencerError;
+
+        use crate::{Client, WriteStrategy};
+
+        fn client(servers: &[&MockServer]) -> Client {
+            let urls = |path: &str| {
+                servers
+                    .iter()
+                    .map(|server| server.url(path).parse().unwrap())
+                    .collect()
+            };
+            Client::with_endpoints(urls("/gateway"), urls("/feeder_gateway"), GATEWAY_TIMEOUT)
+                .unwrap()
+        }
+
+        #[tokio::test]
+        async fn get_skips_unhealthy_endpoint() -> anyhow::Result<()> {
+            let bad = MockServer::start_async().await;
+            let good = MockServer::start_async().await;
+            let bad_mock = bad.mock(|when, then| {
+                when.any_request();
+                then.status(503);
+            });
+            let good_mock = good.mock(|when, then| {
+                when.any_request();
+                then.status(200).json_body(json!({}));
+            });
+            let client = client(&[&bad, &good]);
+
+            for _ in 0..2 {
+                let _: serde_json::Value = client
+                    .feeder_gateway_request()
+                    .get_block()
+                    .with_retry(false)
+                    .get()
+                    .await?;
+            }
+
+            // The bad endpoint is in cooldown after the first failure.
+            bad_mock.assert_hits(1);
+            good_mock.assert_hits(2);
+
+            Ok(())
+        }
+
+        #[tokio::test]
+        async fn starknet_errors_do_not_fail_over() {
+            let first = MockServer::start_async().await;
+            let second = MockServer::start_async().await;
+            first.mock(|when, then| {
+                when.any_request();
+                then.status(400)
+                    .json_body(json!({"code": "StarknetErrorCode.BLOCK_NOT_FOUND", "message": ""}));
+            });
+            let second_mock = second.mock(|when, then| {
+                when.any_request();
+                then.status(200).json_body(json!({}));
+            });
+            let client = client(&[&first, &second]);
+
+            let error = client
+                .feeder_gateway_request()
+                .get_block()
+                .with_retry(false)
+                .get::<serde_json::Value>()
+                .await
+                .unwrap_err();
+
+            assert!(matches!(error, SequencerError::StarknetError(_)));
+            second_mock.assert_hits(0);
+        }
+
+        #[tokio::test]
+        async fn primary_only_write() {
+            let primary = MockServer::start_async().await;
+            let secondary = MockServer::start_async().await;
+            primary.mock(|when, then| {
+                when.any_request();
+                then.status(503);
+            });
+            let secondary_mock = secondary.mock(|when, then| {
+                when.any_request();
+                then.status(200).json_body(json!({}));
+            });
+            let client = client(&[&primary, &secondary]);
+
+            client
+                .gateway_request()
+                .add_transaction()
+                .with_retry(false)
+                .post_with_json::<serde_json::Value, _>(&json!({}), None)
+                .await
+                .unwrap_err();
+
+            secondary_mock.assert_hits(0);
+        }
+
+        #[tokio::test]
+        async fn broadcast_write() -> anyhow::Result<()> {
+            let primary = MockServer::start_async().await;
+            let secondary = MockServer::start_async().await;
+            let primary_mock = primary.mock(|when, then| {
+                when.any_request();
+                then.status(503);
+            });
+            let secondary_mock = secondary.mock(|when, then| {
+                when.any_request();
+                then.status(200).json_body(json!({}));
+            });
+            let client =
+                client(&[&primary, &secondary]).with_write_strategy(WriteStrategy::Broadcast);
+
+            let _: serde_json::Value = client
+                .gateway_request()
+                .add_transaction()
+                .with_retry(false)
+                .broadcast_with_json(&json!({}), None)
+                .await?;
+
+            secondary_mock.assert_hits(1);
+            // The failed request may still be in flight.
+            tokio::time::timeout(std::time::Duration::from_secs(5), async {
+                while primary_mock.hits_async().await == 0 {
+                    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                }
+            })
+            .await?;
+
+            Ok(())
+        }
+    }
 }
@@ -0,0 +1,227 @@
+//! Health tracking and selection for a set of redundant gateway endpoints.
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rand::distributions::{Distribution, WeightedIndex};
+use reqwest::Url;
+use starknet_gateway_types::error::SequencerError;
+
+/// Weight of the latest sample in an endpoint's latency moving average.
+const LATENCY_SMOOTHING: f64 = 0.2;
+/// Lower bound of latency samples, so that weights remain finite.
+const MIN_LATENCY: Duration = Duration::from_millis(1);
+/// An endpoint is skipped for this long after failing, doubling with each
+/// consecutive failure.
+const BASE_COOLDOWN: Duration = Duration::from_secs(5);
+const MAX_COOLDOWN: Duration = Duration::from_secs(300);
+
+/// A non-empty set of interchangeable endpoints, e.g. several proxies in front
+/// of the same feeder gateway.
+///
+/// Health is shared between clones.
+#[derive(Debug, Clone)]
+pub(crate) struct Endpoints(Arc<[Endpoint]>);
+
+#[derive(Debug)]
+struct Endpoint {
+    url: Url,
+    health: Mutex<Health>,
+}
+
+#[derive(Debug, Default)]
+struct Health {
+    consecutive_failures: u32,
+    unhealthy_until: Option<Instant>,
+    /// Exponential moving average, [None] until the first response.
+    latency: Option<Duration>,
+}
+
+impl Endpoints {
+    pub fn new(urls: Vec<Url>) -> anyhow::Result<Self> {
+        anyhow::ensure!(!urls.is_empty(), "At least one endpoint is required");
+
+        Ok(Self(
+            urls.into_iter()
+                .map(|url| Endpoint {
+                    url,
+                    health: Default::default(),
+                })
+                .collect(),
+        ))
+    }
+
+    /// The first configured endpoint.
+    pub fn primary(&self) -> &Url {
+        &self.0[0].url
+    }
+
+    pub fn urls(&self) -> impl Iterator<Item = &Url> {
+        self.0.iter().map(|endpoint| &endpoint.url)
+    }
+
+    pub fn url(&self, index: usize) -> &Url {
+        &self.0[index].url
+    }
+
+    /// Returns the endpoint indices in the order in which they should be tried.
+    ///
+    /// Endpoints which have not responded yet come first so that each of them
+    /// gets a latency sample. Otherwise the first healthy endpoint is picked
+    /// at random, weighted by the inverse of its latency, and the other
+    /// healthy endpoints follow from fastest to slowest. Endpoints that are
+    /// cooling down after a failure come last, so that a request is still
+    /// attempted when all of them are unhealthy.
+    pub fn candidates(&self) -> Vec<usize> {
+        let now = Instant::now();
+
+        let mut unmeasured = Vec::new();
+        let mut healthy = Vec::new();
+        let mut unhealthy = Vec::new();
+
+        for (index, endpoint) in self.0.iter().enumerate() {
+            let health = endpoint.health.lock().unwrap();
+            match (health.unhealthy_until, health.latency) {
+                (Some(until), _) if until > now => unhealthy.push((until, index)),
+                (_, None) => unmeasured.push(index),
+                (_, Some(latency)) => healthy.push((latency, index)),
+            }
+        }
+
+        healthy.sort();
+        unhealthy.sort();
+
+        if healthy.len() > 1 {
+            let weights = healthy
+                .iter()
+                .map(|(latency, _)| 1.0 / latency.max(&MIN_LATENCY).as_secs_f64());
+            let first = WeightedIndex::new(weights)
+                .expect("Weights are positive and finite")
+                .sample(&mut rand::thread_rng());
+            let first = healthy.remove(first);
+            healthy.insert(0, first);
+        }
+
+        unmeasured
+            .into_iter()
+            .chain(healthy.into_iter().map(|(_, index)| index))
+            .chain(unhealthy.into_iter().map(|(_, index)| index))
+            .collect()
+    }
+
+    /// Records a response from the endpoint, even if it was an error reported
+    /// by the sequencer.
+    pub fn report_success(&self, index: usize, latency: Duration) {
+        let mut health = self.0[index].health.lock().unwrap();
+
+        health.consecutive_failures = 0;
+        health.unhealthy_until = None;
+        health.latency = Some(match health.latency {
+            Some(average) => {
+                average.mul_f64(1.0 - LATENCY_SMOOTHING) + latency.mul_f64(LATENCY_SMOOTHING)
+            }
+            None => latency,
+        });
+    }
+
+    /// Records a [failover error](is_failover_error), and puts the endpoint
+    /// into cooldown.
+    pub fn report_failure(&self, index: usize) {
+        let endpoint = &self.0[index];
+        let mut health = endpoint.health.lock().unwrap();
+
+        health.consecutive_failures = health.consecutive_failures.saturating_add(1);
+        let cooldown = BASE_COOLDOWN
+            .saturating_mul(1 << (health.consecutive_failures - 1).min(16))
+            .min(MAX_COOLDOWN);
+        health.unhealthy_until = Some(Instant::now() + cooldown);
+
+        tracing::debug!(url=%endpoint.url, failures=%health.consecutive_failures, ?cooldown, "Gateway endpoint marked as unhealthy");
+    }
+}
+
+/// Errors indicating that the endpoint itself is at fault, rather than the
+/// request, and that another endpoint should be tried: server errors, rate
+/// limiting, timeouts and connection failures.
+pub(crate) fn is_failover_error(e: &SequencerError) -> bool {
+    match e {
+        SequencerError::ReqwestError(e) => {
+            e.is_timeout()
+                || e.is_connect()
+                || e.status().is_some_and(|status| {
+                    status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+                })
+        }
+        SequencerError::InvalidStarknetErrorVariant => true,
+        SequencerError::StarknetError(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endpoints(count: usize) -> Endpoints {
+        Endpoints::new(
+            (0..count)
+                .map(|i| Url::parse(&format!("http://endpoint-{i}/")).unwrap())
+                .collect(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn empty_is_rejected() {
+        Endpoints::new(vec![]).unwrap_err();
+    }
+
+    #[test]
+    fn unmeasured_endpoints_are_tried_first() {
+        let endpoints = endpoints(3);
+        endpoints.report_success(0, Duration::from_millis(10));
+
+        assert_eq!(endpoints.candidates(), vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn unhealthy_endpoints_are_tried_last() {
+        let endpoints = endpoints(3);
+        endpoints.report_failure(0);
+        endpoints.report_failure(1);
+        endpoints.report_failure(1);
+
+        // Endpoint 1 has the longer cooldown.
+        assert_eq!(endpoints.candidates(), vec![2, 0, 1]);
+    }
+
+    #[test]
+    fn success_restores_health() {
+        let endpoints = endpoints(2);
+        endpoints.report_failure(0);
+        endpoints.report_failure(0);
+        endpoints.report_success(0, Duration::from_millis(10));
+
+        let health = endpoints.0[0].health.lock().unwrap();
+        assert_eq!(health.consecutive_failures, 0);
+        assert_eq!(health.unhealthy_until, None);
+    }
+
+    #[test]
+    fn faster_endpoint_is_preferred() {
+        let endpoints = endpoints(2);
+        endpoints.report_success(0, Duration::from_secs(10));
+        endpoints.report_success(1, Duration::from_millis(1));
+
+        let picked_fast = (0..100).filter(|_| endpoints.candidates()[0] == 1).count();
+        assert!(picked_fast > 90, "{picked_fast}");
+    }
+
+    #[test]
+    fn latency_is_averaged() {
+        let endpoints = endpoints(1);
+        endpoints.report_success(0, Duration::from_millis(100));
+        endpoints.report_success(0, Duration::from_millis(200));
+
+        let latency = endpoints.0[0].health.lock().unwrap().latency.unwrap();
+        assert!((latency.as_secs_f64() - 0.12).abs() < 1e-6, "{latency:?}");
+    }
+}
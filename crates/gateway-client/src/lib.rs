@@ -3,6 +3,7 @@ use std::fmt::Debug;
 use std::result::Result;
 use std::time::Duration;
 
+use anyhow::Context;
 use pathfinder_common::{
     BlockHash,
     BlockId,
@@ -18,9 +19,14 @@ use starknet_gateway_types::reply::PendingBlock;
 use starknet_gateway_types::trace::{BlockTrace, TransactionTrace};
 use starknet_gateway_types::{reply, request};
 
+mod archive;
 mod builder;
+mod endpoints;
 mod metrics;
 
+pub use archive::Replay;
+use endpoints::Endpoints;
+
 #[allow(unused_variables)]
 #[mockall::automock]
 #[async_trait::async_trait]
@@ -214,14 +220,19 @@ impl<T: GatewayApi + Sync + Send> GatewayApi for std::sync::Arc<T> {
 /// `backoff [secs] = min((2 ^ N) * 15, 600) [secs]`
 ///
 /// where `N` is the consecutive retry iteration number `{1, 2, ...}`.
+///
+/// Multiple feeder gateway URLs can be configured, in which case read
+/// requests fail over between them. Writes to multiple gateway URLs follow
+/// the configured [WriteStrategy].
 #[derive(Debug, Clone)]
 pub struct Client {
     /// This client is internally refcounted
     inner: reqwest::Client,
-    /// Starknet gateway URL.
-    gateway: Url,
-    /// Starknet feeder gateway URL.
-    feeder_gateway: Url,
+    /// Starknet gateway URLs.
+    gateway: Endpoints,
+    /// Starknet feeder gateway URLs.
+    feeder_gateway: Endpoints,
+    write_strategy: WriteStrategy,
     /// Whether __read only__ requests should be retried, defaults to __true__
     /// for production.
     /// Use [disable_retry_for_tests](Client::disable_retry_for_tests) to
@@ -230,6 +241,8 @@ pub struct Client {
     /// Api key added to each request as a value for 'X-Throttling-Bypass'
     /// header.
     api_key: Option<String>,
+    /// Records or replays feeder gateway responses.
+    archive: Option<archive::Mode>,
 }
 
 impl Client {
@@ -269,6 +282,21 @@ impl Client {
 
     /// Create a Sequencer client for the given [Url]s.
     pub fn with_urls(gateway: Url, feeder_gateway: Url, timeout: Duration) -> anyhow::Result<Self> {
+        Self::with_endpoints(vec![gateway], vec![feeder_gateway], timeout)
+    }
+
+    /// Create a Sequencer client for the given lists of interchangeable
+    /// [Url]s, e.g. several proxies in front of the same gateway. The first
+    /// URL of each list is the primary one.
+    ///
+    /// Read requests are sent to the healthy feeder gateway with the lowest
+    /// latency, and fail over to the next one on server errors, rate limiting
+    /// and timeouts.
+    pub fn with_endpoints(
+        gateway: Vec<Url>,
+        feeder_gateway: Vec<Url>,
+        timeout: Duration,
+    ) -> anyhow::Result<Self> {
         metrics::register();
 
         Ok(Self {
@@ -276,13 +304,43 @@ impl Client {
                 .timeout(timeout)
                 .user_agent(pathfinder_common::consts::USER_AGENT)
                 .build()?,
-            gateway,
-            feeder_gateway,
+            gateway: Endpoints::new(gateway).context("Gateway URLs")?,
+            feeder_gateway: Endpoints::new(feeder_gateway).context("Feeder gateway URLs")?,
+            write_strategy: WriteStrategy::default(),
             retry: true,
             api_key: None,
+            archive: None,
         })
     }
 
+    /// Creates a [Client] which answers feeder gateway requests from the
+    /// given [Replay] instead of the network.
+    ///
+    /// Transactions cannot be submitted.
+    pub fn replay(replay: std::sync::Arc<Replay>) -> Self {
+        let mut client = Self::with_base_url(
+            Url::parse("http://replay.invalid/").unwrap(),
+            Duration::from_secs(5),
+        )
+        .unwrap();
+        client.archive = Some(archive::Mode::Replay(replay));
+        client
+    }
+
+    /// Records all feeder gateway responses to the archive at `path`, for
+    /// later use with [Replay].
+    pub fn with_recording(mut self, path: &std::path::Path) -> anyhow::Result<Self> {
+        let recorder = archive::Recorder::create(path)?;
+        self.archive = Some(archive::Mode::Record(std::sync::Arc::new(recorder)));
+        Ok(self)
+    }
+
+    /// Sets how transactions are submitted if there are multiple gateway URLs.
+    pub fn with_write_strategy(mut self, write_strategy: WriteStrategy) -> Self {
+        self.write_strategy = write_strategy;
+        self
+    }
+
     /// Sets the api key to be used for each request as a value for
     /// 'X-Throttling-Bypass' header.
     pub fn with_api_key(mut self, api_key: Option<String>) -> Self {
@@ -300,16 +358,62 @@ impl Client {
     }
 
     fn gateway_request(&self) -> builder::Request<'_, builder::stage::Method> {
-        builder::Request::builder(&self.inner, self.gateway.clone(), self.api_key.clone())
+        builder::Request::builder(&self.inner, &self.gateway, self.api_key.clone(), None)
     }
 
     fn feeder_gateway_request(&self) -> builder::Request<'_, builder::stage::Method> {
         builder::Request::builder(
             &self.inner,
-            self.feeder_gateway.clone(),
+            &self.feeder_gateway,
             self.api_key.clone(),
+            self.archive.as_ref(),
         )
     }
+
+    /// Submits a transaction according to the [WriteStrategy].
+    ///
+    /// Note that we don't do retries here.
+    /// This method is used to proxy an add transaction operation from the
+    /// JSON-RPC API to the sequencer. Retries should be implemented in the
+    /// JSON-RPC client instead.
+    async fn add_transaction<T>(
+        &self,
+        transaction: &request::add_transaction::AddTransaction,
+        token: Option<&str>,
+    ) -> Result<T, SequencerError>
+    where
+        T: serde::de::DeserializeOwned + Send + 'static,
+    {
+        let request = self
+            .gateway_request()
+            .add_transaction()
+            // mainnet requires a token (but testnet does not so its optional).
+            .with_optional_token(token)
+            .with_retry(false);
+
+        match self.write_strategy {
+            WriteStrategy::PrimaryOnly => {
+                request
+                    .post_with_json(transaction, Some(Duration::MAX))
+                    .await
+            }
+            WriteStrategy::Broadcast => {
+                request
+                    .broadcast_with_json(transaction, Some(Duration::MAX))
+                    .await
+            }
+        }
+    }
+}
+
+/// How transactions are submitted if multiple gateway URLs are configured.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum WriteStrategy {
+    /// Only submit to the first gateway URL.
+    #[default]
+    PrimaryOnly,
+    /// Submit to all gateway URLs, and use the first successful response.
+    Broadcast,
 }
 
 #[async_trait::async_trait]
@@ -446,18 +550,11 @@ impl GatewayApi for Client {
         &self,
         invoke: request::add_transaction::InvokeFunction,
     ) -> Result<reply::add_transaction::InvokeResponse, SequencerError> {
-        // Note that we don't do retries here.
-        // This method is used to proxy an add transaction operation from the JSON-RPC
-        // API to the sequencer. Retries should be implemented in the JSON-RPC
-        // client instead.
-        self.gateway_request()
-            .add_transaction()
-            .with_retry(false)
-            .post_with_json(
-                &request::add_transaction::AddTransaction::Invoke(invoke),
-                Some(Duration::MAX),
-            )
-            .await
+        self.add_transaction(
+            &request::add_transaction::AddTransaction::Invoke(invoke),
+            None,
+        )
+        .await
     }
 
     /// Adds a transaction declaring a class.
@@ -467,20 +564,11 @@ impl GatewayApi for Client {
         declare: request::add_transaction::Declare,
         token: Option<String>,
     ) -> Result<reply::add_transaction::DeclareResponse, SequencerError> {
-        // Note that we don't do retries here.
-        // This method is used to proxy an add transaction operation from the JSON-RPC
-        // API to the sequencer. Retries should be implemented in the JSON-RPC
-        // client instead.
-        self.gateway_request()
-            .add_transaction()
-            // mainnet requires a token (but testnet does not so its optional).
-            .with_optional_token(token.as_deref())
-            .with_retry(false)
-            .post_with_json(
-                &request::add_transaction::AddTransaction::Declare(declare),
-                Some(Duration::MAX),
-            )
-            .await
+        self.add_transaction(
+            &request::add_transaction::AddTransaction::Declare(declare),
+            token.as_deref(),
+        )
+        .await
     }
 
     #[tracing::instrument(skip(self))]
@@ -488,18 +576,11 @@ impl GatewayApi for Client {
         &self,
         deploy: request::add_transaction::DeployAccount,
     ) -> Result<reply::add_transaction::DeployAccountResponse, SequencerError> {
-        // Note that we don't do retries here.
-        // This method is used to proxy an add transaction operation from the JSON-RPC
-        // API to the sequencer. Retries should be implemented in the JSON-RPC
-        // client instead.
-        self.gateway_request()
-            .add_transaction()
-            .with_retry(false)
-            .post_with_json(
-                &request::add_transaction::AddTransaction::DeployAccount(deploy),
-                Some(Duration::MAX),
-            )
-            .await
+        self.add_transaction(
+            &request::add_transaction::AddTransaction::DeployAccount(deploy),
+            None,
+        )
+        .await
     }
 
     #[tracing::instrument(skip(self))]
@@ -68,6 +68,8 @@ pub enum KnownStarknetErrorCode {
     OutOfRangeBlockHash,
     #[serde(rename = "StarknetErrorCode.OUT_OF_RANGE_TRANSACTION_HASH")]
     OutOfRangeTransactionHash,
+    #[serde(rename = "StarknetErrorCode.INVALID_TRANSACTION_HASH")]
+    InvalidTransactionHash,
     #[serde(rename = "StarkErrorCode.MALFORMED_REQUEST")]
     MalformedRequest,
     #[serde(rename = "StarknetErrorCode.UNSUPPORTED_SELECTOR_FOR_FEE")]
@@ -68,8 +68,7 @@ pub struct Block {
 }
 
 #[serde_as]
-#[derive(Clone, Default, Debug, Deserialize, PartialEq, Eq)]
-#[cfg_attr(test, derive(serde::Serialize))]
+#[derive(Clone, Default, Debug, Deserialize, PartialEq, Eq, serde::Serialize)]
 pub struct PendingBlock {
     pub l1_gas_price: GasPrices,
     pub l1_data_gas_price: GasPrices,
@@ -2060,6 +2059,96 @@ impl From<StateUpdate> for pathfinder_common::StateUpdate {
     }
 }
 
+impl From<pathfinder_common::StateUpdate> for StateUpdate {
+    fn from(state_update: pathfinder_common::StateUpdate) -> Self {
+        use pathfinder_common::state_update::ContractClassUpdate;
+
+        let mut state_diff = state_update::StateDiff {
+            old_declared_contracts: state_update.declared_cairo_classes,
+            ..Default::default()
+        };
+
+        // System contract updates are embedded in the storage diffs.
+        let system_storage = state_update
+            .system_contract_updates
+            .into_iter()
+            .map(|(address, update)| (address, update.storage));
+
+        for (address, update) in state_update.contract_updates {
+            match update.class {
+                Some(ContractClassUpdate::Deploy(class_hash)) => state_diff
+                    .deployed_contracts
+                    .push(state_update::DeployedContract {
+                        address,
+                        class_hash,
+                    }),
+                Some(ContractClassUpdate::Replace(class_hash)) => {
+                    state_diff
+                        .replaced_classes
+                        .push(state_update::ReplacedClass {
+                            address,
+                            class_hash,
+                        })
+                }
+                None => {}
+            }
+
+            if let Some(nonce) = update.nonce {
+                state_diff.nonces.insert(address, nonce);
+            }
+
+            if !update.storage.is_empty() {
+                state_diff
+                    .storage_diffs
+                    .insert(address, storage_diffs(update.storage));
+            }
+        }
+
+        for (address, storage) in system_storage {
+            state_diff
+                .storage_diffs
+                .insert(address, storage_diffs(storage));
+        }
+
+        state_diff.declared_classes = state_update
+            .declared_sierra_classes
+            .into_iter()
+            .map(
+                |(class_hash, compiled_class_hash)| state_update::DeclaredSierraClass {
+                    class_hash,
+                    compiled_class_hash,
+                },
+            )
+            .collect();
+
+        // Sort for a stable output.
+        state_diff.deployed_contracts.sort();
+        state_diff.replaced_classes.sort();
+        state_diff.declared_classes.sort();
+
+        Self {
+            block_hash: state_update.block_hash,
+            new_root: state_update.state_commitment,
+            old_root: state_update.parent_state_commitment,
+            state_diff,
+        }
+    }
+}
+
+fn storage_diffs(
+    storage: std::collections::HashMap<
+        pathfinder_common::StorageAddress,
+        pathfinder_common::StorageValue,
+    >,
+) -> Vec<state_update::StorageDiff> {
+    let mut diffs: Vec<_> = storage
+        .into_iter()
+        .map(|(key, value)| state_update::StorageDiff { key, value })
+        .collect();
+    diffs.sort();
+    diffs
+}
+
 /// Types used when deserializing state update related data.
 pub mod state_update {
     use std::collections::{HashMap, HashSet};
@@ -2125,7 +2214,7 @@ pub mod state_update {
 
 /// Used to deserialize replies to Starknet Ethereum contract requests.
 #[serde_as]
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct EthContractAddresses {
     #[serde(rename = "Starknet")]
     #[serde_as(as = "EthereumAddressAsHexStr")]
@@ -2354,6 +2443,57 @@ mod tests {
         assert_eq!(common, expected);
     }
 
+    #[test]
+    fn into_state_update_roundtrip() {
+        use pathfinder_common::macro_prelude::*;
+        use pathfinder_common::ContractAddress;
+
+        let expected = pathfinder_common::StateUpdate::default()
+            .with_block_hash(block_hash_bytes!(b"block hash"))
+            .with_state_commitment(state_commitment_bytes!(b"state commitment"))
+            .with_parent_state_commitment(state_commitment_bytes!(b"parent commitment"))
+            .with_storage_update(
+                contract_address_bytes!(b"contract 0"),
+                storage_address_bytes!(b"storage key 0"),
+                storage_value_bytes!(b"storage val 0"),
+            )
+            .with_system_storage_update(
+                ContractAddress::ONE,
+                storage_address_bytes!(b"system key"),
+                storage_value_bytes!(b"system val"),
+            )
+            .with_deployed_contract(
+                contract_address_bytes!(b"deployed contract"),
+                class_hash_bytes!(b"deployed class"),
+            )
+            .with_declared_cairo_class(class_hash_bytes!(b"cairo 0 0"))
+            .with_declared_sierra_class(
+                sierra_hash_bytes!(b"sierra class"),
+                casm_hash_bytes!(b"casm hash"),
+            )
+            .with_contract_nonce(
+                contract_address_bytes!(b"contract 10"),
+                contract_nonce_bytes!(b"nonce 10"),
+            )
+            .with_replaced_class(
+                contract_address_bytes!(b"contract 0"),
+                class_hash_bytes!(b"replaced class"),
+            );
+
+        let gateway = super::StateUpdate::from(expected.clone());
+        assert_eq!(
+            gateway.state_diff.storage_diffs[&ContractAddress::ONE],
+            vec![StorageDiff {
+                key: storage_address_bytes!(b"system key"),
+                value: storage_value_bytes!(b"system val"),
+            }]
+        );
+
+        let common = pathfinder_common::StateUpdate::from(gateway);
+
+        assert_eq!(common, expected);
+    }
+
     mod receipts {
         use crate::reply::transaction::{ExecutionStatus, Receipt};
 
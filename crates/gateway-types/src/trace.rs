@@ -1,10 +1,11 @@
 use pathfinder_common::{ContractAddress, TransactionHash};
 use pathfinder_crypto::Felt;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::reply::transaction::ExecutionResources;
 
-#[derive(Debug, Deserialize)]
+#[serde_with::skip_serializing_none]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct TransactionTrace {
     pub revert_error: Option<String>,
@@ -17,13 +18,13 @@ pub struct TransactionTrace {
     pub transaction_hash: Option<TransactionHash>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct BlockTrace {
     pub traces: Vec<TransactionTrace>,
 }
 
-#[derive(Debug, Deserialize, Eq, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, Eq, PartialEq)]
 pub enum CallType {
     #[serde(rename = "CALL")]
     Call,
@@ -31,7 +32,7 @@ pub enum CallType {
     Delegate,
 }
 
-#[derive(Debug, Deserialize, Eq, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, Eq, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct Event {
     pub order: i64,
@@ -40,7 +41,7 @@ pub struct Event {
 }
 
 #[serde_with::skip_serializing_none]
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct FunctionInvocation {
     pub calldata: Vec<Felt>,
@@ -66,7 +67,7 @@ pub struct FunctionInvocation {
     pub execution_resources: ExecutionResources,
 }
 
-#[derive(Debug, Deserialize, Eq, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, Eq, PartialEq)]
 pub enum EntryPointType {
     #[serde(rename = "CONSTRUCTOR")]
     Constructor,
@@ -76,7 +77,7 @@ pub enum EntryPointType {
     L1Handler,
 }
 
-#[derive(Debug, Deserialize, Eq, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, Eq, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct MsgToL1 {
     pub order: usize,
@@ -1,5 +1,6 @@
 use anyhow::Context;
 use pathfinder_common::hash::PoseidonHash;
+use pathfinder_common::trie::TrieNode;
 use pathfinder_common::{
     BlockNumber,
     ClassCommitment,
@@ -71,6 +72,61 @@ impl<'tx> ClassCommitmentTree<'tx> {
         let commitment = ClassCommitment(update.root_commitment);
         Ok((commitment, update))
     }
+
+    /// Generates a proof for the given `class`. See [`MerkleTree::get_proof`].
+    pub fn get_proof(
+        tx: &'tx Transaction<'tx>,
+        block: BlockNumber,
+        class: SierraHash,
+    ) -> anyhow::Result<Option<Vec<TrieNode>>> {
+        let root = tx
+            .class_root_index(block)
+            .context("Querying class root index")?;
+
+        let Some(root) = root else {
+            return Ok(None);
+        };
+
+        let storage = ClassStorage {
+            tx,
+            block: Some(block),
+        };
+
+        MerkleTree::<PoseidonHash, 251>::get_proof(root, &storage, class.view_bits())
+    }
+
+    /// Returns up to `limit` classes starting at `start`. See
+    /// [`MerkleTree::get_range`].
+    ///
+    /// Returns `None` if there is no class trie at `block`.
+    pub fn get_range(
+        tx: &'tx Transaction<'tx>,
+        block: BlockNumber,
+        start: SierraHash,
+        limit: usize,
+    ) -> anyhow::Result<Option<Vec<(SierraHash, ClassCommitmentLeafHash)>>> {
+        let root = tx
+            .class_root_index(block)
+            .context("Querying class root index")?;
+
+        let Some(root) = root else {
+            return Ok(None);
+        };
+
+        let storage = ClassStorage {
+            tx,
+            block: Some(block),
+        };
+
+        MerkleTree::<PoseidonHash, 251>::get_range(root, &storage, start.view_bits(), limit)?
+            .into_iter()
+            .map(|(path, value)| {
+                let key = Felt::from_bits(&path).context("Mapping path to sierra hash")?;
+                Ok((SierraHash(key), ClassCommitmentLeafHash(value)))
+            })
+            .collect::<anyhow::Result<_>>()
+            .map(Some)
+    }
 }
 
 struct ClassStorage<'tx> {
@@ -101,6 +101,41 @@ impl<'tx> ContractsStorageTree<'tx> {
         MerkleTree::<PedersenHash, 251>::get_proof(root, &storage, key)
     }
 
+    /// Returns up to `limit` storage slots starting at `start`. See
+    /// [`MerkleTree::get_range`].
+    ///
+    /// Returns `None` if the contract has no storage at `block`.
+    pub fn get_range(
+        tx: &'tx Transaction<'tx>,
+        contract: ContractAddress,
+        block: BlockNumber,
+        start: StorageAddress,
+        limit: usize,
+    ) -> anyhow::Result<Option<Vec<(StorageAddress, StorageValue)>>> {
+        let root = tx
+            .contract_root_index(block, contract)
+            .context("Querying contract root index")?;
+
+        let Some(root) = root else {
+            return Ok(None);
+        };
+
+        let storage = ContractStorage {
+            tx,
+            block: Some(block),
+            contract,
+        };
+
+        MerkleTree::<PedersenHash, 251>::get_range(root, &storage, start.view_bits(), limit)?
+            .into_iter()
+            .map(|(path, value)| {
+                let key = Felt::from_bits(&path).context("Mapping leaf path to storage address")?;
+                Ok((StorageAddress(key), StorageValue(value)))
+            })
+            .collect::<anyhow::Result<_>>()
+            .map(Some)
+    }
+
     pub fn set(&mut self, address: StorageAddress, value: StorageValue) -> anyhow::Result<()> {
         let key = address.view_bits().to_owned();
         self.tree.set(&self.storage, key, value.0)
@@ -211,6 +246,40 @@ impl<'tx> StorageCommitmentTree<'tx> {
         MerkleTree::<PedersenHash, 251>::get_proof(root, &storage, address.view_bits())
     }
 
+    /// Returns up to `limit` contracts starting at `start`. See
+    /// [`MerkleTree::get_range`].
+    ///
+    /// Returns `None` if there is no storage trie at `block`.
+    pub fn get_range(
+        tx: &'tx Transaction<'tx>,
+        block: BlockNumber,
+        start: ContractAddress,
+        limit: usize,
+    ) -> anyhow::Result<Option<Vec<(ContractAddress, ContractStateHash)>>> {
+        let root = tx
+            .storage_root_index(block)
+            .context("Querying storage root index")?;
+
+        let Some(root) = root else {
+            return Ok(None);
+        };
+
+        let storage = StorageTrieStorage {
+            tx,
+            block: Some(block),
+        };
+
+        MerkleTree::<PedersenHash, 251>::get_range(root, &storage, start.view_bits(), limit)?
+            .into_iter()
+            .map(|(path, value)| {
+                let key =
+                    Felt::from_bits(&path).context("Mapping leaf path to contract address")?;
+                Ok((ContractAddress(key), ContractStateHash(value)))
+            })
+            .collect::<anyhow::Result<_>>()
+            .map(Some)
+    }
+
     /// See [`MerkleTree::dfs`]
     pub fn dfs<B, F: FnMut(&InternalNode, &BitSlice<u8, Msb0>) -> ControlFlow<B, Visit>>(
         &mut self,
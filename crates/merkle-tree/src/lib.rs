@@ -5,8 +5,12 @@ pub mod tree;
 
 mod class;
 mod contract;
+mod proof;
+mod range;
 mod transaction;
 
 pub use class::ClassCommitmentTree;
 pub use contract::{ContractsStorageTree, StorageCommitmentTree};
+pub use proof::{verify_proof, Membership, ProofError};
+pub use range::{verify_range, RangeProofError};
 pub use transaction::TransactionOrEventTree;
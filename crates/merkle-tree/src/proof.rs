@@ -0,0 +1,235 @@
+//! Storage-free verification of Merkle-Patricia proofs as generated by
+//! [`MerkleTree::get_proof`](crate::tree::MerkleTree::get_proof).
+//!
+//! The contract and storage tries use [PedersenHash] while the class trie uses
+//! [PoseidonHash]. Proofs returned by `pathfinder_getProof` can be checked by
+//! calling [verify_proof] with the matching hash function:
+//!
+//! ```
+//! # use pathfinder_common::hash::{PedersenHash, PoseidonHash};
+//! # use pathfinder_common::trie::TrieNode;
+//! # use pathfinder_crypto::Felt;
+//! # use pathfinder_merkle_tree::{verify_proof, Membership};
+//! # fn example(root: Felt, key: Felt, proof: &[TrieNode]) -> Result<(), Box<dyn std::error::Error>> {
+//! // Contract or storage trie.
+//! match verify_proof::<PedersenHash>(root, key.view_bits(), proof)? {
+//!     Membership::Member(value) => println!("value is {value}"),
+//!     Membership::NonMember => println!("key is not in the trie"),
+//! }
+//! // Class trie.
+//! let _ = verify_proof::<PoseidonHash>(root, key.view_bits(), proof)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [PedersenHash]: pathfinder_common::hash::PedersenHash
+//! [PoseidonHash]: pathfinder_common::hash::PoseidonHash
+
+use bitvec::prelude::Msb0;
+use bitvec::slice::BitSlice;
+use pathfinder_common::hash::FeltHash;
+use pathfinder_common::trie::TrieNode;
+use pathfinder_crypto::Felt;
+
+use crate::merkle_node::Direction;
+
+/// The height of all Starknet tries.
+const HEIGHT: usize = 251;
+
+/// The outcome of a successfully verified proof.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Membership {
+    /// The key is part of the trie and has the given value.
+    Member(Felt),
+    /// The key is not part of the trie.
+    NonMember,
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum ProofError {
+    #[error("Key has {0} bits, expected {HEIGHT}")]
+    InvalidKeyLength(usize),
+    #[error("Hash of proof node {index} does not match the expected hash")]
+    HashMismatch { index: usize },
+    #[error("Proof node {index} extends past the key")]
+    PathTooLong { index: usize },
+    #[error("Proof ends before reaching a leaf or proving absence")]
+    Incomplete,
+    #[error("Proof contains nodes after reaching a leaf or proving absence")]
+    TrailingNodes,
+}
+
+/// Verifies a proof for `key` against the trie with root hash `root`.
+///
+/// Returns the proven value if the key is part of the trie, or
+/// [Membership::NonMember] if the proof shows that the key is absent.
+///
+/// The algorithm follows this logic:
+/// 1. init expected_hash <- root hash
+/// 2. loop over nodes: current <- nodes[i]
+///    1. verify the current node's hash matches expected_hash (if not then we
+///       have a bad proof)
+///    2. move towards the target - if current is:
+///       1. binary node then choose the child that moves towards the target,
+///          else if
+///       2. edge node then check the path against the target bits
+///          1. If it matches then proceed with the child, else
+///          2. if it does not match then we now have a proof that the target
+///             does not exist
+///    3. nibble off target bits according to which child you got in (2). If all
+///       bits are gone then you have reached the target and the child hash is
+///       the value you wanted and the proof is complete.
+///    4. set expected_hash <- to the child hash
+///
+/// An empty proof is valid only for the empty trie, i.e. a zero `root`.
+pub fn verify_proof<H: FeltHash>(
+    root: Felt,
+    key: &BitSlice<u8, Msb0>,
+    proof: &[TrieNode],
+) -> Result<Membership, ProofError> {
+    if key.len() != HEIGHT {
+        return Err(ProofError::InvalidKeyLength(key.len()));
+    }
+
+    if proof.is_empty() {
+        return if root == Felt::ZERO {
+            Ok(Membership::NonMember)
+        } else {
+            Err(ProofError::Incomplete)
+        };
+    }
+
+    let mut expected_hash = root;
+    let mut remaining_path = key;
+
+    for (index, node) in proof.iter().enumerate() {
+        if remaining_path.is_empty() {
+            return Err(ProofError::TrailingNodes);
+        }
+
+        if node.hash::<H>() != expected_hash {
+            return Err(ProofError::HashMismatch { index });
+        }
+
+        match node {
+            TrieNode::Binary { left, right } => {
+                expected_hash = match Direction::from(remaining_path[0]) {
+                    Direction::Left => *left,
+                    Direction::Right => *right,
+                };
+                remaining_path = &remaining_path[1..];
+            }
+            TrieNode::Edge { child, path } => {
+                let Some(key_path) = remaining_path.get(..path.len()) else {
+                    return Err(ProofError::PathTooLong { index });
+                };
+
+                if path != key_path {
+                    // The target was moved towards as far as possible, and the nodes hash
+                    // correctly up to the root, so the target is not part of the trie.
+                    if index + 1 != proof.len() {
+                        return Err(ProofError::TrailingNodes);
+                    }
+                    return Ok(Membership::NonMember);
+                }
+
+                expected_hash = *child;
+                remaining_path = &remaining_path[path.len()..];
+            }
+        }
+    }
+
+    if remaining_path.is_empty() {
+        Ok(Membership::Member(expected_hash))
+    } else {
+        Err(ProofError::Incomplete)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitvec::prelude::BitVec;
+    use pathfinder_common::felt;
+    use pathfinder_common::hash::{PedersenHash, PoseidonHash};
+
+    use super::*;
+
+    /// Single leaf trie: a root edge node spanning the whole key.
+    fn single_leaf<H: FeltHash>(key: Felt, value: Felt) -> (Felt, Vec<TrieNode>) {
+        let node = TrieNode::Edge {
+            child: value,
+            path: key.view_bits().to_bitvec(),
+        };
+        (node.hash::<H>(), vec![node])
+    }
+
+    #[test]
+    fn member() {
+        let key = felt!("0xabc");
+        let value = felt!("0x123");
+
+        let (root, proof) = single_leaf::<PedersenHash>(key, value);
+        let result = verify_proof::<PedersenHash>(root, key.view_bits(), &proof);
+        assert_eq!(result, Ok(Membership::Member(value)));
+
+        let (root, proof) = single_leaf::<PoseidonHash>(key, value);
+        let result = verify_proof::<PoseidonHash>(root, key.view_bits(), &proof);
+        assert_eq!(result, Ok(Membership::Member(value)));
+    }
+
+    #[test]
+    fn non_member() {
+        let (root, proof) = single_leaf::<PedersenHash>(felt!("0xabc"), felt!("0x123"));
+        let result = verify_proof::<PedersenHash>(root, felt!("0xabd").view_bits(), &proof);
+        assert_eq!(result, Ok(Membership::NonMember));
+    }
+
+    #[test]
+    fn wrong_hash_function() {
+        let key = felt!("0xabc");
+        let (root, proof) = single_leaf::<PedersenHash>(key, felt!("0x123"));
+        let result = verify_proof::<PoseidonHash>(root, key.view_bits(), &proof);
+        assert_eq!(result, Err(ProofError::HashMismatch { index: 0 }));
+    }
+
+    #[test]
+    fn empty_proof() {
+        let key = felt!("0xabc");
+        let result = verify_proof::<PedersenHash>(Felt::ZERO, key.view_bits(), &[]);
+        assert_eq!(result, Ok(Membership::NonMember));
+
+        let result = verify_proof::<PedersenHash>(felt!("0x1"), key.view_bits(), &[]);
+        assert_eq!(result, Err(ProofError::Incomplete));
+    }
+
+    #[test]
+    fn incomplete() {
+        let key = felt!("0xabc");
+        let node = TrieNode::Edge {
+            child: felt!("0x123"),
+            path: key.view_bits()[..10].to_bitvec(),
+        };
+        let root = node.hash::<PedersenHash>();
+        let result = verify_proof::<PedersenHash>(root, key.view_bits(), &[node]);
+        assert_eq!(result, Err(ProofError::Incomplete));
+    }
+
+    #[test]
+    fn trailing_nodes() {
+        let key = felt!("0xabc");
+        let (root, mut proof) = single_leaf::<PedersenHash>(key, felt!("0x123"));
+        proof.push(TrieNode::Binary {
+            left: felt!("0x1"),
+            right: felt!("0x2"),
+        });
+        let result = verify_proof::<PedersenHash>(root, key.view_bits(), &proof);
+        assert_eq!(result, Err(ProofError::TrailingNodes));
+    }
+
+    #[test]
+    fn invalid_key_length() {
+        let key = BitVec::<u8, Msb0>::repeat(false, 10);
+        let result = verify_proof::<PedersenHash>(Felt::ZERO, &key, &[]);
+        assert_eq!(result, Err(ProofError::InvalidKeyLength(10)));
+    }
+}
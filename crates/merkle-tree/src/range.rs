@@ -0,0 +1,294 @@
+//! Storage-free verification of ranges of consecutive leaves, as returned by
+//! [`MerkleTree::get_range`](crate::tree::MerkleTree::get_range).
+//!
+//! A range is proven by a proof for its start key and, unless the range
+//! extends to the end of the trie, a proof for its last key. Together with the
+//! leaves of the range these proofs contain every node required to recompute
+//! the root: the start proof covers all subtrees left of the range and the end
+//! proof all subtrees right of it. This also proves that no leaves of the range
+//! were left out.
+
+use bitvec::prelude::{BitSlice, BitVec, Msb0};
+use pathfinder_common::hash::FeltHash;
+use pathfinder_common::trie::TrieNode;
+use pathfinder_crypto::Felt;
+
+use crate::proof::{verify_proof, Membership, ProofError};
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum RangeProofError {
+    #[error("Invalid start proof: {0}")]
+    StartProof(ProofError),
+    #[error("Invalid end proof: {0}")]
+    EndProof(ProofError),
+    #[error("End proof does not prove the last leaf of the range")]
+    EndProofMismatch,
+    #[error("Leaves are not in ascending key order starting at the range start")]
+    UnorderedLeaves,
+    #[error("Key has more than 251 bits")]
+    InvalidKey,
+    #[error("Proof nodes overlap with the leaves of the range")]
+    Overlapping,
+    #[error("Range does not match the root")]
+    RootMismatch,
+}
+
+/// A subtree of the trie, identified by the path to its root node.
+type Subtree = (BitVec<u8, Msb0>, Felt);
+
+/// Verifies that `leaves` are all leaves of the trie with root hash `root`
+/// whose key lies between `start` and the last key of `leaves`, both
+/// inclusive.
+///
+/// `start_proof` is the proof for `start`. `end_proof` is the proof for the
+/// last key of `leaves`, or empty if the range extends to the end of the trie.
+/// Leaves are `(key, value)` pairs in ascending key order.
+pub fn verify_range<H: FeltHash>(
+    root: Felt,
+    start: Felt,
+    leaves: &[(Felt, Felt)],
+    start_proof: &[TrieNode],
+    end_proof: &[TrieNode],
+) -> Result<(), RangeProofError> {
+    if start.has_more_than_251_bits() {
+        return Err(RangeProofError::InvalidKey);
+    }
+
+    verify_proof::<H>(root, start.view_bits(), start_proof).map_err(RangeProofError::StartProof)?;
+
+    let mut previous: Option<Felt> = None;
+    for (key, _) in leaves {
+        if key.has_more_than_251_bits() {
+            return Err(RangeProofError::InvalidKey);
+        }
+        if *key < start || previous.is_some_and(|previous| previous >= *key) {
+            return Err(RangeProofError::UnorderedLeaves);
+        }
+        previous = Some(*key);
+    }
+
+    let mut subtrees = Vec::new();
+    collect_siblings(start.view_bits(), start_proof, Side::Left, &mut subtrees);
+
+    if !end_proof.is_empty() {
+        let Some((last_key, last_value)) = leaves.last() else {
+            return Err(RangeProofError::EndProofMismatch);
+        };
+
+        match verify_proof::<H>(root, last_key.view_bits(), end_proof) {
+            Ok(Membership::Member(value)) if value == *last_value => {}
+            Ok(_) => return Err(RangeProofError::EndProofMismatch),
+            Err(error) => return Err(RangeProofError::EndProof(error)),
+        }
+
+        collect_siblings(last_key.view_bits(), end_proof, Side::Right, &mut subtrees);
+    }
+
+    subtrees.extend(
+        leaves
+            .iter()
+            .map(|(key, value)| (key.view_bits().to_bitvec(), *value)),
+    );
+    subtrees.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let computed = if subtrees.is_empty() {
+        Felt::ZERO
+    } else {
+        let (hash, path) = build::<H>(&subtrees, 0)?;
+        node_hash::<H>(hash, path)
+    };
+
+    if computed != root {
+        return Err(RangeProofError::RootMismatch);
+    }
+
+    Ok(())
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Left,
+    Right,
+}
+
+/// Collects the subtrees branching off the (already verified) proof for `key`
+/// which lie completely on the given side of `key`.
+fn collect_siblings(
+    key: &BitSlice<u8, Msb0>,
+    proof: &[TrieNode],
+    side: Side,
+    subtrees: &mut Vec<Subtree>,
+) {
+    let mut height = 0;
+
+    for node in proof {
+        match node {
+            TrieNode::Binary { left, right } => {
+                let mut path = key[..height].to_bitvec();
+                match (key[height], side) {
+                    (true, Side::Left) => {
+                        path.push(false);
+                        subtrees.push((path, *left));
+                    }
+                    (false, Side::Right) => {
+                        path.push(true);
+                        subtrees.push((path, *right));
+                    }
+                    _ => {}
+                }
+                height += 1;
+            }
+            TrieNode::Edge { child, path } => {
+                let key_path = &key[height..height + path.len()];
+                if path != key_path {
+                    // The edge leads away from the key, so the whole subtree is either before
+                    // or after it. This is always the last node of the proof.
+                    let before = path.as_bitslice() < key_path;
+                    if before == (side == Side::Left) {
+                        let mut subtree = key[..height].to_bitvec();
+                        subtree.extend_from_bitslice(path);
+                        subtrees.push((subtree, *child));
+                    }
+                    break;
+                }
+                height += path.len();
+            }
+        }
+    }
+}
+
+/// Computes the node at `height` which contains all `subtrees`. These must be
+/// sorted by path and share the first `height` bits of their path.
+///
+/// Returns the hash of the node and the path of the edge leading to it, which
+/// is empty if there is no edge.
+fn build<H: FeltHash>(
+    subtrees: &[Subtree],
+    height: usize,
+) -> Result<(Felt, BitVec<u8, Msb0>), RangeProofError> {
+    let (first, hash) = &subtrees[0];
+    if subtrees.len() == 1 {
+        return Ok((*hash, first[height..].to_bitvec()));
+    }
+
+    let (last, _) = &subtrees[subtrees.len() - 1];
+    let common = first[height..]
+        .iter()
+        .zip(last[height..].iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let split = height + common;
+
+    // One of the subtrees contains another one.
+    if split >= first.len() || split >= last.len() {
+        return Err(RangeProofError::Overlapping);
+    }
+
+    // Sorting guarantees that all subtrees in between extend past the split as
+    // well.
+    let mid = subtrees.partition_point(|(path, _)| !path[split]);
+    let (left, left_path) = build::<H>(&subtrees[..mid], split + 1)?;
+    let (right, right_path) = build::<H>(&subtrees[mid..], split + 1)?;
+
+    let hash = H::hash(
+        node_hash::<H>(left, left_path),
+        node_hash::<H>(right, right_path),
+    );
+
+    Ok((hash, first[height..split].to_bitvec()))
+}
+
+/// The hash of a node including the edge leading to it, if any.
+fn node_hash<H: FeltHash>(hash: Felt, path: BitVec<u8, Msb0>) -> Felt {
+    if path.is_empty() {
+        hash
+    } else {
+        TrieNode::Edge { child: hash, path }.hash::<H>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::felt;
+    use pathfinder_common::hash::PedersenHash;
+
+    use super::*;
+
+    /// Single leaf trie: a root edge node spanning the whole key.
+    fn single_leaf(key: Felt, value: Felt) -> (Felt, Vec<TrieNode>) {
+        let node = TrieNode::Edge {
+            child: value,
+            path: key.view_bits().to_bitvec(),
+        };
+        (node.hash::<PedersenHash>(), vec![node])
+    }
+
+    #[test]
+    fn empty_trie() {
+        let result = verify_range::<PedersenHash>(Felt::ZERO, Felt::ZERO, &[], &[], &[]);
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn single_leaf_range() {
+        let key = felt!("0xabc");
+        let value = felt!("0x123");
+        let (root, proof) = single_leaf(key, value);
+
+        let result = verify_range::<PedersenHash>(root, Felt::ZERO, &[(key, value)], &proof, &[]);
+        assert_eq!(result, Ok(()));
+
+        let result =
+            verify_range::<PedersenHash>(root, Felt::ZERO, &[(key, value)], &proof, &proof);
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn range_after_the_last_leaf() {
+        let (root, proof) = single_leaf(felt!("0xabc"), felt!("0x123"));
+
+        let result = verify_range::<PedersenHash>(root, felt!("0xabd"), &[], &proof, &[]);
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn missing_leaf() {
+        let (root, proof) = single_leaf(felt!("0xabc"), felt!("0x123"));
+
+        let result = verify_range::<PedersenHash>(root, Felt::ZERO, &[], &proof, &[]);
+        assert_eq!(result, Err(RangeProofError::RootMismatch));
+    }
+
+    #[test]
+    fn wrong_value() {
+        let key = felt!("0xabc");
+        let (root, proof) = single_leaf(key, felt!("0x123"));
+
+        let result =
+            verify_range::<PedersenHash>(root, Felt::ZERO, &[(key, felt!("0x1"))], &proof, &[]);
+        assert_eq!(result, Err(RangeProofError::RootMismatch));
+
+        let result =
+            verify_range::<PedersenHash>(root, Felt::ZERO, &[(key, felt!("0x1"))], &proof, &proof);
+        assert_eq!(result, Err(RangeProofError::EndProofMismatch));
+    }
+
+    #[test]
+    fn leaf_before_start() {
+        let key = felt!("0xabc");
+        let value = felt!("0x123");
+        let (root, proof) = single_leaf(key, value);
+
+        let result =
+            verify_range::<PedersenHash>(root, felt!("0xabd"), &[(key, value)], &proof, &[]);
+        assert_eq!(result, Err(RangeProofError::UnorderedLeaves));
+    }
+
+    #[test]
+    fn end_proof_without_leaves() {
+        let (root, proof) = single_leaf(felt!("0xabc"), felt!("0x123"));
+
+        let result = verify_range::<PedersenHash>(root, Felt::ZERO, &[], &proof, &proof);
+        assert_eq!(result, Err(RangeProofError::EndProofMismatch));
+    }
+}
@@ -624,6 +624,68 @@ impl<H: FeltHash, const HEIGHT: usize> MerkleTree<H, HEIGHT> {
         Ok(Some(nodes))
     }
 
+    /// Returns up to `limit` consecutive leaves of the tree, starting at the
+    /// first leaf whose key is greater than or equal to `start`.
+    ///
+    /// Leaves are returned in ascending key order, together with their value.
+    /// Proofs for the bounds of the range can be generated using
+    /// [get_proof](MerkleTree::get_proof).
+    pub fn get_range(
+        root: u64,
+        storage: &impl Storage,
+        start: &BitSlice<u8, Msb0>,
+        limit: usize,
+    ) -> anyhow::Result<Vec<(BitVec<u8, Msb0>, Felt)>> {
+        let mut leaves = Vec::new();
+        if limit == 0 {
+            return Ok(leaves);
+        }
+
+        let result = Self::new(root).dfs(storage, &mut |node, path| {
+            match node {
+                InternalNode::Binary(_) => {
+                    // Skip subtrees which only contain keys before the start of the range.
+                    if path < &start[..path.len()] {
+                        return ControlFlow::Continue(Visit::StopSubtree);
+                    }
+                }
+                InternalNode::Edge(edge) => {
+                    let mut prefix = path.to_bitvec();
+                    prefix.extend_from_bitslice(&edge.path);
+                    if prefix[..] < start[..prefix.len()] {
+                        return ControlFlow::Continue(Visit::StopSubtree);
+                    }
+                }
+                InternalNode::Leaf => {
+                    if path < start {
+                        return ControlFlow::Continue(Visit::ContinueDeeper);
+                    }
+
+                    match storage.leaf(path) {
+                        Ok(Some(value)) => leaves.push((path.to_bitvec(), value)),
+                        Ok(None) => {
+                            return ControlFlow::Break(Err(anyhow::anyhow!("Leaf is missing")))
+                        }
+                        Err(error) => return ControlFlow::Break(Err(error)),
+                    }
+
+                    if leaves.len() == limit {
+                        return ControlFlow::Break(Ok(()));
+                    }
+                }
+                InternalNode::Unresolved(_) => {}
+            }
+
+            ControlFlow::Continue(Visit::ContinueDeeper)
+        })?;
+
+        if let Some(Err(error)) = result {
+            return Err(error.context("Querying leaf value"));
+        }
+
+        Ok(leaves)
+    }
+
     /// Traverses from the current root towards destination node.
     /// Returns the list of nodes along the path.
     ///
@@ -1965,11 +2027,11 @@ mod tests {
         use bitvec::prelude::Msb0;
         use bitvec::slice::BitSlice;
         use pathfinder_common::felt;
-        use pathfinder_common::hash::PedersenHash;
+        use pathfinder_common::hash::{PedersenHash, PoseidonHash};
         use pathfinder_common::trie::TrieNode;
         use pathfinder_crypto::Felt;
 
-        use super::{Direction, TestStorage, TestTree};
+        use super::{MerkleTree, TestStorage, TestTree};
         use crate::storage::Storage;
         use crate::tree::tests::commit_and_persist_with_pruning;
 
@@ -1980,90 +2042,20 @@ mod tests {
         }
 
         /// Verifies that the key `key` with value `value` is indeed part of the
-        /// MPT that has root `root`, given `proofs`.
-        /// Supports proofs of non-membership as well as proof of membership:
-        /// this function returns an enum corresponding to the
-        /// membership of `value`, or returns `None` in case of a hash mismatch.
-        /// The algorithm follows this logic:
-        /// 1. init expected_hash <- root hash
-        /// 2. loop over nodes: current <- nodes[i]
-        ///    1. verify the current node's hash matches expected_hash (if not
-        ///       then we have a bad proof)
-        ///    2. move towards the target - if current is:
-        ///       1. binary node then choose the child that moves towards the
-        ///          target, else if
-        ///       2. edge node then check the path against the target bits
-        ///          1. If it matches then proceed with the child, else
-        ///          2. if it does not match then we now have a proof that the
-        ///             target does not exist
-        ///    3. nibble off target bits according to which child you got in
-        ///       (2). If all bits are gone then you have reached the target and
-        ///       the child hash is the value you wanted and the proof is
-        ///       complete.
-        ///    4. set expected_hash <- to the child hash
-        /// 3. check that the expected_hash is `value` (we should've reached the
-        ///    leaf)
+        /// MPT that has root `root`, given `proofs`. Returns `None` in case of
+        /// an invalid proof or a value mismatch.
         fn verify_proof(
             root: Felt,
             key: &BitSlice<u8, Msb0>,
             value: Felt,
             proofs: &[TrieNode],
         ) -> Option<Membership> {
-            // Protect from ill-formed keys
-            if key.len() != 251 {
-                return None;
-            }
-
-            let mut expected_hash = root;
-            let mut remaining_path: &BitSlice<u8, Msb0> = key;
-
-            for proof_node in proofs.iter() {
-                // Hash mismatch? Return None.
-                if proof_node.hash::<PedersenHash>() != expected_hash {
-                    return None;
-                }
-                match proof_node {
-                    TrieNode::Binary { left, right } => {
-                        // Direction will always correspond to the 0th index
-                        // because we're removing bits on every iteration.
-                        let direction = Direction::from(remaining_path[0]);
-
-                        // Set the next hash to be the left or right hash,
-                        // depending on the direction
-                        expected_hash = match direction {
-                            Direction::Left => *left,
-                            Direction::Right => *right,
-                        };
-
-                        // Advance by a single bit
-                        remaining_path = &remaining_path[1..];
-                    }
-                    TrieNode::Edge { child, path } => {
-                        if path != &remaining_path[..path.len()] {
-                            // If paths don't match, we've found a proof of non membership because
-                            // we:
-                            // 1. Correctly moved towards the target insofar as is possible, and
-                            // 2. hashing all the nodes along the path does result in the root hash,
-                            //    which means
-                            // 3. the target definitely does not exist in this tree
-                            return Some(Membership::NonMember);
-                        }
-
-                        // Set the next hash to the child's hash
-                        expected_hash = *child;
-
-                        // Advance by the whole edge path
-                        remaining_path = &remaining_path[path.len()..];
-                    }
+            match crate::proof::verify_proof::<PedersenHash>(root, key, proofs).ok()? {
+                crate::proof::Membership::Member(proven) if proven == value => {
+                    Some(Membership::Member)
                 }
-            }
-
-            // At this point, we should reach `value` !
-            if expected_hash == value {
-                Some(Membership::Member)
-            } else {
-                // Hash mismatch. Return `None`.
-                None
+                crate::proof::Membership::Member(_) => None,
+                crate::proof::Membership::NonMember => Some(Membership::NonMember),
             }
         }
 
@@ -2340,6 +2332,31 @@ mod tests {
             RandomTree::new(LEN).verify();
         }
 
+        #[test]
+        fn poseidon_tree() {
+            let mut uut = MerkleTree::<PoseidonHash, 251>::empty();
+            let mut storage = TestStorage::default();
+
+            let keys = gen_random_hashes(64);
+            let values = gen_random_hashes(64);
+            keys.iter()
+                .zip(values.iter())
+                .for_each(|(k, v)| uut.set(&storage, k.view_bits().to_owned(), *v).unwrap());
+
+            let (root, root_idx) = commit_and_persist_with_pruning(uut, &mut storage);
+
+            for (key, value) in keys.iter().zip(values.iter()) {
+                let proof =
+                    MerkleTree::<PoseidonHash, 251>::get_proof(root_idx, &storage, key.view_bits())
+                        .unwrap()
+                        .unwrap();
+                let verified =
+                    crate::proof::verify_proof::<PoseidonHash>(root, key.view_bits(), &proof)
+                        .unwrap();
+                assert_eq!(verified, crate::proof::Membership::Member(*value));
+            }
+        }
+
         #[test]
         fn non_membership() {
             const LEN: usize = 256;
@@ -2481,4 +2498,172 @@ mod tests {
             assert!(verified.is_none());
         }
     }
+
+    mod range {
+        use pathfinder_common::felt;
+        use pathfinder_common::hash::PedersenHash;
+        use pathfinder_crypto::Felt;
+
+        use super::{commit_and_persist_with_pruning, TestStorage, TestTree};
+        use crate::range::{verify_range, RangeProofError};
+
+        struct Trie {
+            leaves: Vec<(Felt, Felt)>,
+            root: Felt,
+            root_idx: u64,
+            storage: TestStorage,
+        }
+
+        impl Trie {
+            fn new(len: usize) -> Self {
+                let mut rng = rand::rngs::ThreadRng::default();
+                let mut uut = TestTree::empty();
+                let mut storage = TestStorage::default();
+
+                let mut leaves = Vec::with_capacity(len);
+                while leaves.len() < len {
+                    let key = Felt::random(&mut rng);
+                    if key.has_more_than_251_bits() {
+                        continue;
+                    }
+                    leaves.push((key, Felt::random(&mut rng)));
+                }
+
+                for (key, value) in &leaves {
+                    uut.set(&storage, key.view_bits().to_owned(), *value)
+                        .unwrap();
+                }
+                leaves.sort();
+
+                let (root, root_idx) = commit_and_persist_with_pruning(uut, &mut storage);
+
+                Self {
+                    leaves,
+                    root,
+                    root_idx,
+                    storage,
+                }
+            }
+
+            /// Returns the range starting at `start` and its proof, as served
+            /// to peers.
+            fn range(
+                &self,
+                start: Felt,
+                limit: usize,
+            ) -> (
+                Vec<(Felt, Felt)>,
+                Vec<pathfinder_common::trie::TrieNode>,
+                Vec<pathfinder_common::trie::TrieNode>,
+            ) {
+                let leaves: Vec<_> =
+                    TestTree::get_range(self.root_idx, &self.storage, start.view_bits(), limit)
+                        .unwrap()
+                        .into_iter()
+                        .map(|(path, value)| (Felt::from_bits(&path).unwrap(), value))
+                        .collect();
+
+                let start_proof =
+                    TestTree::get_proof(self.root_idx, &self.storage, start.view_bits())
+                        .unwrap()
+                        .unwrap();
+                let end_proof = match leaves.last() {
+                    Some((last, _)) if leaves.len() == limit => {
+                        TestTree::get_proof(self.root_idx, &self.storage, last.view_bits())
+                            .unwrap()
+                            .unwrap()
+                    }
+                    _ => vec![],
+                };
+
+                (leaves, start_proof, end_proof)
+            }
+        }
+
+        #[test]
+        fn get_range_in_order() {
+            let trie = Trie::new(100);
+
+            let (leaves, ..) = trie.range(Felt::ZERO, 1000);
+            assert_eq!(leaves, trie.leaves);
+
+            let start = trie.leaves[10].0;
+            let (leaves, ..) = trie.range(start, 5);
+            assert_eq!(leaves, trie.leaves[10..15]);
+
+            // Ranges start at the next leaf if the start key is not part of the trie.
+            let start = trie.leaves[10].0 + Felt::ONE;
+            let (leaves, ..) = trie.range(start, 5);
+            assert_eq!(leaves, trie.leaves[11..16]);
+        }
+
+        #[test]
+        fn whole_trie_in_chunks() {
+            let trie = Trie::new(256);
+
+            let mut start = Felt::ZERO;
+            let mut received = Vec::new();
+            loop {
+                let (leaves, start_proof, end_proof) = trie.range(start, 16);
+                verify_range::<PedersenHash>(trie.root, start, &leaves, &start_proof, &end_proof)
+                    .unwrap();
+
+                received.extend_from_slice(&leaves);
+                if end_proof.is_empty() {
+                    break;
+                }
+                start = leaves.last().unwrap().0 + Felt::ONE;
+            }
+
+            assert_eq!(received, trie.leaves);
+        }
+
+        #[test]
+        fn start_key_not_in_trie() {
+            let trie = Trie::new(64);
+
+            let start = trie.leaves[20].0 + Felt::ONE;
+            let (leaves, start_proof, end_proof) = trie.range(start, 8);
+            verify_range::<PedersenHash>(trie.root, start, &leaves, &start_proof, &end_proof)
+                .unwrap();
+        }
+
+        #[test]
+        fn omitted_leaf_is_rejected() {
+            let trie = Trie::new(64);
+
+            let (mut leaves, start_proof, end_proof) = trie.range(Felt::ZERO, 8);
+            leaves.remove(3);
+            let result = verify_range::<PedersenHash>(
+                trie.root,
+                Felt::ZERO,
+                &leaves,
+                &start_proof,
+                &end_proof,
+            );
+            assert_eq!(result, Err(RangeProofError::RootMismatch));
+
+            // Claiming the range ends early hides the remaining leaves.
+            let (leaves, start_proof, _) = trie.range(Felt::ZERO, 8);
+            let result =
+                verify_range::<PedersenHash>(trie.root, Felt::ZERO, &leaves, &start_proof, &[]);
+            assert_eq!(result, Err(RangeProofError::RootMismatch));
+        }
+
+        #[test]
+        fn modified_value_is_rejected() {
+            let trie = Trie::new(64);
+
+            let (mut leaves, start_proof, end_proof) = trie.range(Felt::ZERO, 8);
+            leaves[3].1 = felt!("0x1234");
+            let result = verify_range::<PedersenHash>(
+                trie.root,
+                Felt::ZERO,
+                &leaves,
+                &start_proof,
+                &end_proof,
+            );
+            assert_eq!(result, Err(RangeProofError::RootMismatch));
+        }
+    }
 }
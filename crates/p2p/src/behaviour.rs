@@ -6,7 +6,7 @@ use std::time::{Duration, Instant};
 use std::{cmp, task};
 
 use libp2p::core::Endpoint;
-use libp2p::gossipsub::{self, IdentTopic, MessageAuthenticity, MessageId};
+use libp2p::gossipsub::{self, IdentTopic, MessageAcceptance, MessageAuthenticity, MessageId};
 use libp2p::kad::store::MemoryStore;
 use libp2p::kad::{self};
 use libp2p::multiaddr::Protocol;
@@ -27,11 +27,13 @@ use libp2p::{autonat, dcutr, identify, identity, ping, relay, Multiaddr, PeerId,
 use p2p_proto::class::{ClassesRequest, ClassesResponse};
 use p2p_proto::event::{EventsRequest, EventsResponse};
 use p2p_proto::header::{BlockHeadersRequest, BlockHeadersResponse};
+use p2p_proto::snapshot::{SnapshotRequest, SnapshotResponse};
 use p2p_proto::state::{StateDiffsRequest, StateDiffsResponse};
 use p2p_proto::transaction::{TransactionsRequest, TransactionsResponse};
 use pathfinder_common::ChainId;
 
 use crate::peers::{Connectivity, Direction, KeyedNetworkGroup, Peer, PeerSet};
+use crate::reputation::{Misbehaviour, PeerReputation, Reputations};
 use crate::secret::Secret;
 use crate::sync::codec;
 use crate::Config;
@@ -40,9 +42,16 @@ pub fn kademlia_protocol_name(chain_id: ChainId) -> String {
     format!("/starknet/kad/{}/1.0.0", chain_id.as_str())
 }
 
+/// The gossipsub topic on which transactions which are not yet included in a
+/// block are propagated.
+pub fn transaction_propagation_topic(chain_id: ChainId) -> String {
+    format!("transactions/{}", chain_id.to_hex_str())
+}
+
 pub struct Behaviour {
     cfg: Config,
     peers: PeerSet,
+    reputations: Reputations,
     swarm: crate::Client,
     secret: Secret,
     inner: Inner,
@@ -63,6 +72,7 @@ pub struct Inner {
     state_diffs_sync: p2p_stream::Behaviour<codec::StateDiffs>,
     transactions_sync: p2p_stream::Behaviour<codec::Transactions>,
     events_sync: p2p_stream::Behaviour<codec::Events>,
+    snapshots_sync: p2p_stream::Behaviour<codec::Snapshots>,
 }
 
 impl NetworkBehaviour for Behaviour {
@@ -81,6 +91,7 @@ impl NetworkBehaviour for Behaviour {
 
         self.check_duplicate_connection(peer)?;
         self.prevent_evicted_peer_reconnections(peer)?;
+        self.prevent_banned_peer_connections(peer)?;
 
         // Is the peer connecting over a relay?
         let is_relayed = remote_addr.iter().any(|p| p == Protocol::P2pCircuit);
@@ -123,6 +134,7 @@ impl NetworkBehaviour for Behaviour {
 
         self.check_duplicate_connection(peer)?;
         self.prevent_evicted_peer_reconnections(peer)?;
+        self.prevent_banned_peer_connections(peer)?;
 
         self.inner
             .handle_established_outbound_connection(connection_id, peer, addr, role_override)
@@ -346,6 +358,7 @@ impl NetworkBehaviour for Behaviour {
         // quickly.
         if let Some(peer_id) = peer_id {
             self.prevent_evicted_peer_reconnections(peer_id)?;
+            self.prevent_banned_peer_connections(peer_id)?;
         }
 
         drop(recent_peers);
@@ -394,6 +407,7 @@ impl NetworkBehaviour for Behaviour {
                 // hole-punching.
 
                 self.prevent_evicted_peer_reconnections(peer_id)?;
+                self.prevent_banned_peer_connections(peer_id)?;
 
                 if self.outbound_peers().count() >= self.cfg.max_outbound_peers {
                     self.evict_outbound_peer()?;
@@ -473,8 +487,11 @@ impl Behaviour {
             message.data.hash(&mut s);
             MessageId::from(s.finish().to_string())
         };
+        // Messages are only forwarded to other peers once they were validated, see
+        // [Behaviour::report_message_validation].
         let gossipsub_config = libp2p::gossipsub::ConfigBuilder::default()
             .message_id_fn(message_id_fn)
+            .validate_messages()
             .build()
             .expect("valid gossipsub config");
 
@@ -492,12 +509,14 @@ impl Behaviour {
         let state_diffs_sync = request_response_behavior::<codec::StateDiffs>(p2p_stream_cfg);
         let transactions_sync = request_response_behavior::<codec::Transactions>(p2p_stream_cfg);
         let events_sync = request_response_behavior::<codec::Events>(p2p_stream_cfg);
+        let snapshots_sync = request_response_behavior::<codec::Snapshots>(p2p_stream_cfg);
 
         let (relay_transport, relay) = relay::client::new(peer_id);
 
         (
             Self {
                 peers: PeerSet::new(cfg.eviction_timeout),
+                reputations: Reputations::new(cfg.ban_duration),
                 cfg,
                 swarm,
                 secret: Secret::new(identity),
@@ -520,6 +539,7 @@ impl Behaviour {
                     state_diffs_sync,
                     transactions_sync,
                     events_sync,
+                    snapshots_sync,
                 },
             },
             relay_transport,
@@ -546,6 +566,23 @@ impl Behaviour {
         Ok(())
     }
 
+    /// Reports whether a received gossipsub message is valid. Only valid
+    /// messages are forwarded to other peers.
+    pub fn report_message_validation(
+        &mut self,
+        message_id: &MessageId,
+        propagation_source: &PeerId,
+        acceptance: MessageAcceptance,
+    ) {
+        if let Err(error) = self.inner.gossipsub.report_message_validation_result(
+            message_id,
+            propagation_source,
+            acceptance,
+        ) {
+            tracing::debug!(%message_id, %error, "Reporting message validation result failed");
+        }
+    }
+
     /// Notify the behaviour of a ping event.
     pub fn pinged(&mut self, event: ping::Event) {
         match event.result {
@@ -781,6 +818,16 @@ impl Behaviour {
         }
     }
 
+    /// Prevent peers with a too low reputation from connecting while they are
+    /// banned.
+    fn prevent_banned_peer_connections(&self, peer_id: PeerId) -> Result<(), ConnectionDenied> {
+        if self.reputations.is_banned(&peer_id) {
+            tracing::debug!(%peer_id, "Banned peer attempting to connect, disconnecting");
+            return Err(ConnectionDenied::new("peer is banned"));
+        }
+        Ok(())
+    }
+
     /// Get the IP address from a multiaddr, or disconnect the peer if it
     /// doesn't have one.
     fn get_ip(addr: &Multiaddr) -> Result<IpAddr, ConnectionDenied> {
@@ -802,6 +849,47 @@ impl Behaviour {
         });
     }
 
+    /// Lower the reputation of a misbehaving peer, and disconnect it if it got
+    /// banned as a result.
+    pub fn report(&mut self, peer_id: PeerId, misbehaviour: Misbehaviour) {
+        let banned = self.reputations.penalize(peer_id, misbehaviour);
+        tracing::debug!(%peer_id, ?misbehaviour, score=%self.reputations.score(&peer_id), %banned, "Peer penalized");
+
+        if !banned
+            || !self
+                .peers
+                .get(peer_id)
+                .map_or(false, |peer| peer.is_connected())
+        {
+            return;
+        }
+
+        tracing::debug!(%peer_id, "Disconnecting banned peer");
+        self.peers.update(peer_id, |peer| {
+            peer.connectivity = Connectivity::Disconnecting {
+                connected_at: peer.connected_at(),
+            };
+        });
+        tokio::spawn({
+            let swarm = self.swarm.clone();
+            async move {
+                if let Err(e) = swarm.disconnect(peer_id).await {
+                    tracing::debug!(%peer_id, %e, "Failed to disconnect banned peer");
+                }
+            }
+        });
+    }
+
+    /// Orders peers by descending reputation and removes banned peers.
+    pub fn rank_peers(&mut self, peers: Vec<PeerId>) -> Vec<PeerId> {
+        self.reputations.rank(peers)
+    }
+
+    /// Returns the reputations of all penalized peers, lowest score first.
+    pub fn peer_reputations(&mut self) -> Vec<PeerReputation> {
+        self.reputations.penalized()
+    }
+
     pub fn kademlia_mut(&mut self) -> &mut kad::Behaviour<MemoryStore> {
         &mut self.inner.kademlia
     }
@@ -830,6 +918,10 @@ impl Behaviour {
         &mut self.inner.events_sync
     }
 
+    pub fn snapshots_sync_mut(&mut self) -> &mut p2p_stream::Behaviour<codec::Snapshots> {
+        &mut self.inner.snapshots_sync
+    }
+
     pub fn peers(&self) -> impl Iterator<Item = (PeerId, &Peer)> {
         self.peers.iter()
     }
@@ -879,6 +971,7 @@ pub enum Event {
     StateDiffsSync(p2p_stream::Event<StateDiffsRequest, StateDiffsResponse>),
     TransactionsSync(p2p_stream::Event<TransactionsRequest, TransactionsResponse>),
     EventsSync(p2p_stream::Event<EventsRequest, EventsResponse>),
+    SnapshotsSync(p2p_stream::Event<SnapshotRequest, SnapshotResponse>),
 }
 
 impl From<relay::client::Event> for Event {
@@ -953,6 +1046,12 @@ impl From<p2p_stream::Event<EventsRequest, EventsResponse>> for Event {
     }
 }
 
+impl From<p2p_stream::Event<SnapshotRequest, SnapshotResponse>> for Event {
+    fn from(event: p2p_stream::Event<SnapshotRequest, SnapshotResponse>) -> Self {
+        Event::SnapshotsSync(event)
+    }
+}
+
 fn string_to_key(input: &str) -> kad::RecordKey {
     use sha2::{Digest, Sha256};
     let mut hasher = Sha256::new();
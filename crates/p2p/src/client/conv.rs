@@ -52,6 +52,7 @@ use pathfinder_common::transaction::{
     ResourceBounds,
     TransactionVariant,
 };
+use pathfinder_common::trie::TrieNode;
 use pathfinder_common::{
     AccountDeploymentDataElem,
     ByteCodeOffset,
@@ -430,6 +431,21 @@ impl ToDto<p2p_proto::common::L1DataAvailabilityMode> for L1DataAvailabilityMode
     }
 }
 
+impl ToDto<p2p_proto::snapshot::PatriciaNode> for TrieNode {
+    fn to_dto(self) -> p2p_proto::snapshot::PatriciaNode {
+        use p2p_proto::snapshot::PatriciaNode;
+        match self {
+            TrieNode::Binary { left, right } => PatriciaNode::Binary { left, right },
+            TrieNode::Edge { child, path } => PatriciaNode::Edge {
+                length: path.len() as u32,
+                // Safe as the path of an edge is at most 251 bits long.
+                path: Felt::from_bits(&path).expect("Edge path fits in a felt"),
+                child,
+            },
+        }
+    }
+}
+
 impl TryFromDto<p2p_proto::transaction::Transaction> for TransactionVariant {
     fn try_from_dto(dto: p2p_proto::transaction::Transaction) -> anyhow::Result<Self>
     where
@@ -823,6 +839,38 @@ impl TryFromDto<p2p_proto::common::L1DataAvailabilityMode> for L1DataAvailabilit
     }
 }
 
+impl TryFromDto<p2p_proto::snapshot::PatriciaNode> for TrieNode {
+    fn try_from_dto(dto: p2p_proto::snapshot::PatriciaNode) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        use p2p_proto::snapshot::PatriciaNode;
+        Ok(match dto {
+            PatriciaNode::Binary { left, right } => Self::Binary { left, right },
+            PatriciaNode::Edge {
+                length,
+                path,
+                child,
+            } => {
+                let length = length as usize;
+                anyhow::ensure!(
+                    (1..=251).contains(&length),
+                    "Invalid edge path length: {length}"
+                );
+                let bits = path.view_bits();
+                anyhow::ensure!(
+                    bits[..251 - length].not_any(),
+                    "Edge path does not fit in {length} bits"
+                );
+                Self::Edge {
+                    child,
+                    path: bits[251 - length..].to_bitvec(),
+                }
+            }
+        })
+    }
+}
+
 #[derive(Debug)]
 pub struct CairoDefinition(pub Vec<u8>);
 
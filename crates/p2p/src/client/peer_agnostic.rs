@@ -7,11 +7,13 @@ use std::time::{Duration, Instant};
 
 use futures::channel::mpsc as fmpsc;
 use futures::{Stream, StreamExt};
+use libp2p::gossipsub::{MessageAcceptance, MessageId};
 use libp2p::PeerId;
 use p2p_proto::class::{ClassesRequest, ClassesResponse};
 use p2p_proto::common::{Direction, Iteration};
 use p2p_proto::event::{EventsRequest, EventsResponse};
 use p2p_proto::header::{BlockHeadersRequest, BlockHeadersResponse};
+use p2p_proto::snapshot::{SnapshotRequest, SnapshotResponse};
 use p2p_proto::state::{
     ContractDiff,
     ContractStoredValue,
@@ -19,7 +21,12 @@ use p2p_proto::state::{
     StateDiffsRequest,
     StateDiffsResponse,
 };
-use p2p_proto::transaction::{TransactionWithReceipt, TransactionsRequest, TransactionsResponse};
+use p2p_proto::transaction::{
+    BroadcastedTransaction,
+    TransactionWithReceipt,
+    TransactionsRequest,
+    TransactionsResponse,
+};
 use pathfinder_common::event::Event;
 use pathfinder_common::state_update::{ContractClassUpdate, StateUpdateData};
 use pathfinder_common::transaction::TransactionVariant;
@@ -50,6 +57,7 @@ use traits::{
     ClassStream,
     EventStream,
     HeaderStream,
+    SnapshotClient,
     StateDiffStream,
     StreamItem,
     TransactionStream,
@@ -66,19 +74,26 @@ use crate::client::types::{
     TransactionData,
 };
 use crate::peer_data::PeerData;
+use crate::{Misbehaviour, PeerReputation};
 
 #[derive(Clone, Debug)]
 pub struct Client {
     inner: peer_aware::Client,
     block_propagation_topic: Arc<String>,
+    transaction_propagation_topic: Arc<String>,
     peers: Arc<RwLock<Decaying<HashSet<PeerId>>>>,
 }
 
 impl Client {
-    pub fn new(inner: peer_aware::Client, block_propagation_topic: String) -> Self {
+    pub fn new(
+        inner: peer_aware::Client,
+        block_propagation_topic: String,
+        transaction_propagation_topic: String,
+    ) -> Self {
         Self {
             inner,
             block_propagation_topic: Arc::new(block_propagation_topic),
+            transaction_propagation_topic: Arc::new(transaction_propagation_topic),
             peers: Default::default(),
         }
     }
@@ -100,6 +115,47 @@ impl Client {
             .await
     }
 
+    /// Propagate a transaction which is not yet included in a block.
+    pub async fn propagate_transaction(
+        &self,
+        transaction: BroadcastedTransaction,
+    ) -> anyhow::Result<()> {
+        tracing::debug!(topic=%self.transaction_propagation_topic, "Propagating transaction");
+
+        self.inner
+            .publish_transaction(&self.transaction_propagation_topic, transaction)
+            .await
+    }
+
+    /// Report whether a transaction received via
+    /// [Event::TransactionPropagation](crate::Event::TransactionPropagation) is
+    /// valid. Only valid transactions are forwarded to other peers.
+    pub async fn report_transaction_validation(
+        &self,
+        message_id: MessageId,
+        from: PeerId,
+        acceptance: MessageAcceptance,
+    ) {
+        self.inner
+            .report_message_validation(message_id, from, acceptance)
+            .await
+    }
+
+    /// Lower the reputation of a peer which sent invalid data.
+    ///
+    /// Peers with a low reputation are tried last, and get banned and
+    /// disconnected if their reputation drops too low.
+    pub async fn report_peer(&self, peer: PeerId, misbehaviour: Misbehaviour) {
+        self.inner.report_peer(peer, misbehaviour).await
+    }
+
+    /// Returns the reputations of all penalized peers, lowest score first.
+    pub async fn peer_reputations(&self) -> Vec<PeerReputation> {
+        self.inner.peer_reputations().await
+    }
+
+    /// Returns the known peers in random order, except that peers with a
+    /// lower reputation come last. Banned peers are left out.
     async fn get_random_peers(&self) -> Vec<PeerId> {
         use rand::seq::SliceRandom;
 
@@ -113,25 +169,25 @@ impl Client {
             // Check again because the previous lock in the queue might have been a write
             // lock that has already updated the peers.
             if let Some(peers) = w.get() {
-                return peers.iter().copied().collect::<Vec<_>>();
-            }
-
-            let mut peers = self
-                .inner
-                .get_closest_peers(PeerId::random())
-                .await
-                .unwrap_or_default();
+                peers.iter().copied().collect::<Vec<_>>()
+            } else {
+                let mut peers = self
+                    .inner
+                    .get_closest_peers(PeerId::random())
+                    .await
+                    .unwrap_or_default();
 
-            // We could be on the list
-            peers.remove(self.inner.peer_id());
+                // We could be on the list
+                peers.remove(self.inner.peer_id());
 
-            let peers_vec = peers.iter().copied().collect::<Vec<_>>();
+                let peers_vec = peers.iter().copied().collect::<Vec<_>>();
 
-            w.update(peers);
-            peers_vec
+                w.update(peers);
+                peers_vec
+            }
         };
         peers.shuffle(&mut rand::thread_rng());
-        peers
+        self.inner.rank_peers(peers).await
     }
 }
 
@@ -272,6 +328,36 @@ impl EventStream for Client {
     }
 }
 
+impl SnapshotClient for Client {
+    async fn snapshot_range(self, request: SnapshotRequest) -> Option<PeerData<SnapshotResponse>> {
+        let peers = self.get_random_peers().await;
+
+        for peer in peers {
+            let Ok(mut stream) = self
+                .inner
+                .send_snapshots_sync_request(peer, request)
+                .await
+                .inspect_err(|error| tracing::debug!(%peer, %error, "Snapshot request failed"))
+            else {
+                continue;
+            };
+
+            match stream.next().await {
+                Some(SnapshotResponse::Fin) | None => {
+                    tracing::debug!(%peer, "Peer does not serve the requested snapshot data");
+                }
+                Some(response) => return Some(PeerData::new(peer, response)),
+            }
+        }
+
+        None
+    }
+
+    async fn report_peer(self, peer: PeerId, misbehaviour: Misbehaviour) {
+        self.inner.report_peer(peer, misbehaviour).await
+    }
+}
+
 impl BlockClient for Client {
     async fn transactions_for_block(
         self,
@@ -1,5 +1,6 @@
 use futures::{Future, Stream};
 use libp2p::PeerId;
+use p2p_proto::snapshot::{SnapshotRequest, SnapshotResponse};
 use pathfinder_common::event::Event;
 use pathfinder_common::state_update::StateUpdateData;
 use pathfinder_common::transaction::TransactionVariant;
@@ -13,7 +14,7 @@ use crate::client::types::{
     Receipt,
     TransactionData,
 };
-use crate::PeerData;
+use crate::{Misbehaviour, PeerData};
 
 pub type StreamItem<T> = Result<PeerData<T>, PeerData<anyhow::Error>>;
 
@@ -102,3 +103,23 @@ pub trait BlockClient {
         block: BlockNumber,
     ) -> impl Future<Output = Option<(PeerId, impl Stream<Item = (TransactionHash, Event)> + Send)>> + Send;
 }
+
+pub trait SnapshotClient {
+    /// Requests a single range of one of the state tries, or a class
+    /// definition, trying peers until one of them serves it.
+    ///
+    /// Peers which do not have the tries of the requested block only respond
+    /// with `Fin` and are skipped. The response is returned as is, verifying it
+    /// is left to the caller.
+    fn snapshot_range(
+        self,
+        request: SnapshotRequest,
+    ) -> impl Future<Output = Option<PeerData<SnapshotResponse>>> + Send;
+
+    /// Lower the reputation of a peer which sent invalid snapshot data.
+    fn report_peer(
+        self,
+        peer: PeerId,
+        misbehaviour: Misbehaviour,
+    ) -> impl Future<Output = ()> + Send;
+}
@@ -5,18 +5,19 @@ use std::collections::HashSet;
 
 use anyhow::Context;
 use futures::channel::mpsc::Receiver as ResponseReceiver;
-use libp2p::gossipsub::IdentTopic;
+use libp2p::gossipsub::{IdentTopic, MessageAcceptance, MessageId};
 use libp2p::{Multiaddr, PeerId};
 use p2p_proto::class::{ClassesRequest, ClassesResponse};
 use p2p_proto::event::{EventsRequest, EventsResponse};
 use p2p_proto::header::{BlockHeadersRequest, BlockHeadersResponse, NewBlock};
+use p2p_proto::snapshot::{SnapshotRequest, SnapshotResponse};
 use p2p_proto::state::{StateDiffsRequest, StateDiffsResponse};
-use p2p_proto::transaction::{TransactionsRequest, TransactionsResponse};
+use p2p_proto::transaction::{BroadcastedTransaction, TransactionsRequest, TransactionsResponse};
 use tokio::sync::{mpsc, oneshot};
 
 #[cfg(test)]
 use crate::test_utils;
-use crate::Command;
+use crate::{Command, Misbehaviour, PeerReputation};
 
 #[derive(Clone, Debug)]
 pub struct Client {
@@ -199,6 +200,13 @@ impl Client {
         EventsResponse
     );
 
+    impl_send!(
+        send_snapshots_sync_request,
+        SendSnapshotsSyncRequest,
+        SnapshotRequest,
+        SnapshotResponse
+    );
+
     pub async fn publish(&self, topic: &str, new_block: NewBlock) -> anyhow::Result<()> {
         let (sender, receiver) = oneshot::channel();
         let topic = IdentTopic::new(topic);
@@ -213,6 +221,45 @@ impl Client {
         receiver.await.expect("Sender not to be dropped")
     }
 
+    pub async fn publish_transaction(
+        &self,
+        topic: &str,
+        transaction: BroadcastedTransaction,
+    ) -> anyhow::Result<()> {
+        let (sender, receiver) = oneshot::channel();
+        let topic = IdentTopic::new(topic);
+        self.sender
+            .send(Command::PublishTransaction {
+                topic,
+                transaction,
+                sender,
+            })
+            .await
+            .expect("Command receiver not to be dropped");
+        receiver.await.expect("Sender not to be dropped")
+    }
+
+    /// Reports whether a gossipsub message received from `propagation_source`
+    /// is valid. Only valid messages are forwarded to other peers.
+    pub async fn report_message_validation(
+        &self,
+        message_id: MessageId,
+        propagation_source: PeerId,
+        acceptance: MessageAcceptance,
+    ) {
+        let (sender, receiver) = oneshot::channel();
+        self.sender
+            .send(Command::ReportMessageValidation {
+                message_id,
+                propagation_source,
+                acceptance,
+                sender,
+            })
+            .await
+            .expect("Command receiver not to be dropped");
+        receiver.await.expect("Sender not to be dropped")
+    }
+
     /// Mark a peer as not useful.
     ///
     /// These peers will be candidates for outbound peer eviction.
@@ -225,6 +272,42 @@ impl Client {
         receiver.await.expect("Sender not to be dropped")
     }
 
+    /// Lower the reputation of a peer which misbehaved.
+    ///
+    /// Peers with a low reputation get banned and disconnected.
+    pub async fn report_peer(&self, peer_id: PeerId, misbehaviour: Misbehaviour) {
+        let (sender, receiver) = oneshot::channel();
+        self.sender
+            .send(Command::ReportPeer {
+                peer_id,
+                misbehaviour,
+                sender,
+            })
+            .await
+            .expect("Command receiver not to be dropped");
+        receiver.await.expect("Sender not to be dropped")
+    }
+
+    /// Orders peers by descending reputation, removing banned peers.
+    pub async fn rank_peers(&self, peers: Vec<PeerId>) -> Vec<PeerId> {
+        let (sender, receiver) = oneshot::channel();
+        self.sender
+            .send(Command::RankPeers { peers, sender })
+            .await
+            .expect("Command receiver not to be dropped");
+        receiver.await.expect("Sender not to be dropped")
+    }
+
+    /// Returns the reputations of all penalized peers, lowest score first.
+    pub async fn peer_reputations(&self) -> Vec<PeerReputation> {
+        let (sender, receiver) = oneshot::channel();
+        self.sender
+            .send(Command::PeerReputations { sender })
+            .await
+            .expect("Command receiver not to be dropped");
+        receiver.await.expect("Sender not to be dropped")
+    }
+
     #[cfg(test)]
     pub(crate) fn for_test(&self) -> test_utils::Client {
         test_utils::Client::new(self.sender.clone())
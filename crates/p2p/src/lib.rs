@@ -4,15 +4,16 @@ use std::time::Duration;
 
 use futures::channel::mpsc::{Receiver as ResponseReceiver, Sender as ResponseSender};
 use ipnet::IpNet;
-use libp2p::gossipsub::IdentTopic;
+use libp2p::gossipsub::{IdentTopic, MessageAcceptance, MessageId};
 use libp2p::identity::Keypair;
 use libp2p::kad::RecordKey;
 use libp2p::{swarm, Multiaddr, PeerId, Swarm};
 use p2p_proto::class::{ClassesRequest, ClassesResponse};
 use p2p_proto::event::{EventsRequest, EventsResponse};
 use p2p_proto::header::{BlockHeadersRequest, BlockHeadersResponse, NewBlock};
+use p2p_proto::snapshot::{SnapshotRequest, SnapshotResponse};
 use p2p_proto::state::{StateDiffsRequest, StateDiffsResponse};
-use p2p_proto::transaction::{TransactionsRequest, TransactionsResponse};
+use p2p_proto::transaction::{BroadcastedTransaction, TransactionsRequest, TransactionsResponse};
 use pathfinder_common::{BlockHash, BlockNumber, ChainId};
 use peers::Peer;
 use tokio::sync::{mpsc, oneshot};
@@ -22,6 +23,7 @@ pub mod client;
 mod main_loop;
 mod peer_data;
 mod peers;
+mod reputation;
 mod secret;
 mod sync;
 #[cfg(test)]
@@ -30,11 +32,12 @@ mod test_utils;
 mod tests;
 mod transport;
 
-pub use behaviour::kademlia_protocol_name;
+pub use behaviour::{kademlia_protocol_name, transaction_propagation_topic};
 use client::peer_aware::Client;
 pub use libp2p;
 use main_loop::MainLoop;
 pub use peer_data::PeerData;
+pub use reputation::{Misbehaviour, PeerReputation};
 pub use sync::protocol::PROTOCOLS;
 
 pub fn new(keypair: Keypair, cfg: Config, chain_id: ChainId) -> (Client, EventReceiver, MainLoop) {
@@ -82,6 +85,8 @@ pub struct Config {
     pub low_watermark: usize,
     /// How long to prevent evicted peers from reconnecting.
     pub eviction_timeout: Duration,
+    /// How long to ban peers whose reputation dropped too low.
+    pub ban_duration: Duration,
     pub ip_whitelist: Vec<IpNet>,
     pub bootstrap: BootstrapConfig,
     pub inbound_connections_rate_limit: RateLimit,
@@ -175,15 +180,43 @@ enum Command {
         request: EventsRequest,
         sender: oneshot::Sender<anyhow::Result<ResponseReceiver<EventsResponse>>>,
     },
+    SendSnapshotsSyncRequest {
+        peer_id: PeerId,
+        request: SnapshotRequest,
+        sender: oneshot::Sender<anyhow::Result<ResponseReceiver<SnapshotResponse>>>,
+    },
     PublishPropagationMessage {
         topic: IdentTopic,
         new_block: NewBlock,
         sender: EmptyResultSender,
     },
+    PublishTransaction {
+        topic: IdentTopic,
+        transaction: BroadcastedTransaction,
+        sender: EmptyResultSender,
+    },
+    ReportMessageValidation {
+        message_id: MessageId,
+        propagation_source: PeerId,
+        acceptance: MessageAcceptance,
+        sender: oneshot::Sender<()>,
+    },
     NotUseful {
         peer_id: PeerId,
         sender: oneshot::Sender<()>,
     },
+    ReportPeer {
+        peer_id: PeerId,
+        misbehaviour: Misbehaviour,
+        sender: oneshot::Sender<()>,
+    },
+    RankPeers {
+        peers: Vec<PeerId>,
+        sender: oneshot::Sender<Vec<PeerId>>,
+    },
+    PeerReputations {
+        sender: oneshot::Sender<Vec<PeerReputation>>,
+    },
     /// For testing purposes only
     _Test(TestCommand),
 }
@@ -224,10 +257,23 @@ pub enum Event {
         request: EventsRequest,
         channel: ResponseSender<EventsResponse>,
     },
+    InboundSnapshotsSyncRequest {
+        from: PeerId,
+        request: SnapshotRequest,
+        channel: ResponseSender<SnapshotResponse>,
+    },
     BlockPropagation {
         from: PeerId,
         new_block: NewBlock,
     },
+    /// A transaction was received on the transaction propagation topic. It is
+    /// only forwarded to other peers once it was reported as valid, see
+    /// [Client::report_message_validation].
+    TransactionPropagation {
+        from: PeerId,
+        message_id: MessageId,
+        transaction: BroadcastedTransaction,
+    },
     /// For testing purposes only
     Test(TestEvent),
 }
@@ -3,7 +3,7 @@ use std::fmt::Debug;
 
 use futures::channel::mpsc::Receiver as ResponseReceiver;
 use futures::StreamExt;
-use libp2p::gossipsub::{self, IdentTopic};
+use libp2p::gossipsub::{self, IdentTopic, MessageAcceptance, MessageId};
 use libp2p::kad::{
     self,
     BootstrapError,
@@ -20,8 +20,9 @@ use libp2p::{identify, PeerId};
 use p2p_proto::class::ClassesResponse;
 use p2p_proto::event::EventsResponse;
 use p2p_proto::header::BlockHeadersResponse;
+use p2p_proto::snapshot::SnapshotResponse;
 use p2p_proto::state::StateDiffsResponse;
-use p2p_proto::transaction::TransactionsResponse;
+use p2p_proto::transaction::{BroadcastedTransaction, TransactionsResponse};
 use p2p_proto::{ToProtobuf, TryFromProtobuf};
 use p2p_stream::{self, OutboundRequestId};
 use pathfinder_common::ChainId;
@@ -30,7 +31,16 @@ use tokio::time::Duration;
 
 #[cfg(test)]
 use crate::test_utils;
-use crate::{behaviour, Command, Config, EmptyResultSender, Event, TestCommand, TestEvent};
+use crate::{
+    behaviour,
+    Command,
+    Config,
+    EmptyResultSender,
+    Event,
+    Misbehaviour,
+    TestCommand,
+    TestEvent,
+};
 
 pub struct MainLoop {
     cfg: crate::Config,
@@ -75,6 +85,10 @@ struct PendingRequests {
         OutboundRequestId,
         oneshot::Sender<anyhow::Result<ResponseReceiver<EventsResponse>>>,
     >,
+    pub snapshots: HashMap<
+        OutboundRequestId,
+        oneshot::Sender<anyhow::Result<ResponseReceiver<SnapshotResponse>>>,
+    >,
 }
 
 #[derive(Debug, Default)]
@@ -336,43 +350,22 @@ impl MainLoop {
                 self.swarm.behaviour_mut().pinged(event);
             }
             // ===========================
-            // Block propagation
+            // Block and transaction propagation
             // ===========================
             SwarmEvent::Behaviour(behaviour::Event::Gossipsub(gossipsub::Event::Message {
                 propagation_source: peer_id,
                 message_id: id,
                 message,
             })) => {
-                use prost::Message;
+                let transaction_topic =
+                    IdentTopic::new(behaviour::transaction_propagation_topic(self.chain_id));
 
-                match p2p_proto::proto::header::NewBlock::decode(message.data.as_ref()) {
-                    Ok(new_block) => {
-                        match p2p_proto::header::NewBlock::try_from_protobuf(new_block, "message") {
-                            Ok(new_block) => {
-                                tracing::trace!(
-                                    "Gossipsub Message: [id={}][peer={}] {:?} ({} bytes)",
-                                    id,
-                                    peer_id,
-                                    new_block,
-                                    message.data.len()
-                                );
-                                self.event_sender
-                                    .send(Event::BlockPropagation {
-                                        from: peer_id,
-                                        new_block,
-                                    })
-                                    .await
-                                    .expect("Event receiver not to be dropped");
-                            }
-                            Err(error) => {
-                                tracing::error!(from=%peer_id, %error, "Gossipsub Message")
-                            }
-                        }
-                    }
-                    Err(error) => {
-                        tracing::error!(from=%peer_id, %error, "Gossipsub Message");
-                    }
-                };
+                if message.topic == transaction_topic.hash() {
+                    self.handle_transaction_propagation(peer_id, id, message)
+                        .await;
+                } else {
+                    self.handle_block_propagation(peer_id, id, message).await;
+                }
             }
             // ===========================
             // Discovery
@@ -669,6 +662,41 @@ impl MainLoop {
                     .expect("Event sync request still to be pending")
                     .send(Ok(channel));
             }
+            SwarmEvent::Behaviour(behaviour::Event::SnapshotsSync(
+                p2p_stream::Event::InboundRequest {
+                    request_id,
+                    request,
+                    peer,
+                    channel,
+                },
+            )) => {
+                tracing::debug!(?request, %peer, %request_id, "Received sync request");
+
+                self.event_sender
+                    .send(Event::InboundSnapshotsSyncRequest {
+                        from: peer,
+                        request,
+                        channel,
+                    })
+                    .await
+                    .expect("Event receiver not to be dropped");
+            }
+            SwarmEvent::Behaviour(behaviour::Event::SnapshotsSync(
+                p2p_stream::Event::OutboundRequestSentAwaitingResponses {
+                    request_id,
+                    peer,
+                    channel,
+                },
+            )) => {
+                tracing::debug!(%peer, %request_id, "Snapshot sync request sent");
+
+                let _ = self
+                    .pending_sync_requests
+                    .snapshots
+                    .remove(&request_id)
+                    .expect("Snapshot sync request still to be pending")
+                    .send(Ok(channel));
+            }
             SwarmEvent::Behaviour(behaviour::Event::HeadersSync(
                 p2p_stream::Event::OutboundFailure {
                     request_id, error, ..
@@ -735,6 +763,16 @@ impl MainLoop {
                     let _ = sender.send(Err(error.into()));
                 }
             }
+            SwarmEvent::Behaviour(behaviour::Event::SnapshotsSync(
+                p2p_stream::Event::OutboundFailure {
+                    request_id, error, ..
+                },
+            )) => {
+                tracing::warn!(?request_id, ?error, "Outbound snapshot sync request failed");
+                if let Some(sender) = self.pending_sync_requests.snapshots.remove(&request_id) {
+                    let _ = sender.send(Err(error.into()));
+                }
+            }
             // ===========================
             // NAT hole punching
             // ===========================
@@ -914,6 +952,22 @@ impl MainLoop {
                     .send_request(&peer_id, request);
                 self.pending_sync_requests.events.insert(request_id, sender);
             }
+            Command::SendSnapshotsSyncRequest {
+                peer_id,
+                request,
+                sender,
+            } => {
+                tracing::debug!(?request, "Sending sync request");
+
+                let request_id = self
+                    .swarm
+                    .behaviour_mut()
+                    .snapshots_sync_mut()
+                    .send_request(&peer_id, request);
+                self.pending_sync_requests
+                    .snapshots
+                    .insert(request_id, sender);
+            }
             Command::PublishPropagationMessage {
                 topic,
                 new_block,
@@ -924,14 +978,144 @@ impl MainLoop {
                 let result = self.publish_data(topic, &data);
                 let _ = sender.send(result);
             }
+            Command::PublishTransaction {
+                topic,
+                transaction,
+                sender,
+            } => {
+                use prost::Message;
+                let data: Vec<u8> = transaction.to_protobuf().encode_to_vec();
+                let result = self.publish_data(topic, &data);
+                let _ = sender.send(result);
+            }
+            Command::ReportMessageValidation {
+                message_id,
+                propagation_source,
+                acceptance,
+                sender,
+            } => {
+                self.swarm.behaviour_mut().report_message_validation(
+                    &message_id,
+                    &propagation_source,
+                    acceptance,
+                );
+                let _ = sender.send(());
+            }
             Command::NotUseful { peer_id, sender } => {
                 self.swarm.behaviour_mut().not_useful(peer_id);
                 let _ = sender.send(());
             }
+            Command::ReportPeer {
+                peer_id,
+                misbehaviour,
+                sender,
+            } => {
+                self.swarm.behaviour_mut().report(peer_id, misbehaviour);
+                let _ = sender.send(());
+            }
+            Command::RankPeers { peers, sender } => {
+                let _ = sender.send(self.swarm.behaviour_mut().rank_peers(peers));
+            }
+            Command::PeerReputations { sender } => {
+                let _ = sender.send(self.swarm.behaviour_mut().peer_reputations());
+            }
             Command::_Test(command) => self.handle_test_command(command).await,
         };
     }
 
+    async fn handle_block_propagation(
+        &mut self,
+        peer_id: PeerId,
+        id: MessageId,
+        message: gossipsub::Message,
+    ) {
+        use prost::Message;
+
+        let new_block = p2p_proto::proto::header::NewBlock::decode(message.data.as_ref())
+            .map_err(anyhow::Error::from)
+            .and_then(|new_block| {
+                p2p_proto::header::NewBlock::try_from_protobuf(new_block, "message")
+                    .map_err(anyhow::Error::from)
+            });
+
+        match new_block {
+            Ok(new_block) => {
+                tracing::trace!(
+                    "Gossipsub Message: [id={}][peer={}] {:?} ({} bytes)",
+                    id,
+                    peer_id,
+                    new_block,
+                    message.data.len()
+                );
+                self.swarm.behaviour_mut().report_message_validation(
+                    &id,
+                    &peer_id,
+                    MessageAcceptance::Accept,
+                );
+                self.event_sender
+                    .send(Event::BlockPropagation {
+                        from: peer_id,
+                        new_block,
+                    })
+                    .await
+                    .expect("Event receiver not to be dropped");
+            }
+            Err(error) => {
+                tracing::error!(from=%peer_id, %error, "Gossipsub Message");
+                self.swarm.behaviour_mut().report_message_validation(
+                    &id,
+                    &peer_id,
+                    MessageAcceptance::Reject,
+                );
+            }
+        }
+    }
+
+    async fn handle_transaction_propagation(
+        &mut self,
+        peer_id: PeerId,
+        id: MessageId,
+        message: gossipsub::Message,
+    ) {
+        use prost::Message;
+
+        let transaction =
+            p2p_proto::proto::transaction::BroadcastedTransaction::decode(message.data.as_ref())
+                .map_err(anyhow::Error::from)
+                .and_then(|transaction| {
+                    BroadcastedTransaction::try_from_protobuf(transaction, "message")
+                        .map_err(anyhow::Error::from)
+                });
+
+        match transaction {
+            Ok(transaction) => {
+                tracing::trace!(
+                    "Gossipsub Message: [id={}][peer={}] {:?} ({} bytes)",
+                    id,
+                    peer_id,
+                    transaction,
+                    message.data.len()
+                );
+                // The transaction is validated by the receiver of the event, which reports the
+                // result back so that valid transactions get forwarded.
+                self.event_sender
+                    .send(Event::TransactionPropagation {
+                        from: peer_id,
+                        message_id: id,
+                        transaction,
+                    })
+                    .await
+                    .expect("Event receiver not to be dropped");
+            }
+            Err(error) => {
+                tracing::debug!(from=%peer_id, %error, "Malformed transaction gossipsub message");
+                let behaviour = self.swarm.behaviour_mut();
+                behaviour.report_message_validation(&id, &peer_id, MessageAcceptance::Reject);
+                behaviour.report(peer_id, Misbehaviour::MalformedData);
+            }
+        }
+    }
+
     fn publish_data(&mut self, topic: IdentTopic, data: &[u8]) -> anyhow::Result<()> {
         let message_id = self
             .swarm
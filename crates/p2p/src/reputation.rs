@@ -0,0 +1,301 @@
+//! Reputation scores of peers, based on the data they serve us while syncing.
+//!
+//! Every peer starts with a neutral score of zero. Each reported
+//! [Misbehaviour] lowers the score by its [penalty](Misbehaviour::penalty),
+//! after which the score slowly recovers back towards zero. Peers with lower
+//! scores are tried last when selecting peers for sync requests, and peers
+//! whose score drops to [BAN_THRESHOLD] are banned for a while.
+//!
+//! The scores of all penalized peers can be listed with
+//! [peer_reputations](crate::client::peer_aware::Client::peer_reputations),
+//! while metrics only count the banned and recovering peers so that their
+//! cardinality stays bounded.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use libp2p::PeerId;
+
+/// Peers are banned once their score drops to or below this value.
+pub const BAN_THRESHOLD: i32 = -100;
+
+/// How many penalty points a peer recovers per minute.
+const RECOVERY_PER_MINUTE: i32 = 1;
+
+/// Misbehaviour of a peer which was detected while processing data it sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Misbehaviour {
+    /// The data failed signature verification.
+    BadSignature,
+    /// The data does not match a hash or commitment it is bound to, e.g. a
+    /// state diff which does not match the state diff commitment.
+    CommitmentMismatch,
+    /// The data could not be parsed, e.g. a malformed class definition.
+    MalformedData,
+    /// The data is well formed but not what was requested, e.g. a header which
+    /// does not extend the chain, or too many transactions for a block.
+    UnexpectedData,
+}
+
+impl Misbehaviour {
+    /// The number of points deducted from the peer's score.
+    pub fn penalty(&self) -> i32 {
+        match self {
+            Misbehaviour::BadSignature => 100,
+            Misbehaviour::CommitmentMismatch => 50,
+            Misbehaviour::MalformedData => 25,
+            Misbehaviour::UnexpectedData => 10,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Misbehaviour::BadSignature => "bad_signature",
+            Misbehaviour::CommitmentMismatch => "commitment_mismatch",
+            Misbehaviour::MalformedData => "malformed_data",
+            Misbehaviour::UnexpectedData => "unexpected_data",
+        }
+    }
+}
+
+/// The current reputation of a penalized peer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PeerReputation {
+    pub peer_id: PeerId,
+    pub score: i32,
+    pub banned: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Reputation {
+    score: i32,
+    updated_at: Instant,
+    banned_until: Option<Instant>,
+}
+
+impl Reputation {
+    /// The score including the recovery since the last update.
+    fn current_score(&self) -> i32 {
+        let minutes = self.updated_at.elapsed().as_secs() / 60;
+        let recovered = i32::try_from(minutes)
+            .unwrap_or(i32::MAX)
+            .saturating_mul(RECOVERY_PER_MINUTE);
+        self.score.saturating_add(recovered).min(0)
+    }
+
+    fn is_banned(&self) -> bool {
+        self.banned_until
+            .map_or(false, |banned_until| banned_until > Instant::now())
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Reputations {
+    peers: HashMap<PeerId, Reputation>,
+    /// How long peers stay banned once their score drops to the
+    /// [BAN_THRESHOLD].
+    ban_duration: Duration,
+}
+
+impl Reputations {
+    pub fn new(ban_duration: Duration) -> Self {
+        Self {
+            peers: HashMap::new(),
+            ban_duration,
+        }
+    }
+
+    pub fn score(&self, peer_id: &PeerId) -> i32 {
+        self.peers
+            .get(peer_id)
+            .map_or(0, |reputation| reputation.current_score())
+    }
+
+    pub fn is_banned(&self, peer_id: &PeerId) -> bool {
+        self.peers
+            .get(peer_id)
+            .map_or(false, |reputation| reputation.is_banned())
+    }
+
+    /// Lowers the peer's score. Returns `true` if the peer got banned as a
+    /// result.
+    pub fn penalize(&mut self, peer_id: PeerId, misbehaviour: Misbehaviour) -> bool {
+        let now = Instant::now();
+        let reputation = self.peers.entry(peer_id).or_insert(Reputation {
+            score: 0,
+            updated_at: now,
+            banned_until: None,
+        });
+
+        let was_banned = reputation.is_banned();
+        reputation.score = reputation
+            .current_score()
+            .saturating_sub(misbehaviour.penalty());
+        reputation.updated_at = now;
+
+        let banned = !was_banned && reputation.score <= BAN_THRESHOLD;
+        if banned {
+            reputation.banned_until = Some(now + self.ban_duration);
+        }
+
+        metrics::increment_counter!(
+            "p2p_peer_penalties_total",
+            "misbehaviour" => misbehaviour.as_str()
+        );
+        if banned {
+            metrics::increment_counter!("p2p_peer_bans_total");
+        }
+
+        self.refresh();
+
+        banned
+    }
+
+    /// Removes banned peers and orders the remaining ones by descending score.
+    ///
+    /// The sort is stable, so peers with equal scores keep their relative
+    /// order.
+    pub fn rank(&mut self, mut peers: Vec<PeerId>) -> Vec<PeerId> {
+        self.refresh();
+
+        peers.retain(|peer_id| !self.is_banned(peer_id));
+        peers.sort_by_key(|peer_id| std::cmp::Reverse(self.score(peer_id)));
+        peers
+    }
+
+    /// Returns the reputations of all penalized peers, lowest score first.
+    pub fn penalized(&mut self) -> Vec<PeerReputation> {
+        self.refresh();
+
+        let mut peers = self
+            .peers
+            .iter()
+            .map(|(peer_id, reputation)| PeerReputation {
+                peer_id: *peer_id,
+                score: reputation.current_score(),
+                banned: reputation.is_banned(),
+            })
+            .collect::<Vec<_>>();
+        peers.sort_by_key(|peer| peer.score);
+        peers
+    }
+
+    /// Forgets peers which have fully recovered and are no longer banned, and
+    /// updates the number of penalized peers in the metrics.
+    ///
+    /// Scores only recover lazily, so this is done whenever the reputations
+    /// are used instead of on a timer.
+    fn refresh(&mut self) {
+        self.peers
+            .retain(|_, reputation| reputation.is_banned() || reputation.current_score() < 0);
+
+        let banned = self
+            .peers
+            .values()
+            .filter(|reputation| reputation.is_banned())
+            .count();
+        metrics::gauge!("p2p_penalized_peers", banned as f64, "state" => "banned");
+        metrics::gauge!(
+            "p2p_penalized_peers",
+            (self.peers.len() - banned) as f64,
+            "state" => "recovering"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn peer_is_banned_once_threshold_is_reached() {
+        let mut reputations = Reputations::new(Duration::from_secs(60));
+        let peer = PeerId::random();
+
+        assert!(!reputations.penalize(peer, Misbehaviour::CommitmentMismatch));
+        assert_eq!(reputations.score(&peer), -50);
+        assert!(!reputations.is_banned(&peer));
+
+        assert!(!reputations.penalize(peer, Misbehaviour::MalformedData));
+        assert_eq!(reputations.score(&peer), -75);
+        assert!(!reputations.is_banned(&peer));
+
+        assert!(reputations.penalize(peer, Misbehaviour::CommitmentMismatch));
+        assert!(reputations.is_banned(&peer));
+
+        // Further penalties do not ban the peer again.
+        assert!(!reputations.penalize(peer, Misbehaviour::UnexpectedData));
+        assert!(reputations.is_banned(&peer));
+    }
+
+    #[test]
+    fn ban_expires() {
+        let mut reputations = Reputations::new(Duration::ZERO);
+        let peer = PeerId::random();
+
+        assert!(reputations.penalize(peer, Misbehaviour::BadSignature));
+        assert!(!reputations.is_banned(&peer));
+        assert_eq!(reputations.score(&peer), -100);
+    }
+
+    #[test]
+    fn rank() {
+        let mut reputations = Reputations::new(Duration::from_secs(60));
+        let good = PeerId::random();
+        let bad = PeerId::random();
+        let worse = PeerId::random();
+        let banned = PeerId::random();
+
+        reputations.penalize(bad, Misbehaviour::UnexpectedData);
+        reputations.penalize(worse, Misbehaviour::MalformedData);
+        reputations.penalize(banned, Misbehaviour::BadSignature);
+
+        let ranked = reputations.rank(vec![worse, banned, bad, good]);
+        assert_eq!(ranked, vec![good, bad, worse]);
+    }
+
+    #[test]
+    fn penalized() {
+        let mut reputations = Reputations::new(Duration::from_secs(60));
+        let bad = PeerId::random();
+        let banned = PeerId::random();
+        let recovered = PeerId::random();
+
+        reputations.penalize(bad, Misbehaviour::UnexpectedData);
+        reputations.penalize(banned, Misbehaviour::BadSignature);
+        reputations.penalize(recovered, Misbehaviour::UnexpectedData);
+        reputations.peers.get_mut(&recovered).unwrap().updated_at -= Duration::from_secs(60 * 60);
+
+        assert_eq!(
+            reputations.penalized(),
+            vec![
+                PeerReputation {
+                    peer_id: banned,
+                    score: -100,
+                    banned: true,
+                },
+                PeerReputation {
+                    peer_id: bad,
+                    score: -10,
+                    banned: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn recovered_peers_are_forgotten() {
+        let mut reputations = Reputations::new(Duration::ZERO);
+        let recovered = PeerId::random();
+        let penalized = PeerId::random();
+
+        reputations.penalize(recovered, Misbehaviour::UnexpectedData);
+        reputations.penalize(penalized, Misbehaviour::UnexpectedData);
+        reputations.peers.get_mut(&recovered).unwrap().updated_at -= Duration::from_secs(60 * 60);
+
+        reputations.rank(vec![]);
+
+        assert!(!reputations.peers.contains_key(&recovered));
+        assert!(reputations.peers.contains_key(&penalized));
+    }
+}
@@ -23,6 +23,7 @@ pub mod protocol {
     define_protocol!(Classes, "/starknet/classes/0.1.0-rc.0");
     define_protocol!(Transactions, "/starknet/transactions/0.1.0-rc.0");
     define_protocol!(Events, "/starknet/events/0.1.0-rc.0");
+    define_protocol!(Snapshots, "/starknet/snapshots/0.1.0-rc.0");
 
     pub const PROTOCOLS: &[&str] = &[
         Headers::NAME,
@@ -30,6 +31,7 @@ pub mod protocol {
         Classes::NAME,
         Transactions::NAME,
         Events::NAME,
+        Snapshots::NAME,
     ];
 }
 
@@ -38,7 +40,17 @@ pub(crate) mod codec {
 
     use async_trait::async_trait;
     use futures::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
-    use p2p_proto::{class, event, header, proto, state, transaction, ToProtobuf, TryFromProtobuf};
+    use p2p_proto::{
+        class,
+        event,
+        header,
+        proto,
+        snapshot,
+        state,
+        transaction,
+        ToProtobuf,
+        TryFromProtobuf,
+    };
     use p2p_stream::Codec;
 
     use super::protocol;
@@ -91,6 +103,15 @@ pub(crate) mod codec {
         ONE_MIB,
     >;
 
+    pub type Snapshots = SyncCodec<
+        protocol::Snapshots,
+        snapshot::SnapshotRequest,
+        snapshot::SnapshotResponse,
+        proto::snapshot::SnapshotRequest,
+        proto::snapshot::SnapshotResponse,
+        FOUR_MIB,
+    >;
+
     #[derive(Clone, Debug)]
     pub struct SyncCodec<Protocol, Req, Resp, ProstReq, ProstResp, const RESPONSE_SIZE_LIMIT: usize>(
         PhantomData<(Protocol, Req, Resp, ProstReq, ProstResp)>,
@@ -12,8 +12,9 @@ use libp2p::{Multiaddr, PeerId};
 use p2p_proto::class::{ClassesRequest, ClassesResponse};
 use p2p_proto::event::{EventsRequest, EventsResponse};
 use p2p_proto::header::{BlockHeadersRequest, BlockHeadersResponse, NewBlock};
+use p2p_proto::snapshot::{SnapshotRequest, SnapshotResponse};
 use p2p_proto::state::{StateDiffsRequest, StateDiffsResponse};
-use p2p_proto::transaction::{TransactionsRequest, TransactionsResponse};
+use p2p_proto::transaction::{BroadcastedTransaction, TransactionsRequest, TransactionsResponse};
 use pathfinder_common::ChainId;
 use rstest::rstest;
 use tokio::task::JoinHandle;
@@ -101,6 +102,7 @@ impl Default for TestPeer {
                 ip_whitelist: vec!["::/0".parse().unwrap(), "0.0.0.0/0".parse().unwrap()],
                 bootstrap: Default::default(),
                 eviction_timeout: Duration::from_secs(15 * 60),
+                ban_duration: Duration::from_secs(60 * 60),
                 inbound_connections_rate_limit: RateLimit {
                     max: 1000,
                     interval: Duration::from_secs(1),
@@ -264,6 +266,7 @@ async fn periodic_bootstrap() {
             start_offset: Duration::from_secs(1),
         },
         eviction_timeout: Duration::from_secs(15 * 60),
+        ban_duration: Duration::from_secs(60 * 60),
         inbound_connections_rate_limit: RateLimit {
             max: 1000,
             interval: Duration::from_secs(1),
@@ -416,6 +419,7 @@ async fn reconnect_too_quickly() {
             start_offset: Duration::from_secs(10),
         },
         eviction_timeout: Duration::from_secs(15 * 60),
+        ban_duration: Duration::from_secs(60 * 60),
         inbound_connections_rate_limit: RateLimit {
             max: 1000,
             interval: Duration::from_secs(1),
@@ -521,6 +525,7 @@ async fn duplicate_connection() {
             start_offset: Duration::from_secs(10),
         },
         eviction_timeout: Duration::from_secs(15 * 60),
+        ban_duration: Duration::from_secs(60 * 60),
         inbound_connections_rate_limit: RateLimit {
             max: 1000,
             interval: Duration::from_secs(1),
@@ -610,6 +615,7 @@ async fn outbound_peer_eviction() {
             start_offset: Duration::from_secs(10),
         },
         eviction_timeout: Duration::from_secs(15 * 60),
+        ban_duration: Duration::from_secs(60 * 60),
         inbound_connections_rate_limit: RateLimit {
             max: 1000,
             interval: Duration::from_secs(1),
@@ -742,6 +748,7 @@ async fn inbound_peer_eviction() {
             start_offset: Duration::from_secs(10),
         },
         eviction_timeout: Duration::from_secs(15 * 60),
+        ban_duration: Duration::from_secs(60 * 60),
         inbound_connections_rate_limit: RateLimit {
             max: 1000,
             interval: Duration::from_secs(1),
@@ -830,6 +837,7 @@ async fn evicted_peer_reconnection() {
             start_offset: Duration::from_secs(10),
         },
         eviction_timeout: Duration::from_secs(15 * 60),
+        ban_duration: Duration::from_secs(60 * 60),
         inbound_connections_rate_limit: RateLimit {
             max: 1000,
             interval: Duration::from_secs(1),
@@ -924,6 +932,7 @@ async fn ip_whitelist() {
             start_offset: Duration::from_secs(10),
         },
         eviction_timeout: Duration::from_secs(15 * 60),
+        ban_duration: Duration::from_secs(60 * 60),
         inbound_connections_rate_limit: RateLimit {
             max: 1000,
             interval: Duration::from_secs(1),
@@ -960,6 +969,7 @@ async fn ip_whitelist() {
             start_offset: Duration::from_secs(10),
         },
         eviction_timeout: Duration::from_secs(15 * 60),
+        ban_duration: Duration::from_secs(60 * 60),
         inbound_connections_rate_limit: RateLimit {
             max: 1000,
             interval: Duration::from_secs(1),
@@ -997,6 +1007,7 @@ async fn rate_limit() {
             start_offset: Duration::from_secs(10),
         },
         eviction_timeout: Duration::from_secs(15 * 60),
+        ban_duration: Duration::from_secs(60 * 60),
         inbound_connections_rate_limit: RateLimit {
             max: 2,
             interval: RATE_LIMIT_INTERVAL,
@@ -1094,6 +1105,45 @@ async fn subscription_and_propagation(#[case] peers: (TestPeer, TestPeer)) {
     assert_eq!(msg, expected);
 }
 
+#[rstest]
+#[case::server_to_client(server_to_client().await)]
+#[case::client_to_server(client_to_server().await)]
+#[test_log::test(tokio::test)]
+async fn transaction_propagation(#[case] peers: (TestPeer, TestPeer)) {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let (peer1, peer2) = peers;
+
+    let mut peer2_subscribed_to_peer1 = filter_events(peer1.event_receiver, |event| match event {
+        Event::Test(TestEvent::Subscribed { .. }) => Some(()),
+        _ => None,
+    });
+
+    let mut propagated_to_peer2 = filter_events(peer2.event_receiver, |event| match event {
+        Event::TransactionPropagation {
+            from, transaction, ..
+        } => Some((from, transaction)),
+        _ => None,
+    });
+
+    let topic = crate::transaction_propagation_topic(ChainId::SEPOLIA_TESTNET);
+
+    peer2.client.subscribe_topic(&topic).await.unwrap();
+    peer2_subscribed_to_peer1.recv().await;
+
+    let expected = Faker.fake::<BroadcastedTransaction>();
+
+    peer1
+        .client
+        .publish_transaction(&topic, expected.clone())
+        .await
+        .unwrap();
+
+    let (from, transaction) = propagated_to_peer2.recv().await.unwrap();
+
+    assert_eq!(from, peer1.peer_id);
+    assert_eq!(transaction, expected);
+}
+
 /// Defines a sync test case named [`$test_name`], where there are 2 peers:
 /// - peer2 sends a request to peer1
 /// - peer1 responds with a random number of responses
@@ -1214,3 +1264,11 @@ define_test!(
     InboundEventsSyncRequest,
     send_events_sync_request
 );
+
+define_test!(
+    sync_snapshots,
+    SnapshotRequest,
+    SnapshotResponse,
+    InboundSnapshotsSyncRequest,
+    send_snapshots_sync_request
+);
@@ -0,0 +1,257 @@
+use fake::Dummy;
+use pathfinder_crypto::Felt;
+
+use crate::class::Class;
+use crate::common::{Address, Hash};
+use crate::state::ContractStoredValue;
+use crate::{proto, proto_field, ToProtobuf, TryFromProtobuf};
+
+#[derive(Debug, Clone, PartialEq, Eq, Dummy)]
+pub enum PatriciaNode {
+    Binary {
+        left: Felt,
+        right: Felt,
+    },
+    Edge {
+        length: u32,
+        path: Felt,
+        child: Felt,
+    },
+}
+
+impl ToProtobuf<proto::snapshot::PatriciaNode> for PatriciaNode {
+    fn to_protobuf(self) -> proto::snapshot::PatriciaNode {
+        use proto::snapshot::patricia_node::{Binary, Edge, Node};
+        proto::snapshot::PatriciaNode {
+            node: Some(match self {
+                Self::Binary { left, right } => Node::Binary(Binary {
+                    left: Some(left.to_protobuf()),
+                    right: Some(right.to_protobuf()),
+                }),
+                Self::Edge {
+                    length,
+                    path,
+                    child,
+                } => Node::Edge(Edge {
+                    length,
+                    path: Some(path.to_protobuf()),
+                    child: Some(child.to_protobuf()),
+                }),
+            }),
+        }
+    }
+}
+
+impl TryFromProtobuf<proto::snapshot::PatriciaNode> for PatriciaNode {
+    fn try_from_protobuf(
+        input: proto::snapshot::PatriciaNode,
+        field_name: &'static str,
+    ) -> Result<Self, std::io::Error> {
+        use proto::snapshot::patricia_node::Node;
+        match proto_field(input.node, field_name)? {
+            Node::Binary(binary) => Ok(Self::Binary {
+                left: TryFromProtobuf::try_from_protobuf(binary.left, field_name)?,
+                right: TryFromProtobuf::try_from_protobuf(binary.right, field_name)?,
+            }),
+            Node::Edge(edge) => Ok(Self::Edge {
+                length: edge.length,
+                path: TryFromProtobuf::try_from_protobuf(edge.path, field_name)?,
+                child: TryFromProtobuf::try_from_protobuf(edge.child, field_name)?,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, ToProtobuf, TryFromProtobuf, Dummy)]
+#[protobuf(name = "crate::proto::snapshot::PatriciaRangeProof")]
+pub struct PatriciaRangeProof {
+    pub start: Vec<PatriciaNode>,
+    pub end: Vec<PatriciaNode>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, ToProtobuf, TryFromProtobuf, Dummy)]
+#[protobuf(name = "crate::proto::snapshot::ContractState")]
+pub struct ContractState {
+    pub address: Address,
+    pub class_hash: Hash,
+    pub nonce: Felt,
+    pub storage_root: Hash,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, ToProtobuf, TryFromProtobuf, Dummy)]
+#[protobuf(name = "crate::proto::snapshot::ClassLeaf")]
+pub struct ClassLeaf {
+    pub class_hash: Hash,
+    pub compiled_class_hash: Hash,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ToProtobuf, TryFromProtobuf, Dummy)]
+#[protobuf(name = "crate::proto::snapshot::ContractRangeRequest")]
+pub struct ContractRangeRequest {
+    pub block_number: u64,
+    pub start: Address,
+    pub limit: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, ToProtobuf, TryFromProtobuf, Dummy)]
+#[protobuf(name = "crate::proto::snapshot::ContractRange")]
+pub struct ContractRange {
+    pub storage_commitment: Hash,
+    pub class_commitment: Hash,
+    pub states: Vec<ContractState>,
+    pub proof: PatriciaRangeProof,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ToProtobuf, TryFromProtobuf, Dummy)]
+#[protobuf(name = "crate::proto::snapshot::ContractStorageRangeRequest")]
+pub struct ContractStorageRangeRequest {
+    pub block_number: u64,
+    pub address: Address,
+    pub start: Felt,
+    pub limit: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, ToProtobuf, TryFromProtobuf, Dummy)]
+#[protobuf(name = "crate::proto::snapshot::ContractStorageRange")]
+pub struct ContractStorageRange {
+    pub values: Vec<ContractStoredValue>,
+    pub proof: PatriciaRangeProof,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ToProtobuf, TryFromProtobuf, Dummy)]
+#[protobuf(name = "crate::proto::snapshot::ClassRangeRequest")]
+pub struct ClassRangeRequest {
+    pub block_number: u64,
+    pub start: Hash,
+    pub limit: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, ToProtobuf, TryFromProtobuf, Dummy)]
+#[protobuf(name = "crate::proto::snapshot::ClassRange")]
+pub struct ClassRange {
+    pub classes: Vec<ClassLeaf>,
+    pub proof: PatriciaRangeProof,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ToProtobuf, TryFromProtobuf, Dummy)]
+#[protobuf(name = "crate::proto::snapshot::ClassDefinitionRequest")]
+pub struct ClassDefinitionRequest {
+    pub block_number: u64,
+    pub class_hash: Hash,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Dummy)]
+pub enum SnapshotRequest {
+    ContractRange(ContractRangeRequest),
+    ContractStorageRange(ContractStorageRangeRequest),
+    ClassRange(ClassRangeRequest),
+    ClassDefinition(ClassDefinitionRequest),
+}
+
+impl ToProtobuf<proto::snapshot::SnapshotRequest> for SnapshotRequest {
+    fn to_protobuf(self) -> proto::snapshot::SnapshotRequest {
+        use proto::snapshot::snapshot_request::Request::{
+            ClassDefinition,
+            ClassRange,
+            ContractRange,
+            ContractStorageRange,
+        };
+        proto::snapshot::SnapshotRequest {
+            request: Some(match self {
+                Self::ContractRange(x) => ContractRange(x.to_protobuf()),
+                Self::ContractStorageRange(x) => ContractStorageRange(x.to_protobuf()),
+                Self::ClassRange(x) => ClassRange(x.to_protobuf()),
+                Self::ClassDefinition(x) => ClassDefinition(x.to_protobuf()),
+            }),
+        }
+    }
+}
+
+impl TryFromProtobuf<proto::snapshot::SnapshotRequest> for SnapshotRequest {
+    fn try_from_protobuf(
+        input: proto::snapshot::SnapshotRequest,
+        field_name: &'static str,
+    ) -> Result<Self, std::io::Error> {
+        use proto::snapshot::snapshot_request::Request::{
+            ClassDefinition,
+            ClassRange,
+            ContractRange,
+            ContractStorageRange,
+        };
+        match proto_field(input.request, field_name)? {
+            ContractRange(x) => {
+                TryFromProtobuf::try_from_protobuf(x, field_name).map(Self::ContractRange)
+            }
+            ContractStorageRange(x) => {
+                TryFromProtobuf::try_from_protobuf(x, field_name).map(Self::ContractStorageRange)
+            }
+            ClassRange(x) => {
+                TryFromProtobuf::try_from_protobuf(x, field_name).map(Self::ClassRange)
+            }
+            ClassDefinition(x) => {
+                TryFromProtobuf::try_from_protobuf(x, field_name).map(Self::ClassDefinition)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Dummy)]
+pub enum SnapshotResponse {
+    ContractRange(ContractRange),
+    ContractStorageRange(ContractStorageRange),
+    ClassRange(ClassRange),
+    ClassDefinition(Class),
+    #[default]
+    Fin,
+}
+
+impl ToProtobuf<proto::snapshot::SnapshotResponse> for SnapshotResponse {
+    fn to_protobuf(self) -> proto::snapshot::SnapshotResponse {
+        use proto::snapshot::snapshot_response::SnapshotMessage::{
+            ClassDefinition,
+            ClassRange,
+            ContractRange,
+            ContractStorageRange,
+            Fin,
+        };
+        proto::snapshot::SnapshotResponse {
+            snapshot_message: Some(match self {
+                Self::ContractRange(x) => ContractRange(x.to_protobuf()),
+                Self::ContractStorageRange(x) => ContractStorageRange(x.to_protobuf()),
+                Self::ClassRange(x) => ClassRange(x.to_protobuf()),
+                Self::ClassDefinition(x) => ClassDefinition(x.to_protobuf()),
+                Self::Fin => Fin(proto::common::Fin {}),
+            }),
+        }
+    }
+}
+
+impl TryFromProtobuf<proto::snapshot::SnapshotResponse> for SnapshotResponse {
+    fn try_from_protobuf(
+        input: proto::snapshot::SnapshotResponse,
+        field_name: &'static str,
+    ) -> Result<Self, std::io::Error> {
+        use proto::snapshot::snapshot_response::SnapshotMessage::{
+            ClassDefinition,
+            ClassRange,
+            ContractRange,
+            ContractStorageRange,
+            Fin,
+        };
+        match proto_field(input.snapshot_message, field_name)? {
+            ContractRange(x) => {
+                TryFromProtobuf::try_from_protobuf(x, field_name).map(Self::ContractRange)
+            }
+            ContractStorageRange(x) => {
+                TryFromProtobuf::try_from_protobuf(x, field_name).map(Self::ContractStorageRange)
+            }
+            ClassRange(x) => {
+                TryFromProtobuf::try_from_protobuf(x, field_name).map(Self::ClassRange)
+            }
+            ClassDefinition(x) => {
+                TryFromProtobuf::try_from_protobuf(x, field_name).map(Self::ClassDefinition)
+            }
+            Fin(_) => Ok(Self::Fin),
+        }
+    }
+}
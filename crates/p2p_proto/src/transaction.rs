@@ -1,6 +1,7 @@
 use fake::Dummy;
 use pathfinder_crypto::Felt;
 
+use crate::class::Cairo1Class;
 use crate::common::{Address, Hash, Iteration, VolitionDomain};
 use crate::receipt::Receipt;
 use crate::{proto, proto_field, ToProtobuf, TryFromProtobuf};
@@ -174,6 +175,31 @@ pub enum Transaction {
     L1HandlerV0(L1HandlerV0),
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, ToProtobuf, TryFromProtobuf, Dummy)]
+#[protobuf(name = "crate::proto::transaction::broadcasted_transaction::DeclareV2")]
+pub struct BroadcastedDeclareV2 {
+    pub declare: DeclareV2,
+    pub class: Cairo1Class,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, ToProtobuf, TryFromProtobuf, Dummy)]
+#[protobuf(name = "crate::proto::transaction::broadcasted_transaction::DeclareV3")]
+pub struct BroadcastedDeclareV3 {
+    pub declare: DeclareV3,
+    pub class: Cairo1Class,
+}
+
+/// A transaction which is gossiped to peers before it is included in a block.
+#[derive(Debug, Clone, PartialEq, Eq, Dummy)]
+pub enum BroadcastedTransaction {
+    DeclareV2(BroadcastedDeclareV2),
+    DeclareV3(BroadcastedDeclareV3),
+    DeployAccountV1(DeployAccountV1),
+    DeployAccountV3(DeployAccountV3),
+    InvokeV1(InvokeV1),
+    InvokeV3(InvokeV3),
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, ToProtobuf, TryFromProtobuf, Dummy)]
 #[protobuf(name = "crate::proto::transaction::TransactionWithReceipt")]
 pub struct TransactionWithReceipt {
@@ -268,6 +294,57 @@ impl TryFromProtobuf<proto::transaction::Transaction> for Transaction {
     }
 }
 
+impl ToProtobuf<proto::transaction::BroadcastedTransaction> for BroadcastedTransaction {
+    fn to_protobuf(self) -> proto::transaction::BroadcastedTransaction {
+        use proto::transaction::broadcasted_transaction::Txn::{
+            DeclareV2,
+            DeclareV3,
+            DeployAccountV1,
+            DeployAccountV3,
+            InvokeV1,
+            InvokeV3,
+        };
+        proto::transaction::BroadcastedTransaction {
+            txn: Some(match self {
+                Self::DeclareV2(txn) => DeclareV2(txn.to_protobuf()),
+                Self::DeclareV3(txn) => DeclareV3(txn.to_protobuf()),
+                Self::DeployAccountV1(txn) => DeployAccountV1(txn.to_protobuf()),
+                Self::DeployAccountV3(txn) => DeployAccountV3(txn.to_protobuf()),
+                Self::InvokeV1(txn) => InvokeV1(txn.to_protobuf()),
+                Self::InvokeV3(txn) => InvokeV3(txn.to_protobuf()),
+            }),
+        }
+    }
+}
+
+impl TryFromProtobuf<proto::transaction::BroadcastedTransaction> for BroadcastedTransaction {
+    fn try_from_protobuf(
+        input: proto::transaction::BroadcastedTransaction,
+        field_name: &'static str,
+    ) -> Result<Self, std::io::Error> {
+        use proto::transaction::broadcasted_transaction::Txn::{
+            DeclareV2,
+            DeclareV3,
+            DeployAccountV1,
+            DeployAccountV3,
+            InvokeV1,
+            InvokeV3,
+        };
+        match proto_field(input.txn, field_name)? {
+            DeclareV2(t) => TryFromProtobuf::try_from_protobuf(t, field_name).map(Self::DeclareV2),
+            DeclareV3(t) => TryFromProtobuf::try_from_protobuf(t, field_name).map(Self::DeclareV3),
+            DeployAccountV1(t) => {
+                TryFromProtobuf::try_from_protobuf(t, field_name).map(Self::DeployAccountV1)
+            }
+            DeployAccountV3(t) => {
+                TryFromProtobuf::try_from_protobuf(t, field_name).map(Self::DeployAccountV3)
+            }
+            InvokeV1(t) => TryFromProtobuf::try_from_protobuf(t, field_name).map(Self::InvokeV1),
+            InvokeV3(t) => TryFromProtobuf::try_from_protobuf(t, field_name).map(Self::InvokeV3),
+        }
+    }
+}
+
 impl ToProtobuf<proto::transaction::TransactionsResponse> for TransactionsResponse {
     fn to_protobuf(self) -> proto::transaction::TransactionsResponse {
         use proto::transaction::transactions_response::TransactionMessage::{
@@ -11,8 +11,9 @@ use ipnet::IpNet;
 #[cfg(feature = "p2p")]
 use p2p::libp2p::Multiaddr;
 use pathfinder_common::consts::VERGEN_GIT_DESCRIBE;
-use pathfinder_common::AllowedOrigins;
-use pathfinder_executor::VersionedConstants;
+use pathfinder_common::{AllowedOrigins, BlockNumber};
+use pathfinder_ethereum::L1Depth;
+use pathfinder_executor::{VersionedConstants, VersionedConstantsMap};
 use pathfinder_storage::JournalMode;
 use reqwest::Url;
 
@@ -23,7 +24,11 @@ use reqwest::Url;
 #[command(
     about = "A Starknet node implemented by Equilibrium Labs. Submit bug reports and issues at https://github.com/eqlabs/pathfinder."
 )]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Subcommand>,
+
     #[arg(
         long,
         value_name = "DIR", 
@@ -44,16 +49,23 @@ struct Cli {
 
     #[arg(
         long = "ethereum.url",
-        long_help = r"This should point to the HTTP RPC endpoint of your Ethereum entry-point, typically a local Ethereum client or a hosted gateway service such as Infura or Cloudflare.
+        long_help = r"This should point to the RPC endpoint of your Ethereum entry-point, typically a local Ethereum client or a hosted gateway service such as Infura or Cloudflare.
+
+Both HTTP(S) and WebSocket endpoints are supported. With a WebSocket endpoint, new L1 blocks and core contract logs are received via subscriptions instead of waiting for the next poll.
+
+Several comma separated endpoints can be given, in order of preference. Requests fail over to the next endpoint when one fails or falls behind.
 
 Examples:
     infura: https://mainnet.infura.io/v3/<PROJECT_ID>
-    geth:   https://localhost:8545",
-        value_name = "HTTP(s) URL",
+    geth:   https://localhost:8545
+    geth:   ws://localhost:8546",
+        value_name = "URL",
         value_hint = clap::ValueHint::Url,
+        value_delimiter = ',',
+        required = true,
         env = "PATHFINDER_ETHEREUM_API_URL", 
     )]
-    ethereum_url: Url,
+    ethereum_url: Vec<Url>,
 
     #[arg(
         long = "http-rpc",
@@ -140,6 +152,52 @@ Examples:
     )]
     l1_poll_interval: std::num::NonZeroU64,
 
+    #[arg(
+        long = "sync.l1-depths",
+        long_help = "Additional L1 depths at which to track the Starknet state, as a comma \
+                     separated list of `latest`, `safe` and a number of confirmations, e.g. \
+                     `latest,12`. The state at the finalized L1 block is always tracked, and is \
+                     the only one which marks blocks as accepted on L1.",
+        value_name = "DEPTHS",
+        value_delimiter = ',',
+        value_parser = parse_l1_depth,
+        env = "PATHFINDER_SYNC_L1_DEPTHS"
+    )]
+    l1_depths: Vec<L1Depth>,
+
+    #[arg(
+        long = "sync.l1-messages-backfill",
+        long_help = "The number of finalized L1 blocks whose Starknet messages are fetched when \
+                     no messages have been stored yet, e.g. on the first start. Messages sent in \
+                     earlier L1 blocks are not found by `starknet_getMessagesStatus`.",
+        value_name = "BLOCKS",
+        default_value = "0",
+        env = "PATHFINDER_SYNC_L1_MESSAGES_BACKFILL"
+    )]
+    l1_messages_backfill: u64,
+
+    #[arg(
+        long = "sync.signature-verification",
+        long_help = "How to handle blocks from the feeder gateway whose sequencer signature does \
+                     not verify. `reject` stops syncing at the block, `warn` logs the failure and \
+                     accepts the block, `skip` disables signature verification.",
+        default_value = "warn",
+        env = "PATHFINDER_SYNC_SIGNATURE_VERIFICATION",
+        value_name = "POLICY"
+    )]
+    signature_verification: SignatureVerification,
+
+    #[arg(
+        long = "sync.signature-verification-start",
+        long_help = "Sequencer signatures of blocks before this block number are not verified. \
+                     Useful for historical blocks which predate sequencer signatures.",
+        default_value = "0",
+        env = "PATHFINDER_SYNC_SIGNATURE_VERIFICATION_START",
+        value_name = "BLOCK_NUMBER",
+        value_parser = clap::value_parser!(u64).range(..=i64::MAX as u64)
+    )]
+    signature_verification_start: u64,
+
     #[arg(
         long = "color",
         long_help = "This flag controls when to use colors in the output logs.",
@@ -228,6 +286,38 @@ This should only be enabled for debugging purposes as it adds substantial proces
     )]
     gateway_timeout: std::num::NonZeroU64,
 
+    #[arg(
+        long = "gateway.write-strategy",
+        long_help = "How transactions are submitted if multiple gateway URLs are configured. \
+                     'primary-only' submits to the first URL only, 'broadcast' submits to all of \
+                     them.",
+        value_enum,
+        env = "PATHFINDER_GATEWAY_WRITE_STRATEGY",
+        default_value = "primary-only"
+    )]
+    gateway_write_strategy: GatewayWriteStrategy,
+
+    #[arg(
+        long = "gateway.record-to",
+        value_name = "PATH",
+        long_help = "Record all gateway and feeder gateway responses to an archive at this path, \
+                     which can be replayed in tests and reproductions without network access. \
+                     Responses are appended if the archive already exists.",
+        env = "PATHFINDER_GATEWAY_RECORD_TO"
+    )]
+    gateway_recording: Option<PathBuf>,
+
+    #[arg(
+        long = "gateway.replay-from",
+        value_name = "PATH",
+        long_help = "Answer all feeder gateway requests from an archive recorded with \
+                     '--gateway.record-to' instead of the network. Transactions cannot be \
+                     submitted while replaying.",
+        env = "PATHFINDER_GATEWAY_REPLAY_FROM",
+        conflicts_with_all = ["gateway_recording", "gateway", "feeder_gateway"]
+    )]
+    gateway_replay: Option<PathBuf>,
+
     #[arg(
         long = "storage.event-bloom-filter-cache-size",
         long_help = "The number of blocks whose event bloom filters are cached in memory. This \
@@ -238,6 +328,19 @@ This should only be enabled for debugging purposes as it adds substantial proces
     )]
     event_bloom_filter_cache_size: std::num::NonZeroUsize,
 
+    #[arg(
+        long = "storage.event-address-index",
+        long_help = "Maintain an index of the blocks in which each contract emitted events, so \
+                     that `starknet_getEvents` queries filtered by contract address only visit \
+                     those blocks instead of scanning the whole block range. Enabling the index \
+                     on an existing database builds it from the stored events at start-up, which \
+                     may take a while. Disabling it drops the index.",
+        default_value = "false",
+        action = clap::ArgAction::Set,
+        env = "PATHFINDER_STORAGE_EVENT_ADDRESS_INDEX"
+    )]
+    event_address_index: bool,
+
     #[arg(
         long = "rpc.get-events-max-blocks-to-scan",
         long_help = "The number of blocks to scan for events when querying for events. This limit \
@@ -260,9 +363,10 @@ This should only be enabled for debugging purposes as it adds substantial proces
         long = "storage.state-tries",
         long_help = "When set to `archive` all historical Merkle trie state is preserved. When set to an integer N, only the last N+1 states of the Merkle tries are kept in the database. \
             This can be used to reduce the disk space usage at the cost of only being able to provide storage proofs for the latest N+1 blocks (the state for the latest block is always stored). \
+            When set to `N,every=K`, the state of the Merkle tries is additionally kept for every block that is a multiple of K, so storage proofs remain available for those checkpoint blocks. \
             Defaults to 20 if the database was created with pruning enabled or `archive` if the database is archive-mode.",
         env = "PATHFINDER_STORAGE_STATE_TRIES",
-        value_name = "archive | N",
+        value_name = "archive | N | N,every=K",
         value_parser = parse_state_tries
     )]
     state_tries: Option<StateTries>,
@@ -273,6 +377,72 @@ This should only be enabled for debugging purposes as it adds substantial proces
         env = "PATHFINDER_RPC_CUSTOM_VERSIONED_CONSTANTS_JSON_PATH"
     )]
     custom_versioned_constants_path: Option<PathBuf>,
+
+    #[arg(
+        long = "rpc.custom-versioned-constants-dir",
+        long_help = r"Path to a directory containing versioned constants to use for execution, one JSON file per range of Starknet versions.
+
+Files are named `<start>-<end>.json` for versions from `start` up to but excluding `end`, or `<start>.json` for all versions from `start` onwards. Ranges must not overlap. Versions not covered by any file use the built-in versioned constants.
+
+Examples:
+    0.13.1-0.13.2.json
+    0.13.2.json",
+        value_name = "DIR",
+        env = "PATHFINDER_RPC_CUSTOM_VERSIONED_CONSTANTS_DIR",
+        conflicts_with = "custom_versioned_constants_path"
+    )]
+    custom_versioned_constants_dir: Option<PathBuf>,
+
+    #[arg(
+        long = "rpc.validate-submitted-transactions",
+        long_help = "Validate transactions submitted via the JSON-RPC API against the pending \
+                     state before forwarding them to the gateway. Transactions with an invalid \
+                     nonce, insufficient balance or max fee, or failing validation are rejected \
+                     without contacting the gateway.",
+        default_value = "false",
+        action = clap::ArgAction::Set,
+        env = "PATHFINDER_RPC_VALIDATE_SUBMITTED_TRANSACTIONS"
+    )]
+    validate_submitted_transactions: bool,
+
+    #[arg(
+        long = "rpc.feeder-gateway",
+        long_help = "Serve a read-only subset of the feeder gateway API from the local database \
+                     under `/feeder_gateway/`, next to the JSON-RPC API. Another pathfinder \
+                     instance can sync from this node by pointing `--feeder-gateway-url` at \
+                     it.",
+        default_value = "false",
+        action = clap::ArgAction::Set,
+        env = "PATHFINDER_RPC_FEEDER_GATEWAY"
+    )]
+    serve_feeder_gateway: bool,
+
+    #[arg(
+        long = "rpc.persistent-trace-cache-blocks",
+        long_help = "Keep the traces of up to this many blocks in the database, so that blocks \
+                     traced by `starknet_traceBlockTransactions` and `starknet_traceTransaction` \
+                     are not re-executed after a restart. The traces of the least recently traced \
+                     blocks are evicted first. Disabled by default.",
+        value_name = "BLOCKS",
+        env = "PATHFINDER_RPC_PERSISTENT_TRACE_CACHE_BLOCKS"
+    )]
+    persistent_trace_cache_blocks: Option<std::num::NonZeroU64>,
+}
+
+#[derive(clap::Subcommand)]
+enum Subcommand {
+    /// Re-executes stored transactions and compares the produced receipts,
+    /// fees and state diffs against the stored ones. Mismatches are printed to
+    /// stdout as JSON lines.
+    Replay(crate::replay::Cli),
+}
+
+/// What pathfinder was asked to do on the command line.
+pub enum Command {
+    /// Run the node.
+    Node(Box<Config>),
+    /// Run the `replay` subcommand.
+    Replay(crate::replay::Cli),
 }
 
 #[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq)]
@@ -296,6 +466,19 @@ impl Color {
     }
 }
 
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum SignatureVerification {
+    Reject,
+    Warn,
+    Skip,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum GatewayWriteStrategy {
+    PrimaryOnly,
+    Broadcast,
+}
+
 #[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq)]
 pub enum RpcVersion {
     V06,
@@ -305,18 +488,48 @@ pub enum RpcVersion {
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum StateTries {
     Pruned(u64),
+    Checkpoints { num_blocks_kept: u64, interval: u64 },
     Archive,
 }
 
 fn parse_state_tries(s: &str) -> Result<StateTries, String> {
+    const EXPECTED: &str = "Expected either `archive`, a number or `N,every=K`";
+
     match s {
         "archive" => Ok(StateTries::Archive),
-        _ => {
-            let value: u64 = s
-                .parse()
-                .map_err(|_| "Expected either `archive` or a number".to_string())?;
-            Ok(StateTries::Pruned(value))
-        }
+        _ => match s.split_once(',') {
+            Some((num_blocks_kept, interval)) => {
+                let num_blocks_kept: u64 = num_blocks_kept.parse().map_err(|_| EXPECTED)?;
+                let interval: u64 = interval
+                    .strip_prefix("every=")
+                    .and_then(|x| x.parse().ok())
+                    .ok_or(EXPECTED)?;
+                if interval == 0 {
+                    return Err("Checkpoint interval must be greater than zero".to_string());
+                }
+                Ok(StateTries::Checkpoints {
+                    num_blocks_kept,
+                    interval,
+                })
+            }
+            None => {
+                let value: u64 = s.parse().map_err(|_| EXPECTED)?;
+                Ok(StateTries::Pruned(value))
+            }
+        },
+    }
+}
+
+fn parse_l1_depth(s: &str) -> Result<L1Depth, String> {
+    match s {
+        "latest" => Ok(L1Depth::Latest),
+        "safe" => Ok(L1Depth::Safe),
+        "finalized" => Err("The finalized L1 state is always tracked".to_string()),
+        _ => match s.parse::<u64>() {
+            Ok(0) => Ok(L1Depth::Latest),
+            Ok(confirmations) => Ok(L1Depth::Confirmations(confirmations)),
+            Err(_) => Err("Expected either `latest`, `safe` or a number".to_string()),
+        },
     }
 }
 
@@ -344,21 +557,27 @@ Note that 'custom' requires also setting the --gateway-url and --feeder-gateway-
         long = "feeder-gateway-url",
         value_name = "URL",
         value_hint = clap::ValueHint::Url,
-        long_help = "Specify a custom Starknet feeder gateway url. Can be used to run pathfinder on a custom Starknet network, or to use a gateway proxy. Requires '--network custom'.",
+        long_help = "Specify a custom Starknet feeder gateway url. Can be used to run pathfinder on a custom Starknet network, or to use a gateway proxy. Requires '--network custom'.
+
+Multiple comma separated urls of equivalent feeder gateways can be specified, in which case requests are sent to the healthy one with the lowest latency and fail over to the others on errors.",
         env = "PATHFINDER_FEEDER_GATEWAY_URL", 
+        value_delimiter = ',',
         required_if_eq("network", Network::Custom),
     )]
-    feeder_gateway: Option<Url>,
+    feeder_gateway: Vec<Url>,
 
     #[arg(
         long = "gateway-url",
         value_name = "URL",
         value_hint = clap::ValueHint::Url,
-        long_help = "Specify a custom Starknet gateway url. Can be used to run pathfinder on a custom Starknet network, or to use a gateway proxy. Requires '--network custom'.",
+        long_help = "Specify a custom Starknet gateway url. Can be used to run pathfinder on a custom Starknet network, or to use a gateway proxy. Requires '--network custom'.
+
+Multiple comma separated urls of equivalent gateways can be specified, see '--gateway.write-strategy'.",
         env = "PATHFINDER_GATEWAY_URL",
+        value_delimiter = ',',
         required_if_eq("network", Network::Custom),
     )]
-    gateway: Option<Url>,
+    gateway: Vec<Url>,
 }
 
 #[cfg(feature = "p2p")]
@@ -520,6 +739,38 @@ Example:
         env = "PATHFINDER_P2P_EXPERIMENTAL_EVICTION_TIMEOUT"
     )]
     eviction_timeout: u32,
+
+    #[arg(
+        long = "p2p.experimental.ban-duration",
+        long_help = "How long to ban peers whose reputation dropped too low because they sent \
+                     invalid data.",
+        value_name = "SECONDS",
+        default_value = "3600",
+        env = "PATHFINDER_P2P_EXPERIMENTAL_BAN_DURATION"
+    )]
+    ban_duration: u32,
+
+    #[arg(
+        long = "p2p.experimental.mempool-capacity",
+        long_help = "Maximum number of transactions received from peers which are kept in the \
+                     local pending transaction pool. The oldest transactions are dropped once the \
+                     pool is full.",
+        value_name = "TRANSACTIONS",
+        default_value = "10000",
+        env = "PATHFINDER_P2P_EXPERIMENTAL_MEMPOOL_CAPACITY"
+    )]
+    mempool_capacity: NonZeroUsize,
+
+    #[arg(
+        long = "p2p.experimental.snapshot-sync",
+        long_help = "Download the state at the L1 checkpoint from peers instead of syncing all \
+                     blocks up to it. Only the block headers up to the checkpoint are synced, so \
+                     older transactions, receipts and events are not available.",
+        default_value = "false",
+        action = clap::ArgAction::Set,
+        env = "PATHFINDER_P2P_EXPERIMENTAL_SNAPSHOT_SYNC"
+    )]
+    snapshot_sync: bool,
 }
 
 #[cfg(feature = "p2p")]
@@ -653,6 +904,33 @@ pub fn parse_versioned_constants_or_exit(path: PathBuf) -> VersionedConstants {
     }
 }
 
+fn parse_versioned_constants_dir_or_exit(path: PathBuf) -> VersionedConstantsMap {
+    use clap::error::ErrorKind;
+
+    match VersionedConstantsMap::from_dir(&path) {
+        Ok(versioned_constants) => versioned_constants,
+        Err(error) => Cli::command()
+            .error(
+                ErrorKind::ValueValidation,
+                format!("Invalid versioned constants directory: {error:#}."),
+            )
+            .exit(),
+    }
+}
+
+/// Loads the custom versioned constants from either a single file, which is
+/// used for all Starknet versions starting from 0.13.2, or a directory.
+pub fn custom_versioned_constants_or_exit(
+    path: Option<PathBuf>,
+    dir: Option<PathBuf>,
+) -> VersionedConstantsMap {
+    match (path, dir) {
+        (Some(path), _) => VersionedConstantsMap::latest(parse_versioned_constants_or_exit(path)),
+        (None, Some(dir)) => parse_versioned_constants_dir_or_exit(dir),
+        (None, None) => VersionedConstantsMap::default(),
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 enum ParseVersionedConstantsError {
     #[error("IO error while reading versioned constants: {0}.")]
@@ -675,6 +953,10 @@ pub struct Config {
     pub max_rpc_connections: std::num::NonZeroUsize,
     pub poll_interval: std::time::Duration,
     pub l1_poll_interval: std::time::Duration,
+    pub l1_depths: Vec<L1Depth>,
+    pub l1_messages_backfill: u64,
+    pub signature_verification: SignatureVerification,
+    pub signature_verification_start: BlockNumber,
     pub color: Color,
     pub p2p: P2PConfig,
     pub debug: DebugConfig,
@@ -684,15 +966,22 @@ pub struct Config {
     pub is_rpc_enabled: bool,
     pub gateway_api_key: Option<String>,
     pub gateway_timeout: Duration,
+    pub gateway_write_strategy: GatewayWriteStrategy,
+    pub gateway_recording: Option<PathBuf>,
+    pub gateway_replay: Option<PathBuf>,
     pub event_bloom_filter_cache_size: NonZeroUsize,
+    pub event_address_index: bool,
     pub get_events_max_blocks_to_scan: NonZeroUsize,
     pub get_events_max_uncached_bloom_filters_to_load: NonZeroUsize,
     pub state_tries: Option<StateTries>,
-    pub custom_versioned_constants: Option<VersionedConstants>,
+    pub custom_versioned_constants: VersionedConstantsMap,
+    pub validate_submitted_transactions: bool,
+    pub serve_feeder_gateway: bool,
+    pub persistent_trace_cache_blocks: Option<std::num::NonZeroU64>,
 }
 
 pub struct Ethereum {
-    pub url: Url,
+    pub urls: Vec<Url>,
     pub password: Option<String>,
 }
 
@@ -702,8 +991,8 @@ pub enum NetworkConfig {
     SepoliaTestnet,
     SepoliaIntegration,
     Custom {
-        gateway: Url,
-        feeder_gateway: Url,
+        gateway: Vec<Url>,
+        feeder_gateway: Vec<Url>,
         chain_id: String,
     },
 }
@@ -727,6 +1016,9 @@ pub struct P2PConfig {
     pub max_concurrent_streams: usize,
     pub direct_connection_timeout: Duration,
     pub eviction_timeout: Duration,
+    pub ban_duration: Duration,
+    pub mempool_capacity: NonZeroUsize,
+    pub snapshot_sync: bool,
 }
 
 #[cfg(not(feature = "p2p"))]
@@ -743,8 +1035,8 @@ impl NetworkConfig {
         use Network::*;
         let cfg = match (
             args.network,
-            args.gateway,
-            args.feeder_gateway,
+            Some(args.gateway).filter(|urls| !urls.is_empty()),
+            Some(args.feeder_gateway).filter(|urls| !urls.is_empty()),
             args.chain_id,
         ) {
             (None, None, None, None) => return None,
@@ -874,6 +1166,9 @@ impl P2PConfig {
             max_concurrent_streams: args.max_concurrent_streams,
             direct_connection_timeout: Duration::from_secs(args.direct_connection_timeout.into()),
             eviction_timeout: Duration::from_secs(args.eviction_timeout.into()),
+            ban_duration: Duration::from_secs(args.ban_duration.into()),
+            mempool_capacity: args.mempool_capacity,
+            snapshot_sync: args.snapshot_sync,
         }
     }
 }
@@ -883,7 +1178,7 @@ fn parse_l1_checkpoint_or_exit(
     l1_checkpoint_override: Option<String>,
 ) -> Option<pathfinder_ethereum::EthereumStateUpdate> {
     use clap::error::ErrorKind;
-    use pathfinder_common::{BlockHash, BlockNumber, StateCommitment};
+    use pathfinder_common::{BlockHash, StateCommitment};
 
     #[derive(serde::Deserialize)]
     struct Dto {
@@ -933,18 +1228,27 @@ impl DebugConfig {
     }
 }
 
-impl Config {
-    #[cfg_attr(not(feature = "p2p"), allow(clippy::unit_arg))]
+impl Command {
     pub fn parse() -> Self {
         let cli = Cli::parse();
 
+        match cli.command {
+            Some(Subcommand::Replay(replay)) => Command::Replay(replay),
+            None => Command::Node(Box::new(Config::from_cli(cli))),
+        }
+    }
+}
+
+impl Config {
+    #[cfg_attr(not(feature = "p2p"), allow(clippy::unit_arg))]
+    fn from_cli(cli: Cli) -> Self {
         let network = NetworkConfig::from_components(cli.network);
 
         Config {
             data_directory: cli.data_directory,
             ethereum: Ethereum {
                 password: cli.ethereum_password,
-                url: cli.ethereum_url,
+                urls: cli.ethereum_url,
             },
             rpc_address: cli.rpc_address,
             rpc_cors_domains: parse_cors_or_exit(cli.rpc_cors_domains),
@@ -960,6 +1264,12 @@ impl Config {
             max_rpc_connections: cli.max_rpc_connections,
             poll_interval: Duration::from_secs(cli.poll_interval.get()),
             l1_poll_interval: Duration::from_secs(cli.l1_poll_interval.get()),
+            l1_depths: cli.l1_depths,
+            l1_messages_backfill: cli.l1_messages_backfill,
+            signature_verification: cli.signature_verification,
+            signature_verification_start: BlockNumber::new_or_panic(
+                cli.signature_verification_start,
+            ),
             color: cli.color,
             p2p: P2PConfig::parse_or_exit(cli.p2p),
             debug: DebugConfig::parse(cli.debug),
@@ -969,14 +1279,22 @@ impl Config {
             is_rpc_enabled: cli.is_rpc_enabled,
             gateway_api_key: cli.gateway_api_key,
             event_bloom_filter_cache_size: cli.event_bloom_filter_cache_size,
+            event_address_index: cli.event_address_index,
             get_events_max_blocks_to_scan: cli.get_events_max_blocks_to_scan,
             get_events_max_uncached_bloom_filters_to_load: cli
                 .get_events_max_uncached_bloom_filters_to_load,
             gateway_timeout: Duration::from_secs(cli.gateway_timeout.get()),
+            gateway_write_strategy: cli.gateway_write_strategy,
+            gateway_recording: cli.gateway_recording,
+            gateway_replay: cli.gateway_replay,
             state_tries: cli.state_tries,
-            custom_versioned_constants: cli
-                .custom_versioned_constants_path
-                .map(parse_versioned_constants_or_exit),
+            custom_versioned_constants: custom_versioned_constants_or_exit(
+                cli.custom_versioned_constants_path,
+                cli.custom_versioned_constants_dir,
+            ),
+            validate_submitted_transactions: cli.validate_submitted_transactions,
+            serve_feeder_gateway: cli.serve_feeder_gateway,
+            persistent_trace_cache_blocks: cli.persistent_trace_cache_blocks,
         }
     }
 }
@@ -1017,8 +1335,13 @@ pub struct WebsocketConfig {
 mod tests {
     use assert_matches::assert_matches;
 
-    use super::{AllowedOrigins, RpcCorsDomainsParseError};
-    use crate::config::{parse_cors, ParseVersionedConstantsError};
+    use super::{AllowedOrigins, L1Depth, RpcCorsDomainsParseError, StateTries};
+    use crate::config::{
+        parse_cors,
+        parse_l1_depth,
+        parse_state_tries,
+        ParseVersionedConstantsError,
+    };
 
     #[test]
     fn parse_cors_domains() {
@@ -1095,6 +1418,43 @@ mod tests {
         });
     }
 
+    #[test]
+    fn parse_state_tries_values() {
+        assert_eq!(parse_state_tries("archive"), Ok(StateTries::Archive));
+        assert_eq!(parse_state_tries("20"), Ok(StateTries::Pruned(20)));
+        assert_eq!(
+            parse_state_tries("20,every=1000"),
+            Ok(StateTries::Checkpoints {
+                num_blocks_kept: 20,
+                interval: 1000
+            })
+        );
+
+        for invalid in [
+            "",
+            "x",
+            "20,",
+            "20,1000",
+            "20,every=",
+            "20,every=0",
+            "x,every=10",
+        ] {
+            assert!(parse_state_tries(invalid).is_err(), "input: {invalid}");
+        }
+    }
+
+    #[test]
+    fn parse_l1_depth_values() {
+        assert_eq!(parse_l1_depth("latest"), Ok(L1Depth::Latest));
+        assert_eq!(parse_l1_depth("safe"), Ok(L1Depth::Safe));
+        assert_eq!(parse_l1_depth("0"), Ok(L1Depth::Latest));
+        assert_eq!(parse_l1_depth("12"), Ok(L1Depth::Confirmations(12)));
+
+        for invalid in ["", "x", "-1", "finalized"] {
+            assert!(parse_l1_depth(invalid).is_err(), "input: {invalid}");
+        }
+    }
+
     #[test]
     fn parse_versioned_constants_fails_if_file_not_found() {
         assert_matches!(
@@ -1119,4 +1479,63 @@ mod tests {
         )
         .unwrap();
     }
+
+    #[test]
+    fn replay_subcommand() {
+        use clap::Parser;
+
+        // The node's required arguments are not needed by the subcommand.
+        let cli = super::Cli::try_parse_from([
+            "pathfinder",
+            "replay",
+            "--database",
+            "mainnet.sqlite",
+            "--from-block",
+            "10",
+        ])
+        .unwrap();
+        assert_matches!(cli.command, Some(super::Subcommand::Replay(_)));
+
+        let err = super::Cli::try_parse_from(["pathfinder", "replay", "--database", "db"])
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), clap::error::ErrorKind::MissingRequiredArgument);
+    }
+
+    #[test]
+    fn gateway_replay_conflicts_with_live_gateway() {
+        use clap::Parser;
+
+        let args = [
+            "pathfinder",
+            "--ethereum.url",
+            "https://localhost",
+            "--gateway.replay-from",
+            "archive",
+        ];
+        super::Cli::try_parse_from(args).unwrap();
+
+        for conflicting in [
+            ["--gateway.record-to", "archive"],
+            ["--gateway-url", "https://localhost"],
+            ["--feeder-gateway-url", "https://localhost"],
+        ] {
+            let err = super::Cli::try_parse_from(args.into_iter().chain(conflicting))
+                .err()
+                .unwrap();
+            assert_eq!(
+                err.kind(),
+                clap::error::ErrorKind::ArgumentConflict,
+                "{conflicting:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn replay_subcommand_is_listed_in_help() {
+        use clap::CommandFactory;
+
+        let help = super::Cli::command().render_help().to_string();
+        assert!(help.contains("replay"), "{help}");
+    }
 }
@@ -9,7 +9,7 @@ use std::sync::Arc;
 use anyhow::Context;
 use metrics_exporter_prometheus::PrometheusBuilder;
 use pathfinder_common::consts::VERGEN_GIT_DESCRIBE;
-use pathfinder_common::{BlockNumber, Chain, ChainId, EthereumChain};
+use pathfinder_common::{BlockNumber, Chain, ChainId, EthereumAddress, EthereumChain};
 use pathfinder_ethereum::{EthereumApi, EthereumClient};
 use pathfinder_lib::monitoring::{self};
 use pathfinder_lib::state;
@@ -25,6 +25,7 @@ use tracing::info;
 use crate::config::{NetworkConfig, StateTries};
 
 mod config;
+mod replay;
 mod update;
 
 // The Cairo VM allocates felts on the stack, so during execution it's making
@@ -48,7 +49,21 @@ async fn async_main() -> anyhow::Result<()> {
         std::env::set_var("RUST_LOG", "pathfinder=info");
     }
 
-    let mut config = config::Config::parse();
+    let mut config = match config::Command::parse() {
+        config::Command::Node(config) => *config,
+        config::Command::Replay(cli) => {
+            // The replay output goes to stdout, so logs are written to stderr.
+            tracing_subscriber::fmt()
+                .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+                .with_writer(std::io::stderr)
+                .compact()
+                .init();
+
+            return tokio::task::spawn_blocking(move || replay::run(cli))
+                .await
+                .context("Joining replay task")?;
+        }
+    };
 
     setup_tracing(config.color, config.debug.pretty_log);
 
@@ -79,7 +94,7 @@ async fn async_main() -> anyhow::Result<()> {
 
     let sync_state = Arc::new(SyncState::default());
 
-    let ethereum = EthereumContext::setup(config.ethereum.url.clone(), &config.ethereum.password)
+    let ethereum = EthereumContext::setup(config.ethereum.urls.clone(), &config.ethereum.password)
         .await
         .context("Creating Ethereum context")?;
 
@@ -109,15 +124,31 @@ async fn async_main() -> anyhow::Result<()> {
         .context("Starting monitoring task")?;
     }
 
-    let pathfinder_context = PathfinderContext::configure_and_proxy_check(
+    let mut pathfinder_context = PathfinderContext::configure_and_proxy_check(
         network,
         &config.data_directory,
         config.gateway_api_key.clone(),
         config.gateway_timeout,
+        config.gateway_write_strategy,
     )
     .await
     .context("Configuring pathfinder")?;
 
+    if let Some(path) = &config.gateway_recording {
+        tracing::info!(path=%path.display(), "Recording feeder gateway responses");
+        pathfinder_context.gateway = pathfinder_context
+            .gateway
+            .with_recording(path)
+            .context("Setting up gateway recording")?;
+    }
+
+    if let Some(path) = &config.gateway_replay {
+        tracing::info!(path=%path.display(), "Replaying feeder gateway responses");
+        let replay =
+            starknet_gateway_client::Replay::load(path).context("Loading gateway archive")?;
+        pathfinder_context.gateway = starknet_gateway_client::Client::replay(Arc::new(replay));
+    }
+
     verify_networks(pathfinder_context.network, ethereum.chain)?;
 
     let gateway_public_key = pathfinder_context
@@ -132,10 +163,18 @@ async fn async_main() -> anyhow::Result<()> {
         pathfinder_storage::StorageBuilder::file(pathfinder_context.database.clone())
             .journal_mode(config.sqlite_wal)
             .bloom_filter_cache_size(config.event_bloom_filter_cache_size.get())
+            .event_address_index(config.event_address_index)
             .trie_prune_mode(match config.state_tries {
                 Some(StateTries::Pruned(num_blocks_kept)) => {
                     Some(pathfinder_storage::TriePruneMode::Prune { num_blocks_kept })
                 }
+                Some(StateTries::Checkpoints {
+                    num_blocks_kept,
+                    interval,
+                }) => Some(pathfinder_storage::TriePruneMode::Checkpoints {
+                    num_blocks_kept,
+                    interval,
+                }),
                 Some(StateTries::Archive) => Some(pathfinder_storage::TriePruneMode::Archive),
                 None => None,
             })
@@ -159,14 +198,17 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
         .get()
         .try_into()
         .expect("usize should cast to u32");
-    let rpc_storage = std::cmp::max(10, max_rpc_connections / 8);
-    let rpc_storage = NonZeroU32::new(rpc_storage).expect("A non-zero minimum is set");
-    let rpc_storage = storage_manager.create_read_only_pool(rpc_storage).context(
-        r"Creating database connection pool for RPC
+    let rpc_storage_pool_size = std::cmp::max(10, max_rpc_connections / 8);
+    let rpc_storage_pool_size =
+        NonZeroU32::new(rpc_storage_pool_size).expect("A non-zero minimum is set");
+    let rpc_storage = storage_manager
+        .create_read_only_pool(rpc_storage_pool_size)
+        .context(
+            r"Creating database connection pool for RPC
 
 Hint: This is usually caused by exceeding the file descriptor limit of your system.
       Try increasing the file limit to using `ulimit` or similar tooling.",
-    )?;
+        )?;
 
     let execution_storage_pool_size = config.execution_concurrency.unwrap_or_else(|| {
         std::num::NonZeroU32::new(available_parallelism.get() as u32)
@@ -209,12 +251,20 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
 
     let (tx_pending, rx_pending) = tokio::sync::watch::channel(Default::default());
 
+    let (p2p_handle, gossiper, p2p_client, transaction_gossip) = start_p2p(
+        pathfinder_context.network_id,
+        p2p_storage,
+        config.p2p.clone(),
+    )
+    .await?;
+
     let rpc_config = pathfinder_rpc::context::RpcConfig {
         batch_concurrency_limit: config.rpc_batch_concurrency_limit,
         get_events_max_blocks_to_scan: config.get_events_max_blocks_to_scan,
         get_events_max_uncached_bloom_filters_to_load: config
             .get_events_max_uncached_bloom_filters_to_load,
-        custom_versioned_constants: config.custom_versioned_constants.take(),
+        custom_versioned_constants: std::mem::take(&mut config.custom_versioned_constants),
+        validate_submitted_transactions: config.validate_submitted_transactions,
     };
 
     let context = pathfinder_rpc::context::RpcContext::new(
@@ -237,6 +287,31 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
         context
     };
 
+    let context = match transaction_gossip {
+        Some(transaction_gossip) => context.with_transaction_gossip(transaction_gossip),
+        None => context,
+    };
+
+    let context = match &p2p_client {
+        Some(p2p_client) => context.with_peer_reputations(
+            pathfinder_lib::p2p_network::serve_peer_reputations(p2p_client.clone()),
+        ),
+        None => context,
+    };
+
+    let context = match config.persistent_trace_cache_blocks {
+        Some(max_blocks) => {
+            // Traces are read and stored by concurrent RPC requests.
+            let trace_storage = storage_manager
+                .create_pool(rpc_storage_pool_size)
+                .context("Creating database connection pool for persistent trace cache")?;
+            context.with_trace_cache(
+                pathfinder_executor::TraceCache::default().with_store(trace_storage, max_blocks),
+            )
+        }
+        None => context,
+    };
+
     let default_version = match config.rpc_root_version {
         config::RpcVersion::V06 => pathfinder_rpc::RpcVersion::V06,
         config::RpcVersion::V07 => pathfinder_rpc::RpcVersion::V07,
@@ -247,13 +322,14 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
         Some(ref allowed_origins) => rpc_server.with_cors(allowed_origins.clone()),
         None => rpc_server,
     };
-
-    let (p2p_handle, gossiper, p2p_client) = start_p2p(
-        pathfinder_context.network_id,
-        p2p_storage,
-        config.p2p.clone(),
-    )
-    .await?;
+    let rpc_server = if config.serve_feeder_gateway {
+        rpc_server.with_feeder_gateway(
+            gateway_public_key,
+            EthereumAddress(pathfinder_context.l1_core_address),
+        )
+    } else {
+        rpc_server
+    };
 
     let sync_handle = if config.is_sync_enabled {
         start_sync(
@@ -396,6 +472,7 @@ async fn start_p2p(
     tokio::task::JoinHandle<()>,
     state::Gossiper,
     Option<p2p::client::peer_agnostic::Client>,
+    Option<pathfinder_rpc::gossip::TransactionGossipSender>,
 )> {
     use std::path::Path;
     use std::time::Duration;
@@ -445,6 +522,7 @@ async fn start_p2p(
             ip_whitelist: config.ip_whitelist,
             bootstrap: Default::default(),
             eviction_timeout: config.eviction_timeout,
+            ban_duration: config.ban_duration,
             inbound_connections_rate_limit: p2p::RateLimit {
                 max: 10,
                 interval: Duration::from_secs(1),
@@ -460,15 +538,17 @@ async fn start_p2p(
         listen_on: config.listen_on,
         bootstrap_addresses: config.bootstrap_addresses,
         predefined_peers: config.predefined_peers,
+        mempool_capacity: config.mempool_capacity,
     };
 
-    let (p2p_client, _head_receiver, p2p_handle) =
+    let (p2p_client, _head_receiver, transaction_gossip, p2p_handle) =
         pathfinder_lib::p2p_network::start(context).await?;
 
     Ok((
         p2p_handle,
         state::Gossiper::new(p2p_client.clone()),
         Some(p2p_client),
+        Some(transaction_gossip),
     ))
 }
 
@@ -481,10 +561,11 @@ async fn start_p2p(
     tokio::task::JoinHandle<()>,
     state::Gossiper,
     Option<p2p::client::peer_agnostic::Client>,
+    Option<pathfinder_rpc::gossip::TransactionGossipSender>,
 )> {
     let join_handle = tokio::task::spawn(futures::future::pending());
 
-    Ok((join_handle, Default::default(), None))
+    Ok((join_handle, Default::default(), None, None))
 }
 
 #[cfg(feature = "p2p")]
@@ -524,6 +605,7 @@ fn start_sync(
             gateway_public_key,
             config.p2p.l1_checkpoint_override,
             verify_tree_hashes,
+            config.p2p.snapshot_sync,
         )
     }
 }
@@ -578,6 +660,8 @@ fn start_feeder_gateway_sync(
         state: sync_state.clone(),
         head_poll_interval: config.poll_interval,
         l1_poll_interval: config.l1_poll_interval,
+        l1_depths: config.l1_depths.clone(),
+        l1_messages_backfill: config.l1_messages_backfill,
         pending_data: tx_pending,
         block_validation_mode: state::l2::BlockValidationMode::Strict,
         websocket_txs,
@@ -586,12 +670,21 @@ fn start_feeder_gateway_sync(
         verify_tree_hashes: config.verify_tree_hashes,
         gossiper,
         sequencer_public_key: gateway_public_key,
+        signature_policy: state::l2::SignaturePolicy {
+            verification: match config.signature_verification {
+                config::SignatureVerification::Reject => state::l2::SignatureVerification::Reject,
+                config::SignatureVerification::Warn => state::l2::SignatureVerification::Warn,
+                config::SignatureVerification::Skip => state::l2::SignatureVerification::Skip,
+            },
+            first_signed_block: config.signature_verification_start,
+        },
     };
 
     tokio::spawn(state::sync(sync_context, state::l1::sync, state::l2::sync))
 }
 
 #[cfg(feature = "p2p")]
+#[allow(clippy::too_many_arguments)]
 fn start_p2p_sync(
     storage: Storage,
     pathfinder_context: PathfinderContext,
@@ -600,6 +693,7 @@ fn start_p2p_sync(
     gateway_public_key: pathfinder_common::PublicKey,
     l1_checkpoint_override: Option<pathfinder_ethereum::EthereumStateUpdate>,
     verify_tree_hashes: bool,
+    snapshot_sync: bool,
 ) -> tokio::task::JoinHandle<anyhow::Result<()>> {
     let sync = pathfinder_lib::sync::Sync {
         storage,
@@ -612,6 +706,7 @@ fn start_p2p_sync(
         public_key: gateway_public_key,
         l1_checkpoint_override,
         verify_tree_hashes,
+        snapshot_sync,
     };
     tokio::spawn(sync.run())
 }
@@ -644,12 +739,9 @@ struct EthereumContext {
 impl EthereumContext {
     /// Configure an [EthereumContext]'s transport and read the chain ID using
     /// it.
-    async fn setup(url: reqwest::Url, password: &Option<String>) -> anyhow::Result<Self> {
-        let client = if let Some(password) = password.as_ref() {
-            EthereumClient::with_password(url, password).context("Creating Ethereum client")?
-        } else {
-            EthereumClient::new(url).context("Creating Ethereum client")?
-        };
+    async fn setup(urls: Vec<reqwest::Url>, password: &Option<String>) -> anyhow::Result<Self> {
+        let client = EthereumClient::with_endpoints(urls, password.as_deref())
+            .context("Creating Ethereum client")?;
 
         let chain = client.get_chain().await.context(
             r"Determining Ethereum chain.
@@ -699,7 +791,7 @@ mod pathfinder_context {
     use starknet_gateway_client::Client as GatewayClient;
 
     use super::PathfinderContext;
-    use crate::config::NetworkConfig;
+    use crate::config::{GatewayWriteStrategy, NetworkConfig};
 
     impl PathfinderContext {
         pub async fn configure_and_proxy_check(
@@ -707,6 +799,7 @@ mod pathfinder_context {
             data_directory: &Path,
             api_key: Option<String>,
             gateway_timeout: Duration,
+            gateway_write_strategy: GatewayWriteStrategy,
         ) -> anyhow::Result<Self> {
             let context = match cfg {
                 NetworkConfig::Mainnet => Self {
@@ -742,6 +835,7 @@ mod pathfinder_context {
                     data_directory,
                     api_key,
                     gateway_timeout,
+                    gateway_write_strategy,
                 )
                 .await
                 .context("Configuring custom network")?,
@@ -755,19 +849,26 @@ mod pathfinder_context {
         /// comparing against L1 starknet address against of
         /// the known networks.
         async fn configure_custom(
-            gateway: Url,
-            feeder: Url,
+            gateway: Vec<Url>,
+            feeder: Vec<Url>,
             chain_id: String,
             data_directory: &Path,
             api_key: Option<String>,
             gateway_timeout: Duration,
+            gateway_write_strategy: GatewayWriteStrategy,
         ) -> anyhow::Result<Self> {
             use pathfinder_crypto::Felt;
-            use starknet_gateway_client::GatewayApi;
+            use starknet_gateway_client::{GatewayApi, WriteStrategy};
+
+            let write_strategy = match gateway_write_strategy {
+                GatewayWriteStrategy::PrimaryOnly => WriteStrategy::PrimaryOnly,
+                GatewayWriteStrategy::Broadcast => WriteStrategy::Broadcast,
+            };
 
-            let gateway = GatewayClient::with_urls(gateway, feeder, gateway_timeout)
+            let gateway = GatewayClient::with_endpoints(gateway, feeder, gateway_timeout)
                 .context("Creating gateway client")?
-                .with_api_key(api_key);
+                .with_api_key(api_key)
+                .with_write_strategy(write_strategy);
 
             let network_id =
                 ChainId(Felt::from_be_slice(chain_id.as_bytes()).context("Parsing chain ID")?);
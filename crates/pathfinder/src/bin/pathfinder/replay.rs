@@ -0,0 +1,753 @@
+//! The `pathfinder replay` subcommand.
+//!
+//! Re-executes stored transactions and compares the results against the
+//! receipts, fees and state diffs stored in the database. Every mismatch is
+//! printed to stdout as a single line of JSON, so that the output can be
+//! processed with tools like `jq`.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::num::NonZeroU32;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use pathfinder_common::receipt::Receipt;
+use pathfinder_common::state_update::ContractClassUpdate;
+use pathfinder_common::transaction::Transaction;
+use pathfinder_common::{
+    BlockHeader,
+    BlockNumber,
+    CasmHash,
+    ChainId,
+    ClassHash,
+    ContractAddress,
+    ContractNonce,
+    SierraHash,
+    StorageAddress,
+    StorageValue,
+    TransactionHash,
+};
+use pathfinder_crypto::Felt;
+use pathfinder_executor::types::{PriceUnit, TransactionSimulation};
+use pathfinder_executor::{ExecutionState, VersionedConstantsMap};
+use pathfinder_storage::{BlockId, StorageBuilder};
+use primitive_types::U256;
+use serde_json::{json, Value};
+
+#[derive(clap::Args)]
+pub struct Cli {
+    #[arg(
+        long,
+        value_name = "FILE",
+        value_hint = clap::ValueHint::FilePath,
+        long_help = "Path to the pathfinder database. The database is opened read-only and must \
+                     already be at the latest schema version."
+    )]
+    database: PathBuf,
+
+    #[arg(
+        long,
+        value_name = "HASH",
+        long_help = "Re-execute a single transaction. The transactions preceding it in its block \
+                     are executed first, but only the selected transaction is compared.",
+        value_parser = parse_transaction_hash,
+        conflicts_with_all = ["from_block", "to_block"],
+        required_unless_present = "from_block"
+    )]
+    transaction: Option<TransactionHash>,
+
+    #[arg(
+        long = "from-block",
+        value_name = "NUMBER",
+        long_help = "First block of the range to re-execute"
+    )]
+    from_block: Option<u64>,
+
+    #[arg(
+        long = "to-block",
+        value_name = "NUMBER",
+        long_help = "Last block of the range to re-execute. Defaults to the latest block.",
+        requires = "from_block"
+    )]
+    to_block: Option<u64>,
+
+    #[arg(
+        long = "rpc.custom-versioned-constants-json-path",
+        long_help = "Path to a JSON file containing the versioned constants which should be used \
+                     for execution, if the node was configured with custom ones",
+        value_name = "PATH"
+    )]
+    custom_versioned_constants_path: Option<PathBuf>,
+
+    #[arg(
+        long = "rpc.custom-versioned-constants-dir",
+        long_help = "Path to a directory containing the versioned constants which should be used \
+                     for execution, if the node was configured with custom ones",
+        value_name = "DIR",
+        conflicts_with = "custom_versioned_constants_path"
+    )]
+    custom_versioned_constants_dir: Option<PathBuf>,
+}
+
+fn parse_transaction_hash(s: &str) -> Result<TransactionHash, String> {
+    Felt::from_hex_str(s)
+        .map(TransactionHash)
+        .map_err(|e| e.to_string())
+}
+
+/// Runs the replay and returns an error if any mismatches were found.
+pub fn run(cli: Cli) -> anyhow::Result<()> {
+    let custom_versioned_constants = crate::config::custom_versioned_constants_or_exit(
+        cli.custom_versioned_constants_path,
+        cli.custom_versioned_constants_dir,
+    );
+
+    let storage = StorageBuilder::file(cli.database)
+        .open_read_only()
+        .context("Opening database")?
+        .create_read_only_pool(NonZeroU32::new(1).unwrap())
+        .context("Creating database connection pool")?;
+    let mut db = storage
+        .connection()
+        .context("Opening database connection")?;
+    let db = db.transaction().context("Creating database transaction")?;
+
+    let chain_id = chain_id(&db)?;
+
+    let mut summary = Summary::default();
+
+    if let Some(transaction_hash) = cli.transaction {
+        let (_, receipt, _, block_number) = db
+            .transaction_with_receipt(transaction_hash)
+            .context("Fetching transaction")?
+            .context("Transaction not found")?;
+
+        let mut block = Block::load(&db, block_number)?;
+        let index = receipt.transaction_index.get() as usize;
+        block.transactions.truncate(index + 1);
+        block.receipts.truncate(index + 1);
+
+        let simulations = block.execute(&db, chain_id, custom_versioned_constants)?;
+        block.compare_receipt(index, &simulations[index], &mut summary);
+        summary.blocks += 1;
+    } else {
+        let from_block = cli.from_block.context("Missing block range")?;
+        let to_block = match cli.to_block {
+            Some(to_block) => to_block,
+            None => db
+                .block_id(BlockId::Latest)
+                .context("Fetching latest block")?
+                .context("Database is empty")?
+                .0
+                .get(),
+        };
+
+        tracing::info!(%from_block, %to_block, "Replaying blocks");
+
+        for block_number in from_block..=to_block {
+            let block_number = BlockNumber::new(block_number).context("Invalid block number")?;
+            let block = Block::load(&db, block_number)?;
+            let simulations = block.execute(&db, chain_id, custom_versioned_constants.clone())?;
+
+            for (index, simulation) in simulations.iter().enumerate() {
+                block.compare_receipt(index, simulation, &mut summary);
+            }
+            block.compare_state_diff(&db, &simulations, &mut summary)?;
+
+            summary.blocks += 1;
+            tracing::debug!(%block_number, "Replayed block");
+        }
+    }
+
+    tracing::info!(
+        blocks=%summary.blocks,
+        transactions=%summary.transactions,
+        mismatches=%summary.mismatches,
+        "Replay finished"
+    );
+
+    anyhow::ensure!(
+        summary.mismatches == 0,
+        "Found {} mismatches",
+        summary.mismatches
+    );
+
+    Ok(())
+}
+
+fn chain_id(db: &pathfinder_storage::Transaction<'_>) -> anyhow::Result<ChainId> {
+    use pathfinder_common::consts::{
+        MAINNET_GENESIS_HASH,
+        SEPOLIA_INTEGRATION_GENESIS_HASH,
+        SEPOLIA_TESTNET_GENESIS_HASH,
+    };
+
+    let (_, genesis_hash) = db
+        .block_id(BlockNumber::GENESIS.into())
+        .context("Fetching genesis hash")?
+        .context("Genesis block missing")?;
+
+    let chain_id = match genesis_hash {
+        MAINNET_GENESIS_HASH => ChainId::MAINNET,
+        SEPOLIA_TESTNET_GENESIS_HASH => ChainId::SEPOLIA_TESTNET,
+        SEPOLIA_INTEGRATION_GENESIS_HASH => ChainId::SEPOLIA_INTEGRATION,
+        _ => anyhow::bail!("Replaying custom networks is not supported"),
+    };
+
+    Ok(chain_id)
+}
+
+#[derive(Default)]
+struct Summary {
+    blocks: usize,
+    transactions: usize,
+    mismatches: usize,
+}
+
+impl Summary {
+    fn report(&mut self, mismatch: Value) {
+        self.mismatches += 1;
+        println!("{mismatch}");
+    }
+}
+
+struct Block {
+    header: BlockHeader,
+    transactions: Vec<Transaction>,
+    receipts: Vec<Receipt>,
+}
+
+impl Block {
+    fn load(db: &pathfinder_storage::Transaction<'_>, number: BlockNumber) -> anyhow::Result<Self> {
+        let header = db
+            .block_header(number.into())
+            .context("Fetching block header")?
+            .with_context(|| format!("Block {number} not found"))?;
+        // Empty blocks have no stored transaction data.
+        let (transactions, receipts) = db
+            .transaction_data_for_block(number.into())
+            .context("Fetching transactions")?
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(transaction, receipt, _)| (transaction, receipt))
+            .unzip();
+
+        Ok(Self {
+            header,
+            transactions,
+            receipts,
+        })
+    }
+
+    fn execute(
+        &self,
+        db: &pathfinder_storage::Transaction<'_>,
+        chain_id: ChainId,
+        custom_versioned_constants: VersionedConstantsMap,
+    ) -> anyhow::Result<Vec<TransactionSimulation>> {
+        let execution_state = ExecutionState::trace(
+            db,
+            chain_id,
+            self.header.clone(),
+            None,
+            custom_versioned_constants,
+        );
+
+        let transactions = self
+            .transactions
+            .iter()
+            .map(|transaction| pathfinder_rpc::compose_executor_transaction(transaction, db))
+            .collect::<Result<Vec<_>, _>>()
+            .context("Converting transactions")?;
+
+        pathfinder_executor::simulate(execution_state, transactions, false, false, false)
+            .map_err(|e| anyhow::anyhow!("{e:?}"))
+            .with_context(|| format!("Re-executing block {}", self.header.number))
+    }
+
+    /// Compares the execution status, fee and gas usage of a re-executed
+    /// transaction against its stored receipt.
+    fn compare_receipt(
+        &self,
+        index: usize,
+        simulation: &TransactionSimulation,
+        summary: &mut Summary,
+    ) {
+        summary.transactions += 1;
+
+        let receipt = &self.receipts[index];
+        let mut fields = serde_json::Map::new();
+
+        let replayed_revert_reason = simulation.revert_reason();
+        let stored_revert_reason = receipt.revert_reason();
+        if replayed_revert_reason.is_some() != stored_revert_reason.is_some() {
+            fields.insert(
+                "revert_reason".to_owned(),
+                diff(stored_revert_reason, replayed_revert_reason),
+            );
+        }
+
+        let stored_fee = U256::from_big_endian(receipt.actual_fee.0.as_be_bytes());
+        // L1 handler transactions have a fee of zero in older receipts.
+        if !stored_fee.is_zero() {
+            let estimate = &simulation.fee_estimation;
+
+            if estimate.overall_fee != stored_fee {
+                fields.insert(
+                    "actual_fee".to_owned(),
+                    diff(stored_fee, estimate.overall_fee),
+                );
+            }
+
+            let (gas_price, data_gas_price) = match estimate.unit {
+                PriceUnit::Wei => (
+                    self.header.eth_l1_gas_price.0,
+                    self.header.eth_l1_data_gas_price.0,
+                ),
+                PriceUnit::Fri => (
+                    self.header.strk_l1_gas_price.0,
+                    self.header.strk_l1_data_gas_price.0,
+                ),
+            };
+
+            let stored_data_gas = receipt.execution_resources.data_availability.l1_data_gas;
+            // Older receipts do not contain the total gas consumed, so it is derived
+            // from the fee instead.
+            let stored_gas = match receipt.execution_resources.total_gas_consumed.l1_gas {
+                0 => gas_consumed_from_fee(
+                    stored_fee.low_u128(),
+                    stored_data_gas,
+                    gas_price,
+                    data_gas_price,
+                ),
+                l1_gas => Some(l1_gas),
+            };
+
+            match stored_gas {
+                Some(stored_gas) if estimate.gas_consumed == stored_gas.into() => {}
+                Some(stored_gas) => {
+                    fields.insert(
+                        "gas_consumed".to_owned(),
+                        diff(stored_gas, estimate.gas_consumed.low_u128()),
+                    );
+                }
+                // The stored fee does not even cover the stored data gas, so the receipt
+                // itself is inconsistent.
+                None => {
+                    fields.insert(
+                        "gas_consumed".to_owned(),
+                        diff(Value::Null, estimate.gas_consumed.low_u128()),
+                    );
+                }
+            }
+            if estimate.data_gas_consumed != stored_data_gas.into() {
+                fields.insert(
+                    "data_gas_consumed".to_owned(),
+                    diff(stored_data_gas, estimate.data_gas_consumed.low_u128()),
+                );
+            }
+        }
+
+        let replayed_data_availability = &simulation.trace.execution_resources().data_availability;
+        let stored_data_availability = &receipt.execution_resources.data_availability;
+        if replayed_data_availability.l1_gas != stored_data_availability.l1_gas
+            || replayed_data_availability.l1_data_gas != stored_data_availability.l1_data_gas
+        {
+            fields.insert(
+                "data_availability".to_owned(),
+                diff(
+                    json!({
+                        "l1_gas": stored_data_availability.l1_gas,
+                        "l1_data_gas": stored_data_availability.l1_data_gas,
+                    }),
+                    json!({
+                        "l1_gas": replayed_data_availability.l1_gas,
+                        "l1_data_gas": replayed_data_availability.l1_data_gas,
+                    }),
+                ),
+            );
+        }
+
+        if !fields.is_empty() {
+            summary.report(json!({
+                "block_number": self.header.number,
+                "transaction_hash": receipt.transaction_hash,
+                "transaction_index": index,
+                "receipt": fields,
+            }));
+        }
+    }
+
+    /// Compares the combined state diff of all re-executed transactions
+    /// against the stored state update of the block.
+    fn compare_state_diff(
+        &self,
+        db: &pathfinder_storage::Transaction<'_>,
+        simulations: &[TransactionSimulation],
+        summary: &mut Summary,
+    ) -> anyhow::Result<()> {
+        let stored = db
+            .state_update(self.header.number.into())
+            .context("Fetching state update")?
+            .with_context(|| format!("State update for block {} not found", self.header.number))?;
+
+        let mut stored_diff = StateDiff::default();
+        for (address, update) in stored.contract_updates {
+            stored_diff.storage.extend(
+                update
+                    .storage
+                    .into_iter()
+                    .map(|(key, value)| ((address, key), value)),
+            );
+            if let Some(nonce) = update.nonce {
+                stored_diff.nonces.insert(address, nonce);
+            }
+            if let Some(class) = update.class {
+                stored_diff.classes.insert(address, class);
+            }
+        }
+        // System contract updates are not compared: they are written by the block
+        // pre-processing (e.g. the block hash stored in 0x1 since v0.13), which is not
+        // part of the state diff of any transaction.
+        stored_diff.declared_cairo_classes = stored.declared_cairo_classes;
+        stored_diff.declared_sierra_classes = stored.declared_sierra_classes;
+
+        let mut replayed_diff = StateDiff::default();
+        for simulation in simulations {
+            let state_diff = simulation.trace.state_diff();
+            for (address, diffs) in &state_diff.storage_diffs {
+                replayed_diff
+                    .storage
+                    .extend(diffs.iter().map(|diff| ((*address, diff.key), diff.value)));
+            }
+            replayed_diff.nonces.extend(state_diff.nonces.clone());
+            for deployed in &state_diff.deployed_contracts {
+                replayed_diff.classes.insert(
+                    deployed.address,
+                    ContractClassUpdate::Deploy(deployed.class_hash),
+                );
+            }
+            for replaced in &state_diff.replaced_classes {
+                // A class replaced in the same block as the contract was deployed is still
+                // recorded as a deployment.
+                let class = match replayed_diff.classes.get(&replaced.contract_address) {
+                    Some(ContractClassUpdate::Deploy(_)) => {
+                        ContractClassUpdate::Deploy(replaced.class_hash)
+                    }
+                    _ => ContractClassUpdate::Replace(replaced.class_hash),
+                };
+                replayed_diff
+                    .classes
+                    .insert(replaced.contract_address, class);
+            }
+            replayed_diff
+                .declared_cairo_classes
+                .extend(state_diff.deprecated_declared_classes.iter().copied());
+            replayed_diff.declared_sierra_classes.extend(
+                state_diff
+                    .declared_classes
+                    .iter()
+                    .map(|class| (class.class_hash, class.compiled_class_hash)),
+            );
+        }
+
+        let fields = stored_diff.compare(&replayed_diff);
+        if !fields.is_empty() {
+            summary.report(json!({
+                "block_number": self.header.number,
+                "state_diff": fields,
+            }));
+        }
+
+        Ok(())
+    }
+}
+
+/// A flattened state diff which can be compared entry by entry.
+#[derive(Default)]
+struct StateDiff {
+    storage: HashMap<(ContractAddress, StorageAddress), StorageValue>,
+    nonces: BTreeMap<ContractAddress, ContractNonce>,
+    classes: HashMap<ContractAddress, ContractClassUpdate>,
+    declared_cairo_classes: HashSet<ClassHash>,
+    declared_sierra_classes: HashMap<SierraHash, CasmHash>,
+}
+
+impl StateDiff {
+    fn compare(&self, replayed: &StateDiff) -> serde_json::Map<String, Value> {
+        let mut fields = serde_json::Map::new();
+
+        let storage = self
+            .storage
+            .keys()
+            .chain(replayed.storage.keys())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .filter_map(|key @ (address, storage_address)| {
+                let stored = self.storage.get(key);
+                let replayed = replayed.storage.get(key);
+                (stored != replayed).then(|| {
+                    json!({
+                        "contract_address": address,
+                        "key": storage_address,
+                        "stored": stored,
+                        "replayed": replayed,
+                    })
+                })
+            })
+            .collect::<Vec<_>>();
+        if !storage.is_empty() {
+            fields.insert("storage".to_owned(), storage.into());
+        }
+
+        let nonces = self
+            .nonces
+            .keys()
+            .chain(replayed.nonces.keys())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .filter_map(|address| {
+                let stored = self.nonces.get(address);
+                let replayed = replayed.nonces.get(address);
+                (stored != replayed).then(|| {
+                    json!({
+                        "contract_address": address,
+                        "stored": stored,
+                        "replayed": replayed,
+                    })
+                })
+            })
+            .collect::<Vec<_>>();
+        if !nonces.is_empty() {
+            fields.insert("nonces".to_owned(), nonces.into());
+        }
+
+        let classes = self
+            .classes
+            .keys()
+            .chain(replayed.classes.keys())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .filter_map(|address| {
+                let stored = self.classes.get(address);
+                let replayed = replayed.classes.get(address);
+                (stored != replayed).then(|| {
+                    json!({
+                        "contract_address": address,
+                        "stored": stored.map(class_update),
+                        "replayed": replayed.map(class_update),
+                    })
+                })
+            })
+            .collect::<Vec<_>>();
+        if !classes.is_empty() {
+            fields.insert("classes".to_owned(), classes.into());
+        }
+
+        if self.declared_cairo_classes != replayed.declared_cairo_classes {
+            let stored = self.declared_cairo_classes.iter().collect::<BTreeSet<_>>();
+            let replayed = replayed
+                .declared_cairo_classes
+                .iter()
+                .collect::<BTreeSet<_>>();
+            fields.insert(
+                "declared_cairo_classes".to_owned(),
+                json!({
+                    "stored_only": stored.difference(&replayed).collect::<Vec<_>>(),
+                    "replayed_only": replayed.difference(&stored).collect::<Vec<_>>(),
+                }),
+            );
+        }
+
+        if self.declared_sierra_classes != replayed.declared_sierra_classes {
+            let declared_sierra_classes = self
+                .declared_sierra_classes
+                .keys()
+                .chain(replayed.declared_sierra_classes.keys())
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .filter_map(|sierra_hash| {
+                    let stored = self.declared_sierra_classes.get(sierra_hash);
+                    let replayed = replayed.declared_sierra_classes.get(sierra_hash);
+                    (stored != replayed).then(|| {
+                        json!({
+                            "class_hash": sierra_hash,
+                            "stored": stored,
+                            "replayed": replayed,
+                        })
+                    })
+                })
+                .collect::<Vec<_>>();
+            fields.insert(
+                "declared_sierra_classes".to_owned(),
+                declared_sierra_classes.into(),
+            );
+        }
+
+        fields
+    }
+}
+
+/// Derives the L1 gas consumed by a transaction from its fee, for receipts
+/// which do not contain it. Returns `None` if the fee does not cover the data
+/// gas.
+fn gas_consumed_from_fee(
+    fee: u128,
+    data_gas: u128,
+    gas_price: u128,
+    data_gas_price: u128,
+) -> Option<u128> {
+    fee.checked_sub(data_gas.saturating_mul(data_gas_price))
+        .map(|gas_fee| gas_fee / gas_price.max(1))
+}
+
+fn class_update(update: &ContractClassUpdate) -> Value {
+    match update {
+        ContractClassUpdate::Deploy(class_hash) => json!({ "deployed": class_hash }),
+        ContractClassUpdate::Replace(class_hash) => json!({ "replaced": class_hash }),
+    }
+}
+
+fn diff(stored: impl serde::Serialize, replayed: impl serde::Serialize) -> Value {
+    json!({
+        "stored": stored,
+        "replayed": replayed,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn state_diff_mismatches() {
+        let stored = StateDiff {
+            storage: HashMap::from([
+                (
+                    (contract_address!("0x1"), storage_address!("0x10")),
+                    storage_value!("0x100"),
+                ),
+                (
+                    (contract_address!("0x1"), storage_address!("0x11")),
+                    storage_value!("0x101"),
+                ),
+            ]),
+            nonces: BTreeMap::from([(contract_address!("0x2"), contract_nonce!("0x1"))]),
+            classes: HashMap::from([(
+                contract_address!("0x3"),
+                ContractClassUpdate::Deploy(class_hash!("0x30")),
+            )]),
+            ..Default::default()
+        };
+
+        let replayed = StateDiff {
+            storage: HashMap::from([
+                (
+                    (contract_address!("0x1"), storage_address!("0x10")),
+                    storage_value!("0x100"),
+                ),
+                (
+                    (contract_address!("0x1"), storage_address!("0x11")),
+                    storage_value!("0x102"),
+                ),
+            ]),
+            nonces: BTreeMap::from([(contract_address!("0x2"), contract_nonce!("0x1"))]),
+            classes: HashMap::from([(
+                contract_address!("0x3"),
+                ContractClassUpdate::Replace(class_hash!("0x30")),
+            )]),
+            declared_cairo_classes: HashSet::from([class_hash!("0x40")]),
+            ..Default::default()
+        };
+
+        let fields = stored.compare(&replayed);
+        assert_eq!(
+            Value::Object(fields),
+            json!({
+                "storage": [{
+                    "contract_address": "0x1",
+                    "key": "0x11",
+                    "stored": "0x101",
+                    "replayed": "0x102",
+                }],
+                "classes": [{
+                    "contract_address": "0x3",
+                    "stored": {"deployed": "0x30"},
+                    "replayed": {"replaced": "0x30"},
+                }],
+                "declared_cairo_classes": {
+                    "stored_only": [],
+                    "replayed_only": ["0x40"],
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn gas_consumed_is_derived_from_fee() {
+        assert_eq!(gas_consumed_from_fee(1000, 10, 10, 50), Some(90));
+        // A fee which does not cover the data gas is reported instead of panicking.
+        assert_eq!(gas_consumed_from_fee(100, 10, 10, 50), None);
+    }
+
+    #[test]
+    fn block_with_system_contract_update_replays_cleanly() {
+        use pathfinder_common::state_update::StateUpdate;
+        use pathfinder_common::{BlockHash, StarknetVersion};
+
+        let storage = pathfinder_storage::StorageBuilder::in_memory().unwrap();
+        let mut db = storage.connection().unwrap();
+        let db = db.transaction().unwrap();
+
+        let mut header = BlockHeader::builder()
+            .with_number(BlockNumber::GENESIS)
+            .with_starknet_version(StarknetVersion::new(0, 13, 1, 0))
+            .finalize_with_hash(block_hash_bytes!(b"block 0"));
+        let genesis_hash = header.hash;
+        db.insert_block_header(&header).unwrap();
+        for i in 1..=10u64 {
+            header = header
+                .child_builder()
+                .with_starknet_version(StarknetVersion::new(0, 13, 1, 0))
+                .finalize_with_hash(BlockHash(Felt::from_u64(0x100 + i)));
+            db.insert_block_header(&header).unwrap();
+        }
+
+        // Since v0.13 the block hash of the block ten blocks back is written to 0x1
+        // by the block pre-processing, not by any transaction.
+        let state_update = StateUpdate::default()
+            .with_block_hash(header.hash)
+            .with_system_storage_update(
+                ContractAddress::ONE,
+                storage_address!("0x0"),
+                StorageValue(genesis_hash.0),
+            );
+        db.insert_state_update(header.number, &state_update)
+            .unwrap();
+
+        let block = Block::load(&db, header.number).unwrap();
+        let simulations = block
+            .execute(&db, ChainId::SEPOLIA_TESTNET, Default::default())
+            .unwrap();
+
+        let mut summary = Summary::default();
+        block
+            .compare_state_diff(&db, &simulations, &mut summary)
+            .unwrap();
+        assert_eq!(summary.mismatches, 0);
+    }
+
+    #[test]
+    fn identical_state_diffs() {
+        let diff = StateDiff {
+            storage: HashMap::from([(
+                (contract_address!("0x1"), storage_address!("0x10")),
+                storage_value!("0x100"),
+            )]),
+            ..Default::default()
+        };
+
+        assert!(diff.compare(&diff).is_empty());
+    }
+}
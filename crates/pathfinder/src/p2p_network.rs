@@ -1,3 +1,5 @@
+use std::num::NonZeroUsize;
+
 use anyhow::Context;
 use p2p::client::peer_agnostic;
 use p2p::libp2p::identity::Keypair;
@@ -5,15 +7,31 @@ use p2p::libp2p::multiaddr::Multiaddr;
 use p2p::{HeadRx, HeadTx};
 use p2p_proto::header::BlockHeadersResponse;
 use pathfinder_common::{BlockHash, BlockNumber, ChainId};
+use pathfinder_rpc::gossip::TransactionGossipSender;
+use pathfinder_rpc::peers::{PeerReputation, PeerReputationsSender};
 use pathfinder_storage::Storage;
 use tracing::Instrument;
 
-mod sync_handlers;
+mod mempool;
+pub(crate) mod sync_handlers;
 
-use sync_handlers::{get_classes, get_events, get_headers, get_state_diffs, get_transactions};
+use mempool::TransactionGossip;
+use sync_handlers::{
+    get_classes,
+    get_events,
+    get_headers,
+    get_snapshot,
+    get_state_diffs,
+    get_transactions,
+};
 
 // Silence clippy
-pub type P2PNetworkHandle = (peer_agnostic::Client, HeadRx, tokio::task::JoinHandle<()>);
+pub type P2PNetworkHandle = (
+    peer_agnostic::Client,
+    HeadRx,
+    TransactionGossipSender,
+    tokio::task::JoinHandle<()>,
+);
 
 pub struct P2PContext {
     pub cfg: p2p::Config,
@@ -24,6 +42,7 @@ pub struct P2PContext {
     pub listen_on: Multiaddr,
     pub bootstrap_addresses: Vec<Multiaddr>,
     pub predefined_peers: Vec<Multiaddr>,
+    pub mempool_capacity: NonZeroUsize,
 }
 
 #[tracing::instrument(name = "p2p", skip_all)]
@@ -37,6 +56,7 @@ pub async fn start(context: P2PContext) -> anyhow::Result<P2PNetworkHandle> {
         listen_on,
         bootstrap_addresses,
         predefined_peers,
+        mempool_capacity,
     } = context;
 
     let peer_id = keypair.public().to_peer_id();
@@ -85,13 +105,29 @@ pub async fn start(context: P2PContext) -> anyhow::Result<P2PNetworkHandle> {
     }
 
     let block_propagation_topic = format!("blocks/{}", chain_id.to_hex_str());
+    let transaction_propagation_topic = p2p::transaction_propagation_topic(chain_id);
 
     if !proxy {
         p2p_client.subscribe_topic(&block_propagation_topic).await?;
         tracing::info!(topic=%block_propagation_topic, "Subscribed to");
+        p2p_client
+            .subscribe_topic(&transaction_propagation_topic)
+            .await?;
+        tracing::info!(topic=%transaction_propagation_topic, "Subscribed to");
     }
 
+    let client = peer_agnostic::Client::new(
+        p2p_client,
+        block_propagation_topic,
+        transaction_propagation_topic,
+    );
+
     let (mut tx, rx) = tokio::sync::watch::channel(None);
+    let (gossip_tx, mut gossip_rx) = tokio::sync::mpsc::channel(1024);
+    let gossip =
+        TransactionGossip::new(client.clone(), storage.clone(), chain_id, mempool_capacity);
+
+    let mut prune_interval = tokio::time::interval(mempool::PRUNE_INTERVAL);
 
     let join_handle = {
         tokio::task::spawn(
@@ -102,8 +138,25 @@ pub async fn start(context: P2PContext) -> anyhow::Result<P2PNetworkHandle> {
                             tracing::error!("p2p task ended unexpectedly");
                             break;
                         }
+                        Some(transaction) = gossip_rx.recv() => {
+                            gossip.submitted(transaction).await;
+                        }
+                        _ = prune_interval.tick() => {
+                            gossip.prune_included();
+                        }
                         Some(event) = p2p_events.recv() => {
-                            match handle_p2p_event(event, storage.clone(), &mut tx).await {
+                            let result = match event {
+                                p2p::Event::TransactionPropagation {
+                                    from,
+                                    message_id,
+                                    transaction,
+                                } => {
+                                    gossip.received(from, message_id, transaction);
+                                    Ok(())
+                                }
+                                event => handle_p2p_event(event, storage.clone(), &mut tx).await,
+                            };
+                            match result {
                                 Ok(()) => {},
                                 Err(e) => { tracing::error!("Failed to handle P2P event: {:#}", e) },
                             }
@@ -115,11 +168,32 @@ pub async fn start(context: P2PContext) -> anyhow::Result<P2PNetworkHandle> {
         )
     };
 
-    Ok((
-        peer_agnostic::Client::new(p2p_client, block_propagation_topic),
-        rx,
-        join_handle,
-    ))
+    Ok((client, rx, gossip_tx, join_handle))
+}
+
+/// Answers requests for the reputations of penalized peers, e.g. from the RPC
+/// API, until all senders are dropped.
+pub fn serve_peer_reputations(client: peer_agnostic::Client) -> PeerReputationsSender {
+    let (sender, mut receiver) =
+        tokio::sync::mpsc::channel::<tokio::sync::oneshot::Sender<Vec<PeerReputation>>>(1);
+
+    tokio::spawn(async move {
+        while let Some(request) = receiver.recv().await {
+            let reputations = client
+                .peer_reputations()
+                .await
+                .into_iter()
+                .map(|reputation| PeerReputation {
+                    peer_id: reputation.peer_id.to_string(),
+                    score: reputation.score,
+                    banned: reputation.banned,
+                })
+                .collect();
+            let _ = request.send(reputations);
+        }
+    });
+
+    sender
 }
 
 async fn handle_p2p_event(
@@ -153,6 +227,11 @@ async fn handle_p2p_event(
         } => {
             get_events(storage, request, channel).await?;
         }
+        p2p::Event::InboundSnapshotsSyncRequest {
+            request, channel, ..
+        } => {
+            get_snapshot(storage, request, channel).await?;
+        }
         p2p::Event::BlockPropagation { from, new_block } => {
             tracing::info!(%from, ?new_block, "Block Propagation");
             use p2p_proto::header::NewBlock;
@@ -183,6 +262,9 @@ async fn handle_p2p_event(
                 }
             }
         }
+        p2p::Event::TransactionPropagation { .. } => {
+            unreachable!("Transactions are handled by the transaction gossip")
+        }
         p2p::Event::SyncPeerConnected { .. } | p2p::Event::Test(_) => { /* Ignore me */ }
     }
 
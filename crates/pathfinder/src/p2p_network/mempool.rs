@@ -0,0 +1,586 @@
+//! Propagation of transactions which are not yet included in a block.
+//!
+//! Transactions submitted via the RPC API are published to peers, and valid
+//! transactions received from peers are kept in a bounded [Mempool] and
+//! forwarded to other peers. Transactions are removed from the pool once they
+//! are included in a block.
+
+use std::collections::{HashMap, VecDeque};
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Context;
+use p2p::client::conv::{SierraDefinition, ToDto, TryFromDto};
+use p2p::client::peer_agnostic;
+use p2p::libp2p::gossipsub::{MessageAcceptance, MessageId};
+use p2p::libp2p::PeerId;
+use p2p::Misbehaviour;
+use p2p_proto::class::{Cairo1Class, Cairo1EntryPoints, SierraEntryPoint};
+use p2p_proto::transaction::{
+    BroadcastedDeclareV2,
+    BroadcastedDeclareV3,
+    BroadcastedTransaction,
+    Transaction as P2PTransaction,
+};
+use pathfinder_common::transaction::{ResourceBounds, TransactionVariant};
+use pathfinder_common::{BlockId, ChainId, ContractAddress, TransactionHash, TransactionNonce};
+use pathfinder_crypto::Felt;
+use pathfinder_rpc::v02::types::request::{
+    BroadcastedDeclareTransaction,
+    BroadcastedTransaction as RpcBroadcastedTransaction,
+};
+use pathfinder_rpc::v02::types::SierraContractClass;
+use pathfinder_storage::Storage;
+
+/// How often transactions which were included in a block are removed from the
+/// pool.
+pub const PRUNE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Transactions received from peers, in the order they were received.
+///
+/// The oldest transactions are dropped once the pool is full.
+#[derive(Debug)]
+pub struct Mempool {
+    capacity: NonZeroUsize,
+    transactions: HashMap<TransactionHash, BroadcastedTransaction>,
+    order: VecDeque<TransactionHash>,
+}
+
+impl Mempool {
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        Self {
+            capacity,
+            transactions: Default::default(),
+            order: Default::default(),
+        }
+    }
+
+    pub fn contains(&self, hash: &TransactionHash) -> bool {
+        self.transactions.contains_key(hash)
+    }
+
+    fn len(&self) -> usize {
+        self.transactions.len()
+    }
+
+    /// Adds a transaction to the pool, dropping the oldest transaction if the
+    /// pool is full. Returns `false` if the transaction was already in the
+    /// pool.
+    pub fn insert(&mut self, hash: TransactionHash, transaction: BroadcastedTransaction) -> bool {
+        if self.contains(&hash) {
+            return false;
+        }
+
+        if self.order.len() == self.capacity.get() {
+            if let Some(oldest) = self.order.pop_front() {
+                self.transactions.remove(&oldest);
+            }
+        }
+
+        self.transactions.insert(hash, transaction);
+        self.order.push_back(hash);
+
+        metrics::gauge!("p2p_mempool_transactions", self.len() as f64);
+
+        true
+    }
+
+    fn hashes(&self) -> Vec<TransactionHash> {
+        self.order.iter().copied().collect()
+    }
+
+    /// Removes the given transactions from the pool.
+    fn remove(&mut self, hashes: &[TransactionHash]) {
+        if hashes.is_empty() {
+            return;
+        }
+
+        for hash in hashes {
+            self.transactions.remove(hash);
+        }
+        self.order
+            .retain(|hash| self.transactions.contains_key(hash));
+
+        metrics::gauge!("p2p_mempool_transactions", self.len() as f64);
+    }
+}
+
+/// Publishes transactions submitted via the RPC API, and validates
+/// transactions received from peers before they are forwarded.
+pub struct TransactionGossip {
+    client: peer_agnostic::Client,
+    storage: Storage,
+    chain_id: ChainId,
+    /// Shared with the tasks validating received transactions, so that the
+    /// event loop does not wait for validation.
+    mempool: Arc<Mutex<Mempool>>,
+}
+
+impl TransactionGossip {
+    pub fn new(
+        client: peer_agnostic::Client,
+        storage: Storage,
+        chain_id: ChainId,
+        capacity: NonZeroUsize,
+    ) -> Self {
+        Self {
+            client,
+            storage,
+            chain_id,
+            mempool: Arc::new(Mutex::new(Mempool::new(capacity))),
+        }
+    }
+
+    /// Publishes a transaction which was submitted via the RPC API.
+    pub async fn submitted(&self, transaction: RpcBroadcastedTransaction) {
+        let Some((hash, transaction)) = into_gossiped(transaction, self.chain_id) else {
+            tracing::debug!("Transaction version is not propagated");
+            return;
+        };
+
+        if !self
+            .mempool
+            .lock()
+            .unwrap()
+            .insert(hash, transaction.clone())
+        {
+            return;
+        }
+
+        if let Err(error) = self.client.propagate_transaction(transaction).await {
+            tracing::debug!(transaction_hash=%hash, %error, "Propagating transaction failed");
+        }
+    }
+
+    /// Validates a transaction received from a peer in a separate task. Valid
+    /// transactions are added to the pool and forwarded to other peers.
+    pub fn received(
+        &self,
+        from: PeerId,
+        message_id: MessageId,
+        transaction: BroadcastedTransaction,
+    ) {
+        let client = self.client.clone();
+        let storage = self.storage.clone();
+        let chain_id = self.chain_id;
+        let mempool = self.mempool.clone();
+
+        tokio::spawn(async move {
+            let gossiped = transaction.clone();
+            let validity =
+                match tokio::task::spawn_blocking(move || validate(gossiped, chain_id, storage))
+                    .await
+                    .context("Joining blocking task")
+                {
+                    Ok(Ok(validity)) => validity,
+                    Ok(Err(error)) | Err(error) => {
+                        tracing::error!(%from, "Failed to validate transaction: {:#}", error);
+                        Validity::Ignored
+                    }
+                };
+
+            let acceptance = match validity {
+                Validity::Valid(hash) => {
+                    let inserted = mempool.lock().unwrap().insert(hash, transaction);
+                    if inserted {
+                        tracing::trace!(%from, transaction_hash=%hash, "Received transaction");
+                        MessageAcceptance::Accept
+                    } else {
+                        MessageAcceptance::Ignore
+                    }
+                }
+                Validity::Ignored => MessageAcceptance::Ignore,
+                Validity::Invalid(misbehaviour) => {
+                    tracing::debug!(%from, ?misbehaviour, "Received invalid transaction");
+                    client.report_peer(from, misbehaviour).await;
+                    MessageAcceptance::Reject
+                }
+            };
+
+            client
+                .report_transaction_validation(message_id, from, acceptance)
+                .await;
+        });
+    }
+
+    /// Removes transactions which were included in a block from the pool, in a
+    /// separate task.
+    pub fn prune_included(&self) {
+        let storage = self.storage.clone();
+        let mempool = self.mempool.clone();
+
+        tokio::spawn(async move {
+            let hashes = mempool.lock().unwrap().hashes();
+            if hashes.is_empty() {
+                return;
+            }
+
+            match tokio::task::spawn_blocking(move || included(hashes, storage))
+                .await
+                .context("Joining blocking task")
+            {
+                Ok(Ok(included)) => mempool.lock().unwrap().remove(&included),
+                Ok(Err(error)) | Err(error) => {
+                    tracing::error!("Failed to prune mempool: {:#}", error)
+                }
+            }
+        });
+    }
+}
+
+/// Returns the transactions which are included in a block.
+fn included(
+    hashes: Vec<TransactionHash>,
+    storage: Storage,
+) -> anyhow::Result<Vec<TransactionHash>> {
+    let mut db = storage
+        .connection()
+        .context("Creating database connection")?;
+    let db = db.transaction().context("Creating database transaction")?;
+
+    let mut included = Vec::new();
+    for hash in hashes {
+        if db
+            .transaction_block_hash(hash)
+            .context("Querying transaction")?
+            .is_some()
+        {
+            included.push(hash);
+        }
+    }
+
+    Ok(included)
+}
+
+#[derive(Debug, PartialEq)]
+enum Validity {
+    Valid(TransactionHash),
+    /// The transaction is well formed but should not be propagated, e.g.
+    /// because it is already included in a block or its nonce is outdated.
+    Ignored,
+    Invalid(Misbehaviour),
+}
+
+/// Basic validity checks which are cheap enough to run for every transaction
+/// received from a peer. Transactions are not executed.
+fn validate(
+    transaction: BroadcastedTransaction,
+    chain_id: ChainId,
+    storage: Storage,
+) -> anyhow::Result<Validity> {
+    let (transaction, class) = match transaction {
+        BroadcastedTransaction::DeclareV2(x) => {
+            (P2PTransaction::DeclareV2(x.declare), Some(x.class))
+        }
+        BroadcastedTransaction::DeclareV3(x) => {
+            (P2PTransaction::DeclareV3(x.declare), Some(x.class))
+        }
+        BroadcastedTransaction::DeployAccountV1(x) => (P2PTransaction::DeployAccountV1(x), None),
+        BroadcastedTransaction::DeployAccountV3(x) => (P2PTransaction::DeployAccountV3(x), None),
+        BroadcastedTransaction::InvokeV1(x) => (P2PTransaction::InvokeV1(x), None),
+        BroadcastedTransaction::InvokeV3(x) => (P2PTransaction::InvokeV3(x), None),
+    };
+
+    let Ok(variant) = TransactionVariant::try_from_dto(transaction) else {
+        return Ok(Validity::Invalid(Misbehaviour::MalformedData));
+    };
+
+    if let Some(class) = class {
+        let Ok(SierraDefinition(definition)) = SierraDefinition::try_from_dto(class) else {
+            return Ok(Validity::Invalid(Misbehaviour::MalformedData));
+        };
+        let Ok(class_hash) = starknet_gateway_types::class_hash::compute_class_hash(&definition)
+        else {
+            return Ok(Validity::Invalid(Misbehaviour::MalformedData));
+        };
+
+        let declared = match &variant {
+            TransactionVariant::DeclareV2(tx) => tx.class_hash,
+            TransactionVariant::DeclareV3(tx) => tx.class_hash,
+            _ => unreachable!("Only declare transactions carry a class"),
+        };
+        if class_hash.hash() != declared {
+            return Ok(Validity::Invalid(Misbehaviour::CommitmentMismatch));
+        }
+    }
+
+    let fee_is_set = |resource_bounds: &ResourceBounds| {
+        resource_bounds.l1_gas.max_amount.0 != 0 && resource_bounds.l1_gas.max_price_per_unit.0 != 0
+    };
+    let (sender, nonce, fee_is_set) = match &variant {
+        TransactionVariant::DeclareV2(tx) => {
+            (tx.sender_address, tx.nonce, tx.max_fee.0 != Felt::ZERO)
+        }
+        TransactionVariant::DeclareV3(tx) => {
+            (tx.sender_address, tx.nonce, fee_is_set(&tx.resource_bounds))
+        }
+        TransactionVariant::DeployAccountV1(tx) => {
+            (tx.contract_address, tx.nonce, tx.max_fee.0 != Felt::ZERO)
+        }
+        TransactionVariant::DeployAccountV3(tx) => (
+            tx.contract_address,
+            tx.nonce,
+            fee_is_set(&tx.resource_bounds),
+        ),
+        TransactionVariant::InvokeV1(tx) => {
+            (tx.sender_address, tx.nonce, tx.max_fee.0 != Felt::ZERO)
+        }
+        TransactionVariant::InvokeV3(tx) => {
+            (tx.sender_address, tx.nonce, fee_is_set(&tx.resource_bounds))
+        }
+        _ => unreachable!("Only broadcasted transaction variants are converted"),
+    };
+    if !fee_is_set {
+        return Ok(Validity::Invalid(Misbehaviour::UnexpectedData));
+    }
+
+    let hash = variant.calculate_hash(chain_id, false);
+    let deploys_account = matches!(
+        variant,
+        TransactionVariant::DeployAccountV1(_) | TransactionVariant::DeployAccountV3(_)
+    );
+
+    let mut db = storage
+        .connection()
+        .context("Creating database connection")?;
+    let db = db.transaction().context("Creating database transaction")?;
+
+    if db
+        .transaction_block_hash(hash)
+        .context("Querying transaction")?
+        .is_some()
+    {
+        return Ok(Validity::Ignored);
+    }
+
+    let deployed = db
+        .contract_exists(sender, BlockId::Latest)
+        .context("Querying contract existence")?;
+    // Accounts have to be deployed before they can send transactions, and
+    // can only be deployed once.
+    if deployed == deploys_account {
+        return Ok(Validity::Ignored);
+    }
+
+    if !nonce_is_current(&db, sender, nonce)? {
+        return Ok(Validity::Ignored);
+    }
+
+    Ok(Validity::Valid(hash))
+}
+
+fn nonce_is_current(
+    db: &pathfinder_storage::Transaction<'_>,
+    sender: ContractAddress,
+    nonce: TransactionNonce,
+) -> anyhow::Result<bool> {
+    let current = db
+        .contract_nonce(sender, BlockId::Latest)
+        .context("Querying contract nonce")?
+        .unwrap_or_default();
+
+    Ok(nonce.0 >= current.0)
+}
+
+/// Converts a transaction submitted via the RPC API into its p2p
+/// representation. Returns [None] for transaction versions which are not
+/// propagated.
+fn into_gossiped(
+    transaction: RpcBroadcastedTransaction,
+    chain_id: ChainId,
+) -> Option<(TransactionHash, BroadcastedTransaction)> {
+    let class = match &transaction {
+        RpcBroadcastedTransaction::Declare(BroadcastedDeclareTransaction::V2(tx)) => {
+            Some(class_to_dto(&tx.contract_class))
+        }
+        RpcBroadcastedTransaction::Declare(BroadcastedDeclareTransaction::V3(tx)) => {
+            Some(class_to_dto(&tx.contract_class))
+        }
+        _ => None,
+    };
+    let transaction = transaction.into_common(chain_id);
+
+    let gossiped = match (transaction.variant.to_dto(), class) {
+        (P2PTransaction::DeclareV2(declare), Some(class)) => {
+            BroadcastedTransaction::DeclareV2(BroadcastedDeclareV2 { declare, class })
+        }
+        (P2PTransaction::DeclareV3(declare), Some(class)) => {
+            BroadcastedTransaction::DeclareV3(BroadcastedDeclareV3 { declare, class })
+        }
+        (P2PTransaction::DeployAccountV1(x), None) => BroadcastedTransaction::DeployAccountV1(x),
+        (P2PTransaction::DeployAccountV3(x), None) => BroadcastedTransaction::DeployAccountV3(x),
+        (P2PTransaction::InvokeV1(x), None) => BroadcastedTransaction::InvokeV1(x),
+        (P2PTransaction::InvokeV3(x), None) => BroadcastedTransaction::InvokeV3(x),
+        _ => return None,
+    };
+
+    Some((transaction.hash, gossiped))
+}
+
+fn class_to_dto(class: &SierraContractClass) -> Cairo1Class {
+    let into_dto = |entry_points: &[pathfinder_rpc::v02::types::SierraEntryPoint]| {
+        entry_points
+            .iter()
+            .map(|x| SierraEntryPoint {
+                index: x.function_idx,
+                selector: x.selector,
+            })
+            .collect()
+    };
+
+    Cairo1Class {
+        abi: class.abi.clone(),
+        entry_points: Cairo1EntryPoints {
+            externals: into_dto(&class.entry_points_by_type.external),
+            l1_handlers: into_dto(&class.entry_points_by_type.l1_handler),
+            constructors: into_dto(&class.entry_points_by_type.constructor),
+        },
+        program: class.sierra_program.clone(),
+        contract_class_version: class.contract_class_version.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::{BlockNumber, StateUpdate, TransactionVersion};
+    use pathfinder_rpc::v02::types::request::{
+        BroadcastedInvokeTransaction,
+        BroadcastedInvokeTransactionV1,
+    };
+    use pathfinder_storage::StorageBuilder;
+
+    use super::*;
+
+    fn invoke(nonce: TransactionNonce) -> RpcBroadcastedTransaction {
+        RpcBroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V1(
+            BroadcastedInvokeTransactionV1 {
+                version: TransactionVersion::ONE,
+                max_fee: fee!("0x1000"),
+                signature: vec![transaction_signature_elem!("0x1")],
+                nonce,
+                sender_address: contract_address!("0x123"),
+                calldata: vec![call_param!("0x2")],
+            },
+        ))
+    }
+
+    /// Storage in which the sender of [invoke] is deployed with nonce 1.
+    fn storage() -> Storage {
+        let storage = StorageBuilder::in_memory().unwrap();
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+
+        let header = pathfinder_common::BlockHeader::default();
+        tx.insert_block_header(&header).unwrap();
+        tx.insert_state_update(
+            header.number,
+            &StateUpdate::default()
+                .with_deployed_contract(contract_address!("0x123"), class_hash!("0x1"))
+                .with_contract_nonce(contract_address!("0x123"), contract_nonce!("0x1")),
+        )
+        .unwrap();
+        tx.commit().unwrap();
+
+        storage
+    }
+
+    #[test]
+    fn mempool_drops_oldest_transaction() {
+        let mut mempool = Mempool::new(NonZeroUsize::new(2).unwrap());
+        let (_, transaction) =
+            into_gossiped(invoke(transaction_nonce!("0x1")), ChainId::SEPOLIA_TESTNET).unwrap();
+
+        assert!(mempool.insert(transaction_hash!("0x1"), transaction.clone()));
+        assert!(mempool.insert(transaction_hash!("0x2"), transaction.clone()));
+        assert!(!mempool.insert(transaction_hash!("0x2"), transaction.clone()));
+        assert!(mempool.insert(transaction_hash!("0x3"), transaction));
+
+        assert_eq!(mempool.len(), 2);
+        assert!(!mempool.contains(&transaction_hash!("0x1")));
+        assert!(mempool.contains(&transaction_hash!("0x2")));
+        assert!(mempool.contains(&transaction_hash!("0x3")));
+    }
+
+    #[test]
+    fn included_transactions_are_pruned() {
+        use pathfinder_common::receipt::Receipt;
+        use pathfinder_common::transaction::Transaction;
+
+        let storage = storage();
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+        let transaction = Transaction {
+            hash: transaction_hash!("0x1"),
+            variant: TransactionVariant::InvokeV1(Default::default()),
+        };
+        let receipt = Receipt {
+            transaction_hash: transaction_hash!("0x1"),
+            ..Default::default()
+        };
+        tx.insert_transaction_data(BlockNumber::GENESIS, &[(transaction, receipt)], None)
+            .unwrap();
+        tx.commit().unwrap();
+
+        let mut mempool = Mempool::new(NonZeroUsize::new(3).unwrap());
+        let (_, transaction) =
+            into_gossiped(invoke(transaction_nonce!("0x1")), ChainId::SEPOLIA_TESTNET).unwrap();
+        mempool.insert(transaction_hash!("0x1"), transaction.clone());
+        mempool.insert(transaction_hash!("0x2"), transaction);
+
+        let included = included(mempool.hashes(), storage).unwrap();
+        assert_eq!(included, vec![transaction_hash!("0x1")]);
+
+        mempool.remove(&included);
+        assert_eq!(mempool.hashes(), vec![transaction_hash!("0x2")]);
+    }
+
+    #[test]
+    fn gossiped_transaction_keeps_its_hash() {
+        let submitted = invoke(transaction_nonce!("0x1"));
+        let expected = submitted.clone().into_common(ChainId::SEPOLIA_TESTNET).hash;
+
+        let (hash, transaction) = into_gossiped(submitted, ChainId::SEPOLIA_TESTNET).unwrap();
+        assert_eq!(hash, expected);
+
+        let validity = validate(transaction, ChainId::SEPOLIA_TESTNET, storage()).unwrap();
+        assert_eq!(validity, Validity::Valid(expected));
+    }
+
+    #[test]
+    fn outdated_nonce_is_ignored() {
+        let (_, transaction) =
+            into_gossiped(invoke(transaction_nonce!("0x0")), ChainId::SEPOLIA_TESTNET).unwrap();
+
+        let validity = validate(transaction, ChainId::SEPOLIA_TESTNET, storage()).unwrap();
+        assert_eq!(validity, Validity::Ignored);
+    }
+
+    #[test]
+    fn unknown_sender_is_ignored() {
+        let (_, transaction) =
+            into_gossiped(invoke(transaction_nonce!("0x1")), ChainId::SEPOLIA_TESTNET).unwrap();
+
+        let validity = validate(
+            transaction,
+            ChainId::SEPOLIA_TESTNET,
+            StorageBuilder::in_memory().unwrap(),
+        )
+        .unwrap();
+        assert_eq!(validity, Validity::Ignored);
+    }
+
+    #[test]
+    fn missing_fee_is_invalid() {
+        let (_, mut transaction) =
+            into_gossiped(invoke(transaction_nonce!("0x1")), ChainId::SEPOLIA_TESTNET).unwrap();
+        let BroadcastedTransaction::InvokeV1(invoke) = &mut transaction else {
+            unreachable!()
+        };
+        invoke.max_fee = Felt::ZERO;
+
+        let validity = validate(transaction, ChainId::SEPOLIA_TESTNET, storage()).unwrap();
+        assert_eq!(validity, Validity::Invalid(Misbehaviour::UnexpectedData));
+    }
+}
@@ -13,6 +13,20 @@ use p2p_proto::common::{
 };
 use p2p_proto::event::{EventsRequest, EventsResponse};
 use p2p_proto::header::{BlockHeadersRequest, BlockHeadersResponse};
+use p2p_proto::snapshot::{
+    ClassDefinitionRequest,
+    ClassLeaf,
+    ClassRange,
+    ClassRangeRequest,
+    ContractRange,
+    ContractRangeRequest,
+    ContractState,
+    ContractStorageRange,
+    ContractStorageRangeRequest,
+    PatriciaRangeProof,
+    SnapshotRequest,
+    SnapshotResponse,
+};
 use p2p_proto::state::{
     ContractDiff,
     ContractStoredValue,
@@ -21,12 +35,27 @@ use p2p_proto::state::{
     StateDiffsResponse,
 };
 use p2p_proto::transaction::{TransactionWithReceipt, TransactionsRequest, TransactionsResponse};
-use pathfinder_common::{class_definition, BlockHash, BlockNumber, SignedBlockHeader};
+use pathfinder_common::trie::TrieNode;
+use pathfinder_common::{
+    class_definition,
+    BlockHash,
+    BlockNumber,
+    ClassCommitment,
+    ClassHash,
+    ContractAddress,
+    ContractNonce,
+    ContractRoot,
+    SierraHash,
+    SignedBlockHeader,
+    StorageAddress,
+    StorageCommitment,
+};
+use pathfinder_merkle_tree::{ClassCommitmentTree, ContractsStorageTree, StorageCommitmentTree};
 use pathfinder_storage::{Storage, Transaction};
 use tokio::sync::mpsc;
 
 #[cfg(test)]
-mod tests;
+pub(crate) mod tests;
 
 #[cfg(not(test))]
 const MAX_BLOCKS_COUNT: u64 = 100;
@@ -36,6 +65,9 @@ const MAX_COUNT_IN_TESTS: u64 = 10;
 #[cfg(test)]
 const MAX_BLOCKS_COUNT: u64 = MAX_COUNT_IN_TESTS;
 
+/// The maximum number of leaves in a single snapshot range.
+const MAX_SNAPSHOT_RANGE: u32 = 1024;
+
 pub async fn get_headers(
     storage: Storage,
     request: BlockHeadersRequest,
@@ -76,6 +108,14 @@ pub async fn get_events(
     spawn_blocking_get(request, storage, blocking::get_events, tx).await
 }
 
+pub async fn get_snapshot(
+    storage: Storage,
+    request: SnapshotRequest,
+    tx: futures::channel::mpsc::Sender<SnapshotResponse>,
+) -> anyhow::Result<()> {
+    spawn_blocking_get(request, storage, blocking::get_snapshot, tx).await
+}
+
 pub(crate) mod blocking {
     use super::*;
 
@@ -123,6 +163,41 @@ pub(crate) mod blocking {
     ) -> anyhow::Result<()> {
         iterate(db_tx, request.iteration, get_events_for_block, tx)
     }
+
+    #[tracing::instrument(skip(db_tx, tx))]
+    pub(crate) fn get_snapshot(
+        db_tx: Transaction<'_>,
+        request: SnapshotRequest,
+        tx: mpsc::Sender<SnapshotResponse>,
+    ) -> anyhow::Result<()> {
+        let response = match request {
+            SnapshotRequest::ContractRange(request) => {
+                get_contract_range(&db_tx, request)?.map(SnapshotResponse::ContractRange)
+            }
+            SnapshotRequest::ContractStorageRange(request) => {
+                get_contract_storage_range(&db_tx, request)?
+                    .map(SnapshotResponse::ContractStorageRange)
+            }
+            SnapshotRequest::ClassRange(request) => {
+                get_class_range(&db_tx, request)?.map(SnapshotResponse::ClassRange)
+            }
+            SnapshotRequest::ClassDefinition(request) => {
+                get_class_definition(&db_tx, request)?.map(SnapshotResponse::ClassDefinition)
+            }
+        };
+
+        // Only Fin is sent if we don't have the tries of the requested block or the
+        // requested class.
+        if let Some(response) = response {
+            tx.blocking_send(response)
+                .map_err(|_| anyhow::anyhow!("Sending snapshot range"))?;
+        }
+
+        tx.blocking_send(SnapshotResponse::Fin)
+            .map_err(|_| anyhow::anyhow!("Sending Fin"))?;
+
+        Ok(())
+    }
 }
 
 fn get_header(
@@ -146,69 +221,26 @@ fn get_header(
     Ok(false)
 }
 
-#[derive(Debug, Clone)]
-enum ClassDefinition {
-    Cairo(Vec<u8>),
-    Sierra { sierra: Vec<u8>, _casm: Vec<u8> },
-}
-
 fn get_classes_for_block(
     db_tx: &Transaction<'_>,
     block_number: BlockNumber,
     tx: &mpsc::Sender<ClassesResponse>,
 ) -> anyhow::Result<bool> {
-    let get_definition =
-        |block_number: BlockNumber, class_hash| -> anyhow::Result<ClassDefinition> {
-            let definition = db_tx
-                .class_definition_at(block_number.into(), class_hash)?
-                .ok_or_else(|| {
-                    anyhow::anyhow!(
-                        "Class definition {} not found at block {}",
-                        class_hash,
-                        block_number
-                    )
-                })?;
-            let casm_definition = db_tx.casm_definition(class_hash)?;
-            Ok(match casm_definition {
-                Some(_casm) => ClassDefinition::Sierra {
-                    sierra: definition,
-                    _casm: Vec::new(), // TODO casm
-                },
-                None => ClassDefinition::Cairo(definition),
-            })
-        };
-
     let Some(declared_classes) = db_tx.declared_classes_at(block_number.into())? else {
         return Ok(false);
     };
 
     for class_hash in declared_classes {
-        let class_definition = get_definition(block_number, class_hash)?;
+        let class = class_at(db_tx, block_number, class_hash)?.ok_or_else(|| {
+            anyhow::anyhow!(
+                "Class definition {} not found at block {}",
+                class_hash,
+                block_number
+            )
+        })?;
 
         tracing::trace!(?class_hash, "Sending class definition");
 
-        let class: Class = match class_definition {
-            ClassDefinition::Cairo(definition) => {
-                let cairo_class =
-                    serde_json::from_slice::<class_definition::Cairo<'_>>(&definition)?;
-                Class::Cairo0 {
-                    class: cairo_class.to_dto(),
-                    domain: 0, // TODO
-                }
-            }
-            ClassDefinition::Sierra {
-                sierra,
-                _casm: _, /* TODO */
-            } => {
-                let sierra_class = serde_json::from_slice::<class_definition::Sierra<'_>>(&sierra)?;
-
-                Class::Cairo1 {
-                    class: sierra_class.to_dto(),
-                    domain: 0, // TODO
-                }
-            }
-        };
-
         tx.blocking_send(ClassesResponse::Class(class))
             .map_err(|_| anyhow::anyhow!("Sending class"))?;
     }
@@ -216,6 +248,37 @@ fn get_classes_for_block(
     Ok(true)
 }
 
+/// The definition of a class declared at or before `block_number`.
+fn class_at(
+    db_tx: &Transaction<'_>,
+    block_number: BlockNumber,
+    class_hash: ClassHash,
+) -> anyhow::Result<Option<Class>> {
+    let Some(definition) = db_tx.class_definition_at(block_number.into(), class_hash)? else {
+        return Ok(None);
+    };
+
+    let class = match db_tx.casm_definition(class_hash)? {
+        // TODO casm
+        Some(_casm) => {
+            let sierra_class = serde_json::from_slice::<class_definition::Sierra<'_>>(&definition)?;
+            Class::Cairo1 {
+                class: sierra_class.to_dto(),
+                domain: 0, // TODO
+            }
+        }
+        None => {
+            let cairo_class = serde_json::from_slice::<class_definition::Cairo<'_>>(&definition)?;
+            Class::Cairo0 {
+                class: cairo_class.to_dto(),
+                domain: 0, // TODO
+            }
+        }
+    };
+
+    Ok(Some(class))
+}
+
 fn get_state_diff(
     db_tx: &Transaction<'_>,
     block_number: BlockNumber,
@@ -324,6 +387,212 @@ fn get_events_for_block(
     Ok(true)
 }
 
+fn get_contract_range(
+    db_tx: &Transaction<'_>,
+    request: ContractRangeRequest,
+) -> anyhow::Result<Option<ContractRange>> {
+    let Some(block) = BlockNumber::new(request.block_number) else {
+        return Ok(None);
+    };
+    let Some(header) = db_tx.block_header(block.into())? else {
+        return Ok(None);
+    };
+
+    let start = ContractAddress(request.start.0);
+    let limit = snapshot_range_limit(request.limit);
+
+    let (contracts, proof) = if header.storage_commitment == StorageCommitment::ZERO {
+        (vec![], PatriciaRangeProof::default())
+    } else {
+        let Some(contracts) = StorageCommitmentTree::get_range(db_tx, block, start, limit)? else {
+            return Ok(None);
+        };
+        let last = full_range_end(&contracts, limit);
+        let Some(proof) = range_proof(start, last, |address| {
+            StorageCommitmentTree::get_proof(db_tx, block, &address)
+        })?
+        else {
+            return Ok(None);
+        };
+        (contracts, proof)
+    };
+
+    let states = contracts
+        .into_iter()
+        .map(|(address, _)| {
+            let class_hash = db_tx
+                .contract_class_hash(block.into(), address)
+                .context("Querying contract's class hash")?
+                .unwrap_or(ClassHash::ZERO);
+            let nonce = db_tx
+                .contract_nonce(address, block.into())
+                .context("Querying contract's nonce")?
+                .unwrap_or(ContractNonce::ZERO);
+            let storage_root = db_tx
+                .contract_root(block, address)
+                .context("Querying contract's storage root")?
+                .unwrap_or(ContractRoot::ZERO);
+
+            Ok(ContractState {
+                address: Address(address.0),
+                class_hash: Hash(class_hash.0),
+                nonce: nonce.0,
+                storage_root: Hash(storage_root.0),
+            })
+        })
+        .collect::<anyhow::Result<_>>()?;
+
+    Ok(Some(ContractRange {
+        storage_commitment: Hash(header.storage_commitment.0),
+        class_commitment: Hash(header.class_commitment.0),
+        states,
+        proof,
+    }))
+}
+
+fn get_contract_storage_range(
+    db_tx: &Transaction<'_>,
+    request: ContractStorageRangeRequest,
+) -> anyhow::Result<Option<ContractStorageRange>> {
+    let Some(block) = BlockNumber::new(request.block_number) else {
+        return Ok(None);
+    };
+    if !db_tx.block_exists(block.into())? {
+        return Ok(None);
+    }
+
+    let contract = ContractAddress(request.address.0);
+    let start = StorageAddress(request.start);
+    let limit = snapshot_range_limit(request.limit);
+
+    // A contract without storage has an empty storage trie.
+    let Some(values) = ContractsStorageTree::get_range(db_tx, contract, block, start, limit)?
+    else {
+        return Ok(Some(ContractStorageRange {
+            values: vec![],
+            proof: PatriciaRangeProof::default(),
+        }));
+    };
+    let last = full_range_end(&values, limit);
+    let Some(proof) = range_proof(start, last, |key| {
+        ContractsStorageTree::get_proof(db_tx, contract, block, key.view_bits())
+    })?
+    else {
+        return Ok(None);
+    };
+
+    Ok(Some(ContractStorageRange {
+        values: values
+            .into_iter()
+            .map(|(key, value)| ContractStoredValue {
+                key: key.0,
+                value: value.0,
+            })
+            .collect(),
+        proof,
+    }))
+}
+
+fn get_class_range(
+    db_tx: &Transaction<'_>,
+    request: ClassRangeRequest,
+) -> anyhow::Result<Option<ClassRange>> {
+    let Some(block) = BlockNumber::new(request.block_number) else {
+        return Ok(None);
+    };
+    let Some(header) = db_tx.block_header(block.into())? else {
+        return Ok(None);
+    };
+
+    if header.class_commitment == ClassCommitment::ZERO {
+        return Ok(Some(ClassRange {
+            classes: vec![],
+            proof: PatriciaRangeProof::default(),
+        }));
+    }
+
+    let start = SierraHash(request.start.0);
+    let limit = snapshot_range_limit(request.limit);
+
+    let Some(classes) = ClassCommitmentTree::get_range(db_tx, block, start, limit)? else {
+        return Ok(None);
+    };
+    let last = full_range_end(&classes, limit);
+    let Some(proof) = range_proof(start, last, |class| {
+        ClassCommitmentTree::get_proof(db_tx, block, class)
+    })?
+    else {
+        return Ok(None);
+    };
+
+    let classes = classes
+        .into_iter()
+        .map(|(sierra_hash, _)| {
+            let casm_hash = db_tx
+                .casm_hash_at(block.into(), ClassHash(sierra_hash.0))
+                .context("Querying CASM hash")?
+                .context("CASM hash is missing")?;
+
+            Ok(ClassLeaf {
+                class_hash: Hash(sierra_hash.0),
+                compiled_class_hash: Hash(casm_hash.0),
+            })
+        })
+        .collect::<anyhow::Result<_>>()?;
+
+    Ok(Some(ClassRange { classes, proof }))
+}
+
+fn get_class_definition(
+    db_tx: &Transaction<'_>,
+    request: ClassDefinitionRequest,
+) -> anyhow::Result<Option<Class>> {
+    let Some(block) = BlockNumber::new(request.block_number) else {
+        return Ok(None);
+    };
+
+    class_at(db_tx, block, ClassHash(request.class_hash.0))
+}
+
+fn snapshot_range_limit(limit: u32) -> usize {
+    limit.clamp(1, MAX_SNAPSHOT_RANGE) as usize
+}
+
+/// The last key of a range, if the range is full. Ranges which are not full
+/// extend to the end of the trie.
+fn full_range_end<K: Copy, V>(leaves: &[(K, V)], limit: usize) -> Option<K> {
+    if leaves.len() == limit {
+        leaves.last().map(|(key, _)| *key)
+    } else {
+        None
+    }
+}
+
+/// Proves a range of leaves from `start` to `last`, or to the end of the trie
+/// if `last` is `None`. Returns `None` if the trie is not available.
+fn range_proof<K>(
+    start: K,
+    last: Option<K>,
+    get_proof: impl Fn(K) -> anyhow::Result<Option<Vec<TrieNode>>>,
+) -> anyhow::Result<Option<PatriciaRangeProof>> {
+    let Some(start) = get_proof(start).context("Generating start proof")? else {
+        return Ok(None);
+    };
+
+    let end = match last {
+        Some(last) => match get_proof(last).context("Generating end proof")? {
+            Some(end) => end,
+            None => return Ok(None),
+        },
+        None => vec![],
+    };
+
+    Ok(Some(PatriciaRangeProof {
+        start: start.into_iter().map(ToDto::to_dto).collect(),
+        end: end.into_iter().map(ToDto::to_dto).collect(),
+    }))
+}
+
 /// Assupmtions:
 /// - `block_handler` returns `Ok(true)` if the iteration should continue,
 /// - `T::default()` always returns the `Fin` variant of the implementing type.
@@ -648,3 +648,451 @@ mod prop {
         }
     }
 }
+
+/// Snapshot ranges served from a small state, which is also the source of the
+/// snapshot sync tests.
+pub(crate) mod snapshot {
+    use std::collections::HashMap;
+
+    use p2p::client::conv::TryFromDto;
+    use p2p_proto::common::{Address, Hash};
+    use p2p_proto::snapshot::{
+        ClassDefinitionRequest,
+        ClassRangeRequest,
+        ContractRangeRequest,
+        ContractStorageRangeRequest,
+        PatriciaRangeProof,
+    };
+    use pathfinder_common::hash::{FeltHash, PedersenHash, PoseidonHash};
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::trie::TrieNode;
+    use pathfinder_common::{
+        calculate_class_commitment_leaf_hash,
+        BlockNumber,
+        CasmHash,
+        ClassHash,
+        ContractAddress,
+        ContractNonce,
+        ContractRoot,
+        SierraHash,
+        StateCommitment,
+        StateUpdate,
+        StorageAddress,
+        StorageValue,
+    };
+    use pathfinder_crypto::Felt;
+    use pathfinder_merkle_tree::contract_state::calculate_contract_state_hash;
+    use pathfinder_merkle_tree::verify_range;
+    use pathfinder_storage::fake::{self as fake_storage, Block};
+    use pathfinder_storage::{Storage, StorageBuilder};
+    use starknet_gateway_test_fixtures::class_definitions::{
+        CAIRO_0_10_TUPLES_INTEGRATION as CAIRO,
+        CAIRO_0_11_SIERRA as SIERRA0,
+        CAIRO_2_0_0_STACK_OVERFLOW as SIERRA2,
+    };
+
+    use crate::p2p_network::sync_handlers::{
+        get_class_definition,
+        get_class_range,
+        get_contract_range,
+        get_contract_storage_range,
+    };
+    use crate::state::{update_starknet_state, StarknetStateUpdate};
+
+    pub(crate) const CAIRO_HASH: ClassHash =
+        class_hash!("0x542460935cea188d21e752d8459d82d60497866aaad21f873cbb61621d34f7f");
+    pub(crate) const SIERRA0_HASH: SierraHash =
+        sierra_hash!("0x04e70b19333ae94bd958625f7b61ce9eec631653597e68645e13780061b2136c");
+    pub(crate) const SIERRA2_HASH: SierraHash =
+        sierra_hash!("0x03dd9347d22f1ea2d5fbc7bd1f0860c6c334973499f9f1989fcb81bfff5191da");
+
+    /// Stores the genesis block with its state and tries. Returns the state
+    /// update and the state commitment of the block.
+    pub(crate) fn source() -> (Storage, StateUpdate, StateCommitment) {
+        let state_update = StateUpdate::default()
+            .with_declared_cairo_class(CAIRO_HASH)
+            .with_declared_sierra_class(SIERRA0_HASH, casm_hash!("0x10"))
+            .with_declared_sierra_class(SIERRA2_HASH, casm_hash!("0x20"))
+            .with_deployed_contract(contract_address!("0x100"), CAIRO_HASH)
+            .with_deployed_contract(contract_address!("0x101"), ClassHash(SIERRA2_HASH.0))
+            .with_deployed_contract(contract_address!("0x102"), CAIRO_HASH)
+            .with_deployed_contract(contract_address!("0x103"), CAIRO_HASH)
+            .with_deployed_contract(contract_address!("0x104"), ClassHash(SIERRA0_HASH.0))
+            .with_contract_nonce(contract_address!("0x100"), contract_nonce!("0x3"))
+            .with_contract_nonce(contract_address!("0x103"), contract_nonce!("0x1"))
+            .with_storage_update(
+                contract_address!("0x100"),
+                storage_address!("0x1"),
+                storage_value!("0x11"),
+            )
+            .with_storage_update(
+                contract_address!("0x100"),
+                storage_address!("0x2"),
+                storage_value!("0x12"),
+            )
+            .with_storage_update(
+                contract_address!("0x100"),
+                storage_address!("0x3"),
+                storage_value!("0x13"),
+            )
+            .with_storage_update(
+                contract_address!("0x100"),
+                storage_address!("0x4"),
+                storage_value!("0x14"),
+            )
+            .with_storage_update(
+                contract_address!("0x100"),
+                storage_address!("0x5"),
+                storage_value!("0x15"),
+            )
+            .with_storage_update(
+                contract_address!("0x101"),
+                storage_address!("0x1"),
+                storage_value!("0x21"),
+            )
+            .with_storage_update(
+                contract_address!("0x103"),
+                storage_address!("0x7"),
+                storage_value!("0x31"),
+            )
+            .with_system_storage_update(
+                ContractAddress::ONE,
+                storage_address!("0x1"),
+                storage_value!("0x41"),
+            )
+            .with_system_storage_update(
+                ContractAddress::ONE,
+                storage_address!("0x2"),
+                storage_value!("0x42"),
+            );
+
+        let mut block = Block::default();
+        block.state_update = state_update.clone();
+        block.cairo_defs = vec![(CAIRO_HASH, CAIRO.to_vec())];
+        block.sierra_defs = vec![
+            (SIERRA0_HASH, SIERRA0.to_vec(), b"casm0".to_vec()),
+            (SIERRA2_HASH, SIERRA2.to_vec(), b"casm2".to_vec()),
+        ];
+
+        let (storage, state_commitment) = store(block);
+
+        (storage, state_update, state_commitment)
+    }
+
+    /// Stores `block` as the genesis block, together with its state and tries.
+    /// Returns the state commitment of the block.
+    pub(crate) fn store(mut block: Block) -> (Storage, StateCommitment) {
+        block.header.header.number = BlockNumber::GENESIS;
+        block.header.header.hash = block_hash!("0xb10c");
+        let state_update = &block.state_update;
+
+        let storage = StorageBuilder::in_memory().unwrap();
+        fake_storage::fill(&storage, std::slice::from_ref(&block));
+
+        let mut db = storage.connection().unwrap();
+        let db = db.transaction().unwrap();
+        let (storage_commitment, class_commitment) = update_starknet_state(
+            &db,
+            StarknetStateUpdate {
+                contract_updates: &state_update.contract_updates,
+                system_contract_updates: &state_update.system_contract_updates,
+                declared_sierra_classes: &state_update.declared_sierra_classes,
+            },
+            true,
+            BlockNumber::GENESIS,
+            storage.clone(),
+        )
+        .unwrap();
+        db.update_storage_and_class_commitments(
+            BlockNumber::GENESIS,
+            storage_commitment,
+            class_commitment,
+        )
+        .unwrap();
+        db.commit().unwrap();
+
+        (
+            storage,
+            StateCommitment::calculate(storage_commitment, class_commitment),
+        )
+    }
+
+    /// Verifies a served range, returns whether it extends to the end of the
+    /// trie.
+    fn verify<H: FeltHash>(
+        root: Felt,
+        start: Felt,
+        leaves: &[(Felt, Felt)],
+        proof: PatriciaRangeProof,
+    ) -> bool {
+        let start_proof = proof
+            .start
+            .into_iter()
+            .map(|node| TrieNode::try_from_dto(node).unwrap())
+            .collect::<Vec<_>>();
+        let end_proof = proof
+            .end
+            .into_iter()
+            .map(|node| TrieNode::try_from_dto(node).unwrap())
+            .collect::<Vec<_>>();
+
+        verify_range::<H>(root, start, leaves, &start_proof, &end_proof).unwrap();
+
+        end_proof.is_empty()
+    }
+
+    #[test]
+    fn contract_ranges_cover_the_trie() {
+        let (storage, state_update, _) = source();
+        let mut db = storage.connection().unwrap();
+        let db = db.transaction().unwrap();
+
+        let mut start = Felt::ZERO;
+        let mut addresses = vec![];
+        loop {
+            let range = get_contract_range(
+                &db,
+                ContractRangeRequest {
+                    block_number: 0,
+                    start: Address(start),
+                    limit: 2,
+                },
+            )
+            .unwrap()
+            .unwrap();
+
+            let leaves = range
+                .states
+                .iter()
+                .map(|state| {
+                    let hash = calculate_contract_state_hash(
+                        ClassHash(state.class_hash.0),
+                        ContractRoot(state.storage_root.0),
+                        ContractNonce(state.nonce),
+                    );
+                    (state.address.0, hash.0)
+                })
+                .collect::<Vec<_>>();
+            let done =
+                verify::<PedersenHash>(range.storage_commitment.0, start, &leaves, range.proof);
+
+            addresses.extend(leaves.iter().map(|(address, _)| ContractAddress(*address)));
+            match (done, leaves.last()) {
+                (false, Some((last, _))) => start = *last + Felt::ONE,
+                _ => break,
+            }
+        }
+
+        let mut expected = state_update
+            .contract_updates
+            .keys()
+            .chain(state_update.system_contract_updates.keys())
+            .copied()
+            .collect::<Vec<_>>();
+        expected.sort();
+        assert_eq!(addresses, expected);
+    }
+
+    #[test]
+    fn contract_storage_ranges_cover_the_trie() {
+        let (storage, state_update, _) = source();
+        let mut db = storage.connection().unwrap();
+        let db = db.transaction().unwrap();
+
+        let contract = contract_address!("0x100");
+        let root = db
+            .contract_root(BlockNumber::GENESIS, contract)
+            .unwrap()
+            .unwrap();
+
+        let mut start = Felt::ZERO;
+        let mut ranges = vec![];
+        loop {
+            let range = get_contract_storage_range(
+                &db,
+                ContractStorageRangeRequest {
+                    block_number: 0,
+                    address: Address(contract.0),
+                    start,
+                    limit: 2,
+                },
+            )
+            .unwrap()
+            .unwrap();
+
+            let leaves = range
+                .values
+                .iter()
+                .map(|value| (value.key, value.value))
+                .collect::<Vec<_>>();
+            let done = verify::<PedersenHash>(root.0, start, &leaves, range.proof);
+
+            ranges.push(leaves.clone());
+            match (done, leaves.last()) {
+                (false, Some((last, _))) => start = *last + Felt::ONE,
+                _ => break,
+            }
+        }
+
+        assert_eq!(
+            ranges.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![2, 2, 1]
+        );
+        let values = ranges
+            .into_iter()
+            .flatten()
+            .map(|(key, value)| (StorageAddress(key), StorageValue(value)))
+            .collect();
+        assert_eq!(state_update.contract_updates[&contract].storage, values);
+    }
+
+    #[test]
+    fn contract_without_storage_has_an_empty_range() {
+        let (storage, _, _) = source();
+        let mut db = storage.connection().unwrap();
+        let db = db.transaction().unwrap();
+
+        let range = get_contract_storage_range(
+            &db,
+            ContractStorageRangeRequest {
+                block_number: 0,
+                address: Address(felt!("0x102")),
+                start: Felt::ZERO,
+                limit: 2,
+            },
+        )
+        .unwrap()
+        .unwrap();
+
+        assert!(range.values.is_empty());
+        assert_eq!(range.proof, PatriciaRangeProof::default());
+    }
+
+    #[test]
+    fn class_ranges_cover_the_trie() {
+        let (storage, state_update, _) = source();
+        let mut db = storage.connection().unwrap();
+        let db = db.transaction().unwrap();
+
+        let header = db
+            .block_header(BlockNumber::GENESIS.into())
+            .unwrap()
+            .unwrap();
+
+        let mut start = Felt::ZERO;
+        let mut classes = vec![];
+        loop {
+            let range = get_class_range(
+                &db,
+                ClassRangeRequest {
+                    block_number: 0,
+                    start: Hash(start),
+                    limit: 1,
+                },
+            )
+            .unwrap()
+            .unwrap();
+
+            let leaves = range
+                .classes
+                .iter()
+                .map(|class| {
+                    let casm_hash = CasmHash(class.compiled_class_hash.0);
+                    (
+                        class.class_hash.0,
+                        calculate_class_commitment_leaf_hash(casm_hash).0,
+                    )
+                })
+                .collect::<Vec<_>>();
+            let done =
+                verify::<PoseidonHash>(header.class_commitment.0, start, &leaves, range.proof);
+
+            classes.extend(range.classes.into_iter().map(|class| {
+                (
+                    SierraHash(class.class_hash.0),
+                    CasmHash(class.compiled_class_hash.0),
+                )
+            }));
+            match (done, leaves.last()) {
+                (false, Some((last, _))) => start = *last + Felt::ONE,
+                _ => break,
+            }
+        }
+
+        assert_eq!(
+            classes.into_iter().collect::<HashMap<_, _>>(),
+            state_update.declared_sierra_classes
+        );
+    }
+
+    #[test]
+    fn unknown_block_is_not_served() {
+        let (storage, _, _) = source();
+        let mut db = storage.connection().unwrap();
+        let db = db.transaction().unwrap();
+
+        let contracts = get_contract_range(
+            &db,
+            ContractRangeRequest {
+                block_number: 1,
+                start: Address(Felt::ZERO),
+                limit: 2,
+            },
+        )
+        .unwrap();
+        let classes = get_class_range(
+            &db,
+            ClassRangeRequest {
+                block_number: 1,
+                start: Hash(Felt::ZERO),
+                limit: 2,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(contracts, None);
+        assert_eq!(classes, None);
+    }
+
+    #[test]
+    fn class_definition() {
+        let (storage, _, _) = source();
+        let mut db = storage.connection().unwrap();
+        let db = db.transaction().unwrap();
+
+        let cairo = get_class_definition(
+            &db,
+            ClassDefinitionRequest {
+                block_number: 0,
+                class_hash: Hash(CAIRO_HASH.0),
+            },
+        )
+        .unwrap();
+        let sierra = get_class_definition(
+            &db,
+            ClassDefinitionRequest {
+                block_number: 0,
+                class_hash: Hash(SIERRA2_HASH.0),
+            },
+        )
+        .unwrap();
+        let unknown = get_class_definition(
+            &db,
+            ClassDefinitionRequest {
+                block_number: 0,
+                class_hash: Hash(felt!("0x404")),
+            },
+        )
+        .unwrap();
+
+        assert!(matches!(
+            cairo,
+            Some(p2p_proto::class::Class::Cairo0 { .. })
+        ));
+        assert!(matches!(
+            sierra,
+            Some(p2p_proto::class::Class::Cairo1 { .. })
+        ));
+        assert_eq!(unknown, None);
+    }
+}
@@ -6,6 +6,7 @@ pub mod revert;
 
 use std::collections::HashMap;
 use std::future::Future;
+use std::ops::RangeInclusive;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
@@ -20,7 +21,7 @@ use pathfinder_common::{
     StateDiffCommitment,
 };
 use pathfinder_crypto::Felt;
-use pathfinder_ethereum::{EthereumApi, EthereumStateUpdate};
+use pathfinder_ethereum::{EthereumApi, EthereumStateUpdate, L1Depth, MessageLog};
 use pathfinder_merkle_tree::contract_state::update_contract_state;
 use pathfinder_merkle_tree::{ClassCommitmentTree, StorageCommitmentTree};
 use pathfinder_rpc::v02::types::syncing::{self, NumberedBlock, Syncing};
@@ -38,6 +39,25 @@ use crate::state::l2::{BlockChain, L2SyncContext};
 #[derive(Debug)]
 pub enum SyncEvent {
     L1Update(EthereumStateUpdate),
+    /// The Starknet state on L1 at a depth other than finalized, as of the L1
+    /// block `l1_block_number`.
+    L1DepthUpdate {
+        depth: L1Depth,
+        l1_block_number: u64,
+        update: EthereumStateUpdate,
+    },
+    /// New Starknet [message logs](MessageLog) found on L1, covering all L1
+    /// blocks up to and including `l1_block_number`.
+    L1Messages {
+        messages: Vec<MessageLog>,
+        l1_block_number: u64,
+    },
+    /// All Starknet [message logs](MessageLog) found in the L1 blocks after the
+    /// finalized L1 block `finalized`. These replace the ones found before.
+    L1UnfinalizedMessages {
+        messages: Vec<MessageLog>,
+        finalized: u64,
+    },
     /// New L2 [block update](StateUpdate) found.
     Block(
         (
@@ -79,6 +99,11 @@ pub struct SyncContext<G, E> {
     pub state: Arc<SyncState>,
     pub head_poll_interval: Duration,
     pub l1_poll_interval: Duration,
+    /// L1 depths other than finalized at which the Starknet state is tracked.
+    pub l1_depths: Vec<L1Depth>,
+    /// The number of finalized L1 blocks whose messages are fetched when no
+    /// messages have been stored yet.
+    pub l1_messages_backfill: u64,
     pub pending_data: WatchSender<PendingData>,
     pub block_validation_mode: l2::BlockValidationMode,
     pub websocket_txs: Option<TopicBroadcasters>,
@@ -87,6 +112,7 @@ pub struct SyncContext<G, E> {
     pub verify_tree_hashes: bool,
     pub gossiper: Gossiper,
     pub sequencer_public_key: PublicKey,
+    pub signature_policy: l2::SignaturePolicy,
 }
 
 impl<G, E> From<&SyncContext<G, E>> for L1SyncContext<E>
@@ -99,6 +125,9 @@ where
             chain: value.chain,
             core_address: value.core_address,
             poll_interval: value.l1_poll_interval,
+            depths: value.l1_depths.clone(),
+            messages_backfill: value.l1_messages_backfill,
+            storage: value.storage.clone(),
         }
     }
 }
@@ -115,6 +144,7 @@ where
             block_validation_mode: value.block_validation_mode,
             storage: value.storage.clone(),
             sequencer_public_key: value.sequencer_public_key,
+            signature_policy: value.signature_policy,
         }
     }
 }
@@ -185,6 +215,8 @@ where
         state,
         head_poll_interval,
         l1_poll_interval: _,
+        l1_depths: _,
+        l1_messages_backfill: _,
         pending_data,
         block_validation_mode: _,
         websocket_txs,
@@ -193,6 +225,7 @@ where
         verify_tree_hashes: _,
         gossiper,
         sequencer_public_key: _,
+        signature_policy: _,
     } = context;
 
     let mut db_conn = storage
@@ -262,6 +295,7 @@ where
     let (tx_current, rx_current) = tokio::sync::watch::channel((current_num, current_hash));
     let consumer_context = ConsumerContext {
         storage: storage.clone(),
+        chain_id: context.chain_id,
         state,
         pending_data,
         verify_tree_hashes: context.verify_tree_hashes,
@@ -434,6 +468,7 @@ where
 
 struct ConsumerContext {
     pub storage: Storage,
+    pub chain_id: ChainId,
     pub state: Arc<SyncState>,
     pub pending_data: WatchSender<PendingData>,
     pub verify_tree_hashes: bool,
@@ -447,6 +482,7 @@ async fn consumer(
 ) -> anyhow::Result<()> {
     let ConsumerContext {
         storage,
+        chain_id,
         state,
         pending_data,
         verify_tree_hashes,
@@ -479,8 +515,48 @@ async fn consumer(
         use SyncEvent::*;
         match event {
             L1Update(update) => {
-                l1_update(&mut db_conn, &update).await?;
+                let l1_accepted = l1_update(&mut db_conn, &update).await?;
                 tracing::info!("L1 sync updated to block {}", update.block_number);
+
+                if let (Some(sender), Some(l1_accepted)) = (&websocket_txs, l1_accepted) {
+                    if sender.l1_accepted.receiver_count() > 0 {
+                        if let Err(e) = sender.l1_accepted.send(l1_accepted) {
+                            tracing::error!(error=?e, "Failed to send L1 accepted blocks over websocket broadcaster.");
+                            websocket_txs = None;
+                        }
+                    }
+                }
+            }
+            L1DepthUpdate {
+                depth,
+                l1_block_number,
+                update,
+            } => {
+                l1_depth_update(&mut db_conn, depth, l1_block_number, &update).await?;
+                tracing::debug!(?depth, l1_block_number, block=%update.block_number, "L1 state updated");
+            }
+            L1Messages {
+                messages,
+                l1_block_number,
+            } => {
+                l1_messages_update(&mut db_conn, &messages, l1_block_number, chain_id).await?;
+                tracing::debug!(
+                    count = messages.len(),
+                    l1_block_number,
+                    "Stored L1 message logs"
+                );
+            }
+            L1UnfinalizedMessages {
+                messages,
+                finalized,
+            } => {
+                l1_unfinalized_messages_update(&mut db_conn, &messages, finalized, chain_id)
+                    .await?;
+                tracing::trace!(
+                    count = messages.len(),
+                    finalized,
+                    "Stored unfinalized L1 message logs"
+                );
             }
             Block(
                 (block, (tx_comm, ev_comm, rc_comm)),
@@ -589,12 +665,21 @@ async fn consumer(
                 }
             }
             Reorg(reorg_tail) => {
-                l2_reorg(&mut db_conn, reorg_tail)
+                let reorg = l2_reorg(&mut db_conn, reorg_tail)
                     .await
                     .with_context(|| format!("Reorg L2 state to {reorg_tail:?}"))?;
 
                 next_number = reorg_tail;
 
+                if let Some(sender) = &websocket_txs {
+                    if sender.reorgs.receiver_count() > 0 {
+                        if let Err(e) = sender.reorgs.send(reorg) {
+                            tracing::error!(error=?e, "Failed to send reorg over websocket broadcaster.");
+                            websocket_txs = None;
+                        }
+                    }
+                }
+
                 let new_head = match reorg_tail {
                     BlockNumber::GENESIS => None,
                     other => Some(other - 1),
@@ -785,15 +870,20 @@ async fn propagate_head(gossiper: &Gossiper, last_propagated: &mut Instant, head
     *last_propagated = Instant::now();
 }
 
+/// Returns the range of L2 blocks which became accepted on L1 with this
+/// update, if any.
 async fn l1_update(
     connection: &mut Connection,
     update: &EthereumStateUpdate,
-) -> anyhow::Result<()> {
+) -> anyhow::Result<Option<RangeInclusive<BlockNumber>>> {
     tokio::task::block_in_place(move || {
         let transaction = connection
             .transaction_with_behavior(TransactionBehavior::Immediate)
             .context("Create database transaction")?;
 
+        let previous = transaction.l1_l2_pointer()?;
+        let mut l1_accepted = None;
+
         transaction
             .upsert_l1_state(update)
             .context("Insert update")?;
@@ -808,14 +898,89 @@ async fn l1_update(
                     .update_l1_l2_pointer(Some(update.block_number))
                     .context("Updating L1-L2 pointer")?;
                 tracing::info!(block=?update.block_number, "Updated L1/L2 match");
+
+                // Without a previous match, only the current L1 head is announced
+                // rather than every block since genesis.
+                let first = previous.map(|head| head + 1).unwrap_or(update.block_number);
+                if first <= update.block_number {
+                    l1_accepted = Some(first..=update.block_number);
+                }
             } else {
                 tracing::warn!(block_number=?update.block_number, L1=?update.block_hash, L2=?l2_hash, "L1/L2 block hash mismatch");
-                if let Some(matching_block_number) = transaction.l1_l2_pointer()? {
+                if let Some(matching_block_number) = previous {
                     tracing::warn!(block_number=?matching_block_number, "Most recent L1/L2 block hash match")
                 }
             }
         }
 
+        transaction
+            .commit()
+            .context("Commit database transaction")?;
+
+        Ok(l1_accepted)
+    })
+}
+
+async fn l1_depth_update(
+    connection: &mut Connection,
+    depth: L1Depth,
+    l1_block_number: u64,
+    update: &EthereumStateUpdate,
+) -> anyhow::Result<()> {
+    tokio::task::block_in_place(move || {
+        let transaction = connection
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .context("Create database transaction")?;
+
+        transaction
+            .upsert_l1_state_at_depth(depth, l1_block_number, update)
+            .context("Insert update")?;
+
+        transaction.commit().context("Commit database transaction")
+    })
+}
+
+async fn l1_messages_update(
+    connection: &mut Connection,
+    messages: &[MessageLog],
+    l1_block_number: u64,
+    chain_id: ChainId,
+) -> anyhow::Result<()> {
+    tokio::task::block_in_place(move || {
+        let transaction = connection
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .context("Create database transaction")?;
+
+        // Messages stored while these L1 blocks were not finalized are replaced.
+        let head = transaction
+            .l1_messages_head()
+            .context("Query L1 messages head")?;
+        transaction
+            .replace_l1_messages_after(head, messages, chain_id)
+            .context("Insert L1 messages")?;
+        transaction
+            .update_l1_messages_head(l1_block_number)
+            .context("Update L1 messages head")?;
+
+        transaction.commit().context("Commit database transaction")
+    })
+}
+
+async fn l1_unfinalized_messages_update(
+    connection: &mut Connection,
+    messages: &[MessageLog],
+    finalized: u64,
+    chain_id: ChainId,
+) -> anyhow::Result<()> {
+    tokio::task::block_in_place(move || {
+        let transaction = connection
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .context("Create database transaction")?;
+
+        transaction
+            .replace_l1_messages_after(Some(finalized), messages, chain_id)
+            .context("Replace unfinalized L1 messages")?;
+
         transaction.commit().context("Commit database transaction")
     })
 }
@@ -936,6 +1101,7 @@ async fn l2_update(
             .context("Insert signature into database")?;
 
         // Track combined L1 and L2 state.
+        let mut l1_accepted = None;
         let l1_l2_head = transaction.l1_l2_pointer().context("Query L1-L2 head")?;
         let expected_next = l1_l2_head
             .map(|head| head + 1)
@@ -950,6 +1116,7 @@ async fn l2_update(
                     transaction
                         .update_l1_l2_pointer(Some(header.number))
                         .context("Update L1-L2 head")?;
+                    l1_accepted = Some(header.number..=header.number);
                 }
             }
         }
@@ -974,6 +1141,15 @@ async fn l2_update(
                     return Ok(());
                 }
             }
+            if let Some(l1_accepted) =
+                l1_accepted.filter(|_| sender.l1_accepted.receiver_count() > 0)
+            {
+                if let Err(e) = sender.l1_accepted.send(l1_accepted) {
+                    tracing::error!(error=?e, "Failed to send L1 accepted block over websocket broadcaster.");
+                    *websocket_txs = None;
+                    return Ok(());
+                }
+            }
         }
 
         Ok(())
@@ -982,7 +1158,12 @@ async fn l2_update(
     Ok(())
 }
 
-async fn l2_reorg(connection: &mut Connection, reorg_tail: BlockNumber) -> anyhow::Result<()> {
+/// Reverts L2 state to the parent of `reorg_tail` and returns the reorg for
+/// websocket subscribers.
+async fn l2_reorg(
+    connection: &mut Connection,
+    reorg_tail: BlockNumber,
+) -> anyhow::Result<pathfinder_rpc::Reorg> {
     tokio::task::block_in_place(move || {
         let transaction = connection
             .transaction_with_behavior(TransactionBehavior::Immediate)
@@ -994,6 +1175,23 @@ async fn l2_reorg(connection: &mut Connection, reorg_tail: BlockNumber) -> anyho
             .context("Latest block number is none during reorg")?
             .0;
 
+        let (_, first_block_hash) = transaction
+            .block_id(reorg_tail.into())
+            .context("Querying first reorged block")?
+            .context("First reorged block is missing")?;
+        let new_head = match reorg_tail.parent() {
+            Some(parent) => transaction
+                .block_id(parent.into())
+                .context("Querying new head")?,
+            None => None,
+        };
+        let reorg = pathfinder_rpc::Reorg {
+            first_block_number: reorg_tail,
+            first_block_hash,
+            new_head_number: new_head.map(|(number, _)| number),
+            new_head_hash: new_head.map(|(_, hash)| hash),
+        };
+
         transaction
             .increment_reorg_counter()
             .context("Incrementing reorg counter")?;
@@ -1047,7 +1245,11 @@ async fn l2_reorg(connection: &mut Connection, reorg_tail: BlockNumber) -> anyho
             }
         }
 
-        transaction.commit().context("Commit database transaction")
+        transaction
+            .commit()
+            .context("Commit database transaction")?;
+
+        Ok(reorg)
     })
 }
 
@@ -1195,7 +1397,9 @@ pub fn update_starknet_state(
 
 #[cfg(test)]
 mod tests {
+    use std::num::NonZeroUsize;
     use std::sync::Arc;
+    use std::time::Duration;
 
     use pathfinder_common::macro_prelude::*;
     use pathfinder_common::{
@@ -1205,8 +1409,11 @@ mod tests {
         BlockHash,
         BlockHeader,
         BlockNumber,
+        Chain,
+        ChainId,
         ClassHash,
         EventCommitment,
+        PublicKey,
         ReceiptCommitment,
         SierraHash,
         StateCommitment,
@@ -1215,8 +1422,10 @@ mod tests {
         TransactionCommitment,
     };
     use pathfinder_crypto::Felt;
-    use pathfinder_rpc::SyncState;
+    use pathfinder_rpc::context::WebsocketContext;
+    use pathfinder_rpc::{Reorg, SyncState};
     use pathfinder_storage::StorageBuilder;
+    use starknet_gateway_client::{Client, Replay};
     use starknet_gateway_types::reply::{self, Block, GasPrices};
 
     use super::l2;
@@ -1347,6 +1556,7 @@ mod tests {
         let (tx, _rx) = tokio::sync::watch::channel(Default::default());
         let context = ConsumerContext {
             storage,
+            chain_id: ChainId::SEPOLIA_TESTNET,
             state: Arc::new(SyncState::default()),
             pending_data: tx,
             verify_tree_hashes: false,
@@ -1372,6 +1582,45 @@ mod tests {
         assert!(!should_not_exist);
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn l1_update_announces_blocks_since_previous_match() {
+        let storage = StorageBuilder::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+
+        let tx = connection.transaction().unwrap();
+        let mut headers =
+            vec![BlockHeader::builder().finalize_with_hash(block_hash_bytes!(b"genesis"))];
+        for i in 1..5 {
+            let header = headers
+                .last()
+                .unwrap()
+                .child_builder()
+                .finalize_with_hash(BlockHash(Felt::from_u64(i)));
+            headers.push(header);
+        }
+        for header in &headers {
+            tx.insert_block_header(header).unwrap();
+        }
+        tx.commit().unwrap();
+
+        let update = |header: &BlockHeader| pathfinder_ethereum::EthereumStateUpdate {
+            state_root: header.state_commitment,
+            block_number: header.number,
+            block_hash: header.hash,
+        };
+
+        // The first update only announces the current L1 head.
+        let l1_accepted = super::l1_update(&mut connection, &update(&headers[2]))
+            .await
+            .unwrap();
+        assert_eq!(l1_accepted, Some(headers[2].number..=headers[2].number));
+
+        let l1_accepted = super::l1_update(&mut connection, &update(&headers[4]))
+            .await
+            .unwrap();
+        assert_eq!(l1_accepted, Some(headers[3].number..=headers[4].number));
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn reorg() {
         let storage = StorageBuilder::in_memory().unwrap();
@@ -1396,6 +1645,7 @@ mod tests {
         let (tx, _rx) = tokio::sync::watch::channel(Default::default());
         let context = ConsumerContext {
             storage,
+            chain_id: ChainId::SEPOLIA_TESTNET,
             state: Arc::new(SyncState::default()),
             pending_data: tx,
             verify_tree_hashes: false,
@@ -1420,6 +1670,57 @@ mod tests {
         assert!(!block_2_exists);
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn reorg_is_broadcast() {
+        let storage = StorageBuilder::in_memory().unwrap();
+
+        let (event_tx, event_rx) = tokio::sync::mpsc::channel(100);
+
+        let blocks = generate_block_data();
+        let block_hash_1 = blocks[1].0 .0.block_hash;
+        let block_hash_2 = blocks[2].0 .0.block_hash;
+        for (a, b, c, d, e) in blocks {
+            event_tx
+                .send(SyncEvent::Block(a, b, c, d, e))
+                .await
+                .unwrap();
+        }
+        event_tx
+            .send(SyncEvent::Reorg(BlockNumber::new_or_panic(2)))
+            .await
+            .unwrap();
+        drop(event_tx);
+
+        let (tx, rx) = tokio::sync::watch::channel(Default::default());
+        let websocket = WebsocketContext::new(
+            NonZeroUsize::new(10).unwrap(),
+            NonZeroUsize::new(10).unwrap(),
+            rx,
+        );
+        let mut reorgs = websocket.broadcasters.reorgs.subscribe();
+        let context = ConsumerContext {
+            storage,
+            chain_id: ChainId::SEPOLIA_TESTNET,
+            state: Arc::new(SyncState::default()),
+            pending_data: tx,
+            verify_tree_hashes: false,
+            websocket_txs: Some(websocket.broadcasters),
+        };
+
+        let (tx, _rx) = tokio::sync::watch::channel(Default::default());
+        consumer(event_rx, context, tx).await.unwrap();
+
+        assert_eq!(
+            reorgs.try_recv().unwrap(),
+            Reorg {
+                first_block_number: BlockNumber::new_or_panic(2),
+                first_block_hash: block_hash_2,
+                new_head_number: Some(BlockNumber::new_or_panic(1)),
+                new_head_hash: Some(block_hash_1),
+            }
+        );
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn blocks_are_not_skipped_after_a_reorg() {
         // A bug caused reorg'd block numbers to be skipped. This
@@ -1459,6 +1760,7 @@ mod tests {
         let (tx, _rx) = tokio::sync::watch::channel(Default::default());
         let context = ConsumerContext {
             storage,
+            chain_id: ChainId::SEPOLIA_TESTNET,
             state: Arc::new(SyncState::default()),
             pending_data: tx,
             verify_tree_hashes: false,
@@ -1507,6 +1809,7 @@ mod tests {
         let (tx, _rx) = tokio::sync::watch::channel(Default::default());
         let context = ConsumerContext {
             storage,
+            chain_id: ChainId::SEPOLIA_TESTNET,
             state: Arc::new(SyncState::default()),
             pending_data: tx,
             verify_tree_hashes: false,
@@ -1544,6 +1847,7 @@ mod tests {
         let (tx, _rx) = tokio::sync::watch::channel(Default::default());
         let context = ConsumerContext {
             storage,
+            chain_id: ChainId::SEPOLIA_TESTNET,
             state: Arc::new(SyncState::default()),
             pending_data: tx,
             verify_tree_hashes: false,
@@ -1584,6 +1888,7 @@ mod tests {
         let (tx, _rx) = tokio::sync::watch::channel(Default::default());
         let context = ConsumerContext {
             storage,
+            chain_id: ChainId::SEPOLIA_TESTNET,
             state: Arc::new(SyncState::default()),
             pending_data: tx,
             verify_tree_hashes: false,
@@ -1627,6 +1932,7 @@ mod tests {
         let (tx, _rx) = tokio::sync::watch::channel(Default::default());
         let context = ConsumerContext {
             storage,
+            chain_id: ChainId::SEPOLIA_TESTNET,
             state: Arc::new(SyncState::default()),
             pending_data: tx,
             verify_tree_hashes: false,
@@ -1636,4 +1942,121 @@ mod tests {
         let (tx, _rx) = tokio::sync::watch::channel(Default::default());
         consumer(event_rx, context, tx).await.unwrap();
     }
+
+    /// Runs [sync](super::sync) into a new database until it has stored the
+    /// given block.
+    async fn sync_until(sequencer: Client, head: BlockNumber) {
+        let storage = StorageBuilder::in_memory().unwrap();
+        let (pending_data, _rx) = tokio::sync::watch::channel(Default::default());
+        let context = super::SyncContext {
+            storage: storage.clone(),
+            ethereum: pathfinder_ethereum::EthereumClient::new(
+                "http://localhost:1".parse().unwrap(),
+            )
+            .unwrap(),
+            chain: Chain::SepoliaTestnet,
+            chain_id: ChainId::SEPOLIA_TESTNET,
+            core_address: Default::default(),
+            sequencer,
+            state: Arc::new(SyncState::default()),
+            head_poll_interval: Duration::from_millis(100),
+            l1_poll_interval: Duration::from_secs(60),
+            l1_depths: vec![],
+            l1_messages_backfill: 0,
+            pending_data,
+            block_validation_mode: l2::BlockValidationMode::AllowMismatch,
+            websocket_txs: None,
+            block_cache_size: 10,
+            restart_delay: Duration::from_millis(100),
+            verify_tree_hashes: false,
+            gossiper: Default::default(),
+            sequencer_public_key: PublicKey::ZERO,
+            signature_policy: Default::default(),
+        };
+        // L1 is not part of the archive.
+        let sync = tokio::spawn(super::sync(
+            context,
+            |_, _| std::future::pending::<anyhow::Result<()>>(),
+            l2::sync,
+        ));
+
+        let mut connection = storage.connection().unwrap();
+        tokio::time::timeout(Duration::from_secs(30), async {
+            loop {
+                let tx = connection.transaction().unwrap();
+                if tx.block_exists(head.into()).unwrap() {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .expect("Sync should reach the recorded head");
+        sync.abort();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn sync_from_replayed_archive() {
+        use httpmock::prelude::*;
+        use serde_json::json;
+
+        let blocks = generate_block_data();
+        let head = blocks.last().unwrap().0 .0.clone();
+
+        let server = MockServer::start_async().await;
+        server.mock(|when, then| {
+            when.path("/feeder_gateway/get_block")
+                .query_param("blockNumber", "latest");
+            then.status(200).json_body(
+                json!({"block_number": head.block_number.get(), "block_hash": head.block_hash}),
+            );
+        });
+        for ((block, _), state_update, signature, _, _) in &blocks {
+            let number = block.block_number.get().to_string();
+            let state_update = reply::StateUpdate::from(state_update.as_ref().clone());
+            server.mock(|when, then| {
+                when.path("/feeder_gateway/get_state_update")
+                    .query_param("blockNumber", &number);
+                then.status(200)
+                    .json_body(json!({"block": block, "state_update": state_update}));
+            });
+            server.mock(|when, then| {
+                when.path("/feeder_gateway/get_signature")
+                    .query_param("blockNumber", &number);
+                then.status(200).json_body_obj(&reply::BlockSignature::V1(
+                    reply::BlockSignatureV1 {
+                        block_hash: block.block_hash,
+                        signature: [signature.r, signature.s],
+                    },
+                ));
+            });
+        }
+        // Neither the next nor the pending block exist yet.
+        let next = (head.block_number + 1).get().to_string();
+        for number in [next.as_str(), "pending"] {
+            for method in ["get_state_update", "get_signature"] {
+                server.mock(|when, then| {
+                    when.path(format!("/feeder_gateway/{method}"))
+                        .query_param("blockNumber", number);
+                    then.status(400).json_body(
+                        json!({"code": "StarknetErrorCode.BLOCK_NOT_FOUND", "message": ""}),
+                    );
+                });
+            }
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("archive");
+        let recording =
+            Client::with_base_url(server.base_url().parse().unwrap(), Duration::from_secs(5))
+                .unwrap()
+                .with_recording(&path)
+                .unwrap();
+        sync_until(recording, head.block_number).await;
+
+        // Replay the recorded session without the gateway.
+        drop(server);
+        let replay = Client::replay(Arc::new(Replay::load(&path).unwrap()));
+        sync_until(replay, head.block_number).await;
+    }
 }
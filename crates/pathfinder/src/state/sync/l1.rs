@@ -1,14 +1,20 @@
 use std::num::NonZeroU64;
 use std::time::Duration;
 
+use anyhow::Context;
 use pathfinder_common::Chain;
-use pathfinder_ethereum::{EthereumApi, EthereumStateUpdate};
+use pathfinder_ethereum::{EthereumApi, EthereumStateUpdate, L1Depth, MessageLog};
 use pathfinder_retry::Retry;
+use pathfinder_storage::Storage;
 use primitive_types::H160;
 use tokio::sync::mpsc;
 
 use crate::state::sync::SyncEvent;
 
+/// The maximum number of L1 blocks requested in a single `eth_getLogs` call.
+/// Most providers limit the block range of a single query.
+const MAX_LOG_BLOCK_RANGE: u64 = 1_000;
+
 #[derive(Clone)]
 pub struct L1SyncContext<EthereumClient> {
     pub ethereum: EthereumClient,
@@ -16,11 +22,35 @@ pub struct L1SyncContext<EthereumClient> {
     /// The Starknet core contract address on Ethereum
     pub core_address: H160,
     pub poll_interval: Duration,
+    pub storage: Storage,
+    /// L1 depths other than finalized at which the Starknet state is tracked.
+    pub depths: Vec<L1Depth>,
+    /// The number of finalized L1 blocks whose messages are fetched when no
+    /// messages have been stored yet.
+    pub messages_backfill: u64,
 }
 
 /// Syncs L1 state update logs. Emits [Ethereum state
 /// update](EthereumStateUpdate) which should be handled to update storage and
 /// respond to queries.
+///
+/// The state at each of the additional `depths` is emitted as an
+/// [L1DepthUpdate](SyncEvent::L1DepthUpdate) whenever it or the L1 block it was
+/// read at changes. Those may be reverted by L1
+/// reorgs, so they never mark blocks as accepted on L1. Failing to read them
+/// is logged rather than retried, so that it does not hold up finalized state.
+///
+/// Also emits the core contract's message logs of every finalized L1 block
+/// since the last stored one. If no message logs were stored yet, tracking
+/// starts `messages_backfill` blocks before the current finalized L1 block.
+/// The message logs of the L1 blocks after the finalized one are emitted as
+/// [unfinalized messages](SyncEvent::L1UnfinalizedMessages) whenever they
+/// change, so that messages can be found before they are finalized. Failing to
+/// read those is logged rather than retried.
+///
+/// L1 is polled every `poll_interval`, and additionally whenever a
+/// [subscription](EthereumApi::subscribe) reports a new L1 head or core
+/// contract log.
 pub async fn sync<T>(
     tx_event: mpsc::Sender<SyncEvent>,
     context: L1SyncContext<T>,
@@ -33,9 +63,33 @@ where
         chain: _,
         core_address,
         poll_interval,
+        storage,
+        depths,
+        messages_backfill,
     } = context;
 
     let mut previous = EthereumStateUpdate::default();
+    let mut previous_at_depth = vec![(0, EthereumStateUpdate::default()); depths.len()];
+    let mut previous_unfinalized = None;
+    let retained = depths.clone();
+    let mut messages_head = tokio::task::spawn_blocking(move || {
+        let mut db = storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+        db.retain_l1_states_at_depth(&retained)
+            .context("Removing untracked L1 depths")?;
+        let head = db.l1_messages_head()?;
+        db.commit().context("Committing database transaction")?;
+        anyhow::Ok(head)
+    })
+    .await
+    .context("Joining database task")??;
+
+    let mut notifications = ethereum.subscribe(&core_address).await;
+    if notifications.is_some() {
+        tracing::info!("Subscribed to L1 updates");
+    }
 
     loop {
         let state_update = Retry::exponential(
@@ -52,6 +106,121 @@ where
             tx_event.send(SyncEvent::L1Update(state_update)).await?;
         }
 
-        tokio::time::sleep(poll_interval).await;
+        for (depth, previous) in depths.iter().zip(previous_at_depth.iter_mut()) {
+            let state_update = match ethereum.get_starknet_state_at(&core_address, *depth).await {
+                Ok(state_update) => state_update,
+                Err(error) => {
+                    tracing::debug!(?depth, %error, "Failed to fetch L1 state");
+                    continue;
+                }
+            };
+
+            if *previous != state_update {
+                *previous = state_update;
+                let (l1_block_number, update) = state_update;
+                tx_event
+                    .send(SyncEvent::L1DepthUpdate {
+                        depth: *depth,
+                        l1_block_number,
+                        update,
+                    })
+                    .await?;
+            }
+        }
+
+        let finalized = Retry::exponential(
+            || async { ethereum.get_finalized_block_number().await },
+            NonZeroU64::new(1).unwrap(),
+        )
+        .factor(NonZeroU64::new(2).unwrap())
+        .max_delay(poll_interval / 2)
+        .when(|_| true)
+        .await?;
+
+        let mut from_block = messages_head
+            .map(|head| head + 1)
+            .unwrap_or(finalized.saturating_sub(messages_backfill));
+        while from_block <= finalized {
+            let to_block = finalized.min(from_block + MAX_LOG_BLOCK_RANGE - 1);
+
+            let messages = Retry::exponential(
+                || async {
+                    ethereum
+                        .get_messages(&core_address, from_block, to_block)
+                        .await
+                },
+                NonZeroU64::new(1).unwrap(),
+            )
+            .factor(NonZeroU64::new(2).unwrap())
+            .max_delay(poll_interval / 2)
+            .when(|_| true)
+            .await?;
+
+            tx_event
+                .send(SyncEvent::L1Messages {
+                    messages,
+                    l1_block_number: to_block,
+                })
+                .await?;
+
+            messages_head = Some(to_block);
+            from_block = to_block + 1;
+        }
+
+        match unfinalized_messages(&ethereum, &core_address, finalized).await {
+            Ok(messages) => {
+                let unfinalized = (finalized, messages);
+                if previous_unfinalized.as_ref() != Some(&unfinalized) {
+                    tx_event
+                        .send(SyncEvent::L1UnfinalizedMessages {
+                            messages: unfinalized.1.clone(),
+                            finalized,
+                        })
+                        .await?;
+                    previous_unfinalized = Some(unfinalized);
+                }
+            }
+            Err(error) => tracing::debug!(%error, "Failed to fetch unfinalized L1 messages"),
+        }
+
+        match notifications.as_mut() {
+            Some(rx) => {
+                tokio::select! {
+                    _ = tokio::time::sleep(poll_interval) => {}
+                    notification = rx.recv() => match notification {
+                        Some(notification) => tracing::trace!(?notification, "L1 notification"),
+                        None => {
+                            tracing::warn!("L1 subscription ended, falling back to polling");
+                            notifications = None;
+                        }
+                    }
+                }
+            }
+            None => tokio::time::sleep(poll_interval).await,
+        }
+    }
+}
+
+/// Returns the message logs of all L1 blocks after `finalized` up to the
+/// latest L1 block.
+async fn unfinalized_messages<T: EthereumApi>(
+    ethereum: &T,
+    core_address: &H160,
+    finalized: u64,
+) -> anyhow::Result<Vec<MessageLog>> {
+    let latest = ethereum.get_latest_block_number().await?;
+
+    let mut messages = Vec::new();
+    let mut from_block = finalized + 1;
+    while from_block <= latest {
+        let to_block = latest.min(from_block + MAX_LOG_BLOCK_RANGE - 1);
+        messages.extend(
+            ethereum
+                .get_messages(core_address, from_block, to_block)
+                .await?,
+        );
+        from_block = to_block + 1;
     }
+
+    Ok(messages)
 }
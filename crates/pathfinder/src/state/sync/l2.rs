@@ -14,9 +14,11 @@ use pathfinder_common::{
     PublicKey,
     ReceiptCommitment,
     StateCommitment,
+    StateDiffCommitment,
     StateUpdate,
     TransactionCommitment,
 };
+use pathfinder_crypto::signature::SignatureError;
 use pathfinder_storage::Storage;
 use starknet_gateway_client::GatewayApi;
 use starknet_gateway_types::error::SequencerError;
@@ -95,6 +97,7 @@ pub struct L2SyncContext<GatewayClient> {
     pub block_validation_mode: BlockValidationMode,
     pub storage: Storage,
     pub sequencer_public_key: PublicKey,
+    pub signature_policy: SignaturePolicy,
 }
 
 pub async fn sync<GatewayClient>(
@@ -114,6 +117,7 @@ where
         block_validation_mode,
         storage,
         sequencer_public_key,
+        signature_policy,
     } = context;
 
     'outer: loop {
@@ -301,20 +305,21 @@ where
         // Check block commitment signature
         let (signature, state_update) = match block_validation_mode {
             BlockValidationMode::Strict => {
+                let block_number = block.block_number;
                 let block_hash = block.block_hash;
-                let (verify_result, signature, state_update) = tokio::task::spawn_blocking(move || -> (Result<(), pathfinder_crypto::signature::SignatureError>, BlockCommitmentSignature, Box<StateUpdate>) {
-                    let verify_result = signature
-                        .verify(
-                            sequencer_public_key,
-                            block_hash,
-                            computed_state_diff_commitment,
-                        );
-                    (verify_result, signature, state_update)
-                }).await?;
-                if let Err(error) = verify_result {
-                    tracing::warn!(%error, block_number=%block.block_number, "Block commitment signature mismatch");
-                }
-                (signature, state_update)
+                tokio::task::spawn_blocking(move || -> anyhow::Result<_> {
+                    verify_signature(
+                        signature_policy,
+                        sequencer_public_key,
+                        block_number,
+                        block_hash,
+                        computed_state_diff_commitment,
+                        &signature,
+                    )?;
+                    Ok((signature, state_update))
+                })
+                .await
+                .context("Joining blocking task")??
             }
             BlockValidationMode::AllowMismatch => (signature, state_update),
         };
@@ -514,6 +519,68 @@ pub enum BlockValidationMode {
     AllowMismatch,
 }
 
+/// How to handle blocks whose sequencer signature does not verify.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SignatureVerification {
+    /// Fail the sync of the block with [L2SyncError::BadSignature].
+    Reject,
+    /// Log the failure and accept the block.
+    #[default]
+    Warn,
+    /// Do not verify signatures.
+    Skip,
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SignaturePolicy {
+    pub verification: SignatureVerification,
+    /// Signatures of blocks before this one are not verified. Blocks which
+    /// predate sequencer signatures do not have a valid one.
+    pub first_signed_block: BlockNumber,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum L2SyncError {
+    #[error("Invalid sequencer signature for block {block_number}: {error}")]
+    BadSignature {
+        block_number: BlockNumber,
+        error: SignatureError,
+    },
+}
+
+/// Verifies the sequencer signature of a block according to `policy`.
+fn verify_signature(
+    policy: SignaturePolicy,
+    public_key: PublicKey,
+    block_number: BlockNumber,
+    block_hash: BlockHash,
+    state_diff_commitment: StateDiffCommitment,
+    signature: &BlockCommitmentSignature,
+) -> Result<(), L2SyncError> {
+    if policy.verification == SignatureVerification::Skip
+        || block_number < policy.first_signed_block
+    {
+        return Ok(());
+    }
+
+    let Err(error) = signature.verify(public_key, block_hash, state_diff_commitment) else {
+        return Ok(());
+    };
+
+    metrics::increment_counter!("block_signature_verification_failures_total");
+
+    match policy.verification {
+        SignatureVerification::Reject => Err(L2SyncError::BadSignature {
+            block_number,
+            error,
+        }),
+        SignatureVerification::Warn | SignatureVerification::Skip => {
+            tracing::warn!(%error, %block_number, "Block commitment signature mismatch");
+            Ok(())
+        }
+    }
+}
+
 async fn download_block(
     block_number: BlockNumber,
     chain: Chain,
@@ -914,6 +981,7 @@ mod tests {
                 block_validation_mode: MODE,
                 storage,
                 sequencer_public_key: PublicKey::ZERO,
+                signature_policy: Default::default(),
             };
 
             let latest = tokio::sync::watch::channel(Default::default());
@@ -1309,6 +1377,7 @@ mod tests {
                     block_validation_mode: MODE,
                     storage: StorageBuilder::in_memory().unwrap(),
                     sequencer_public_key: PublicKey::ZERO,
+                    signature_policy: Default::default(),
                 };
                 let latest_track = tokio::sync::watch::channel(Default::default());
 
@@ -2574,4 +2643,53 @@ mod tests {
             assert!(uut.get(&BlockNumber::new_or_panic(3)).is_none());
         }
     }
+
+    mod verify_signature {
+        use assert_matches::assert_matches;
+        use pathfinder_common::macro_prelude::*;
+
+        use super::super::*;
+
+        fn verify(
+            verification: SignatureVerification,
+            block_number: u64,
+        ) -> Result<(), L2SyncError> {
+            let policy = SignaturePolicy {
+                verification,
+                first_signed_block: BlockNumber::new_or_panic(10),
+            };
+
+            super::super::verify_signature(
+                policy,
+                public_key!("0x1"),
+                BlockNumber::new_or_panic(block_number),
+                block_hash!("0x2"),
+                state_diff_commitment!("0x3"),
+                &BlockCommitmentSignature::default(),
+            )
+        }
+
+        #[test]
+        fn reject() {
+            assert_matches!(
+                verify(SignatureVerification::Reject, 10),
+                Err(L2SyncError::BadSignature { block_number, .. }) if block_number == BlockNumber::new_or_panic(10)
+            );
+        }
+
+        #[test]
+        fn warn() {
+            assert_matches!(verify(SignatureVerification::Warn, 10), Ok(()));
+        }
+
+        #[test]
+        fn skip() {
+            assert_matches!(verify(SignatureVerification::Skip, 10), Ok(()));
+        }
+
+        #[test]
+        fn blocks_before_first_signed_block_are_not_verified() {
+            assert_matches!(verify(SignatureVerification::Reject, 9), Ok(()));
+        }
+    }
 }
@@ -24,7 +24,9 @@ use pathfinder_storage::Transaction;
 /// blocks we're keeping trie history for then removing deleted nodes for the
 /// reorged-away blocks would break the trie for the blocks _before_
 /// the reorg target. Instead, we move all the removed nodes in the reorged-away
-/// range to be "owned" by the revert target block.
+/// range to be "owned" by the first block after the revert target. They may
+/// still be part of the target's trie, and therefore also of a trie checkpoint
+/// at or before the target.
 ///
 /// For trie roots: if there was a root change in the interval we're reverting
 /// that will be taken care by the revert anyway (we're changing the trie during
@@ -170,3 +172,100 @@ fn revert_class_updates(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::hash::PedersenHash;
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::{ContractAddress, StateUpdate, StorageValue};
+    use pathfinder_crypto::Felt;
+    use pathfinder_merkle_tree::{verify_proof, ContractsStorageTree, Membership};
+    use pathfinder_storage::{Storage, StorageBuilder, TriePruneMode};
+
+    use super::*;
+    use crate::state::sync::{update_starknet_state, StarknetStateUpdate};
+
+    /// Updates the same storage slot of the system contract in each of the
+    /// blocks, so that every block replaces the tries of the previous one.
+    fn insert_blocks(
+        tx: &Transaction<'_>,
+        storage: &Storage,
+        blocks: std::ops::Range<u64>,
+        value: u64,
+    ) -> Vec<StorageCommitment> {
+        blocks
+            .map(|block| {
+                let state_update = StateUpdate::default().with_system_storage_update(
+                    ContractAddress::ONE,
+                    storage_address!("0x1"),
+                    StorageValue(Felt::from_u64(block + value)),
+                );
+                let (storage_commitment, _) = update_starknet_state(
+                    tx,
+                    StarknetStateUpdate {
+                        contract_updates: &state_update.contract_updates,
+                        system_contract_updates: &state_update.system_contract_updates,
+                        declared_sierra_classes: &state_update.declared_sierra_classes,
+                    },
+                    true,
+                    BlockNumber::new_or_panic(block),
+                    storage.clone(),
+                )
+                .unwrap();
+                storage_commitment
+            })
+            .collect()
+    }
+
+    #[test]
+    fn proofs_at_checkpoint_after_reorg() {
+        let storage = StorageBuilder::in_memory_with_trie_pruning(TriePruneMode::Checkpoints {
+            num_blocks_kept: 1,
+            interval: 2,
+        })
+        .unwrap();
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+
+        let storage_commitments = insert_blocks(&tx, &storage, 0..5, 0);
+
+        // Reorg back to the checkpoint at block 2 and sync a few blocks of the new
+        // fork so that the removals of the reorged-away blocks get pruned.
+        let target = BlockNumber::new_or_panic(2);
+        revert_starknet_state(
+            &tx,
+            BlockNumber::new_or_panic(4),
+            target,
+            BlockHeader::default(),
+        )
+        .unwrap();
+        for block in [4, 3] {
+            tx.purge_block(BlockNumber::new_or_panic(block)).unwrap();
+        }
+        insert_blocks(&tx, &storage, 3..6, 100);
+
+        let proof = StorageCommitmentTree::get_proof(&tx, target, &ContractAddress::ONE)
+            .unwrap()
+            .expect("Storage trie at the checkpoint");
+        let membership = verify_proof::<PedersenHash>(
+            storage_commitments[2].0,
+            ContractAddress::ONE.view_bits(),
+            &proof,
+        )
+        .unwrap();
+        assert!(matches!(membership, Membership::Member(_)));
+
+        let contract_root = tx
+            .contract_root(target, ContractAddress::ONE)
+            .unwrap()
+            .unwrap();
+        let key = storage_address!("0x1");
+        let proof =
+            ContractsStorageTree::get_proof(&tx, ContractAddress::ONE, target, key.view_bits())
+                .unwrap()
+                .expect("Contract trie at the checkpoint");
+        let membership =
+            verify_proof::<PedersenHash>(contract_root.0, key.view_bits(), &proof).unwrap();
+        assert_eq!(membership, Membership::Member(Felt::from_u64(2)));
+    }
+}
@@ -7,6 +7,7 @@ use anyhow::Context;
 use error::SyncError2;
 use futures::{pin_mut, Stream, StreamExt};
 use p2p::client::peer_agnostic::Client as P2PClient;
+use p2p::PeerData;
 use pathfinder_common::{
     block_hash,
     BlockHash,
@@ -29,6 +30,7 @@ mod class_definitions;
 mod error;
 mod events;
 mod headers;
+mod snapshot;
 mod state_updates;
 mod storage_adapters;
 mod stream;
@@ -36,6 +38,7 @@ mod track;
 mod transactions;
 
 const CHECKPOINT_MARGIN: u64 = 10;
+const SNAPSHOT_RETRY_DELAY: Duration = Duration::from_secs(10);
 
 pub struct Sync {
     pub storage: pathfinder_storage::Storage,
@@ -48,19 +51,30 @@ pub struct Sync {
     pub public_key: PublicKey,
     pub l1_checkpoint_override: Option<EthereumStateUpdate>,
     pub verify_tree_hashes: bool,
+    pub snapshot_sync: bool,
 }
 
 impl Sync {
     pub async fn run(self) -> anyhow::Result<()> {
-        let (next, parent_hash) = self.checkpoint_sync().await?;
+        let (next, parent_hash) = if self.snapshot_sync {
+            self.snapshot_sync().await?
+        } else {
+            self.checkpoint_sync().await?
+        };
 
         // TODO: depending on how this is implemented, we might want to loop around it.
         self.track_sync(next, parent_hash).await
     }
 
-    async fn handle_error(&self, err: error::SyncError) {
-        // TODO
-        tracing::debug!(?err, "Log and punish as appropriate");
+    async fn handle_error(&self, err: PeerData<SyncError2>) {
+        let PeerData { peer, data: err } = err;
+        match err.misbehaviour() {
+            Some(misbehaviour) => {
+                tracing::debug!(%peer, %err, "Penalizing peer");
+                self.p2p.report_peer(peer, misbehaviour).await;
+            }
+            None => tracing::debug!(?err, "Sync failed"),
+        }
     }
 
     async fn get_checkpoint(&self) -> anyhow::Result<pathfinder_ethereum::EthereumStateUpdate> {
@@ -98,7 +112,7 @@ impl Sync {
 
             // Handle the error
             if let Err(err) = result {
-                self.handle_error(err).await;
+                self.handle_error(err.into_v2()).await;
                 continue;
             }
 
@@ -116,6 +130,125 @@ impl Sync {
         Ok((checkpoint.block_number + 1, checkpoint.block_hash))
     }
 
+    /// Run snapshot sync until it completes successfully. Instead of syncing
+    /// all blocks up to the checkpoint, only the headers and the state at the
+    /// checkpoint are downloaded. Returns the next block number to sync and
+    /// its parent hash.
+    async fn snapshot_sync(&self) -> anyhow::Result<(BlockNumber, BlockHash)> {
+        let snapshot = snapshot::Sync {
+            storage: self.storage.clone(),
+            p2p: self.p2p.clone(),
+            fgw_client: self.fgw_client.clone(),
+            verify_tree_hashes: self.verify_tree_hashes,
+        };
+
+        loop {
+            match self.try_snapshot_sync(&snapshot).await {
+                Ok(next) => return Ok(next),
+                Err(error) => {
+                    tracing::warn!(%error, "Snapshot sync failed, retrying");
+                    tokio::time::sleep(SNAPSHOT_RETRY_DELAY).await;
+                }
+            }
+        }
+    }
+
+    async fn try_snapshot_sync(
+        &self,
+        snapshot: &snapshot::Sync<P2PClient, GatewayClient>,
+    ) -> anyhow::Result<(BlockNumber, BlockHash)> {
+        // An interrupted snapshot is resumed, its headers are already stored.
+        let storage = self.storage.clone();
+        let in_progress = tokio::task::spawn_blocking(move || -> anyhow::Result<_> {
+            let mut db = storage
+                .connection()
+                .context("Creating database connection")?;
+            let db = db.transaction().context("Creating database transaction")?;
+            let Some(progress) = db
+                .snapshot_progress()
+                .context("Querying snapshot progress")?
+            else {
+                return Ok(None);
+            };
+            let block_hash = db
+                .block_hash(progress.block_number.into())
+                .context("Querying block hash")?
+                .context("Header of the snapshot block is missing")?;
+            Ok(Some(EthereumStateUpdate {
+                state_root: progress.state_commitment,
+                block_number: progress.block_number,
+                block_hash,
+            }))
+        })
+        .await
+        .context("Joining blocking task")??;
+
+        if let Some(anchor) = in_progress {
+            snapshot.run(anchor).await?;
+            snapshot
+                .download_class_definitions(anchor.block_number)
+                .await?;
+            return Ok((anchor.block_number + 1, anchor.block_hash));
+        }
+
+        // Any other existing state is a completed snapshot, only class definitions
+        // might still be missing.
+        let storage = self.storage.clone();
+        let head = tokio::task::spawn_blocking(move || -> anyhow::Result<_> {
+            let mut db = storage
+                .connection()
+                .context("Creating database connection")?;
+            let db = db.transaction().context("Creating database transaction")?;
+            let Some(head) = db
+                .highest_block_with_state_update()
+                .context("Querying highest block with state")?
+            else {
+                return Ok(None);
+            };
+            let hash = db
+                .block_hash(head.into())
+                .context("Querying block hash")?
+                .context("Header of the highest block with state is missing")?;
+            Ok(Some((head, hash)))
+        })
+        .await
+        .context("Joining blocking task")??;
+
+        if let Some((head, hash)) = head {
+            snapshot.download_class_definitions(head).await?;
+            return Ok((head + 1, hash));
+        }
+
+        let anchor = self.get_checkpoint().await?;
+        checkpoint::persist_anchor(self.storage.clone(), anchor).await?;
+
+        let headers = checkpoint::Sync {
+            storage: self.storage.clone(),
+            p2p: self.p2p.clone(),
+            eth_client: self.eth_client.clone(),
+            eth_address: self.eth_address,
+            fgw_client: self.fgw_client.clone(),
+            chain: self.chain,
+            chain_id: self.chain_id,
+            public_key: self.public_key,
+            verify_tree_hashes: self.verify_tree_hashes,
+        }
+        .sync_headers(anchor)
+        .await;
+
+        if let Err(err) = headers {
+            self.handle_error(err.into_v2()).await;
+            anyhow::bail!("Syncing headers up to the snapshot block failed");
+        }
+
+        snapshot.run(anchor).await?;
+        snapshot
+            .download_class_definitions(anchor.block_number)
+            .await?;
+
+        Ok((anchor.block_number + 1, anchor.block_hash))
+    }
+
     /// Run the track sync until it completes successfully, requires the
     /// number and parent hash of the first block to sync
     async fn track_sync(&self, next: BlockNumber, parent_hash: BlockHash) -> anyhow::Result<()> {
@@ -132,6 +265,10 @@ impl Sync {
 
         tracing::info!("Track sync completed: {result:#?}");
 
+        if let Err(err) = result {
+            self.handle_error(err).await;
+        }
+
         Ok(())
     }
 }
@@ -140,7 +140,7 @@ impl Sync {
     ///
     /// No guarantees are made about any headers newer than the anchor.
     #[tracing::instrument(level = "debug", skip(self, anchor))]
-    async fn sync_headers(&self, anchor: EthereumStateUpdate) -> Result<(), SyncError> {
+    pub(super) async fn sync_headers(&self, anchor: EthereumStateUpdate) -> Result<(), SyncError> {
         tracing::info!(?anchor);
 
         while let Some(gap) =
@@ -656,7 +656,10 @@ async fn rollback_to_anchor(
     .context("Joining blocking task")?
 }
 
-async fn persist_anchor(storage: Storage, anchor: EthereumStateUpdate) -> anyhow::Result<()> {
+pub(super) async fn persist_anchor(
+    storage: Storage,
+    anchor: EthereumStateUpdate,
+) -> anyhow::Result<()> {
     spawn_blocking(move || {
         let mut db = storage
             .connection()
@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use p2p::libp2p::PeerId;
-use p2p::PeerData;
+use p2p::{Misbehaviour, PeerData};
 use pathfinder_common::{BlockNumber, ClassHash, SignedBlockHeader};
 
 #[derive(Debug, thiserror::Error)]
@@ -126,6 +126,42 @@ pub(super) enum SyncError2 {
     StateRootMismatch,
 }
 
+impl SyncError2 {
+    /// The misbehaviour of the peer which sent the data that caused this
+    /// error, if the error can be attributed to the peer.
+    pub fn misbehaviour(&self) -> Option<Misbehaviour> {
+        match self {
+            SyncError2::BadHeaderSignature => Some(Misbehaviour::BadSignature),
+            SyncError2::BadBlockHash
+            | SyncError2::StateDiffCommitmentMismatch
+            | SyncError2::EventCommitmentMismatch
+            | SyncError2::TransactionCommitmentMismatch
+            | SyncError2::StateRootMismatch => Some(Misbehaviour::CommitmentMismatch),
+            SyncError2::BadClassLayout
+            | SyncError2::InvalidDto
+            | SyncError2::CairoDefinitionError
+            | SyncError2::SierraDefinitionError => Some(Misbehaviour::MalformedData),
+            SyncError2::Discontinuity
+            | SyncError2::UnexpectedClass
+            | SyncError2::TooManyEvents
+            | SyncError2::TooFewEvents
+            | SyncError2::TooManyTransactions
+            | SyncError2::TooFewTransactions
+            | SyncError2::EventsTransactionsMismatch
+            | SyncError2::IncorrectStateDiffCount
+            | SyncError2::IncorrectClassDefinitionCount
+            | SyncError2::ClassDefinitionsDeclarationsMismatch => {
+                Some(Misbehaviour::UnexpectedData)
+            }
+            // Errors caused by our own node.
+            SyncError2::Other(_)
+            | SyncError2::StarknetVersionNotFound
+            | SyncError2::StateDiffCommitmentNotFound
+            | SyncError2::TransactionCommitmentNotFound => None,
+        }
+    }
+}
+
 impl PartialEq for SyncError2 {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
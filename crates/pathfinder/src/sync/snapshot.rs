@@ -0,0 +1,1225 @@
+//! Snapshot sync downloads the leaves of the state tries at the anchor block
+//! from peers, instead of applying every state diff since genesis.
+//!
+//! Every range is verified against the state commitment of the anchor and is
+//! written to the tries in its own database transaction, together with the
+//! progress made so far. An interrupted snapshot sync resumes from the stored
+//! progress, and the storage of a single contract is streamed range by range,
+//! so neither a restart nor a large contract requires downloading or holding
+//! the whole state at once. Class definitions are fetched from peers once the
+//! state is complete.
+
+use std::collections::HashMap;
+
+use anyhow::Context;
+use futures::{StreamExt, TryStreamExt};
+use p2p::client::conv::{CairoDefinition, SierraDefinition, TryFromDto};
+use p2p::client::peer_agnostic::traits::SnapshotClient;
+use p2p::client::types::ClassDefinition as P2PClassDefinition;
+use p2p::{Misbehaviour, PeerData};
+use p2p_proto::common::{Address, Hash};
+use p2p_proto::snapshot::{
+    ClassDefinitionRequest,
+    ClassRangeRequest,
+    ContractRangeRequest,
+    ContractStorageRangeRequest,
+    PatriciaRangeProof,
+    SnapshotRequest,
+    SnapshotResponse,
+};
+use pathfinder_common::hash::{FeltHash, PedersenHash, PoseidonHash};
+use pathfinder_common::state_update::{ContractClassUpdate, ContractUpdate, StateUpdateData};
+use pathfinder_common::trie::TrieNode;
+use pathfinder_common::{
+    calculate_class_commitment_leaf_hash,
+    BlockNumber,
+    CasmHash,
+    ClassCommitment,
+    ClassHash,
+    ContractAddress,
+    ContractNonce,
+    ContractRoot,
+    SierraHash,
+    StateCommitment,
+    StorageAddress,
+    StorageCommitment,
+    StorageValue,
+};
+use pathfinder_crypto::Felt;
+use pathfinder_ethereum::EthereumStateUpdate;
+use pathfinder_merkle_tree::contract_state::calculate_contract_state_hash;
+use pathfinder_merkle_tree::{
+    verify_range,
+    ClassCommitmentTree,
+    ContractsStorageTree,
+    StorageCommitmentTree,
+};
+use pathfinder_storage::{SnapshotProgress, Storage, Transaction};
+use starknet_gateway_client::GatewayApi;
+use tokio::sync::mpsc;
+use tokio::task::spawn_blocking;
+
+use crate::sync::class_definitions::{CompileSierraToCasm, ComputeHash, Store, VerifyLayout};
+use crate::sync::stream::ProcessStage;
+
+/// Number of leaves requested per range.
+#[cfg(not(test))]
+const RANGE_LIMIT: u32 = 1024;
+#[cfg(test)]
+const RANGE_LIMIT: u32 = 2;
+
+/// Number of contracts whose storage is downloaded concurrently.
+const STORAGE_CONCURRENCY: usize = 16;
+
+/// Number of class definitions downloaded concurrently.
+const CLASS_CONCURRENCY: usize = 8;
+
+/// Number of invalid responses tolerated for a single range before giving up.
+const MAX_ATTEMPTS: usize = 5;
+
+pub struct Sync<P, G> {
+    pub storage: Storage,
+    pub p2p: P,
+    /// Only used to fetch CASM definitions which fail to compile locally.
+    pub fgw_client: G,
+    pub verify_tree_hashes: bool,
+}
+
+/// Leaf of the storage commitment trie.
+#[derive(Debug, Clone, Copy)]
+struct Contract {
+    address: ContractAddress,
+    class_hash: ClassHash,
+    nonce: ContractNonce,
+    storage_root: ContractRoot,
+}
+
+/// Verified data handed over to the database writer. Each write is committed
+/// in its own transaction together with the resulting progress.
+enum Write {
+    ClassCommitment(ClassCommitment),
+    Classes {
+        classes: Vec<(SierraHash, CasmHash)>,
+        next: Option<ClassHash>,
+    },
+    /// A range of a contract's storage. The contract itself is written once
+    /// all of its storage has been written.
+    Storage {
+        contract: ContractAddress,
+        values: Vec<(StorageAddress, StorageValue)>,
+        next: Option<StorageAddress>,
+    },
+    Contracts {
+        contracts: Vec<Contract>,
+        next: Option<ContractAddress>,
+    },
+    /// All ranges have been written.
+    Finish,
+}
+
+/// A response which failed verification.
+struct Rejected(Misbehaviour, anyhow::Error);
+
+impl Rejected {
+    fn unexpected() -> Self {
+        Self(
+            Misbehaviour::UnexpectedData,
+            anyhow::anyhow!("Unexpected response"),
+        )
+    }
+}
+
+impl From<anyhow::Error> for Rejected {
+    fn from(error: anyhow::Error) -> Self {
+        Self(Misbehaviour::CommitmentMismatch, error)
+    }
+}
+
+impl<P, G> Sync<P, G>
+where
+    P: SnapshotClient + Clone,
+    G: GatewayApi + Clone + Send + 'static,
+{
+    /// Downloads and persists the state at the anchor block, resuming from
+    /// the stored progress of a previous attempt.
+    ///
+    /// The header of the anchor block must already be stored. The commitments
+    /// of the header are only set once the complete state matches the
+    /// anchor's state commitment.
+    pub async fn run(&self, anchor: EthereumStateUpdate) -> anyhow::Result<()> {
+        let storage = self.storage.clone();
+        let progress = spawn_blocking(move || {
+            let mut db = storage
+                .connection()
+                .context("Creating database connection")?;
+            let db = db.transaction().context("Creating database transaction")?;
+
+            let progress = match db
+                .snapshot_progress()
+                .context("Querying snapshot progress")?
+            {
+                Some(progress) => {
+                    anyhow::ensure!(
+                        progress.block_number == anchor.block_number,
+                        "Snapshot sync of block {} is already in progress",
+                        progress.block_number
+                    );
+                    progress
+                }
+                None => {
+                    let progress = SnapshotProgress {
+                        block_number: anchor.block_number,
+                        state_commitment: anchor.state_root,
+                        class_commitment: None,
+                        next_class: Some(ClassHash::ZERO),
+                        next_contract: Some(ContractAddress::ZERO),
+                    };
+                    db.upsert_snapshot_progress(&progress)
+                        .context("Inserting snapshot progress")?;
+                    db.commit().context("Committing database transaction")?;
+                    progress
+                }
+            };
+
+            Ok(progress)
+        })
+        .await
+        .context("Joining blocking task")??;
+
+        tracing::info!(
+            block=%anchor.block_number,
+            next_class=?progress.next_class,
+            next_contract=?progress.next_contract,
+            "Starting snapshot sync"
+        );
+
+        let (tx, rx) = mpsc::channel(1);
+        let storage = self.storage.clone();
+        let verify_tree_hashes = self.verify_tree_hashes;
+        let writer = spawn_blocking(move || persist(storage, progress, verify_tree_hashes, rx));
+
+        let downloaded = self.download(progress, tx).await;
+        let written = writer.await.context("Joining blocking task")?;
+
+        // A failing writer also causes the download to fail, so its error takes
+        // precedence.
+        written?;
+        downloaded?;
+
+        tracing::info!(block=%anchor.block_number, "Snapshot sync completed");
+
+        Ok(())
+    }
+
+    /// Downloads the definitions of all classes declared by the snapshot at
+    /// `block` which are not stored yet.
+    pub async fn download_class_definitions(&self, block: BlockNumber) -> anyhow::Result<()> {
+        let storage = self.storage.clone();
+        let missing = spawn_blocking(move || {
+            let mut db = storage
+                .connection()
+                .context("Creating database connection")?;
+            let db = db.transaction().context("Creating database transaction")?;
+            db.missing_class_definitions(block)
+        })
+        .await
+        .context("Joining blocking task")??;
+
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        tracing::info!(count=%missing.len(), "Downloading class definitions");
+
+        futures::stream::iter(missing)
+            .map(|hash| self.download_class_definition(block, hash))
+            .buffer_unordered(CLASS_CONCURRENCY)
+            .try_collect::<()>()
+            .await
+    }
+
+    async fn download_class_definition(
+        &self,
+        block: BlockNumber,
+        hash: ClassHash,
+    ) -> anyhow::Result<()> {
+        let request = SnapshotRequest::ClassDefinition(ClassDefinitionRequest {
+            block_number: block.get(),
+            class_hash: Hash(hash.0),
+        });
+
+        let class = self
+            .fetch(request, |response| {
+                let SnapshotResponse::ClassDefinition(class) = response else {
+                    return Err(Rejected::unexpected());
+                };
+
+                let malformed = |error| Rejected(Misbehaviour::MalformedData, error);
+                let definition = match class {
+                    p2p_proto::class::Class::Cairo0 { class, domain: _ } => {
+                        P2PClassDefinition::Cairo {
+                            block_number: block,
+                            definition: CairoDefinition::try_from_dto(class).map_err(malformed)?.0,
+                        }
+                    }
+                    p2p_proto::class::Class::Cairo1 { class, domain: _ } => {
+                        P2PClassDefinition::Sierra {
+                            block_number: block,
+                            sierra_definition: SierraDefinition::try_from_dto(class)
+                                .map_err(malformed)?
+                                .0,
+                        }
+                    }
+                };
+
+                let class = VerifyLayout
+                    .map(definition)
+                    .and_then(|class| ComputeHash.map(class))
+                    .map_err(|error| malformed(error.into()))?;
+                if class.hash != hash {
+                    return Err(anyhow::anyhow!(
+                        "Class hash mismatch: expected {hash}, computed {}",
+                        class.hash
+                    )
+                    .into());
+                }
+
+                Ok(class)
+            })
+            .await?;
+
+        let fgw_client = self.fgw_client.clone();
+        let storage = self.storage.clone();
+        let handle = tokio::runtime::Handle::current();
+
+        spawn_blocking(move || {
+            let compiled = CompileSierraToCasm::new(fgw_client, handle).map(class)?;
+            let db = storage
+                .connection()
+                .context("Creating database connection")?;
+            Store(db).map(compiled)?;
+            Ok(())
+        })
+        .await
+        .context("Joining blocking task")?
+    }
+
+    async fn download(
+        &self,
+        progress: SnapshotProgress,
+        tx: mpsc::Sender<Write>,
+    ) -> anyhow::Result<()> {
+        let block = progress.block_number;
+
+        let class_commitment = match progress.class_commitment {
+            Some(class_commitment) => class_commitment,
+            None => {
+                let class_commitment = self
+                    .class_commitment(block, progress.state_commitment)
+                    .await?;
+                tx.send(Write::ClassCommitment(class_commitment))
+                    .await
+                    .context("Snapshot writer stopped")?;
+                class_commitment
+            }
+        };
+
+        if let Some(start) = progress.next_class {
+            self.download_classes(block, class_commitment, start, &tx)
+                .await?;
+        }
+
+        if let Some(start) = progress.next_contract {
+            self.download_contracts(
+                block,
+                progress.state_commitment,
+                class_commitment,
+                start,
+                &tx,
+            )
+            .await?;
+        }
+
+        tx.send(Write::Finish)
+            .await
+            .context("Snapshot writer stopped")
+    }
+
+    /// Fetches the class commitment of `block`, which is bound to the state
+    /// commitment together with the storage commitment of any contract range.
+    async fn class_commitment(
+        &self,
+        block: BlockNumber,
+        state_commitment: StateCommitment,
+    ) -> anyhow::Result<ClassCommitment> {
+        let request = SnapshotRequest::ContractRange(ContractRangeRequest {
+            block_number: block.get(),
+            start: Address(Felt::ZERO),
+            limit: 1,
+        });
+
+        self.fetch(request, |response| {
+            let SnapshotResponse::ContractRange(range) = response else {
+                return Err(Rejected::unexpected());
+            };
+
+            let storage_commitment = StorageCommitment(range.storage_commitment.0);
+            let class_commitment = ClassCommitment(range.class_commitment.0);
+            if StateCommitment::calculate(storage_commitment, class_commitment) != state_commitment
+            {
+                return Err(anyhow::anyhow!("State commitment mismatch").into());
+            }
+
+            Ok(class_commitment)
+        })
+        .await
+    }
+
+    /// Downloads the leaves of the class trie, starting at `start`.
+    async fn download_classes(
+        &self,
+        block: BlockNumber,
+        class_commitment: ClassCommitment,
+        start: ClassHash,
+        tx: &mpsc::Sender<Write>,
+    ) -> anyhow::Result<()> {
+        let mut start = start.0;
+
+        // No Sierra class has been declared.
+        if class_commitment == ClassCommitment::ZERO {
+            return tx
+                .send(Write::Classes {
+                    classes: vec![],
+                    next: None,
+                })
+                .await
+                .context("Snapshot writer stopped");
+        }
+
+        loop {
+            let request = SnapshotRequest::ClassRange(ClassRangeRequest {
+                block_number: block.get(),
+                start: Hash(start),
+                limit: RANGE_LIMIT,
+            });
+
+            let (classes, done) = self
+                .fetch(request, |response| {
+                    let SnapshotResponse::ClassRange(range) = response else {
+                        return Err(Rejected::unexpected());
+                    };
+
+                    let classes = range
+                        .classes
+                        .iter()
+                        .map(|class| {
+                            (
+                                SierraHash(class.class_hash.0),
+                                CasmHash(class.compiled_class_hash.0),
+                            )
+                        })
+                        .collect::<Vec<_>>();
+                    let leaves = classes
+                        .iter()
+                        .map(|(sierra, casm)| {
+                            (sierra.0, calculate_class_commitment_leaf_hash(*casm).0)
+                        })
+                        .collect::<Vec<_>>();
+                    let done =
+                        verify::<PoseidonHash>(class_commitment.0, start, &leaves, range.proof)?;
+
+                    Ok((classes, done))
+                })
+                .await?;
+
+            let next = match (done, classes.last()) {
+                (false, Some((last, _))) => next_key(last.0),
+                _ => None,
+            };
+
+            tracing::debug!(count=%classes.len(), "Downloaded class range");
+
+            tx.send(Write::Classes {
+                classes,
+                next: next.map(ClassHash),
+            })
+            .await
+            .context("Snapshot writer stopped")?;
+
+            match next {
+                Some(next) => start = next,
+                None => return Ok(()),
+            }
+        }
+    }
+
+    /// Downloads all contracts including their storage, starting at `start`.
+    async fn download_contracts(
+        &self,
+        block: BlockNumber,
+        state_commitment: StateCommitment,
+        class_commitment: ClassCommitment,
+        start: ContractAddress,
+        tx: &mpsc::Sender<Write>,
+    ) -> anyhow::Result<()> {
+        let mut start = start.0;
+
+        loop {
+            let request = SnapshotRequest::ContractRange(ContractRangeRequest {
+                block_number: block.get(),
+                start: Address(start),
+                limit: RANGE_LIMIT,
+            });
+
+            let (contracts, done) = self
+                .fetch(request, |response| {
+                    let SnapshotResponse::ContractRange(range) = response else {
+                        return Err(Rejected::unexpected());
+                    };
+
+                    let storage_commitment = StorageCommitment(range.storage_commitment.0);
+                    if ClassCommitment(range.class_commitment.0) != class_commitment
+                        || StateCommitment::calculate(storage_commitment, class_commitment)
+                            != state_commitment
+                    {
+                        return Err(anyhow::anyhow!("State commitment mismatch").into());
+                    }
+
+                    let contracts = range
+                        .states
+                        .iter()
+                        .map(|state| Contract {
+                            address: ContractAddress(state.address.0),
+                            class_hash: ClassHash(state.class_hash.0),
+                            nonce: ContractNonce(state.nonce),
+                            storage_root: ContractRoot(state.storage_root.0),
+                        })
+                        .collect::<Vec<_>>();
+                    let leaves = contracts
+                        .iter()
+                        .map(|contract| {
+                            let hash = calculate_contract_state_hash(
+                                contract.class_hash,
+                                contract.storage_root,
+                                contract.nonce,
+                            );
+                            (contract.address.0, hash.0)
+                        })
+                        .collect::<Vec<_>>();
+                    let done =
+                        verify::<PedersenHash>(storage_commitment.0, start, &leaves, range.proof)?;
+
+                    Ok((contracts, done))
+                })
+                .await?;
+
+            // Storage of these contracts might have been partially written by a
+            // previous attempt.
+            let storage = self.storage.clone();
+            let addresses = contracts
+                .iter()
+                .map(|contract| contract.address)
+                .collect::<Vec<_>>();
+            let storage_progress = spawn_blocking(move || {
+                let mut db = storage
+                    .connection()
+                    .context("Creating database connection")?;
+                let db = db.transaction().context("Creating database transaction")?;
+
+                let mut progress = HashMap::new();
+                for address in addresses {
+                    if let Some(next) = db
+                        .snapshot_storage_progress(address)
+                        .context("Querying snapshot storage progress")?
+                    {
+                        progress.insert(address, next);
+                    }
+                }
+                anyhow::Ok(progress)
+            })
+            .await
+            .context("Joining blocking task")??;
+
+            // Contracts whose storage has been stored completely are skipped.
+            let pending = contracts.iter().filter_map(|contract| {
+                let start = storage_progress
+                    .get(&contract.address)
+                    .copied()
+                    .unwrap_or(Some(StorageAddress::ZERO))?;
+                Some((contract, start))
+            });
+            futures::stream::iter(pending)
+                .map(|(contract, start)| self.download_storage(block, *contract, start, tx))
+                .buffer_unordered(STORAGE_CONCURRENCY)
+                .try_collect::<()>()
+                .await?;
+
+            let next = match (done, contracts.last()) {
+                (false, Some(last)) => next_key(last.address.0),
+                _ => None,
+            };
+
+            tracing::debug!(count=%contracts.len(), "Downloaded contract range");
+
+            tx.send(Write::Contracts {
+                contracts,
+                next: next.map(ContractAddress),
+            })
+            .await
+            .context("Snapshot writer stopped")?;
+
+            match next {
+                Some(next) => start = next,
+                None => return Ok(()),
+            }
+        }
+    }
+
+    /// Downloads the storage of a single contract, starting at `start`. Each
+    /// range is handed over to the writer as soon as it is verified.
+    async fn download_storage(
+        &self,
+        block: BlockNumber,
+        contract: Contract,
+        start: StorageAddress,
+        tx: &mpsc::Sender<Write>,
+    ) -> anyhow::Result<()> {
+        let root = contract.storage_root.0;
+        let mut start = start.0;
+
+        // A zero root is an empty storage trie.
+        if root == Felt::ZERO {
+            return Ok(());
+        }
+
+        loop {
+            let request = SnapshotRequest::ContractStorageRange(ContractStorageRangeRequest {
+                block_number: block.get(),
+                address: Address(contract.address.0),
+                start,
+                limit: RANGE_LIMIT,
+            });
+
+            let (values, done) = self
+                .fetch(request, |response| {
+                    let SnapshotResponse::ContractStorageRange(range) = response else {
+                        return Err(Rejected::unexpected());
+                    };
+
+                    let leaves = range
+                        .values
+                        .iter()
+                        .map(|value| (value.key, value.value))
+                        .collect::<Vec<_>>();
+                    let done = verify::<PedersenHash>(root, start, &leaves, range.proof)?;
+
+                    Ok((leaves, done))
+                })
+                .await?;
+
+            let Some((last, _)) = values.last().copied() else {
+                return Ok(());
+            };
+            let next = if done { None } else { next_key(last) };
+
+            tx.send(Write::Storage {
+                contract: contract.address,
+                values: values
+                    .into_iter()
+                    .map(|(key, value)| (StorageAddress(key), StorageValue(value)))
+                    .collect(),
+                next: next.map(StorageAddress),
+            })
+            .await
+            .context("Snapshot writer stopped")?;
+
+            match next {
+                Some(next) => start = next,
+                None => return Ok(()),
+            }
+        }
+    }
+
+    /// Requests data from peers until one responds with data that passes
+    /// `verify`. Peers responding with invalid data are penalized.
+    async fn fetch<T>(
+        &self,
+        request: SnapshotRequest,
+        verify: impl Fn(SnapshotResponse) -> Result<T, Rejected>,
+    ) -> anyhow::Result<T> {
+        for _ in 0..MAX_ATTEMPTS {
+            let PeerData { peer, data } = self
+                .p2p
+                .clone()
+                .snapshot_range(request)
+                .await
+                .with_context(|| format!("No peer serves snapshot data {request:?}"))?;
+
+            match verify(data) {
+                Ok(verified) => return Ok(verified),
+                Err(Rejected(misbehaviour, error)) => {
+                    tracing::debug!(%peer, %error, "Invalid snapshot data");
+                    self.p2p.clone().report_peer(peer, misbehaviour).await;
+                }
+            }
+        }
+
+        anyhow::bail!("Too many invalid responses for snapshot data {request:?}")
+    }
+}
+
+/// The key following `key`, or `None` if `key` is the last key of a trie and
+/// there is nothing left to download.
+fn next_key(key: Felt) -> Option<Felt> {
+    let next = key + Felt::ONE;
+    (!next.has_more_than_251_bits()).then_some(next)
+}
+
+/// Verifies the range proof of `leaves` against `root`. Returns whether the
+/// range extends to the end of the trie.
+fn verify<H: FeltHash>(
+    root: Felt,
+    start: Felt,
+    leaves: &[(Felt, Felt)],
+    proof: PatriciaRangeProof,
+) -> anyhow::Result<bool> {
+    let start_proof = proof
+        .start
+        .into_iter()
+        .map(TrieNode::try_from_dto)
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let end_proof = proof
+        .end
+        .into_iter()
+        .map(TrieNode::try_from_dto)
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    verify_range::<H>(root, start, leaves, &start_proof, &end_proof)?;
+
+    Ok(end_proof.is_empty())
+}
+
+/// Writes the downloaded state at the block of `progress`. Each write is
+/// committed together with the progress it results in, so that an interrupted
+/// download can resume from there.
+fn persist(
+    storage: Storage,
+    mut progress: SnapshotProgress,
+    verify_hashes: bool,
+    mut rx: mpsc::Receiver<Write>,
+) -> anyhow::Result<()> {
+    let mut db = storage
+        .connection()
+        .context("Creating database connection")?;
+    let block = progress.block_number;
+
+    // The download was interrupted if the channel closes before `Finish`.
+    while let Some(write) = rx.blocking_recv() {
+        let db = db.transaction().context("Creating database transaction")?;
+
+        match write {
+            Write::ClassCommitment(class_commitment) => {
+                progress.class_commitment = Some(class_commitment);
+            }
+            Write::Classes { classes, next } => {
+                insert_classes(&db, block, classes, verify_hashes)?;
+                progress.next_class = next;
+            }
+            Write::Storage {
+                contract,
+                values,
+                next,
+            } => {
+                insert_storage(&db, block, contract, values, verify_hashes)?;
+                db.upsert_snapshot_storage_progress(contract, next)
+                    .context("Updating snapshot storage progress")?;
+            }
+            Write::Contracts { contracts, next } => {
+                insert_contracts(&db, block, contracts, verify_hashes)?;
+                progress.next_contract = next;
+            }
+            Write::Finish => {
+                finish(&db, block, progress.state_commitment)?;
+                return db.commit().context("Committing database transaction");
+            }
+        }
+
+        db.upsert_snapshot_progress(&progress)
+            .context("Updating snapshot progress")?;
+        db.commit().context("Committing database transaction")?;
+    }
+
+    Ok(())
+}
+
+fn insert_classes(
+    db: &Transaction<'_>,
+    block: BlockNumber,
+    classes: Vec<(SierraHash, CasmHash)>,
+    verify_hashes: bool,
+) -> anyhow::Result<()> {
+    let mut class_commitment_tree = ClassCommitmentTree::load(db, block)
+        .context("Loading class commitment tree")?
+        .with_verify_hashes(verify_hashes);
+
+    for (sierra, casm) in &classes {
+        let leaf_hash = calculate_class_commitment_leaf_hash(*casm);
+        db.insert_class_commitment_leaf(block, &leaf_hash, casm)
+            .context("Adding class commitment leaf")?;
+        class_commitment_tree
+            .set(*sierra, leaf_hash)
+            .context("Updating class commitment tree")?;
+    }
+
+    let (_, trie_update) = class_commitment_tree
+        .commit()
+        .context("Apply class commitment tree updates")?;
+    let root_idx = db
+        .insert_class_trie(&trie_update, block)
+        .context("Persisting class trie")?;
+    db.insert_class_root(block, root_idx)
+        .context("Inserting class root index")?;
+
+    let state_update = StateUpdateData {
+        declared_sierra_classes: classes.into_iter().collect(),
+        ..Default::default()
+    };
+    db.insert_state_update_data(block, &state_update)
+        .context("Inserting declared classes")
+}
+
+fn insert_storage(
+    db: &Transaction<'_>,
+    block: BlockNumber,
+    contract: ContractAddress,
+    values: Vec<(StorageAddress, StorageValue)>,
+    verify_hashes: bool,
+) -> anyhow::Result<()> {
+    let mut contract_tree = ContractsStorageTree::load(db, contract, block)
+        .context("Loading contract storage tree")?
+        .with_verify_hashes(verify_hashes);
+
+    for (key, value) in &values {
+        contract_tree
+            .set(*key, *value)
+            .context("Update contract storage tree")?;
+    }
+
+    let (_, trie_update) = contract_tree
+        .commit()
+        .context("Apply contract storage tree changes")?;
+    let root_idx = db
+        .insert_contract_trie(&trie_update, block)
+        .context("Persisting contract trie")?;
+    db.insert_contract_root(block, contract, root_idx)
+        .context("Inserting contract's root index")?;
+
+    let update = ContractUpdate {
+        storage: values.into_iter().collect(),
+        ..Default::default()
+    };
+    let state_update = StateUpdateData {
+        contract_updates: [(contract, update)].into(),
+        ..Default::default()
+    };
+    db.insert_state_update_data(block, &state_update)
+        .context("Inserting contract storage")
+}
+
+/// Inserts contracts whose storage has been written completely.
+fn insert_contracts(
+    db: &Transaction<'_>,
+    block: BlockNumber,
+    contracts: Vec<Contract>,
+    verify_hashes: bool,
+) -> anyhow::Result<()> {
+    let mut storage_commitment_tree = StorageCommitmentTree::load(db, block)
+        .context("Loading storage commitment tree")?
+        .with_verify_hashes(verify_hashes);
+    let mut state_update = StateUpdateData::default();
+
+    for contract in contracts {
+        let storage_root = db
+            .contract_root(block, contract.address)
+            .context("Querying contract's storage root")?
+            .unwrap_or_default();
+        anyhow::ensure!(
+            storage_root == contract.storage_root,
+            "Storage of contract {} is incomplete",
+            contract.address
+        );
+
+        let state_hash =
+            calculate_contract_state_hash(contract.class_hash, storage_root, contract.nonce);
+        db.insert_contract_state_hash(block, contract.address, state_hash)
+            .context("Inserting contract state hash")?;
+        storage_commitment_tree
+            .set(contract.address, state_hash)
+            .context("Updating storage commitment tree")?;
+        db.delete_snapshot_storage_progress(contract.address)
+            .context("Deleting snapshot storage progress")?;
+
+        // System contracts have neither a class nor a nonce.
+        state_update.contract_updates.insert(
+            contract.address,
+            ContractUpdate {
+                class: (contract.class_hash != ClassHash::ZERO)
+                    .then_some(ContractClassUpdate::Deploy(contract.class_hash)),
+                nonce: (contract.nonce != ContractNonce::ZERO).then_some(contract.nonce),
+                ..Default::default()
+            },
+        );
+    }
+
+    db.insert_state_update_data(block, &state_update)
+        .context("Inserting contract state")?;
+
+    let (_, trie_update) = storage_commitment_tree
+        .commit()
+        .context("Apply storage commitment tree updates")?;
+    let root_idx = db
+        .insert_storage_trie(&trie_update, block)
+        .context("Persisting storage trie")?;
+    db.insert_storage_root(block, root_idx)
+        .context("Inserting storage root index")
+}
+
+/// Checks the written tries against `expected` and sets the commitments of
+/// the header, which completes the snapshot.
+fn finish(
+    db: &Transaction<'_>,
+    block: BlockNumber,
+    expected: StateCommitment,
+) -> anyhow::Result<()> {
+    // Committing an unchanged trie only yields its root.
+    let (storage_commitment, _) = StorageCommitmentTree::load(db, block)
+        .context("Loading storage commitment tree")?
+        .commit()
+        .context("Calculating storage commitment")?;
+    let (class_commitment, _) = ClassCommitmentTree::load(db, block)
+        .context("Loading class commitment tree")?
+        .commit()
+        .context("Calculating class commitment")?;
+
+    anyhow::ensure!(
+        StateCommitment::calculate(storage_commitment, class_commitment) == expected,
+        "State commitment mismatch after applying the snapshot"
+    );
+
+    db.update_storage_and_class_commitments(block, storage_commitment, class_commitment)
+        .context("Updating storage and class commitments")?;
+    db.delete_snapshot_progress()
+        .context("Deleting snapshot progress")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use p2p::libp2p::PeerId;
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::{BlockHeader, StateUpdate};
+    use pathfinder_storage::fake::Block;
+    use pathfinder_storage::StorageBuilder;
+    use starknet_gateway_types::error::SequencerError;
+
+    use super::*;
+    use crate::p2p_network::sync_handlers;
+    use crate::p2p_network::sync_handlers::tests::snapshot::{
+        source,
+        store,
+        CAIRO_HASH,
+        SIERRA0_HASH,
+        SIERRA2_HASH,
+    };
+
+    /// Serves snapshot data from a source database, until its budget of
+    /// requests is used up.
+    #[derive(Clone)]
+    struct FakePeer {
+        storage: Storage,
+        budget: Arc<AtomicUsize>,
+        reports: Arc<Mutex<Vec<Misbehaviour>>>,
+    }
+
+    impl FakePeer {
+        fn new(storage: Storage, budget: usize) -> Self {
+            Self {
+                storage,
+                budget: Arc::new(AtomicUsize::new(budget)),
+                reports: Default::default(),
+            }
+        }
+
+        fn remaining(&self) -> usize {
+            self.budget.load(Ordering::SeqCst)
+        }
+    }
+
+    impl SnapshotClient for FakePeer {
+        async fn snapshot_range(
+            self,
+            request: SnapshotRequest,
+        ) -> Option<PeerData<SnapshotResponse>> {
+            self.budget
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |budget| {
+                    budget.checked_sub(1)
+                })
+                .ok()?;
+
+            let (tx, mut rx) = mpsc::channel(2);
+            spawn_blocking(move || {
+                let mut db = self.storage.connection().unwrap();
+                let db = db.transaction().unwrap();
+                sync_handlers::blocking::get_snapshot(db, request, tx).unwrap();
+            })
+            .await
+            .unwrap();
+
+            match rx.recv().await.unwrap() {
+                SnapshotResponse::Fin => None,
+                response => Some(PeerData::for_tests(response)),
+            }
+        }
+
+        async fn report_peer(self, _: PeerId, misbehaviour: Misbehaviour) {
+            self.reports.lock().unwrap().push(misbehaviour);
+        }
+    }
+
+    #[derive(Clone)]
+    struct FakeFgw;
+
+    #[async_trait::async_trait]
+    impl GatewayApi for FakeFgw {
+        async fn pending_casm_by_hash(&self, _: ClassHash) -> Result<bytes::Bytes, SequencerError> {
+            Ok(bytes::Bytes::from_static(b"I'm from the fgw!"))
+        }
+    }
+
+    /// Creates a database which only contains the header of the snapshot block,
+    /// and the anchor of the snapshot.
+    fn destination(source: &Storage) -> (Storage, EthereumStateUpdate) {
+        let mut db = source.connection().unwrap();
+        let db = db.transaction().unwrap();
+        let header = db
+            .block_header(BlockNumber::GENESIS.into())
+            .unwrap()
+            .unwrap();
+        let anchor = EthereumStateUpdate {
+            state_root: StateCommitment::calculate(
+                header.storage_commitment,
+                header.class_commitment,
+            ),
+            block_number: header.number,
+            block_hash: header.hash,
+        };
+
+        let storage = StorageBuilder::in_memory().unwrap();
+        let mut db = storage.connection().unwrap();
+        let db = db.transaction().unwrap();
+        db.insert_block_header(&BlockHeader {
+            storage_commitment: StorageCommitment::ZERO,
+            class_commitment: ClassCommitment::ZERO,
+            ..header
+        })
+        .unwrap();
+        db.commit().unwrap();
+
+        (storage, anchor)
+    }
+
+    fn sync(storage: Storage, peer: FakePeer) -> Sync<FakePeer, FakeFgw> {
+        Sync {
+            storage,
+            p2p: peer,
+            fgw_client: FakeFgw,
+            verify_tree_hashes: true,
+        }
+    }
+
+    fn assert_state(storage: &Storage, expected: &StateUpdate, anchor: EthereumStateUpdate) {
+        let mut db = storage.connection().unwrap();
+        let db = db.transaction().unwrap();
+        let block = BlockNumber::GENESIS;
+
+        let header = db.block_header(block.into()).unwrap().unwrap();
+        assert_eq!(
+            StateCommitment::calculate(header.storage_commitment, header.class_commitment),
+            anchor.state_root
+        );
+        assert_eq!(db.snapshot_progress().unwrap(), None);
+
+        for (address, update) in &expected.contract_updates {
+            assert_eq!(
+                db.contract_class_hash(block.into(), *address).unwrap(),
+                update.class.as_ref().map(|class| class.class_hash())
+            );
+            assert_eq!(
+                db.contract_nonce(*address, block.into()).unwrap(),
+                update.nonce
+            );
+            for (key, value) in &update.storage {
+                assert_eq!(
+                    db.storage_value(block.into(), *address, *key).unwrap(),
+                    Some(*value)
+                );
+            }
+        }
+        for (address, update) in &expected.system_contract_updates {
+            for (key, value) in &update.storage {
+                assert_eq!(
+                    db.storage_value(block.into(), *address, *key).unwrap(),
+                    Some(*value)
+                );
+            }
+        }
+        for (sierra, casm) in &expected.declared_sierra_classes {
+            assert_eq!(
+                db.casm_hash_at(block.into(), ClassHash(sierra.0)).unwrap(),
+                Some(*casm)
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn syncs_state_and_classes_from_peer() {
+        let (source_storage, state_update, _) = source();
+        let (storage, anchor) = destination(&source_storage);
+        let snapshot = sync(storage.clone(), FakePeer::new(source_storage, usize::MAX));
+
+        snapshot.run(anchor).await.unwrap();
+        assert_state(&storage, &state_update, anchor);
+
+        snapshot
+            .download_class_definitions(BlockNumber::GENESIS)
+            .await
+            .unwrap();
+
+        let mut db = storage.connection().unwrap();
+        let db = db.transaction().unwrap();
+        assert!(db
+            .missing_class_definitions(BlockNumber::GENESIS)
+            .unwrap()
+            .is_empty());
+        for hash in [
+            CAIRO_HASH,
+            ClassHash(SIERRA0_HASH.0),
+            ClassHash(SIERRA2_HASH.0),
+        ] {
+            assert!(db.class_definition(hash).unwrap().is_some());
+        }
+        // Does not compile locally.
+        assert_eq!(
+            db.casm_definition(ClassHash(SIERRA0_HASH.0)).unwrap(),
+            Some(b"I'm from the fgw!".to_vec())
+        );
+        assert!(snapshot.p2p.reports.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn resumes_interrupted_sync() {
+        let (source_storage, state_update, _) = source();
+
+        // Number of requests required for the complete state.
+        let (storage, anchor) = destination(&source_storage);
+        let peer = FakePeer::new(source_storage.clone(), usize::MAX);
+        sync(storage, peer.clone()).run(anchor).await.unwrap();
+        let required = usize::MAX - peer.remaining();
+
+        let (storage, anchor) = destination(&source_storage);
+        let interrupted = FakePeer::new(source_storage.clone(), required / 2);
+        sync(storage.clone(), interrupted)
+            .run(anchor)
+            .await
+            .unwrap_err();
+
+        let progress = {
+            let mut db = storage.connection().unwrap();
+            let db = db.transaction().unwrap();
+            db.snapshot_progress().unwrap().unwrap()
+        };
+        assert!(progress.class_commitment.is_some());
+
+        let peer = FakePeer::new(source_storage, usize::MAX);
+        sync(storage.clone(), peer.clone())
+            .run(anchor)
+            .await
+            .unwrap();
+        assert_state(&storage, &state_update, anchor);
+
+        // Only the remaining ranges are requested.
+        assert!(usize::MAX - peer.remaining() < required);
+    }
+
+    #[tokio::test]
+    async fn rejects_state_not_matching_anchor() {
+        let (source_storage, _, _) = source();
+        let (storage, mut anchor) = destination(&source_storage);
+        anchor.state_root = StateCommitment(Felt::ONE);
+        let peer = FakePeer::new(source_storage, usize::MAX);
+
+        sync(storage.clone(), peer.clone())
+            .run(anchor)
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            *peer.reports.lock().unwrap(),
+            vec![Misbehaviour::CommitmentMismatch; MAX_ATTEMPTS]
+        );
+        let mut db = storage.connection().unwrap();
+        let db = db.transaction().unwrap();
+        assert_eq!(db.highest_block_with_state_update().unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn stops_at_the_last_trie_key() {
+        // 2^251 - 1, the last key of every trie.
+        let last = felt!("0x7ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff");
+
+        // Each trie has exactly one full range, which ends at the last key.
+        let state_update = StateUpdate::default()
+            .with_declared_sierra_class(sierra_hash!("0x1"), casm_hash!("0x10"))
+            .with_declared_sierra_class(SierraHash(last), casm_hash!("0x20"))
+            .with_deployed_contract(ContractAddress(last), class_hash!("0x1"))
+            .with_storage_update(
+                ContractAddress(last),
+                storage_address!("0x1"),
+                storage_value!("0x11"),
+            )
+            .with_storage_update(
+                ContractAddress(last),
+                StorageAddress(last),
+                storage_value!("0x12"),
+            )
+            .with_system_storage_update(
+                ContractAddress::ONE,
+                storage_address!("0x1"),
+                storage_value!("0x41"),
+            );
+        let block = Block {
+            state_update: state_update.clone(),
+            sierra_defs: vec![
+                (sierra_hash!("0x1"), b"sierra".to_vec(), b"casm".to_vec()),
+                (SierraHash(last), b"sierra".to_vec(), b"casm".to_vec()),
+            ],
+            ..Default::default()
+        };
+        let (source_storage, _) = store(block);
+
+        let (storage, anchor) = destination(&source_storage);
+        let peer = FakePeer::new(source_storage, usize::MAX);
+        sync(storage.clone(), peer.clone())
+            .run(anchor)
+            .await
+            .unwrap();
+        assert_state(&storage, &state_update, anchor);
+
+        // The class commitment and a single range per trie, nothing is requested
+        // beyond the last key.
+        assert_eq!(usize::MAX - peer.remaining(), 5);
+        assert!(peer.reports.lock().unwrap().is_empty());
+    }
+}
@@ -2,10 +2,12 @@ use std::num::NonZeroUsize;
 use std::sync::Arc;
 
 use pathfinder_common::ChainId;
-use pathfinder_executor::{TraceCache, VersionedConstants};
+use pathfinder_executor::{TraceCache, VersionedConstantsMap};
 use pathfinder_storage::Storage;
 
+use crate::gossip::TransactionGossipSender;
 pub use crate::jsonrpc::websocket::WebsocketContext;
+use crate::peers::PeerReputationsSender;
 use crate::pending::{PendingData, PendingWatcher};
 use crate::SyncState;
 
@@ -17,7 +19,8 @@ pub struct RpcConfig {
     pub batch_concurrency_limit: NonZeroUsize,
     pub get_events_max_blocks_to_scan: NonZeroUsize,
     pub get_events_max_uncached_bloom_filters_to_load: NonZeroUsize,
-    pub custom_versioned_constants: Option<VersionedConstants>,
+    pub custom_versioned_constants: VersionedConstantsMap,
+    pub validate_submitted_transactions: bool,
 }
 
 #[derive(Clone)]
@@ -30,6 +33,8 @@ pub struct RpcContext {
     pub chain_id: ChainId,
     pub sequencer: SequencerClient,
     pub websocket: Option<WebsocketContext>,
+    pub transaction_gossip: Option<TransactionGossipSender>,
+    pub peer_reputations: Option<PeerReputationsSender>,
     pub config: RpcConfig,
 }
 
@@ -53,6 +58,8 @@ impl RpcContext {
             pending_data,
             sequencer,
             websocket: None,
+            transaction_gossip: None,
+            peer_reputations: None,
             config,
         }
     }
@@ -101,7 +108,8 @@ impl RpcContext {
             batch_concurrency_limit: NonZeroUsize::new(8).unwrap(),
             get_events_max_blocks_to_scan: NonZeroUsize::new(1000).unwrap(),
             get_events_max_uncached_bloom_filters_to_load: NonZeroUsize::new(1000).unwrap(),
-            custom_versioned_constants: None,
+            custom_versioned_constants: VersionedConstantsMap::default(),
+            validate_submitted_transactions: false,
         };
 
         Self::new(
@@ -144,10 +152,28 @@ impl RpcContext {
         context.with_pending_data(rx)
     }
 
+    pub fn with_trace_cache(self, cache: TraceCache) -> Self {
+        Self { cache, ..self }
+    }
+
     pub fn with_websockets(self, websockets: WebsocketContext) -> Self {
         Self {
             websocket: Some(websockets),
             ..self
         }
     }
+
+    pub fn with_transaction_gossip(self, transaction_gossip: TransactionGossipSender) -> Self {
+        Self {
+            transaction_gossip: Some(transaction_gossip),
+            ..self
+        }
+    }
+
+    pub fn with_peer_reputations(self, peer_reputations: PeerReputationsSender) -> Self {
+        Self {
+            peer_reputations: Some(peer_reputations),
+            ..self
+        }
+    }
 }
@@ -1,8 +1,10 @@
 // TODO In v06 some stuff is different, see with_v06_format in
 // simulate_transactions
 
+use std::collections::BTreeSet;
+
 use anyhow::anyhow;
-use pathfinder_common::{ContractAddress, ContractNonce};
+use pathfinder_common::{CasmHash, ContractAddress, ContractNonce, SierraHash, StorageAddress};
 use serde::ser::Error;
 
 use super::serialize::SerializeStruct;
@@ -490,3 +492,158 @@ impl crate::dto::serialize::SerializeForVersion for ExecuteInvocation<'_> {
         }
     }
 }
+
+pub struct AccessList<'a>(pub &'a pathfinder_executor::types::AccessList);
+
+impl crate::dto::serialize::SerializeForVersion for AccessList<'_> {
+    fn serialize(
+        &self,
+        serializer: super::serialize::Serializer,
+    ) -> Result<super::serialize::Ok, super::serialize::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("reads", &AccessSet(&self.0.reads))?;
+        serializer.serialize_field("writes", &AccessSet(&self.0.writes))?;
+        serializer.serialize_iter(
+            "calls",
+            self.0.calls.len(),
+            &mut self.0.calls.iter().map(CallAccess),
+        )?;
+        serializer.end()
+    }
+}
+
+struct AccessSet<'a>(&'a pathfinder_executor::types::AccessSet);
+
+impl crate::dto::serialize::SerializeForVersion for AccessSet<'_> {
+    fn serialize(
+        &self,
+        serializer: super::serialize::Serializer,
+    ) -> Result<super::serialize::Ok, super::serialize::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_iter(
+            "storage",
+            self.0.storage.len(),
+            &mut self.0.storage.iter().map(StorageAccess),
+        )?;
+        serializer.serialize_iter(
+            "nonces",
+            self.0.nonces.len(),
+            &mut self.0.nonces.iter().map(|v| crate::dto::Felt(&v.0)),
+        )?;
+        serializer.serialize_iter(
+            "class_hashes",
+            self.0.class_hashes.len(),
+            &mut self.0.class_hashes.iter().map(|v| crate::dto::Felt(&v.0)),
+        )?;
+        serializer.serialize_iter(
+            "declared_classes",
+            self.0.declared_classes.len(),
+            &mut self.0.declared_classes.iter().map(DeclaredClass),
+        )?;
+        serializer.serialize_iter(
+            "deprecated_declared_classes",
+            self.0.deprecated_declared_classes.len(),
+            &mut self
+                .0
+                .deprecated_declared_classes
+                .iter()
+                .map(|v| crate::dto::Felt(&v.0)),
+        )?;
+        serializer.end()
+    }
+}
+
+struct DeclaredClass<'a>((&'a SierraHash, &'a CasmHash));
+
+impl crate::dto::serialize::SerializeForVersion for DeclaredClass<'_> {
+    fn serialize(
+        &self,
+        serializer: super::serialize::Serializer,
+    ) -> Result<super::serialize::Ok, super::serialize::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("class_hash", &crate::dto::Felt(&self.0 .0 .0))?;
+        serializer.serialize_field("compiled_class_hash", &crate::dto::Felt(&self.0 .1 .0))?;
+        serializer.end()
+    }
+}
+
+struct StorageAccess<'a>((&'a ContractAddress, &'a BTreeSet<StorageAddress>));
+
+impl crate::dto::serialize::SerializeForVersion for StorageAccess<'_> {
+    fn serialize(
+        &self,
+        serializer: super::serialize::Serializer,
+    ) -> Result<super::serialize::Ok, super::serialize::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("contract_address", &crate::dto::Felt(&self.0 .0 .0))?;
+        serializer.serialize_iter(
+            "keys",
+            self.0 .1.len(),
+            &mut self.0 .1.iter().map(|v| crate::dto::Felt(&v.0)),
+        )?;
+        serializer.end()
+    }
+}
+
+struct CallAccess<'a>(&'a pathfinder_executor::types::CallAccess);
+
+impl crate::dto::serialize::SerializeForVersion for CallAccess<'_> {
+    fn serialize(
+        &self,
+        serializer: super::serialize::Serializer,
+    ) -> Result<super::serialize::Ok, super::serialize::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field(
+            "contract_address",
+            &crate::dto::Felt(&self.0.contract_address.0),
+        )?;
+        serializer.serialize_optional(
+            "class_hash",
+            self.0.class_hash.as_ref().map(|v| crate::dto::Felt(&v.0)),
+        )?;
+        serializer.serialize_iter(
+            "storage_reads",
+            self.0.storage_reads.len(),
+            &mut self.0.storage_reads.iter().map(|v| crate::dto::Felt(&v.0)),
+        )?;
+        serializer.serialize_iter(
+            "storage_writes",
+            self.0.storage_writes.len(),
+            &mut self.0.storage_writes.iter().map(|v| crate::dto::Felt(&v.0)),
+        )?;
+        serializer.serialize_iter(
+            "nonce_reads",
+            self.0.nonce_reads.len(),
+            &mut self.0.nonce_reads.iter().map(|v| crate::dto::Felt(&v.0)),
+        )?;
+        serializer.serialize_iter(
+            "nonce_writes",
+            self.0.nonce_writes.len(),
+            &mut self.0.nonce_writes.iter().map(|v| crate::dto::Felt(&v.0)),
+        )?;
+        serializer.serialize_iter(
+            "class_hash_reads",
+            self.0.class_hash_reads.len(),
+            &mut self
+                .0
+                .class_hash_reads
+                .iter()
+                .map(|v| crate::dto::Felt(&v.0)),
+        )?;
+        serializer.serialize_iter(
+            "class_hash_writes",
+            self.0.class_hash_writes.len(),
+            &mut self
+                .0
+                .class_hash_writes
+                .iter()
+                .map(|v| crate::dto::Felt(&v.0)),
+        )?;
+        serializer.serialize_iter(
+            "calls",
+            self.0.inner_calls.len(),
+            &mut self.0.inner_calls.iter().map(CallAccess),
+        )?;
+        serializer.end()
+    }
+}
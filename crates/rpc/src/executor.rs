@@ -1,6 +1,18 @@
 use anyhow::Context;
 use pathfinder_common::transaction::TransactionVariant;
-use pathfinder_common::{ChainId, StarknetVersion};
+use pathfinder_common::{
+    BlockNumber,
+    BlockTimestamp,
+    ChainId,
+    ClassHash,
+    ContractAddress,
+    ContractNonce,
+    GasPrice,
+    SequencerAddress,
+    StarknetVersion,
+    StorageAddress,
+    StorageValue,
+};
 use pathfinder_executor::{ClassInfo, IntoStarkFelt};
 use starknet_api::core::PatriciaKey;
 
@@ -22,6 +34,164 @@ impl From<anyhow::Error> for ExecutionStateError {
     }
 }
 
+/// Overrides the state of a contract for `starknet_call`,
+/// `starknet_estimateFee` and `starknet_simulateTransactions`. This is an
+/// extension of the specification.
+#[derive(serde::Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct StateOverride {
+    pub contract_address: ContractAddress,
+    #[serde(default)]
+    pub nonce: Option<ContractNonce>,
+    /// Replaces the contract's class, which must be declared at the block
+    /// being executed on.
+    #[serde(default)]
+    pub class_hash: Option<ClassHash>,
+    #[serde(default)]
+    pub storage: Vec<StorageOverride>,
+    /// Balance of the contract in the ETH fee token.
+    #[serde(default, deserialize_with = "deserialize_balance")]
+    pub eth_balance: Option<u128>,
+    /// Balance of the contract in the STRK fee token.
+    #[serde(default, deserialize_with = "deserialize_balance")]
+    pub strk_balance: Option<u128>,
+}
+
+#[derive(serde::Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct StorageOverride {
+    pub key: StorageAddress,
+    pub value: StorageValue,
+}
+
+fn deserialize_balance<'de, D>(deserializer: D) -> Result<Option<u128>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::Deserialize;
+
+    let Some(balance) = Option::<pathfinder_crypto::Felt>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+    let (high, low) = balance.as_be_bytes().split_at(16);
+    if high.iter().any(|byte| *byte != 0) {
+        return Err(serde::de::Error::custom("Balance must fit into 128 bits"));
+    }
+
+    Ok(Some(u128::from_be_bytes(low.try_into().expect("16 bytes"))))
+}
+
+fn deserialize_sequencer_address<'de, D>(
+    deserializer: D,
+) -> Result<Option<SequencerAddress>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::Deserialize;
+
+    let Some(address) = Option::<pathfinder_crypto::Felt>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+    if address.has_more_than_251_bits() {
+        return Err(serde::de::Error::custom(
+            "Sequencer address must be less than 2^251",
+        ));
+    }
+
+    Ok(Some(SequencerAddress(address)))
+}
+
+/// Overrides the block context of `starknet_estimateFee` and
+/// `starknet_simulateTransactions`, while the state is still read from the
+/// requested block. This is an extension of the specification.
+#[serde_with::serde_as]
+#[derive(serde::Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct BlockOverrides {
+    #[serde(default)]
+    pub block_number: Option<BlockNumber>,
+    #[serde(default)]
+    pub timestamp: Option<BlockTimestamp>,
+    #[serde(default, deserialize_with = "deserialize_sequencer_address")]
+    pub sequencer_address: Option<SequencerAddress>,
+    #[serde(default)]
+    pub l1_gas_price: ResourcePriceOverride,
+    #[serde(default)]
+    pub l1_data_gas_price: ResourcePriceOverride,
+    /// Selects the versioned constants, e.g. `0.13.2`.
+    #[serde_as(as = "Option<serde_with::DisplayFromStr>")]
+    #[serde(default)]
+    pub starknet_version: Option<StarknetVersion>,
+}
+
+#[serde_with::serde_as]
+#[derive(serde::Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct ResourcePriceOverride {
+    #[serde_as(as = "Option<pathfinder_serde::GasPriceAsHexStr>")]
+    #[serde(default)]
+    pub price_in_wei: Option<GasPrice>,
+    #[serde_as(as = "Option<pathfinder_serde::GasPriceAsHexStr>")]
+    #[serde(default)]
+    pub price_in_fri: Option<GasPrice>,
+}
+
+impl From<BlockOverrides> for pathfinder_executor::BlockOverrides {
+    fn from(value: BlockOverrides) -> Self {
+        Self {
+            number: value.block_number,
+            timestamp: value.timestamp,
+            sequencer_address: value.sequencer_address,
+            eth_l1_gas_price: value.l1_gas_price.price_in_wei,
+            strk_l1_gas_price: value.l1_gas_price.price_in_fri,
+            eth_l1_data_gas_price: value.l1_data_gas_price.price_in_wei,
+            strk_l1_data_gas_price: value.l1_data_gas_price.price_in_fri,
+            starknet_version: value.starknet_version,
+        }
+    }
+}
+
+/// Collects the overrides of an RPC request. Later overrides of the same value
+/// take precedence.
+pub(crate) fn map_state_overrides(
+    overrides: Vec<StateOverride>,
+) -> pathfinder_executor::StateOverrides {
+    let mut state_overrides = pathfinder_executor::StateOverrides::default();
+
+    for contract_override in overrides {
+        if let Some(balance) = contract_override.eth_balance {
+            state_overrides.set_balance(
+                pathfinder_executor::ETH_FEE_TOKEN_ADDRESS,
+                contract_override.contract_address,
+                balance,
+            );
+        }
+        if let Some(balance) = contract_override.strk_balance {
+            state_overrides.set_balance(
+                pathfinder_executor::STRK_FEE_TOKEN_ADDRESS,
+                contract_override.contract_address,
+                balance,
+            );
+        }
+
+        let contract = state_overrides.contract(contract_override.contract_address);
+        if contract_override.nonce.is_some() {
+            contract.nonce = contract_override.nonce;
+        }
+        if contract_override.class_hash.is_some() {
+            contract.class_hash = contract_override.class_hash;
+        }
+        contract.storage.extend(
+            contract_override
+                .storage
+                .into_iter()
+                .map(|entry| (entry.key, entry.value)),
+        );
+    }
+
+    state_overrides
+}
+
 pub const VERSIONS_LOWER_THAN_THIS_SHOULD_FALL_BACK_TO_FETCHING_TRACE_FROM_GATEWAY:
     StarknetVersion = StarknetVersion::new(0, 13, 1, 1);
 
@@ -0,0 +1,816 @@
+//! A read-only subset of the feeder gateway API, served from the local
+//! database.
+//!
+//! Replies use the same JSON shapes as the Starknet feeder gateway, so that
+//! other nodes, including pathfinder itself, can sync from this node by using
+//! it as their feeder gateway.
+//!
+//! Transaction traces are produced like those of `starknet_traceTransaction`,
+//! i.e. from the trace cache or by executing the transaction's block.
+use anyhow::Context;
+use axum::extract::{Query, State};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Json;
+use http::StatusCode;
+use pathfinder_common::{
+    BlockHash,
+    BlockId,
+    BlockNumber,
+    ClassHash,
+    EthereumAddress,
+    PublicKey,
+    StarknetVersion,
+    TransactionHash,
+};
+use pathfinder_crypto::Felt;
+use pathfinder_executor::types as executor;
+use starknet_gateway_types::error::{KnownStarknetErrorCode, StarknetError};
+use starknet_gateway_types::{reply, trace};
+
+use crate::context::RpcContext;
+use crate::method::trace_transaction::TraceTransactionError;
+
+/// Blocks from this version onwards commit to their receipts and state diff.
+const V_0_13_2: StarknetVersion = StarknetVersion::new(0, 13, 2, 0);
+
+#[derive(Clone)]
+pub(crate) struct FeederGateway {
+    context: RpcContext,
+    public_key: PublicKey,
+    core_contract_address: EthereumAddress,
+}
+
+impl FeederGateway {
+    pub fn new(
+        context: RpcContext,
+        public_key: PublicKey,
+        core_contract_address: EthereumAddress,
+    ) -> Self {
+        Self {
+            context,
+            public_key,
+            core_contract_address,
+        }
+    }
+
+    pub fn router(self) -> axum::Router {
+        axum::Router::new()
+            .route("/feeder_gateway/get_block", get(get_block))
+            .route("/feeder_gateway/get_state_update", get(get_state_update))
+            .route("/feeder_gateway/get_class_by_hash", get(get_class_by_hash))
+            .route(
+                "/feeder_gateway/get_compiled_class_by_class_hash",
+                get(get_compiled_class_by_class_hash),
+            )
+            .route("/feeder_gateway/get_signature", get(get_signature))
+            .route(
+                "/feeder_gateway/get_transaction_trace",
+                get(get_transaction_trace),
+            )
+            .route("/feeder_gateway/get_public_key", get(get_public_key))
+            .route(
+                "/feeder_gateway/get_contract_addresses",
+                get(get_contract_addresses),
+            )
+            .with_state(self)
+    }
+
+    /// Runs `f` against a database transaction on a blocking thread.
+    async fn read<T, F>(&self, f: F) -> Result<T, Error>
+    where
+        T: Send + 'static,
+        F: FnOnce(&pathfinder_storage::Transaction<'_>, &RpcContext) -> Result<T, Error>
+            + Send
+            + 'static,
+    {
+        let context = self.context.clone();
+        let span = tracing::Span::current();
+
+        tokio::task::spawn_blocking(move || {
+            let _g = span.enter();
+            let mut db = context
+                .storage
+                .connection()
+                .context("Opening database connection")?;
+            let db = db.transaction().context("Creating database transaction")?;
+
+            f(&db, &context)
+        })
+        .await
+        .context("Joining blocking task")?
+    }
+}
+
+/// Query parameters of the feeder gateway, as sent by
+/// [starknet_gateway_client::Client].
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Params {
+    block_number: Option<String>,
+    block_hash: Option<String>,
+    class_hash: Option<String>,
+    header_only: Option<String>,
+    include_block: Option<String>,
+    transaction_hash: Option<String>,
+}
+
+impl Params {
+    /// Defaults to the latest block, like the feeder gateway.
+    fn block(&self) -> Result<BlockId, Error> {
+        match (&self.block_number, &self.block_hash) {
+            (Some(_), Some(_)) => Err(Error::malformed(
+                "Only one of blockNumber and blockHash may be specified",
+            )),
+            (None, Some(hash)) => parse_felt(hash, "blockHash").map(|hash| BlockHash(hash).into()),
+            (Some(number), None) => match number.as_str() {
+                "latest" => Ok(BlockId::Latest),
+                "pending" => Ok(BlockId::Pending),
+                number => number
+                    .parse()
+                    .ok()
+                    .and_then(BlockNumber::new)
+                    .map(BlockId::Number)
+                    .ok_or_else(|| Error::malformed("Invalid blockNumber")),
+            },
+            (None, None) => Ok(BlockId::Latest),
+        }
+    }
+
+    fn class_hash(&self) -> Result<ClassHash, Error> {
+        let class_hash = self
+            .class_hash
+            .as_deref()
+            .ok_or_else(|| Error::malformed("Missing classHash"))?;
+        parse_felt(class_hash, "classHash").map(ClassHash)
+    }
+
+    fn transaction_hash(&self) -> Result<TransactionHash, Error> {
+        let transaction_hash = self
+            .transaction_hash
+            .as_deref()
+            .ok_or_else(|| Error::malformed("Missing transactionHash"))?;
+        parse_felt(transaction_hash, "transactionHash").map(TransactionHash)
+    }
+
+    fn header_only(&self) -> bool {
+        self.header_only.as_deref() == Some("true")
+    }
+
+    fn include_block(&self) -> bool {
+        self.include_block.as_deref() == Some("true")
+    }
+}
+
+fn parse_felt(value: &str, name: &str) -> Result<Felt, Error> {
+    Felt::from_hex_str(value).map_err(|_| Error::malformed(format!("Invalid {name}")))
+}
+
+#[derive(Debug)]
+enum Error {
+    /// Returned to the client as a Starknet error, which the
+    /// [gateway client](starknet_gateway_client::Client) parses.
+    Starknet(StarknetError),
+    Internal(anyhow::Error),
+}
+
+impl Error {
+    fn starknet(code: KnownStarknetErrorCode, message: impl Into<String>) -> Self {
+        Self::Starknet(StarknetError {
+            code: code.into(),
+            message: message.into(),
+        })
+    }
+
+    fn malformed(message: impl Into<String>) -> Self {
+        Self::starknet(KnownStarknetErrorCode::MalformedRequest, message)
+    }
+
+    fn block_not_found() -> Self {
+        Self::starknet(KnownStarknetErrorCode::BlockNotFound, "Block not found")
+    }
+
+    fn transaction_not_found(transaction_hash: TransactionHash) -> Self {
+        Self::starknet(
+            KnownStarknetErrorCode::InvalidTransactionHash,
+            format!("Transaction with hash {transaction_hash} is not found"),
+        )
+    }
+
+    fn undeclared_class(class_hash: ClassHash) -> Self {
+        Self::starknet(
+            KnownStarknetErrorCode::UndeclaredClass,
+            format!("Class with hash {class_hash} is not declared"),
+        )
+    }
+}
+
+impl From<anyhow::Error> for Error {
+    fn from(e: anyhow::Error) -> Self {
+        Self::Internal(e)
+    }
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        match self {
+            Error::Starknet(e) => (StatusCode::BAD_REQUEST, Json(e)).into_response(),
+            Error::Internal(error) => {
+                tracing::warn!(?error, "Feeder gateway request failed");
+                (StatusCode::INTERNAL_SERVER_ERROR, "Internal error").into_response()
+            }
+        }
+    }
+}
+
+async fn get_block(
+    State(gateway): State<FeederGateway>,
+    Query(params): Query<Params>,
+) -> Result<Response, Error> {
+    #[derive(serde::Serialize)]
+    struct Header {
+        block_hash: BlockHash,
+        block_number: BlockNumber,
+    }
+
+    let block = params.block()?;
+    let header_only = params.header_only();
+
+    gateway
+        .read(move |db, context| {
+            let Ok(block) = pathfinder_storage::BlockId::try_from(block) else {
+                if header_only {
+                    // The pending block has no hash yet.
+                    return Err(Error::block_not_found());
+                }
+                let pending = context
+                    .pending_data
+                    .get(db)
+                    .context("Querying pending data")?;
+                return Ok(Json(pending.block.as_ref()).into_response());
+            };
+
+            if header_only {
+                let (block_number, block_hash) = db
+                    .block_id(block)
+                    .context("Querying block id")?
+                    .ok_or_else(Error::block_not_found)?;
+                return Ok(Json(Header {
+                    block_hash,
+                    block_number,
+                })
+                .into_response());
+            }
+
+            let block = stored_block(db, block)?.ok_or_else(Error::block_not_found)?;
+            Ok(Json(block).into_response())
+        })
+        .await
+}
+
+async fn get_state_update(
+    State(gateway): State<FeederGateway>,
+    Query(params): Query<Params>,
+) -> Result<Response, Error> {
+    #[derive(serde::Serialize)]
+    struct WithBlock<B> {
+        block: B,
+        state_update: reply::StateUpdate,
+    }
+
+    let block = params.block()?;
+    let include_block = params.include_block();
+
+    gateway
+        .read(move |db, context| {
+            let Ok(block) = pathfinder_storage::BlockId::try_from(block) else {
+                let pending = context
+                    .pending_data
+                    .get(db)
+                    .context("Querying pending data")?;
+                let state_update = reply::StateUpdate::from((*pending.state_update).clone());
+
+                return Ok(if include_block {
+                    Json(WithBlock {
+                        block: pending.block.as_ref(),
+                        state_update,
+                    })
+                    .into_response()
+                } else {
+                    Json(state_update).into_response()
+                });
+            };
+
+            let state_update = db
+                .state_update(block)
+                .context("Querying state update")?
+                .ok_or_else(Error::block_not_found)?;
+            let state_update = reply::StateUpdate::from(state_update);
+
+            if include_block {
+                // Use the block hash to remain consistent with the state update.
+                let block = stored_block(db, state_update.block_hash.into())?
+                    .ok_or_else(Error::block_not_found)?;
+                Ok(Json(WithBlock {
+                    block,
+                    state_update,
+                })
+                .into_response())
+            } else {
+                Ok(Json(state_update).into_response())
+            }
+        })
+        .await
+}
+
+/// Class definitions are served regardless of the requested block, pending
+/// classes are part of the database as well.
+async fn get_class_by_hash(
+    State(gateway): State<FeederGateway>,
+    Query(params): Query<Params>,
+) -> Result<Response, Error> {
+    let class_hash = params.class_hash()?;
+
+    gateway
+        .read(move |db, _| {
+            let definition = db
+                .class_definition(class_hash)
+                .context("Querying class definition")?
+                .ok_or_else(|| Error::undeclared_class(class_hash))?;
+            Ok(raw_json(definition))
+        })
+        .await
+}
+
+async fn get_compiled_class_by_class_hash(
+    State(gateway): State<FeederGateway>,
+    Query(params): Query<Params>,
+) -> Result<Response, Error> {
+    let class_hash = params.class_hash()?;
+
+    gateway
+        .read(move |db, _| {
+            let definition = db
+                .casm_definition(class_hash)
+                .context("Querying CASM definition")?
+                .ok_or_else(|| Error::undeclared_class(class_hash))?;
+            Ok(raw_json(definition))
+        })
+        .await
+}
+
+async fn get_signature(
+    State(gateway): State<FeederGateway>,
+    Query(params): Query<Params>,
+) -> Result<Response, Error> {
+    let block = pathfinder_storage::BlockId::try_from(params.block()?)
+        .map_err(|_| Error::block_not_found())?;
+
+    gateway
+        .read(move |db, _| {
+            let (_, block_hash) = db
+                .block_id(block)
+                .context("Querying block id")?
+                .ok_or_else(Error::block_not_found)?;
+            let signature = db
+                .signature(block_hash.into())
+                .context("Querying block signature")?
+                .ok_or_else(Error::block_not_found)?;
+
+            Ok(Json(reply::BlockSignature::V1(reply::BlockSignatureV1 {
+                block_hash,
+                signature: [signature.r, signature.s],
+            }))
+            .into_response())
+        })
+        .await
+}
+
+async fn get_transaction_trace(
+    State(gateway): State<FeederGateway>,
+    Query(params): Query<Params>,
+) -> Result<Response, Error> {
+    let transaction_hash = params.transaction_hash()?;
+
+    let (trace, _) =
+        crate::method::trace_transaction::trace(gateway.context.clone(), transaction_hash)
+            .await
+            .map_err(|e| match e {
+                TraceTransactionError::TxnHashNotFound => {
+                    Error::transaction_not_found(transaction_hash)
+                }
+                TraceTransactionError::Internal(e) | TraceTransactionError::Custom(e) => {
+                    Error::Internal(e)
+                }
+                e => Error::Internal(anyhow::anyhow!("Tracing transaction: {e:?}")),
+            })?;
+
+    let transaction = gateway
+        .read(move |db, context| {
+            let pending = context
+                .pending_data
+                .get(db)
+                .context("Querying pending data")?;
+            if let Some(transaction) = pending
+                .block
+                .transactions
+                .iter()
+                .find(|tx| tx.hash == transaction_hash)
+            {
+                return Ok(transaction.clone());
+            }
+
+            db.transaction(transaction_hash)
+                .context("Querying transaction")?
+                .ok_or_else(|| Error::transaction_not_found(transaction_hash))
+        })
+        .await?;
+
+    Ok(Json(gateway_trace(trace, signature(&transaction))).into_response())
+}
+
+async fn get_public_key(State(gateway): State<FeederGateway>) -> Json<PublicKey> {
+    Json(gateway.public_key)
+}
+
+async fn get_contract_addresses(
+    State(gateway): State<FeederGateway>,
+) -> Json<reply::EthContractAddresses> {
+    Json(reply::EthContractAddresses {
+        starknet: gateway.core_contract_address,
+    })
+}
+
+/// Class definitions are stored as the JSON served by the feeder gateway.
+fn raw_json(json: Vec<u8>) -> Response {
+    ([(http::header::CONTENT_TYPE, "application/json")], json).into_response()
+}
+
+fn signature(transaction: &pathfinder_common::transaction::Transaction) -> Vec<Felt> {
+    use pathfinder_common::transaction::TransactionVariant;
+
+    let signature = match &transaction.variant {
+        TransactionVariant::DeclareV0(tx) | TransactionVariant::DeclareV1(tx) => &tx.signature,
+        TransactionVariant::DeclareV2(tx) => &tx.signature,
+        TransactionVariant::DeclareV3(tx) => &tx.signature,
+        TransactionVariant::DeployAccountV1(tx) => &tx.signature,
+        TransactionVariant::DeployAccountV3(tx) => &tx.signature,
+        TransactionVariant::InvokeV0(tx) => &tx.signature,
+        TransactionVariant::InvokeV1(tx) => &tx.signature,
+        TransactionVariant::InvokeV3(tx) => &tx.signature,
+        TransactionVariant::DeployV0(_)
+        | TransactionVariant::DeployV1(_)
+        | TransactionVariant::L1Handler(_) => return Vec::new(),
+    };
+
+    signature.iter().map(|elem| elem.0).collect()
+}
+
+/// The inverse of
+/// [map_gateway_trace](crate::method::trace_block_transactions::map_gateway_trace).
+fn gateway_trace(
+    trace: executor::TransactionTrace,
+    signature: Vec<Felt>,
+) -> trace::TransactionTrace {
+    let (validate_invocation, function_invocation, fee_transfer_invocation, revert_error) =
+        match trace {
+            executor::TransactionTrace::Declare(trace) => (
+                trace.validate_invocation,
+                None,
+                trace.fee_transfer_invocation,
+                None,
+            ),
+            executor::TransactionTrace::DeployAccount(trace) => (
+                trace.validate_invocation,
+                trace.constructor_invocation,
+                trace.fee_transfer_invocation,
+                None,
+            ),
+            executor::TransactionTrace::Invoke(trace) => {
+                let (function_invocation, revert_error) = match trace.execute_invocation {
+                    executor::ExecuteInvocation::FunctionInvocation(invocation) => {
+                        (invocation, None)
+                    }
+                    executor::ExecuteInvocation::RevertedReason(reason) => (None, Some(reason)),
+                };
+                (
+                    trace.validate_invocation,
+                    function_invocation,
+                    trace.fee_transfer_invocation,
+                    revert_error,
+                )
+            }
+            executor::TransactionTrace::L1Handler(trace) => {
+                (None, trace.function_invocation, None, None)
+            }
+        };
+
+    trace::TransactionTrace {
+        revert_error,
+        validate_invocation: validate_invocation.map(gateway_function_invocation),
+        function_invocation: function_invocation.map(gateway_function_invocation),
+        fee_transfer_invocation: fee_transfer_invocation.map(gateway_function_invocation),
+        signature,
+        transaction_hash: None,
+    }
+}
+
+fn gateway_function_invocation(
+    invocation: executor::FunctionInvocation,
+) -> trace::FunctionInvocation {
+    let resources = invocation.computation_resources;
+
+    trace::FunctionInvocation {
+        calldata: invocation.calldata,
+        contract_address: invocation.contract_address,
+        selector: Some(invocation.selector),
+        call_type: Some(match invocation.call_type {
+            executor::CallType::Call => trace::CallType::Call,
+            executor::CallType::Delegate => trace::CallType::Delegate,
+        }),
+        caller_address: invocation.caller_address,
+        internal_calls: invocation
+            .internal_calls
+            .into_iter()
+            .map(gateway_function_invocation)
+            .collect(),
+        class_hash: invocation.class_hash,
+        entry_point_type: Some(match invocation.entry_point_type {
+            executor::EntryPointType::Constructor => trace::EntryPointType::Constructor,
+            executor::EntryPointType::External => trace::EntryPointType::External,
+            executor::EntryPointType::L1Handler => trace::EntryPointType::L1Handler,
+        }),
+        events: invocation
+            .events
+            .into_iter()
+            .map(|event| trace::Event {
+                order: event.order,
+                data: event.data,
+                keys: event.keys,
+            })
+            .collect(),
+        messages: invocation
+            .messages
+            .into_iter()
+            .map(|message| trace::MsgToL1 {
+                order: message.order,
+                payload: message.payload,
+                to_address: message.to_address,
+            })
+            .collect(),
+        result: invocation.result,
+        execution_resources: reply::transaction::ExecutionResources {
+            builtin_instance_counter: reply::transaction::BuiltinCounters {
+                pedersen_builtin: resources.pedersen_builtin_applications as u64,
+                range_check_builtin: resources.range_check_builtin_applications as u64,
+                ecdsa_builtin: resources.ecdsa_builtin_applications as u64,
+                bitwise_builtin: resources.bitwise_builtin_applications as u64,
+                ec_op_builtin: resources.ec_op_builtin_applications as u64,
+                keccak_builtin: resources.keccak_builtin_applications as u64,
+                poseidon_builtin: resources.poseidon_builtin_applications as u64,
+                segment_arena_builtin: resources.segment_arena_builtin as u64,
+                ..Default::default()
+            },
+            n_steps: resources.steps as u64,
+            n_memory_holes: resources.memory_holes as u64,
+            data_availability: None,
+            total_gas_consumed: None,
+        },
+    }
+}
+
+fn stored_block(
+    db: &pathfinder_storage::Transaction<'_>,
+    block: pathfinder_storage::BlockId,
+) -> Result<Option<reply::Block>, Error> {
+    let Some(header) = db.block_header(block).context("Querying block header")? else {
+        return Ok(None);
+    };
+    let block = header.number.into();
+    let data = db
+        .transaction_data_for_block(block)
+        .context("Querying transactions")?
+        .context("Transaction data missing")?;
+    let status = if db
+        .block_is_l1_accepted(block)
+        .context("Querying L1 status")?
+    {
+        reply::Status::AcceptedOnL1
+    } else {
+        reply::Status::AcceptedOnL2
+    };
+
+    let (transactions, transaction_receipts) = data
+        .into_iter()
+        .map(|(transaction, receipt, events)| (transaction, (receipt, events)))
+        .unzip();
+
+    let v0_13_2 = header.starknet_version >= V_0_13_2;
+
+    Ok(Some(reply::Block {
+        block_hash: header.hash,
+        block_number: header.number,
+        l1_gas_price: reply::GasPrices {
+            price_in_wei: header.eth_l1_gas_price,
+            price_in_fri: header.strk_l1_gas_price,
+        },
+        l1_data_gas_price: reply::GasPrices {
+            price_in_wei: header.eth_l1_data_gas_price,
+            price_in_fri: header.strk_l1_data_gas_price,
+        },
+        parent_block_hash: header.parent_hash,
+        sequencer_address: Some(header.sequencer_address),
+        state_commitment: header.state_commitment,
+        status,
+        timestamp: header.timestamp,
+        transaction_receipts,
+        transactions,
+        starknet_version: header.starknet_version,
+        transaction_commitment: header.transaction_commitment,
+        event_commitment: header.event_commitment,
+        l1_da_mode: header.l1_da_mode.into(),
+        receipt_commitment: v0_13_2.then_some(header.receipt_commitment),
+        state_diff_commitment: v0_13_2.then_some(header.state_diff_commitment),
+        state_diff_length: v0_13_2.then_some(header.state_diff_length),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use assert_matches::assert_matches;
+    use pathfinder_common::felt;
+    use pathfinder_common::macro_prelude::*;
+    use primitive_types::H160;
+    use starknet_gateway_client::{Client, GatewayApi};
+    use starknet_gateway_types::error::SequencerError;
+
+    use super::*;
+    use crate::{RpcServer, RpcVersion};
+
+    async fn client(context: RpcContext) -> Client {
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let (_jh, addr) = RpcServer::new(addr, context, RpcVersion::V07)
+            .with_feeder_gateway(
+                PublicKey(felt!("0x1234")),
+                EthereumAddress(H160::from_low_u64_be(0xabcd)),
+            )
+            .spawn()
+            .await
+            .unwrap();
+
+        Client::with_base_url(
+            format!("http://{addr}/").parse().unwrap(),
+            gateway_test_utils::GATEWAY_TIMEOUT,
+        )
+        .unwrap()
+        .disable_retry_for_tests()
+    }
+
+    #[tokio::test]
+    async fn block_header() {
+        let client = client(RpcContext::for_tests()).await;
+
+        let latest = client.block_header(BlockId::Latest).await.unwrap();
+        assert_eq!(
+            latest,
+            (BlockNumber::new_or_panic(2), block_hash_bytes!(b"latest"))
+        );
+
+        let by_hash = client
+            .block_header(block_hash_bytes!(b"block 1").into())
+            .await
+            .unwrap();
+        assert_eq!(
+            by_hash,
+            (BlockNumber::new_or_panic(1), block_hash_bytes!(b"block 1"))
+        );
+    }
+
+    #[tokio::test]
+    async fn block_not_found() {
+        let client = client(RpcContext::for_tests()).await;
+
+        let error = client
+            .block_header(BlockNumber::new_or_panic(10).into())
+            .await
+            .unwrap_err();
+        assert_matches!(
+            error,
+            SequencerError::StarknetError(e) => assert_eq!(e.code, KnownStarknetErrorCode::BlockNotFound.into())
+        );
+    }
+
+    #[tokio::test]
+    async fn state_update_with_block() {
+        let context = RpcContext::for_tests();
+        let expected = {
+            let mut db = context.storage.connection().unwrap();
+            let db = db.transaction().unwrap();
+            db.state_update(BlockNumber::new_or_panic(1).into())
+                .unwrap()
+                .unwrap()
+        };
+        let client = client(context).await;
+
+        let (block, state_update) = client
+            .state_update_with_block(BlockNumber::new_or_panic(1))
+            .await
+            .unwrap();
+
+        assert_eq!(block.block_hash, block_hash_bytes!(b"block 1"));
+        assert_eq!(block.status, reply::Status::AcceptedOnL2);
+        assert_eq!(state_update, expected);
+    }
+
+    #[tokio::test]
+    async fn pending_block() {
+        let context = RpcContext::for_tests_with_pending().await;
+        let expected = {
+            let mut db = context.storage.connection().unwrap();
+            let db = db.transaction().unwrap();
+            context.pending_data.get(&db).unwrap()
+        };
+        let client = client(context).await;
+
+        let (block, state_update) = client.pending_block().await.unwrap();
+
+        assert_eq!(block, *expected.block);
+        assert_eq!(state_update, *expected.state_update);
+    }
+
+    #[tokio::test]
+    async fn class_definition() {
+        let client = client(RpcContext::for_tests()).await;
+
+        let definition = client
+            .pending_class_by_hash(class_hash_bytes!(b"class 0 hash"))
+            .await
+            .unwrap();
+        assert_eq!(
+            definition.as_ref(),
+            starknet_gateway_test_fixtures::class_definitions::CONTRACT_DEFINITION
+        );
+
+        let error = client
+            .pending_class_by_hash(class_hash_bytes!(b"undeclared"))
+            .await
+            .unwrap_err();
+        assert_matches!(
+            error,
+            SequencerError::StarknetError(e) => assert_eq!(e.code, KnownStarknetErrorCode::UndeclaredClass.into())
+        );
+    }
+
+    #[tokio::test]
+    async fn transaction_trace() {
+        let (context, _, traces) =
+            crate::method::trace_block_transactions::tests::setup_multi_tx_trace_test()
+                .await
+                .unwrap();
+        let client = client(context.clone()).await;
+
+        for expected in traces {
+            let hash = expected.transaction_hash;
+            let (trace, _) = crate::method::trace_transaction::trace(context.clone(), hash)
+                .await
+                .unwrap();
+            let transaction = {
+                let mut db = context.storage.connection().unwrap();
+                let db = db.transaction().unwrap();
+                db.transaction(hash).unwrap().unwrap()
+            };
+            let expected = gateway_trace(trace, signature(&transaction));
+
+            let trace = client.transaction_trace(hash).await.unwrap();
+            assert_eq!(
+                serde_json::to_value(trace).unwrap(),
+                serde_json::to_value(expected).unwrap()
+            );
+        }
+
+        let error = client
+            .transaction_trace(transaction_hash_bytes!(b"unknown"))
+            .await
+            .unwrap_err();
+        assert_matches!(
+            error,
+            SequencerError::StarknetError(e) => assert_eq!(e.code, KnownStarknetErrorCode::InvalidTransactionHash.into())
+        );
+    }
+
+    #[tokio::test]
+    async fn public_key_and_contract_addresses() {
+        let client = client(RpcContext::for_tests()).await;
+
+        assert_eq!(
+            client.public_key().await.unwrap(),
+            PublicKey(felt!("0x1234"))
+        );
+        assert_eq!(
+            client.eth_contract_addresses().await.unwrap().starknet,
+            EthereumAddress(H160::from_low_u64_be(0xabcd))
+        );
+    }
+}
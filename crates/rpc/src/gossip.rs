@@ -0,0 +1,179 @@
+//! Propagation of transactions submitted via the RPC API to p2p peers.
+
+use pathfinder_common::transaction::{Transaction, TransactionVariant};
+use pathfinder_common::ClassHash;
+use starknet_gateway_types::error::SequencerError;
+use tokio::sync::mpsc;
+
+use crate::context::RpcContext;
+use crate::v02::types::request::{
+    BroadcastedDeclareTransaction,
+    BroadcastedDeployAccountTransaction,
+    BroadcastedInvokeTransaction,
+    BroadcastedTransaction,
+};
+
+/// Hands submitted transactions over to the p2p network for propagation.
+pub type TransactionGossipSender = mpsc::Sender<BroadcastedTransaction>;
+
+/// Propagates a submitted transaction to p2p peers, based on the gateway's
+/// response to the submission.
+///
+/// Transactions accepted by the gateway are propagated as is, while those it
+/// rejected are not propagated at all. If the gateway could not be reached the
+/// transaction is still propagated, and `fallback` creates the response from
+/// the locally computed transaction. This keeps peers working as a submission
+/// path while the gateway is degraded.
+pub(crate) fn gossip_submitted<T>(
+    context: &RpcContext,
+    transaction: BroadcastedTransaction,
+    gateway_response: Result<T, SequencerError>,
+    fallback: impl FnOnce(&Transaction) -> T,
+) -> Result<T, SequencerError> {
+    let Some(sender) = &context.transaction_gossip else {
+        return gateway_response;
+    };
+
+    if !is_gossiped(&transaction) {
+        return gateway_response;
+    }
+
+    match gateway_response {
+        Ok(response) => {
+            if let Err(error) = sender.try_send(transaction) {
+                tracing::debug!(%error, "Failed to queue transaction for propagation");
+            }
+            Ok(response)
+        }
+        Err(SequencerError::ReqwestError(gateway_error)) => {
+            let common = transaction.clone().into_common(context.chain_id);
+
+            match sender.try_send(transaction) {
+                Ok(()) => {
+                    tracing::warn!(
+                        transaction_hash=%common.hash, error=%gateway_error,
+                        "Gateway unreachable, transaction was only propagated to peers"
+                    );
+                    Ok(fallback(&common))
+                }
+                Err(error) => {
+                    tracing::debug!(%error, "Failed to queue transaction for propagation");
+                    Err(SequencerError::ReqwestError(gateway_error))
+                }
+            }
+        }
+        Err(error) => Err(error),
+    }
+}
+
+/// The class hash of a locally computed declare transaction.
+pub(crate) fn declared_class_hash(transaction: &Transaction) -> ClassHash {
+    match &transaction.variant {
+        TransactionVariant::DeclareV0(tx) | TransactionVariant::DeclareV1(tx) => tx.class_hash,
+        TransactionVariant::DeclareV2(tx) => tx.class_hash,
+        TransactionVariant::DeclareV3(tx) => tx.class_hash,
+        _ => unreachable!("Only declare transactions declare a class"),
+    }
+}
+
+/// Only the transaction versions still accepted by the sequencer are
+/// propagated. Query-only transactions are never propagated.
+fn is_gossiped(transaction: &BroadcastedTransaction) -> bool {
+    if transaction.version().has_query_version() {
+        return false;
+    }
+
+    match transaction {
+        BroadcastedTransaction::Declare(BroadcastedDeclareTransaction::V2(tx)) => {
+            tx.contract_class.class_hash().is_ok()
+        }
+        BroadcastedTransaction::Declare(BroadcastedDeclareTransaction::V3(tx)) => {
+            tx.contract_class.class_hash().is_ok()
+        }
+        BroadcastedTransaction::DeployAccount(BroadcastedDeployAccountTransaction::V1(tx)) => {
+            // Version 0 deploy account transactions share the same representation.
+            tx.version.without_query_version() == 1
+        }
+        BroadcastedTransaction::DeployAccount(BroadcastedDeployAccountTransaction::V3(_))
+        | BroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V1(_))
+        | BroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V3(_)) => true,
+        BroadcastedTransaction::Declare(_)
+        | BroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V0(_)) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::TransactionVersion;
+
+    use super::*;
+    use crate::v02::types::request::BroadcastedInvokeTransactionV1;
+
+    fn invoke(version: TransactionVersion) -> BroadcastedTransaction {
+        BroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V1(
+            BroadcastedInvokeTransactionV1 {
+                version,
+                max_fee: fee!("0x1000"),
+                signature: vec![],
+                nonce: transaction_nonce!("0x1"),
+                sender_address: contract_address!("0x123"),
+                calldata: vec![],
+            },
+        ))
+    }
+
+    fn gateway_error() -> SequencerError {
+        SequencerError::StarknetError(starknet_gateway_types::error::StarknetError {
+            code: starknet_gateway_types::error::KnownStarknetErrorCode::InvalidTransactionNonce
+                .into(),
+            message: String::new(),
+        })
+    }
+
+    #[tokio::test]
+    async fn accepted_transactions_are_propagated() {
+        let (sender, mut receiver) = mpsc::channel(1);
+        let context = RpcContext::for_tests().with_transaction_gossip(sender);
+
+        let response = gossip_submitted(&context, invoke(TransactionVersion::ONE), Ok(()), |_| ());
+
+        assert!(response.is_ok());
+        assert_eq!(
+            receiver.try_recv().unwrap(),
+            invoke(TransactionVersion::ONE)
+        );
+    }
+
+    #[tokio::test]
+    async fn rejected_transactions_are_not_propagated() {
+        let (sender, mut receiver) = mpsc::channel(1);
+        let context = RpcContext::for_tests().with_transaction_gossip(sender);
+
+        let response = gossip_submitted(
+            &context,
+            invoke(TransactionVersion::ONE),
+            Err::<(), _>(gateway_error()),
+            |_| (),
+        );
+
+        assert!(response.is_err());
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn query_transactions_are_not_propagated() {
+        let (sender, mut receiver) = mpsc::channel(1);
+        let context = RpcContext::for_tests().with_transaction_gossip(sender);
+
+        let response = gossip_submitted(
+            &context,
+            invoke(TransactionVersion::ONE_WITH_QUERY_VERSION),
+            Ok(()),
+            |_| (),
+        );
+
+        assert!(response.is_ok());
+        assert!(receiver.try_recv().is_err());
+    }
+}
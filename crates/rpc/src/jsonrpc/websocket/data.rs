@@ -2,7 +2,7 @@
 
 use std::sync::Arc;
 
-use pathfinder_common::{EventKey, TransactionHash};
+use pathfinder_common::{BlockHash, BlockNumber, EventKey, TransactionHash};
 use serde::ser::Error;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -15,11 +15,21 @@ use crate::method::get_events::types::EmittedEvent;
 #[serde(tag = "kind")]
 pub(super) enum Params {
     #[serde(rename = "newHeads")]
-    NewHeads,
+    NewHeads(NewHeadsParams),
     #[serde(rename = "events")]
     Events(EventFilterParams),
     #[serde(rename = "transactionStatus")]
     TransactionStatus(TransactionStatusParams),
+    #[serde(rename = "l1Accepted")]
+    L1Accepted(L1AcceptedParams),
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub(super) struct NewHeadsParams {
+    /// Replay the headers of stored blocks starting at this block before
+    /// streaming new ones.
+    #[serde(default)]
+    pub(super) from_block: Option<BlockNumber>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -28,6 +38,10 @@ pub(super) struct EventFilterParams {
     pub(super) address: Option<pathfinder_common::ContractAddress>,
     #[serde(default)]
     pub(super) keys: Vec<Vec<EventKey>>,
+    /// Replay the events of stored blocks starting at this block before
+    /// streaming new ones.
+    #[serde(default)]
+    pub(super) from_block: Option<BlockNumber>,
 }
 
 #[derive(Debug, serde::Deserialize, Serialize)]
@@ -35,6 +49,13 @@ pub(super) struct TransactionStatusParams {
     pub(super) transaction_hash: TransactionHash,
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+pub(super) struct L1AcceptedParams {
+    /// Transactions to notify about once they are accepted on L1.
+    #[serde(default)]
+    pub(super) transaction_hashes: Vec<TransactionHash>,
+}
+
 #[derive(Deserialize, Serialize)]
 pub(super) struct SubscriptionId {
     pub(super) id: u32,
@@ -93,6 +114,8 @@ pub(super) enum ResponseEvent {
     Responses(RpcResponses),
     Event(SubscriptionItem<Arc<EmittedEvent>>),
     TransactionStatus(SubscriptionItem<Arc<TransactionStatusUpdate>>),
+    L1Accepted(SubscriptionItem<Arc<L1AcceptedUpdate>>),
+    Reorg(SubscriptionItem<Arc<Reorg>>),
     RpcError(RpcError),
 }
 
@@ -105,6 +128,35 @@ pub enum TransactionStatusUpdate {
     Reverted = 3,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum L1AcceptedUpdate {
+    Block {
+        block_number: BlockNumber,
+        block_hash: BlockHash,
+    },
+    Transaction {
+        transaction_hash: TransactionHash,
+        block_number: BlockNumber,
+        block_hash: BlockHash,
+    },
+}
+
+/// Notifies a subscriber that blocks it was sent are no longer part of the
+/// canonical chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename = "reorg")]
+pub struct Reorg {
+    /// The first block which was invalidated.
+    pub first_block_number: BlockNumber,
+    pub first_block_hash: BlockHash,
+    /// The last block which is still valid, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_head_number: Option<BlockNumber>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_head_hash: Option<BlockHash>,
+}
+
 impl ResponseEvent {
     pub(super) fn kind(&self) -> &'static str {
         match self {
@@ -117,6 +169,8 @@ impl ResponseEvent {
             ResponseEvent::Responses(_) => "Responses",
             ResponseEvent::Event(_) => "Event",
             ResponseEvent::TransactionStatus(_) => "TransactionStatus",
+            ResponseEvent::L1Accepted(_) => "L1Accepted",
+            ResponseEvent::Reorg(_) => "Reorg",
             ResponseEvent::InternalError(_, _) => "InternalError",
             ResponseEvent::RpcError(_) => "RpcError",
         }
@@ -165,6 +219,8 @@ impl Serialize for ResponseEvent {
             .serialize(serializer),
             ResponseEvent::Responses(responses) => responses.serialize(serializer),
             ResponseEvent::TransactionStatus(status) => status.serialize(serializer),
+            ResponseEvent::L1Accepted(update) => update.serialize(serializer),
+            ResponseEvent::Reorg(reorg) => reorg.serialize(serializer),
             ResponseEvent::RpcError(error) => error.serialize(serializer),
         }
     }
@@ -1,20 +1,22 @@
 //! See [the parent module documentation](super)
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::marker::PhantomData;
 use std::num::NonZeroUsize;
-use std::ops::ControlFlow;
+use std::ops::{ControlFlow, RangeInclusive};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+use anyhow::Context;
 use axum::extract::ws::{Message, WebSocket};
 use axum::extract::{State, WebSocketUpgrade};
 use axum::response::IntoResponse;
 use futures::sink::Buffer;
 use futures::stream::{SplitSink, SplitStream};
 use futures::{SinkExt, StreamExt};
-use pathfinder_common::{BlockNumber, TransactionHash};
-use serde::Serialize;
+use pathfinder_common::{BlockHash, BlockId, BlockNumber, TransactionHash};
+use pathfinder_storage::Storage;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use starknet_gateway_client::GatewayApi;
 use starknet_gateway_types::reply::transaction_status::{ExecutionStatus, FinalityStatus};
@@ -23,7 +25,8 @@ use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::{broadcast, mpsc, watch};
 use tracing::error;
 
-use super::{Params, TransactionStatusUpdate};
+use super::{L1AcceptedUpdate, Params, Reorg, TransactionStatusUpdate};
+use crate::context::RpcContext;
 use crate::error::ApplicationError;
 use crate::jsonrpc::request::RawParams;
 use crate::jsonrpc::router::RpcRequestError;
@@ -39,6 +42,9 @@ use crate::{BlockHeader, PendingData};
 
 const SUBSCRIBE_METHOD: &str = "pathfinder_subscribe";
 const UNSUBSCRIBE_METHOD: &str = "pathfinder_unsubscribe";
+/// The maximum number of transactions a single `l1Accepted` subscription can
+/// watch.
+const L1_ACCEPTED_TRANSACTION_LIMIT: usize = 100;
 
 #[derive(Clone)]
 pub struct WebsocketContext {
@@ -185,7 +191,7 @@ async fn read(
                 parsed_request.params,
                 response_sender.clone(),
                 source.clone(),
-                router.context.sequencer.clone(),
+                &router.context,
             ) {
                 Ok(resp) => resp,
                 Err(e) => {
@@ -266,7 +272,7 @@ impl SubscriptionManager {
         request_params: RawParams<'_>,
         response_sender: mpsc::Sender<ResponseEvent>,
         websocket_source: TopicBroadcasters,
-        gateway: impl GatewayApi + Send + 'static,
+        context: &RpcContext,
     ) -> anyhow::Result<ResponseEvent> {
         let params = match request_params.deserialize::<Params>() {
             Ok(x) => x,
@@ -281,34 +287,87 @@ impl SubscriptionManager {
             }
         };
 
+        if let Params::L1Accepted(params) = &params {
+            if params.transaction_hashes.len() > L1_ACCEPTED_TRANSACTION_LIMIT {
+                return Ok(ResponseEvent::InvalidParams(
+                    request_id,
+                    format!(
+                        "Too many transaction hashes: {} exceeds the limit of \
+                         {L1_ACCEPTED_TRANSACTION_LIMIT}",
+                        params.transaction_hashes.len()
+                    ),
+                ));
+            }
+        }
+
         let subscription_id = self.next_id;
         self.next_id += 1;
         let handle = match params {
-            Params::NewHeads => {
+            Params::NewHeads(params) => {
+                // Subscribe before replaying so that no live headers are missed.
                 let receiver = websocket_source.new_head.subscribe();
+                let reorgs = websocket_source.reorgs.subscribe();
+                let replay = params.from_block.map(|from_block| Replay {
+                    storage: context.storage.clone(),
+                    from_block,
+                    kind: ReplayKind::Headers,
+                });
                 tokio::spawn(header_subscription(
                     response_sender,
                     receiver,
+                    reorgs,
                     subscription_id,
+                    replay,
                 ))
             }
             Params::Events(filter) => {
                 let l2_blocks = websocket_source.l2_blocks.subscribe();
                 let pending_data = websocket_source.pending_data.clone();
+                let reorgs = websocket_source.reorgs.subscribe();
+                let filter = Arc::new(filter);
+                let replay = filter.from_block.map(|from_block| Replay {
+                    storage: context.storage.clone(),
+                    from_block,
+                    kind: ReplayKind::Events {
+                        filter: filter.clone(),
+                        max_blocks_to_scan: context.config.get_events_max_blocks_to_scan,
+                        max_uncached_bloom_filters_to_load: context
+                            .config
+                            .get_events_max_uncached_bloom_filters_to_load,
+                    },
+                });
                 tokio::spawn(event_subscription(
                     response_sender,
                     l2_blocks,
                     pending_data,
+                    reorgs,
                     subscription_id,
                     filter,
+                    replay,
+                ))
+            }
+            Params::TransactionStatus(params) => {
+                let reorgs = websocket_source.reorgs.subscribe();
+                tokio::spawn(transaction_status_subscription(
+                    response_sender,
+                    reorgs,
+                    subscription_id,
+                    params.transaction_hash,
+                    context.sequencer.clone(),
+                ))
+            }
+            Params::L1Accepted(params) => {
+                let receiver = websocket_source.l1_accepted.subscribe();
+                let reorgs = websocket_source.reorgs.subscribe();
+                tokio::spawn(l1_accepted_subscription(
+                    response_sender,
+                    receiver,
+                    reorgs,
+                    context.storage.clone(),
+                    subscription_id,
+                    params.transaction_hashes,
                 ))
             }
-            Params::TransactionStatus(params) => tokio::spawn(transaction_status_subscription(
-                response_sender,
-                subscription_id,
-                params.transaction_hash,
-                gateway,
-            )),
         };
 
         self.subscriptions.insert(subscription_id, handle);
@@ -329,25 +388,91 @@ impl SubscriptionManager {
 async fn header_subscription(
     msg_sender: mpsc::Sender<ResponseEvent>,
     mut headers: broadcast::Receiver<Arc<Value>>,
+    mut reorgs: broadcast::Receiver<Reorg>,
     subscription_id: u32,
+    replay: Option<Replay>,
 ) {
+    let mut replayed = match replay {
+        Some(replay) => match replay.run(&msg_sender, subscription_id).await {
+            Some(replayed) => Some(replayed),
+            None => return,
+        },
+        None => None,
+    };
+    let mut invalidated = InvalidatedBlocks::default();
+
     loop {
-        let response = match headers.recv().await {
-            Ok(header) => ResponseEvent::Header(SubscriptionItem {
-                subscription_id,
-                item: header,
-            }),
-            Err(RecvError::Closed) => break,
-            Err(RecvError::Lagged(amount)) => {
-                tracing::debug!(%subscription_id, %amount, kind="header", "Subscription consumer too slow, closing.");
-
-                // No explicit break here, the loop will be broken by the dropped receiver.
-                ResponseEvent::SubscriptionClosed {
-                    subscription_id,
-                    reason: "Lagging stream, some headers were skipped. Closing subscription."
-                        .to_owned(),
+        // Reorgs are polled first so that they are never sent after blocks of the new
+        // chain. Invalidated blocks still queued up are skipped instead.
+        let response = tokio::select! {
+            biased;
+            reorg = reorgs.recv() => match reorg {
+                Ok(reorg) => match handle_reorg(reorg, &mut invalidated, replayed.as_mut()) {
+                    Some(reorg) => ResponseEvent::Reorg(SubscriptionItem {
+                        subscription_id,
+                        item: Arc::new(reorg),
+                    }),
+                    None => continue,
+                },
+                Err(RecvError::Closed) => break,
+                Err(RecvError::Lagged(amount)) => {
+                    tracing::debug!(%subscription_id, %amount, kind="header", "Subscription consumer too slow, closing.");
+                    let response = ResponseEvent::SubscriptionClosed {
+                        subscription_id,
+                        reason: "Lagging stream, some reorgs were skipped. Closing subscription."
+                            .to_owned(),
+                    };
+                    msg_sender.send(response).await.ok();
+                    break;
                 }
-            }
+            },
+            header = headers.recv() => match header {
+                Ok(header) => {
+                    let block_id = header_block_id(&header);
+                    if let Some((_, hash, parent_hash)) = block_id {
+                        if invalidated.contains(hash, parent_hash) {
+                            continue;
+                        }
+                    }
+
+                    if let Some(replayed_blocks) = replayed.as_mut() {
+                        match block_id
+                            .map(|(number, hash, parent_hash)| {
+                                replayed_blocks.handover(number, hash, parent_hash)
+                            })
+                            .unwrap_or(Handover::Live)
+                        {
+                            Handover::Skip => continue,
+                            Handover::Live => replayed = None,
+                            Handover::Reorg(reorg) => {
+                                let response = ResponseEvent::Reorg(SubscriptionItem {
+                                    subscription_id,
+                                    item: Arc::new(reorg),
+                                });
+                                if msg_sender.send(response).await.is_err() {
+                                    break;
+                                }
+                            }
+                        }
+                    }
+
+                    ResponseEvent::Header(SubscriptionItem {
+                        subscription_id,
+                        item: header,
+                    })
+                }
+                Err(RecvError::Closed) => break,
+                Err(RecvError::Lagged(amount)) => {
+                    tracing::debug!(%subscription_id, %amount, kind="header", "Subscription consumer too slow, closing.");
+
+                    // No explicit break here, the loop will be broken by the dropped receiver.
+                    ResponseEvent::SubscriptionClosed {
+                        subscription_id,
+                        reason: "Lagging stream, some headers were skipped. Closing subscription."
+                            .to_owned(),
+                    }
+                }
+            },
         };
 
         if msg_sender.send(response).await.is_err() {
@@ -360,20 +485,65 @@ async fn event_subscription(
     msg_sender: mpsc::Sender<ResponseEvent>,
     mut l2_blocks: broadcast::Receiver<Arc<Block>>,
     mut pending_data: watch::Receiver<PendingData>,
+    mut reorgs: broadcast::Receiver<Reorg>,
     subscription_id: u32,
-    filter: EventFilterParams,
+    filter: Arc<EventFilterParams>,
+    replay: Option<Replay>,
 ) {
+    let mut replayed = match replay {
+        Some(replay) => match replay.run(&msg_sender, subscription_id).await {
+            Some(replayed) => Some(replayed),
+            None => return,
+        },
+        None => None,
+    };
+
     let key_filter_is_empty = filter.keys.iter().flatten().count() == 0;
     let keys: Vec<std::collections::HashSet<_>> = filter
         .keys
         .iter()
         .map(|keys| keys.iter().collect())
         .collect();
-    let mut last_block: Option<BlockNumber> = None;
+    let mut last_block: Option<BlockNumber> = replayed.as_ref().and_then(ReplayedBlocks::last);
     let mut next_receipt_idx = 0;
+    let mut invalidated = InvalidatedBlocks::default();
     'outer: loop {
         let (receipts, block_number) = loop {
+            // Reorgs are polled first so that they are never sent after blocks of the new
+            // chain. Invalidated blocks still queued up are skipped instead.
             tokio::select! {
+                biased;
+                result = reorgs.recv() => {
+                    match result {
+                        Ok(reorg) => {
+                            if last_block.is_some_and(|last| last >= reorg.first_block_number) {
+                                last_block = reorg.new_head_number;
+                                next_receipt_idx = 0;
+                            }
+                            let Some(reorg) = handle_reorg(reorg, &mut invalidated, replayed.as_mut()) else {
+                                continue;
+                            };
+                            let response = ResponseEvent::Reorg(SubscriptionItem {
+                                subscription_id,
+                                item: Arc::new(reorg),
+                            });
+                            if msg_sender.send(response).await.is_err() {
+                                break 'outer;
+                            }
+                        }
+                        Err(RecvError::Closed) => break 'outer,
+                        Err(RecvError::Lagged(amount)) => {
+                            tracing::debug!(%subscription_id, %amount, kind="event", "Subscription consumer too slow, closing.");
+                            let response = ResponseEvent::SubscriptionClosed {
+                                subscription_id,
+                                reason: "Lagging stream, some reorgs were skipped. Closing subscription."
+                                    .to_owned(),
+                            };
+                            msg_sender.send(response).await.ok();
+                            break 'outer;
+                        }
+                    }
+                }
                 result = pending_data.changed() => {
                     match result {
                         Ok(()) => {
@@ -406,6 +576,30 @@ async fn event_subscription(
                 result = l2_blocks.recv() => {
                     match result {
                         Ok(block) => {
+                            if invalidated.contains(block.block_hash, block.parent_block_hash) {
+                                continue;
+                            }
+                            if let Some(replayed_blocks) = replayed.as_mut() {
+                                match replayed_blocks.handover(
+                                    block.block_number,
+                                    block.block_hash,
+                                    block.parent_block_hash,
+                                ) {
+                                    Handover::Skip => continue,
+                                    Handover::Live => replayed = None,
+                                    Handover::Reorg(reorg) => {
+                                        let response = ResponseEvent::Reorg(SubscriptionItem {
+                                            subscription_id,
+                                            item: Arc::new(reorg),
+                                        });
+                                        if msg_sender.send(response).await.is_err() {
+                                            break 'outer;
+                                        }
+                                        last_block = block.block_number.parent();
+                                        next_receipt_idx = 0;
+                                    }
+                                }
+                            }
                             if let Some(last_block) = last_block {
                                 if block.block_number.get() <= last_block.get() {
                                     // Should not be possible.
@@ -489,6 +683,7 @@ async fn event_subscription(
 
 async fn transaction_status_subscription(
     msg_sender: mpsc::Sender<ResponseEvent>,
+    mut reorgs: broadcast::Receiver<Reorg>,
     subscription_id: u32,
     transaction_hash: TransactionHash,
     gateway: impl GatewayApi + Send + 'static,
@@ -578,7 +773,596 @@ async fn transaction_status_subscription(
                 }
             }
         }
-        poll_interval.tick().await;
+
+        // Reorgs are forwarded while waiting for the next poll, which then happens
+        // right away as the transaction's status may have changed.
+        tokio::select! {
+            biased;
+            reorg = reorgs.recv() => match reorg {
+                Ok(reorg) => {
+                    let response = ResponseEvent::Reorg(SubscriptionItem {
+                        subscription_id,
+                        item: Arc::new(reorg),
+                    });
+                    if msg_sender.send(response).await.is_err() {
+                        break;
+                    }
+                }
+                Err(RecvError::Closed) => break,
+                Err(RecvError::Lagged(amount)) => {
+                    tracing::debug!(%subscription_id, %amount, kind="transactionStatus", "Subscription consumer too slow, closing.");
+                    let response = ResponseEvent::SubscriptionClosed {
+                        subscription_id,
+                        reason: "Lagging stream, some reorgs were skipped. Closing subscription."
+                            .to_owned(),
+                    };
+                    msg_sender.send(response).await.ok();
+                    break;
+                }
+            },
+            _ = poll_interval.tick() => {}
+        }
+    }
+}
+
+async fn l1_accepted_subscription(
+    msg_sender: mpsc::Sender<ResponseEvent>,
+    mut l1_accepted: broadcast::Receiver<RangeInclusive<BlockNumber>>,
+    mut reorgs: broadcast::Receiver<Reorg>,
+    storage: Storage,
+    subscription_id: u32,
+    mut transaction_hashes: Vec<TransactionHash>,
+) {
+    /// The number of block headers fetched from storage at once.
+    const BATCH_SIZE: u64 = 1_000;
+
+    loop {
+        // Watched transactions may already be accepted on L1 when subscribing, so
+        // check them before waiting for the first update.
+        if !transaction_hashes.is_empty() {
+            let storage = storage.clone();
+            let result = tokio::task::spawn_blocking(move || {
+                let updates = take_l1_accepted_transactions(&storage, &mut transaction_hashes)?;
+                anyhow::Ok((transaction_hashes, updates))
+            })
+            .await
+            .map_err(anyhow::Error::from)
+            .and_then(|result| result);
+
+            let updates = match result {
+                Ok((remaining, updates)) => {
+                    transaction_hashes = remaining;
+                    updates
+                }
+                Err(e) => {
+                    tracing::warn!(%subscription_id, error=%e, kind="l1Accepted", "Failed to query transactions, closing.");
+                    let response = ResponseEvent::SubscriptionClosed {
+                        subscription_id,
+                        reason: "Unable to query transactions. Closing subscription.".to_owned(),
+                    };
+                    msg_sender.send(response).await.ok();
+                    break;
+                }
+            };
+
+            for update in updates {
+                let response = ResponseEvent::L1Accepted(SubscriptionItem {
+                    subscription_id,
+                    item: Arc::new(update),
+                });
+                if msg_sender.send(response).await.is_err() {
+                    return;
+                }
+            }
+        }
+
+        let blocks = tokio::select! {
+            biased;
+            reorg = reorgs.recv() => match reorg {
+                Ok(reorg) => {
+                    let response = ResponseEvent::Reorg(SubscriptionItem {
+                        subscription_id,
+                        item: Arc::new(reorg),
+                    });
+                    if msg_sender.send(response).await.is_err() {
+                        break;
+                    }
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+                Err(RecvError::Lagged(amount)) => {
+                    tracing::debug!(%subscription_id, %amount, kind="l1Accepted", "Subscription consumer too slow, closing.");
+                    let response = ResponseEvent::SubscriptionClosed {
+                        subscription_id,
+                        reason: "Lagging stream, some reorgs were skipped. Closing subscription."
+                            .to_owned(),
+                    };
+                    msg_sender.send(response).await.ok();
+                    break;
+                }
+            },
+            blocks = l1_accepted.recv() => match blocks {
+                Ok(blocks) => blocks,
+                Err(RecvError::Closed) => break,
+                Err(RecvError::Lagged(amount)) => {
+                    tracing::debug!(%subscription_id, %amount, kind="l1Accepted", "Subscription consumer too slow, closing.");
+                    let response = ResponseEvent::SubscriptionClosed {
+                        subscription_id,
+                        reason: "Lagging stream, some blocks were skipped. Closing subscription."
+                            .to_owned(),
+                    };
+                    msg_sender.send(response).await.ok();
+                    break;
+                }
+            },
+        };
+
+        let mut next = *blocks.start();
+        while next <= *blocks.end() {
+            let last = (*blocks.end()).min(next + (BATCH_SIZE - 1));
+            let storage = storage.clone();
+            let result = tokio::task::spawn_blocking(move || -> anyhow::Result<_> {
+                let mut db = storage.connection()?;
+                let db = db.transaction()?;
+
+                let mut block_ids = Vec::new();
+                for number in next.get()..=last.get() {
+                    let number = BlockNumber::new_or_panic(number);
+                    if let Some(block_id) = db.block_id(number.into())? {
+                        block_ids.push(block_id);
+                    }
+                }
+                Ok(block_ids)
+            })
+            .await
+            .map_err(anyhow::Error::from)
+            .and_then(|result| result);
+
+            let block_ids = match result {
+                Ok(block_ids) => block_ids,
+                Err(e) => {
+                    tracing::warn!(%subscription_id, error=%e, kind="l1Accepted", "Failed to query blocks, closing.");
+                    let response = ResponseEvent::SubscriptionClosed {
+                        subscription_id,
+                        reason: "Unable to query blocks. Closing subscription.".to_owned(),
+                    };
+                    msg_sender.send(response).await.ok();
+                    return;
+                }
+            };
+
+            for (block_number, block_hash) in block_ids {
+                let response = ResponseEvent::L1Accepted(SubscriptionItem {
+                    subscription_id,
+                    item: Arc::new(L1AcceptedUpdate::Block {
+                        block_number,
+                        block_hash,
+                    }),
+                });
+                if msg_sender.send(response).await.is_err() {
+                    return;
+                }
+            }
+
+            next = last + 1;
+        }
+    }
+}
+
+/// Removes the transactions which are accepted on L1 from `transaction_hashes`
+/// and returns the corresponding updates.
+fn take_l1_accepted_transactions(
+    storage: &Storage,
+    transaction_hashes: &mut Vec<TransactionHash>,
+) -> anyhow::Result<Vec<L1AcceptedUpdate>> {
+    let mut db = storage.connection()?;
+    let db = db.transaction()?;
+
+    let Some(l1_head) = db.l1_l2_pointer()? else {
+        return Ok(vec![]);
+    };
+
+    let mut updates = Vec::new();
+    let mut remaining = Vec::new();
+    for &transaction_hash in transaction_hashes.iter() {
+        let block = match db.transaction_block_hash(transaction_hash)? {
+            Some(block_hash) => db.block_id(block_hash.into())?,
+            None => None,
+        };
+
+        match block {
+            Some((block_number, block_hash)) if block_number <= l1_head => {
+                updates.push(L1AcceptedUpdate::Transaction {
+                    transaction_hash,
+                    block_number,
+                    block_hash,
+                });
+            }
+            _ => remaining.push(transaction_hash),
+        }
+    }
+    *transaction_hashes = remaining;
+
+    Ok(updates)
+}
+
+/// Replays stored history to a subscriber before it switches to live data.
+struct Replay {
+    storage: Storage,
+    from_block: BlockNumber,
+    kind: ReplayKind,
+}
+
+#[derive(Clone)]
+enum ReplayKind {
+    Headers,
+    Events {
+        filter: Arc<EventFilterParams>,
+        max_blocks_to_scan: NonZeroUsize,
+        max_uncached_bloom_filters_to_load: NonZeroUsize,
+    },
+}
+
+enum ReplayBatch {
+    /// The latest stored block has been replayed.
+    Done,
+    /// The last replayed block is no longer part of the canonical chain.
+    Rewound,
+    Blocks {
+        block_ids: Vec<(BlockNumber, BlockHash)>,
+        items: Vec<ResponseEvent>,
+    },
+}
+
+impl Replay {
+    /// The number of blocks read from storage at once.
+    const BATCH_SIZE: u64 = 100;
+
+    /// Streams stored blocks from `from_block` up to the latest stored block.
+    ///
+    /// Returns the replayed blocks which are used to hand over to the live
+    /// stream, or `None` if the subscription should end.
+    async fn run(
+        self,
+        msg_sender: &mpsc::Sender<ResponseEvent>,
+        subscription_id: u32,
+    ) -> Option<ReplayedBlocks> {
+        let mut replayed = ReplayedBlocks::new(self.from_block);
+        let mut next = self.from_block;
+
+        loop {
+            let storage = self.storage.clone();
+            let kind = self.kind.clone();
+            let last = replayed.blocks.back().copied();
+            let result = tokio::task::spawn_blocking(move || {
+                load_replay_batch(&storage, &kind, subscription_id, next, last)
+            })
+            .await
+            .map_err(anyhow::Error::from)
+            .and_then(|result| result);
+
+            let batch = match result {
+                Ok(batch) => batch,
+                Err(e) => {
+                    tracing::warn!(%subscription_id, error=%e, "Failed to replay blocks, closing.");
+                    let response = ResponseEvent::SubscriptionClosed {
+                        subscription_id,
+                        reason: "Unable to replay blocks. Closing subscription.".to_owned(),
+                    };
+                    msg_sender.send(response).await.ok();
+                    return None;
+                }
+            };
+
+            match batch {
+                ReplayBatch::Done => return Some(replayed),
+                ReplayBatch::Rewound => {
+                    let storage = self.storage.clone();
+                    let blocks = replayed.blocks.clone();
+                    let result = tokio::task::spawn_blocking(move || find_fork(&storage, &blocks))
+                        .await
+                        .map_err(anyhow::Error::from)
+                        .and_then(|result| result);
+
+                    let reorg = match result {
+                        Ok(reorg) => reorg,
+                        Err(e) => {
+                            tracing::warn!(%subscription_id, error=%e, "Failed to find fork point, closing.");
+                            let response = ResponseEvent::SubscriptionClosed {
+                                subscription_id,
+                                reason: "Unable to replay blocks. Closing subscription.".to_owned(),
+                            };
+                            msg_sender.send(response).await.ok();
+                            return None;
+                        }
+                    };
+
+                    replayed
+                        .blocks
+                        .retain(|(number, _)| *number < reorg.first_block_number);
+                    next = reorg.first_block_number;
+
+                    let response = ResponseEvent::Reorg(SubscriptionItem {
+                        subscription_id,
+                        item: Arc::new(reorg),
+                    });
+                    if msg_sender.send(response).await.is_err() {
+                        return None;
+                    }
+                }
+                ReplayBatch::Blocks { block_ids, items } => {
+                    for response in items {
+                        if msg_sender.send(response).await.is_err() {
+                            return None;
+                        }
+                    }
+
+                    if let Some(&(last, _)) = block_ids.last() {
+                        next = last + 1;
+                    }
+                    for (number, hash) in block_ids {
+                        replayed.push(number, hash);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn load_replay_batch(
+    storage: &Storage,
+    kind: &ReplayKind,
+    subscription_id: u32,
+    from: BlockNumber,
+    last_replayed: Option<(BlockNumber, BlockHash)>,
+) -> anyhow::Result<ReplayBatch> {
+    let mut db = storage.connection()?;
+    let db = db.transaction()?;
+
+    if let Some((number, hash)) = last_replayed {
+        if db.block_id(number.into())? != Some((number, hash)) {
+            return Ok(ReplayBatch::Rewound);
+        }
+    }
+
+    let latest = match db.block_id(BlockId::Latest)? {
+        Some((latest, _)) if latest >= from => latest,
+        _ => return Ok(ReplayBatch::Done),
+    };
+    let to = latest.min(from + (Replay::BATCH_SIZE - 1));
+
+    let mut block_ids = Vec::new();
+    for number in from.get()..=to.get() {
+        let number = BlockNumber::new_or_panic(number);
+        if let Some(block_id) = db.block_id(number.into())? {
+            block_ids.push(block_id);
+        }
+    }
+    if block_ids.is_empty() {
+        return Ok(ReplayBatch::Done);
+    }
+
+    let mut items = Vec::new();
+    match kind {
+        ReplayKind::Headers => {
+            for &(number, _) in &block_ids {
+                let header = db
+                    .block_header(number.into())?
+                    .context("Block header missing")?;
+                items.push(ResponseEvent::Header(SubscriptionItem {
+                    subscription_id,
+                    item: Arc::new(serde_json::to_value(BlockHeader(header))?),
+                }));
+            }
+        }
+        ReplayKind::Events {
+            filter,
+            max_blocks_to_scan,
+            max_uncached_bloom_filters_to_load,
+        } => {
+            let mut constraints = pathfinder_storage::EventFilter {
+                from_block: Some(from),
+                to_block: Some(to),
+                contract_address: filter.address,
+                keys: filter.keys.clone(),
+                page_size: pathfinder_storage::EVENT_PAGE_SIZE_LIMIT,
+                offset: 0,
+            };
+            loop {
+                let page = db.events(
+                    &constraints,
+                    *max_blocks_to_scan,
+                    *max_uncached_bloom_filters_to_load,
+                )?;
+                items.extend(page.events.into_iter().map(|event| {
+                    ResponseEvent::Event(SubscriptionItem {
+                        subscription_id,
+                        item: Arc::new(EmittedEvent {
+                            data: event.data,
+                            keys: event.keys,
+                            from_address: event.from_address,
+                            block_hash: None,
+                            block_number: Some(event.block_number),
+                            transaction_hash: event.transaction_hash,
+                        }),
+                    })
+                }));
+
+                match page.continuation_token {
+                    Some(token) => {
+                        constraints.from_block = Some(token.block_number);
+                        constraints.offset = token.offset;
+                    }
+                    None => break,
+                }
+            }
+        }
+    }
+
+    Ok(ReplayBatch::Blocks { block_ids, items })
+}
+
+/// Finds the newest replayed block which is still part of the canonical
+/// chain.
+fn find_fork(
+    storage: &Storage,
+    replayed: &VecDeque<(BlockNumber, BlockHash)>,
+) -> anyhow::Result<Reorg> {
+    let mut db = storage.connection()?;
+    let db = db.transaction()?;
+
+    let mut first_invalid = *replayed.back().context("No blocks were replayed")?;
+    for &(number, hash) in replayed.iter().rev() {
+        if db.block_id(number.into())? == Some((number, hash)) {
+            return Ok(Reorg {
+                first_block_number: first_invalid.0,
+                first_block_hash: first_invalid.1,
+                new_head_number: Some(number),
+                new_head_hash: Some(hash),
+            });
+        }
+        first_invalid = (number, hash);
+    }
+
+    Ok(Reorg {
+        first_block_number: first_invalid.0,
+        first_block_hash: first_invalid.1,
+        new_head_number: None,
+        new_head_hash: None,
+    })
+}
+
+/// The most recent blocks sent to a subscriber during a replay. Used to hand
+/// over to the live stream without gaps or duplicates.
+struct ReplayedBlocks {
+    from_block: BlockNumber,
+    blocks: VecDeque<(BlockNumber, BlockHash)>,
+}
+
+enum Handover {
+    /// The live block was already replayed or precedes the replay.
+    Skip,
+    /// The live block follows the replayed blocks.
+    Live,
+    /// The live block replaces a replayed block.
+    Reorg(Reorg),
+}
+
+impl ReplayedBlocks {
+    /// The number of replayed blocks kept to detect reorgs.
+    const CAPACITY: usize = 1_000;
+
+    fn new(from_block: BlockNumber) -> Self {
+        Self {
+            from_block,
+            blocks: VecDeque::new(),
+        }
+    }
+
+    fn push(&mut self, number: BlockNumber, hash: BlockHash) {
+        if self.blocks.len() == Self::CAPACITY {
+            self.blocks.pop_front();
+        }
+        self.blocks.push_back((number, hash));
+    }
+
+    fn last(&self) -> Option<BlockNumber> {
+        self.blocks.back().map(|(number, _)| *number)
+    }
+
+    /// Compares a block received from the live stream with the replayed
+    /// blocks.
+    fn handover(
+        &mut self,
+        number: BlockNumber,
+        hash: BlockHash,
+        parent_hash: BlockHash,
+    ) -> Handover {
+        if number < self.from_block {
+            return Handover::Skip;
+        }
+
+        match self.blocks.iter().position(|(n, _)| *n == number) {
+            Some(i) if self.blocks[i].1 == hash => Handover::Skip,
+            Some(i) => {
+                let (first_block_number, first_block_hash) = self.blocks[i];
+                self.blocks.truncate(i);
+                self.blocks.push_back((number, hash));
+                Handover::Reorg(Reorg {
+                    first_block_number,
+                    first_block_hash,
+                    new_head_number: number.parent(),
+                    new_head_hash: number.parent().map(|_| parent_hash),
+                })
+            }
+            None if self.last().map_or(true, |last| number > last) => Handover::Live,
+            // Older than any block kept, so it must have been replayed.
+            None => Handover::Skip,
+        }
+    }
+}
+
+/// Extracts the number, hash and parent hash from a serialized [BlockHeader].
+fn header_block_id(header: &Value) -> Option<(BlockNumber, BlockHash, BlockHash)> {
+    let number = header.get("number")?.as_u64().and_then(BlockNumber::new)?;
+    let hash = BlockHash::deserialize(header.get("hash")?).ok()?;
+    let parent_hash = BlockHash::deserialize(header.get("parent_hash")?).ok()?;
+    Some((number, hash, parent_hash))
+}
+
+/// Blocks invalidated by the latest reorg which may still be queued up in a
+/// subscription's receiver.
+#[derive(Default)]
+struct InvalidatedBlocks(HashSet<BlockHash>);
+
+impl InvalidatedBlocks {
+    fn reorg(&mut self, reorg: &Reorg) {
+        self.0.clear();
+        self.0.insert(reorg.first_block_hash);
+    }
+
+    /// Returns true if the block is, or descends from, the first block
+    /// invalidated by the latest reorg.
+    ///
+    /// Invalidated blocks are always received before blocks of the new chain,
+    /// so tracking stops at the first valid block.
+    fn contains(&mut self, hash: BlockHash, parent_hash: BlockHash) -> bool {
+        if self.0.is_empty() {
+            return false;
+        }
+
+        if self.0.contains(&hash) || self.0.contains(&parent_hash) {
+            self.0.insert(hash);
+            true
+        } else {
+            self.0.clear();
+            false
+        }
+    }
+}
+
+/// Returns the reorg notice to send for a reorg broadcast by the sync process.
+///
+/// No notice is sent if the subscriber is still being sent replayed blocks and
+/// none of them were invalidated.
+fn handle_reorg(
+    reorg: Reorg,
+    invalidated: &mut InvalidatedBlocks,
+    replayed: Option<&mut ReplayedBlocks>,
+) -> Option<Reorg> {
+    invalidated.reorg(&reorg);
+
+    match replayed {
+        Some(replayed) => {
+            let affected = replayed
+                .last()
+                .is_some_and(|last| last >= reorg.first_block_number);
+            replayed
+                .blocks
+                .retain(|(number, _)| *number < reorg.first_block_number);
+            affected.then_some(reorg)
+        }
+        None => Some(reorg),
     }
 }
 
@@ -625,6 +1409,10 @@ where
 pub struct TopicBroadcasters {
     pub new_head: JsonBroadcaster<BlockHeader>,
     pub l2_blocks: broadcast::Sender<Arc<Block>>,
+    /// Ranges of L2 blocks which became accepted on L1.
+    pub l1_accepted: broadcast::Sender<RangeInclusive<BlockNumber>>,
+    /// L2 reorgs, sent before any block of the new chain.
+    pub reorgs: broadcast::Sender<Reorg>,
     pub pending_data: watch::Receiver<PendingData>,
 }
 
@@ -639,6 +1427,8 @@ impl TopicBroadcasters {
                 item_type: PhantomData {},
             },
             l2_blocks: broadcast::channel(capacity.get()).0,
+            l1_accepted: broadcast::channel(capacity.get()).0,
+            reorgs: broadcast::channel(capacity.get()).0,
             pending_data,
         }
     }
@@ -657,13 +1447,18 @@ mod tests {
     use pathfinder_common::transaction::Transaction;
     use pathfinder_common::{
         block_hash,
+        block_hash_bytes,
+        contract_address_bytes,
         event_commitment,
+        event_data_bytes,
         event_key,
+        event_key_bytes,
         receipt_commitment,
         state_commitment,
         state_diff_commitment,
         transaction_commitment,
         transaction_hash,
+        transaction_hash_bytes,
         BlockNumber,
         BlockTimestamp,
         ContractAddress,
@@ -687,7 +1482,6 @@ mod tests {
     use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
 
     use super::*;
-    use crate::context::RpcContext;
     use crate::jsonrpc::websocket::data::successful_response;
     use crate::jsonrpc::{RpcError, RpcResponse};
 
@@ -1197,8 +1991,10 @@ mod tests {
         }
 
         let (msg_sender, mut msg_receiver) = mpsc::channel(10);
+        let (_reorg_sender, reorgs) = broadcast::channel(1);
         tokio::spawn(transaction_status_subscription(
             msg_sender,
+            reorgs,
             0,
             transaction_hash!("0x1"),
             Mock(Mutex::new(
@@ -1282,8 +2078,10 @@ mod tests {
         }
 
         let (msg_sender, mut msg_receiver) = mpsc::channel(10);
+        let (_reorg_sender, reorgs) = broadcast::channel(1);
         tokio::spawn(transaction_status_subscription(
             msg_sender,
+            reorgs,
             0,
             transaction_hash!("0x1"),
             Mock(Mutex::new(
@@ -1367,8 +2165,10 @@ mod tests {
         }
 
         let (msg_sender, mut msg_receiver) = mpsc::channel(10);
+        let (_reorg_sender, reorgs) = broadcast::channel(1);
         tokio::spawn(transaction_status_subscription(
             msg_sender,
+            reorgs,
             0,
             transaction_hash!("0x1"),
             Mock(Mutex::new(
@@ -1436,6 +2236,67 @@ mod tests {
         assert!(msg.is_none());
     }
 
+    #[tokio::test]
+    async fn subscribe_transaction_status_reorg() {
+        struct Mock;
+
+        #[async_trait::async_trait]
+        impl GatewayApi for Mock {
+            async fn transaction(
+                &self,
+                _: TransactionHash,
+            ) -> Result<TransactionStatus, SequencerError> {
+                Ok(TransactionStatus {
+                    status: Status::Received,
+                    finality_status: FinalityStatus::Received,
+                    execution_status: ExecutionStatus::Succeeded,
+                })
+            }
+        }
+
+        let (msg_sender, mut msg_receiver) = mpsc::channel(10);
+        let (reorg_sender, reorgs) = broadcast::channel(1);
+        tokio::spawn(transaction_status_subscription(
+            msg_sender,
+            reorgs,
+            0,
+            transaction_hash!("0x1"),
+            Mock,
+        ));
+
+        let msg = timeout(Duration::from_secs(2), msg_receiver.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        match msg {
+            ResponseEvent::TransactionStatus(SubscriptionItem {
+                subscription_id: 0,
+                item,
+            }) if item.as_ref() == &TransactionStatusUpdate::Received => {}
+            _ => panic!("Unexpected message: {:?}", msg),
+        }
+
+        let reorg = Reorg {
+            first_block_number: BlockNumber::new_or_panic(2),
+            first_block_hash: block_hash!("0x2"),
+            new_head_number: Some(BlockNumber::new_or_panic(1)),
+            new_head_hash: Some(block_hash!("0x1")),
+        };
+        reorg_sender.send(reorg).unwrap();
+
+        let msg = timeout(Duration::from_secs(2), msg_receiver.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        match msg {
+            ResponseEvent::Reorg(SubscriptionItem {
+                subscription_id: 0,
+                item,
+            }) if item.as_ref() == &reorg => {}
+            _ => panic!("Unexpected message: {:?}", msg),
+        }
+    }
+
     #[tokio::test]
     async fn subscribe_transaction_status_does_not_exist() {
         let mut client = Client::new().await;
@@ -1481,6 +2342,372 @@ mod tests {
     // to the same topic and receive duplicated messages as a result.
     // TODO Subscription limit?
 
+    #[tokio::test]
+    async fn subscribe_l1_accepted() {
+        let mut client = Client::new().await;
+
+        // "txn 0" is in block 0, which is accepted on L1, while "txn 1" is in block 1
+        // which is not.
+        let txn0 = transaction_hash_bytes!(b"txn 0");
+        let txn1 = transaction_hash_bytes!(b"txn 1");
+
+        let req_id = RequestId::Number(37);
+        client
+            .send_request(&RpcRequest {
+                method: Cow::from(SUBSCRIBE_METHOD),
+                params: RawParams(Some(&value(&json!({
+                    "kind": "l1Accepted",
+                    "transaction_hashes": [txn0, txn1],
+                })))),
+                id: req_id.clone(),
+            })
+            .await;
+
+        client
+            .expect_response(&successful_response(&0, req_id).unwrap())
+            .await;
+
+        client
+            .expect_response(&SubscriptionItem {
+                subscription_id: 0,
+                item: L1AcceptedUpdate::Transaction {
+                    transaction_hash: txn0,
+                    block_number: BlockNumber::GENESIS,
+                    block_hash: block_hash_bytes!(b"genesis"),
+                },
+            })
+            .await;
+        client.expect_no_response().await;
+
+        let mut db = client.storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+        tx.update_l1_l2_pointer(Some(BlockNumber::new_or_panic(1)))
+            .unwrap();
+        tx.commit().unwrap();
+
+        client
+            .l1_accepted
+            .send(BlockNumber::new_or_panic(1)..=BlockNumber::new_or_panic(1))
+            .unwrap();
+
+        client
+            .expect_response(&SubscriptionItem {
+                subscription_id: 0,
+                item: L1AcceptedUpdate::Block {
+                    block_number: BlockNumber::new_or_panic(1),
+                    block_hash: block_hash_bytes!(b"block 1"),
+                },
+            })
+            .await;
+        client
+            .expect_response(&SubscriptionItem {
+                subscription_id: 0,
+                item: L1AcceptedUpdate::Transaction {
+                    transaction_hash: txn1,
+                    block_number: BlockNumber::new_or_panic(1),
+                    block_hash: block_hash_bytes!(b"block 1"),
+                },
+            })
+            .await;
+        client.expect_no_response().await;
+
+        client.destroy().await;
+    }
+
+    #[tokio::test]
+    async fn subscribe_l1_accepted_too_many_transactions() {
+        let mut client = Client::new().await;
+
+        let transaction_hashes = (0..=L1_ACCEPTED_TRANSACTION_LIMIT as u64)
+            .map(|i| TransactionHash(Felt::from_u64(i)))
+            .collect::<Vec<_>>();
+        client
+            .send_request(&RpcRequest {
+                method: Cow::from(SUBSCRIBE_METHOD),
+                params: RawParams(Some(&value(&json!({
+                    "kind": "l1Accepted",
+                    "transaction_hashes": transaction_hashes,
+                })))),
+                id: RequestId::Number(37),
+            })
+            .await;
+
+        client
+            .expect_response(&RpcResponse {
+                output: Err(RpcError::InvalidParams(format!(
+                    "Too many transaction hashes: {} exceeds the limit of \
+                     {L1_ACCEPTED_TRANSACTION_LIMIT}",
+                    L1_ACCEPTED_TRANSACTION_LIMIT + 1
+                ))),
+                id: RequestId::Number(37),
+            })
+            .await;
+        client.expect_no_response().await;
+
+        client.destroy().await;
+    }
+
+    #[tokio::test]
+    async fn subscribe_new_heads_from_block() {
+        let mut client = Client::new().await;
+
+        let (header1, header2) = {
+            let mut db = client.storage.connection().unwrap();
+            let tx = db.transaction().unwrap();
+            (
+                tx.block_header(BlockNumber::new_or_panic(1).into())
+                    .unwrap()
+                    .unwrap(),
+                tx.block_header(BlockNumber::new_or_panic(2).into())
+                    .unwrap()
+                    .unwrap(),
+            )
+        };
+
+        let req_id = RequestId::Number(37);
+        client
+            .send_request(&RpcRequest {
+                method: Cow::from(SUBSCRIBE_METHOD),
+                params: RawParams(Some(&value(&json!({
+                    "kind": "newHeads",
+                    "from_block": 1,
+                })))),
+                id: req_id.clone(),
+            })
+            .await;
+
+        client
+            .expect_response(&successful_response(&0, req_id).unwrap())
+            .await;
+
+        for header in [&header1, &header2] {
+            client
+                .expect_response(&SubscriptionItem {
+                    subscription_id: 0,
+                    item: BlockHeader(header.clone()),
+                })
+                .await;
+        }
+        client.expect_no_response().await;
+
+        // The live stream repeating a replayed block is skipped.
+        client
+            .head_sender
+            .send_if_receiving(BlockHeader(header2.clone()))
+            .unwrap();
+        client.expect_no_response().await;
+
+        // A different block replacing a replayed one is preceded by a reorg notice.
+        let reorged = pathfinder_common::BlockHeader {
+            hash: block_hash!("0xabc"),
+            ..header2.clone()
+        };
+        client
+            .head_sender
+            .send_if_receiving(BlockHeader(reorged.clone()))
+            .unwrap();
+        client
+            .expect_response(&SubscriptionItem {
+                subscription_id: 0,
+                item: Reorg {
+                    first_block_number: header2.number,
+                    first_block_hash: header2.hash,
+                    new_head_number: Some(header1.number),
+                    new_head_hash: Some(header1.hash),
+                },
+            })
+            .await;
+        client
+            .expect_response(&SubscriptionItem {
+                subscription_id: 0,
+                item: BlockHeader(reorged.clone()),
+            })
+            .await;
+
+        let next = pathfinder_common::BlockHeader {
+            hash: block_hash!("0xdef"),
+            parent_hash: reorged.hash,
+            number: BlockNumber::new_or_panic(3),
+            ..reorged
+        };
+        client
+            .head_sender
+            .send_if_receiving(BlockHeader(next.clone()))
+            .unwrap();
+        client
+            .expect_response(&SubscriptionItem {
+                subscription_id: 0,
+                item: BlockHeader(next),
+            })
+            .await;
+        client.expect_no_response().await;
+
+        client.destroy().await;
+    }
+
+    #[tokio::test]
+    async fn subscribe_events_from_block() {
+        let mut client = Client::new().await;
+
+        let req_id = RequestId::Number(37);
+        client
+            .send_request(&RpcRequest {
+                method: Cow::from(SUBSCRIBE_METHOD),
+                params: RawParams(Some(&value(&json!({
+                    "kind": "events",
+                    "from_block": 0,
+                })))),
+                id: req_id.clone(),
+            })
+            .await;
+
+        client
+            .expect_response(&successful_response(&0, req_id).unwrap())
+            .await;
+
+        client
+            .expect_response(&SubscriptionItem {
+                subscription_id: 0,
+                item: EmittedEvent {
+                    from_address: contract_address_bytes!(b"event 0 from addr"),
+                    data: vec![event_data_bytes!(b"event 0 data")],
+                    keys: vec![event_key_bytes!(b"event 0 key")],
+                    block_hash: None,
+                    block_number: Some(BlockNumber::GENESIS),
+                    transaction_hash: transaction_hash_bytes!(b"txn 0"),
+                },
+            })
+            .await;
+        client.expect_no_response().await;
+
+        // Live blocks following the stored ones are streamed as usual.
+        client.l2_blocks.send(block_sample().into()).unwrap();
+        client
+            .expect_response(&SubscriptionItem {
+                subscription_id: 0,
+                item: EmittedEvent {
+                    from_address: ContractAddress::new_or_panic(Felt::from_hex_str("2").unwrap()),
+                    data: vec![EventData(Felt::from_hex_str("a").unwrap())],
+                    keys: vec![
+                        EventKey(Felt::from_hex_str("b").unwrap()),
+                        event_key!("0xdeadbeef"),
+                    ],
+                    block_hash: None,
+                    block_number: Some(BlockNumber::new_or_panic(1000)),
+                    transaction_hash: transaction_hash!("0x1"),
+                },
+            })
+            .await;
+
+        client.destroy().await;
+    }
+
+    #[test]
+    fn find_fork_after_rewind() {
+        let storage = RpcContext::for_tests().storage;
+
+        let replayed = [
+            (BlockNumber::GENESIS, block_hash_bytes!(b"genesis")),
+            (BlockNumber::new_or_panic(1), block_hash_bytes!(b"block 1")),
+            (BlockNumber::new_or_panic(2), block_hash!("0xabc")),
+            (BlockNumber::new_or_panic(3), block_hash!("0xdef")),
+        ]
+        .into_iter()
+        .collect();
+
+        let reorg = find_fork(&storage, &replayed).unwrap();
+        assert_eq!(
+            reorg,
+            Reorg {
+                first_block_number: BlockNumber::new_or_panic(2),
+                first_block_hash: block_hash!("0xabc"),
+                new_head_number: Some(BlockNumber::new_or_panic(1)),
+                new_head_hash: Some(block_hash_bytes!(b"block 1")),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn reorg_notifications() {
+        let mut client = Client::new().await;
+
+        for (req_id, kind) in [(37, "newHeads"), (38, "events")] {
+            let req_id = RequestId::Number(req_id);
+            client
+                .send_request(&RpcRequest {
+                    method: Cow::from(SUBSCRIBE_METHOD),
+                    params: RawParams(Some(&value(&json!([kind])))),
+                    id: req_id.clone(),
+                })
+                .await;
+            let subscription_id = if kind == "newHeads" { 0 } else { 1 };
+            client
+                .expect_response(&successful_response(&subscription_id, req_id).unwrap())
+                .await;
+        }
+
+        let reorg = Reorg {
+            first_block_number: BlockNumber::new_or_panic(1000),
+            first_block_hash: block_hash!("0x1"),
+            new_head_number: Some(BlockNumber::new_or_panic(999)),
+            new_head_hash: Some(block_hash!("0x2")),
+        };
+        client.reorgs.send(reorg).unwrap();
+
+        // Every subscription is notified, in no particular order.
+        let mut received = Vec::new();
+        for _ in 0..2 {
+            let message = timeout(Duration::from_secs(2), client.receiver.next())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            let Message::Text(raw_text) = message else {
+                panic!("Unexpected type of message")
+            };
+            received.push(serde_json::from_str::<Value>(&raw_text).unwrap());
+        }
+        received.sort_by_key(|message| message["result"]["subscription"].as_u64());
+        assert_eq!(
+            received,
+            vec![
+                serde_json::to_value(SubscriptionItem {
+                    subscription_id: 0,
+                    item: reorg,
+                })
+                .unwrap(),
+                serde_json::to_value(SubscriptionItem {
+                    subscription_id: 1,
+                    item: reorg,
+                })
+                .unwrap(),
+            ]
+        );
+        client.expect_no_response().await;
+
+        client.destroy().await;
+    }
+
+    #[test]
+    fn invalidated_blocks_are_skipped() {
+        let mut invalidated = InvalidatedBlocks::default();
+        assert!(!invalidated.contains(block_hash!("0x1"), block_hash!("0x0")));
+
+        invalidated.reorg(&Reorg {
+            first_block_number: BlockNumber::new_or_panic(1),
+            first_block_hash: block_hash!("0x1"),
+            new_head_number: Some(BlockNumber::GENESIS),
+            new_head_hash: Some(block_hash!("0x0")),
+        });
+
+        // The invalidated block and its descendants.
+        assert!(invalidated.contains(block_hash!("0x1"), block_hash!("0x0")));
+        assert!(invalidated.contains(block_hash!("0x2"), block_hash!("0x1")));
+        // The new chain.
+        assert!(!invalidated.contains(block_hash!("0xa"), block_hash!("0x0")));
+        assert!(!invalidated.contains(block_hash!("0x3"), block_hash!("0x2")));
+    }
+
     fn value<S>(payload: &S) -> Box<RawValue>
     where
         S: Serialize + ?Sized,
@@ -1581,7 +2808,10 @@ mod tests {
         server_handle: JoinHandle<()>,
         head_sender: JsonBroadcaster<BlockHeader>,
         l2_blocks: broadcast::Sender<Arc<Block>>,
+        l1_accepted: broadcast::Sender<RangeInclusive<BlockNumber>>,
+        reorgs: broadcast::Sender<Reorg>,
         pending_data_sender: watch::Sender<PendingData>,
+        storage: Storage,
     }
 
     impl Client {
@@ -1602,6 +2832,8 @@ mod tests {
             let websocket_context = context.websocket.clone().unwrap();
             let head_sender = websocket_context.broadcasters.new_head.clone();
             let l2_blocks = websocket_context.broadcasters.l2_blocks.clone();
+            let l1_accepted = websocket_context.broadcasters.l1_accepted.clone();
+            let reorgs = websocket_context.broadcasters.reorgs.clone();
 
             let router = axum::Router::new()
                 .route("/ws", get(websocket_handler))
@@ -1634,7 +2866,10 @@ mod tests {
                 receiver,
                 server_handle,
                 l2_blocks,
+                l1_accepted,
+                reorgs,
                 pending_data_sender: pending_data_tx,
+                storage: context.storage.clone(),
             }
         }
 
@@ -3,11 +3,14 @@ pub mod context;
 mod dto;
 mod error;
 mod executor;
+mod feeder_gateway;
 mod felt;
+pub mod gossip;
 mod jsonrpc;
 pub(crate) mod method;
 pub mod middleware;
 mod pathfinder;
+pub mod peers;
 mod pending;
 #[cfg(test)]
 mod test_setup;
@@ -15,6 +18,8 @@ pub mod v02;
 pub mod v03;
 pub mod v06;
 pub mod v07;
+pub mod v08;
+mod validation;
 
 use std::net::SocketAddr;
 use std::result::Result;
@@ -26,7 +31,7 @@ use axum::response::IntoResponse;
 use context::RpcContext;
 pub use executor::compose_executor_transaction;
 use http_body::Body;
-use pathfinder_common::AllowedOrigins;
+use pathfinder_common::{AllowedOrigins, EthereumAddress, PublicKey};
 pub use pending::PendingData;
 use tokio::sync::RwLock;
 use tokio::task::JoinHandle;
@@ -35,7 +40,7 @@ use tower_http::ServiceBuilderExt;
 
 use crate::jsonrpc::rpc_handler;
 use crate::jsonrpc::websocket::websocket_handler;
-pub use crate::jsonrpc::websocket::{BlockHeader, TopicBroadcasters};
+pub use crate::jsonrpc::websocket::{BlockHeader, Reorg, TopicBroadcasters};
 use crate::v02::types::syncing::Syncing;
 
 const DEFAULT_MAX_CONNECTIONS: usize = 1024;
@@ -45,6 +50,7 @@ pub enum RpcVersion {
     V06,
     #[default]
     V07,
+    V08,
     PathfinderV01,
 }
 
@@ -53,6 +59,7 @@ impl RpcVersion {
         match self {
             RpcVersion::V06 => "v0.6",
             RpcVersion::V07 => "v0.7",
+            RpcVersion::V08 => "v0.8",
             RpcVersion::PathfinderV01 => "v0.1",
         }
     }
@@ -69,6 +76,7 @@ pub struct RpcServer {
     max_connections: usize,
     cors: Option<CorsLayer>,
     default_version: RpcVersion,
+    feeder_gateway: Option<feeder_gateway::FeederGateway>,
 }
 
 impl RpcServer {
@@ -79,6 +87,7 @@ impl RpcServer {
             max_connections: DEFAULT_MAX_CONNECTIONS,
             cors: None,
             default_version,
+            feeder_gateway: None,
         }
     }
 
@@ -94,6 +103,24 @@ impl RpcServer {
         }
     }
 
+    /// Also serves a read-only subset of the feeder gateway API from storage,
+    /// under `/feeder_gateway/`.
+    pub fn with_feeder_gateway(
+        self,
+        public_key: PublicKey,
+        core_contract_address: EthereumAddress,
+    ) -> Self {
+        let feeder_gateway = feeder_gateway::FeederGateway::new(
+            self.context.clone(),
+            public_key,
+            core_contract_address,
+        );
+        Self {
+            feeder_gateway: Some(feeder_gateway),
+            ..self
+        }
+    }
+
     /// Starts the HTTP-RPC server.
     pub async fn spawn(
         self,
@@ -162,11 +189,13 @@ impl RpcServer {
 
         let v06_routes = v06::register_routes().build(self.context.clone());
         let v07_routes = v07::register_routes().build(self.context.clone());
+        let v08_routes = v08::register_routes().build(self.context.clone());
         let pathfinder_routes = pathfinder::register_routes().build(self.context.clone());
 
         let default_router = match self.default_version {
             RpcVersion::V06 => v06_routes.clone(),
             RpcVersion::V07 => v07_routes.clone(),
+            RpcVersion::V08 => v08_routes.clone(),
             RpcVersion::PathfinderV01 => {
                 anyhow::bail!("Did not expect default RPC version to be Pathfinder v0.1")
             }
@@ -181,6 +210,8 @@ impl RpcServer {
             .with_state(v06_routes.clone())
             .route("/rpc/v0_7", post(rpc_handler))
             .with_state(v07_routes.clone())
+            .route("/rpc/v0_8", post(rpc_handler))
+            .with_state(v08_routes.clone())
             .route("/rpc/pathfinder/v0.1", post(rpc_handler))
             .route("/rpc/pathfinder/v0_1", post(rpc_handler))
             .with_state(pathfinder_routes.clone());
@@ -193,12 +224,19 @@ impl RpcServer {
                 .with_state(v06_routes)
                 .route("/ws/rpc/v0_7", get(websocket_handler))
                 .with_state(v07_routes)
+                .route("/ws/rpc/v0_8", get(websocket_handler))
+                .with_state(v08_routes)
                 .route("/ws/rpc/pathfinder/v0_1", get(websocket_handler))
                 .with_state(pathfinder_routes)
         } else {
             router.with_state(default_router)
         };
 
+        let router = match self.feeder_gateway {
+            Some(feeder_gateway) => router.merge(feeder_gateway.router()),
+            None => router,
+        };
+
         let router = router.layer(middleware);
 
         let server_handle = tokio::spawn(async move {
@@ -15,6 +15,7 @@ pub mod get_class;
 pub mod get_class_at;
 pub mod get_class_hash_at;
 pub mod get_events;
+pub mod get_messages_status;
 pub mod get_nonce;
 pub mod get_state_update;
 pub mod get_storage_at;
@@ -44,6 +45,7 @@ pub use get_class::get_class;
 pub use get_class_at::get_class_at;
 pub use get_class_hash_at::get_class_hash_at;
 pub use get_events::get_events;
+pub use get_messages_status::get_messages_status;
 pub use get_nonce::get_nonce;
 pub use get_state_update::get_state_update;
 pub use get_storage_at::get_storage_at;
@@ -8,7 +8,7 @@ use starknet_gateway_types::request::add_transaction::{
 };
 
 use crate::context::RpcContext;
-use crate::v02::types::request::BroadcastedDeclareTransaction;
+use crate::v02::types::request::{BroadcastedDeclareTransaction, BroadcastedTransaction};
 
 #[derive(Debug)]
 pub enum AddDeclareTransactionError {
@@ -63,6 +63,8 @@ impl From<anyhow::Error> for AddDeclareTransactionError {
     }
 }
 
+crate::validation::impl_from_validation_error!(AddDeclareTransactionError);
+
 impl From<SequencerError> for AddDeclareTransactionError {
     fn from(e: SequencerError) -> Self {
         use starknet_gateway_types::error::KnownStarknetErrorCode::{
@@ -154,6 +156,15 @@ pub async fn add_declare_transaction(
 ) -> Result<Output, AddDeclareTransactionError> {
     use starknet_gateway_types::request::add_transaction;
 
+    let Transaction::Declare(declare) = &input.declare_transaction;
+    if !matches!(declare, BroadcastedDeclareTransaction::V0(_)) {
+        crate::validation::validate_submitted(
+            &context,
+            BroadcastedTransaction::Declare(declare.clone()),
+        )
+        .await?;
+    }
+
     match input.declare_transaction {
         Transaction::Declare(BroadcastedDeclareTransaction::V0(_)) => {
             Err(AddDeclareTransactionError::UnsupportedTransactionVersion)
@@ -186,6 +197,7 @@ pub async fn add_declare_transaction(
             })
         }
         Transaction::Declare(BroadcastedDeclareTransaction::V2(tx)) => {
+            let gossiped = BroadcastedDeclareTransaction::V2(tx.clone());
             let contract_definition: SierraContractDefinition = tx
                 .contract_class
                 .try_into()
@@ -205,14 +217,25 @@ pub async fn add_declare_transaction(
                     }),
                     input.token,
                 )
-                .await?;
+                .await
+                .map(|response| Output {
+                    transaction_hash: response.transaction_hash,
+                    class_hash: response.class_hash,
+                });
+            let output = crate::gossip::gossip_submitted(
+                &context,
+                BroadcastedTransaction::Declare(gossiped),
+                response,
+                |transaction| Output {
+                    transaction_hash: transaction.hash,
+                    class_hash: crate::gossip::declared_class_hash(transaction),
+                },
+            )?;
 
-            Ok(Output {
-                transaction_hash: response.transaction_hash,
-                class_hash: response.class_hash,
-            })
+            Ok(output)
         }
         Transaction::Declare(BroadcastedDeclareTransaction::V3(tx)) => {
+            let gossiped = BroadcastedDeclareTransaction::V3(tx.clone());
             let contract_definition: SierraContractDefinition = tx
                 .contract_class
                 .try_into()
@@ -247,12 +270,22 @@ pub async fn add_declare_transaction(
                     }),
                     input.token,
                 )
-                .await?;
+                .await
+                .map(|response| Output {
+                    transaction_hash: response.transaction_hash,
+                    class_hash: response.class_hash,
+                });
+            let output = crate::gossip::gossip_submitted(
+                &context,
+                BroadcastedTransaction::Declare(gossiped),
+                response,
+                |transaction| Output {
+                    transaction_hash: transaction.hash,
+                    class_hash: crate::gossip::declared_class_hash(transaction),
+                },
+            )?;
 
-            Ok(Output {
-                transaction_hash: response.transaction_hash,
-                class_hash: response.class_hash,
-            })
+            Ok(output)
         }
     }
 }
@@ -6,6 +6,7 @@ use crate::context::RpcContext;
 use crate::v02::types::request::{
     BroadcastedDeployAccountTransaction,
     BroadcastedDeployAccountTransactionV1,
+    BroadcastedTransaction,
 };
 
 #[derive(serde::Deserialize, Debug, PartialEq, Eq)]
@@ -57,6 +58,8 @@ impl From<AddDeployAccountTransactionError> for crate::error::ApplicationError {
     }
 }
 
+crate::validation::impl_from_validation_error!(AddDeployAccountTransactionError);
+
 impl From<SequencerError> for AddDeployAccountTransactionError {
     fn from(e: SequencerError) -> Self {
         use starknet_gateway_types::error::KnownStarknetErrorCode::{
@@ -107,10 +110,23 @@ pub async fn add_deploy_account_transaction(
         Transaction::DeployAccount(tx) => tx.deployed_contract_address(),
     };
     let Transaction::DeployAccount(tx) = input.deploy_account_transaction;
-    let response = add_deploy_account_transaction_impl(&context, tx).await?;
+    crate::validation::validate_submitted(
+        &context,
+        BroadcastedTransaction::DeployAccount(tx.clone()),
+    )
+    .await?;
+    let response = add_deploy_account_transaction_impl(&context, tx.clone())
+        .await
+        .map(|response| response.transaction_hash);
+    let transaction_hash = crate::gossip::gossip_submitted(
+        &context,
+        BroadcastedTransaction::DeployAccount(tx),
+        response,
+        |transaction| transaction.hash,
+    )?;
 
     Ok(Output {
-        transaction_hash: response.transaction_hash,
+        transaction_hash,
         contract_address,
     })
 }
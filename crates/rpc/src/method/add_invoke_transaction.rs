@@ -3,7 +3,7 @@ use starknet_gateway_client::GatewayApi;
 use starknet_gateway_types::error::SequencerError;
 
 use crate::context::RpcContext;
-use crate::v02::types::request::BroadcastedInvokeTransaction;
+use crate::v02::types::request::{BroadcastedInvokeTransaction, BroadcastedTransaction};
 
 #[derive(serde::Deserialize, Debug, PartialEq, Eq)]
 #[serde(tag = "type")]
@@ -54,6 +54,8 @@ impl From<AddInvokeTransactionError> for crate::error::ApplicationError {
     }
 }
 
+crate::validation::impl_from_validation_error!(AddInvokeTransactionError);
+
 impl From<SequencerError> for AddInvokeTransactionError {
     fn from(e: SequencerError) -> Self {
         use starknet_gateway_types::error::KnownStarknetErrorCode::{
@@ -97,11 +99,19 @@ pub async fn add_invoke_transaction(
     input: Input,
 ) -> Result<Output, AddInvokeTransactionError> {
     let Transaction::Invoke(tx) = input.invoke_transaction;
-    let response = add_invoke_transaction_impl(&context, tx).await?;
-
-    Ok(Output {
-        transaction_hash: response.transaction_hash,
-    })
+    crate::validation::validate_submitted(&context, BroadcastedTransaction::Invoke(tx.clone()))
+        .await?;
+    let response = add_invoke_transaction_impl(&context, tx.clone())
+        .await
+        .map(|response| response.transaction_hash);
+    let transaction_hash = crate::gossip::gossip_submitted(
+        &context,
+        BroadcastedTransaction::Invoke(tx),
+        response,
+        |transaction| transaction.hash,
+    )?;
+
+    Ok(Output { transaction_hash })
 }
 
 pub(crate) async fn add_invoke_transaction_impl(
@@ -4,6 +4,7 @@ use pathfinder_executor::{ExecutionState, L1BlobDataAvailability};
 
 use crate::context::RpcContext;
 use crate::error::ApplicationError;
+use crate::executor::{map_state_overrides, StateOverride};
 
 #[derive(Debug)]
 pub enum CallError {
@@ -64,6 +65,8 @@ impl From<CallError> for ApplicationError {
 pub struct Input {
     pub request: FunctionCall,
     pub block_id: BlockId,
+    #[serde(default)]
+    pub state_overrides: Vec<StateOverride>,
 }
 
 #[derive(serde::Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -115,7 +118,8 @@ pub async fn call(context: RpcContext, input: Input) -> Result<Output, CallError
             pending,
             L1BlobDataAvailability::Disabled,
             context.config.custom_versioned_constants,
-        );
+        )
+        .with_state_overrides(map_state_overrides(input.state_overrides));
 
         let result = pathfinder_executor::call(
             state,
@@ -170,6 +174,7 @@ mod tests {
                     calldata: vec![call_param!("0x1234"), call_param!("0x2345")],
                 },
                 block_id: block_hash!("0xbbbbbbbb").into(),
+                state_overrides: vec![],
             };
             assert_eq!(input, expected);
         }
@@ -189,9 +194,50 @@ mod tests {
                     calldata: vec![call_param!("0x1234"), call_param!("0x2345")],
                 },
                 block_id: block_hash!("0xbbbbbbbb").into(),
+                state_overrides: vec![],
             };
             assert_eq!(input, expected);
         }
+
+        #[test]
+        fn state_overrides() {
+            let named = json!({
+                "request": { "contract_address": "0xabcde", "entry_point_selector": "0xee", "calldata": [] },
+                "block_id": "latest",
+                "state_overrides": [{
+                    "contract_address": "0xabcde",
+                    "nonce": "0x5",
+                    "storage": [{ "key": "0x1", "value": "0x2" }],
+                    "strk_balance": "0x1000"
+                }]
+            });
+
+            let input = serde_json::from_value::<Input>(named).unwrap();
+            assert_eq!(
+                input.state_overrides,
+                vec![crate::executor::StateOverride {
+                    contract_address: contract_address!("0xabcde"),
+                    nonce: Some(contract_nonce!("0x5")),
+                    class_hash: None,
+                    storage: vec![crate::executor::StorageOverride {
+                        key: storage_address!("0x1"),
+                        value: storage_value!("0x2"),
+                    }],
+                    eth_balance: None,
+                    strk_balance: Some(0x1000),
+                }]
+            );
+
+            let too_large = json!({
+                "request": { "contract_address": "0xabcde", "entry_point_selector": "0xee", "calldata": [] },
+                "block_id": "latest",
+                "state_overrides": [{
+                    "contract_address": "0xabcde",
+                    "eth_balance": "0x100000000000000000000000000000000"
+                }]
+            });
+            serde_json::from_value::<Input>(too_large).unwrap_err();
+        }
     }
 
     mod in_memory {
@@ -292,6 +338,7 @@ mod tests {
                     calldata: vec![CallParam(*test_key.get())],
                 },
                 block_id: BlockId::Latest,
+                state_overrides: vec![],
             };
             let result = call(context, input).await.unwrap();
             assert_eq!(result, Output(vec![CallResultValue(test_value.0)]));
@@ -319,6 +366,7 @@ mod tests {
                     calldata: vec![CallParam(*test_key.get())],
                 },
                 block_id: BlockId::Latest,
+                state_overrides: vec![],
             };
             let result = call(context.clone(), input).await.unwrap();
             assert_eq!(result, Output(vec![CallResultValue(test_value.0)]));
@@ -331,11 +379,41 @@ mod tests {
                     calldata: vec![CallParam(*test_key.get())],
                 },
                 block_id: BlockId::Pending,
+                state_overrides: vec![],
             };
             let result = call(context, input).await.unwrap();
             assert_eq!(result, Output(vec![CallResultValue(new_value.0)]));
         }
 
+        #[tokio::test]
+        async fn storage_override() {
+            let (context, _last_block_header, contract_address, test_key, _test_value) =
+                test_context().await;
+
+            let overridden_value = StorageValue(felt!("0x42"));
+            let input = Input {
+                request: FunctionCall {
+                    contract_address,
+                    entry_point_selector: EntryPoint::hashed(b"get_value"),
+                    calldata: vec![CallParam(*test_key.get())],
+                },
+                block_id: BlockId::Latest,
+                state_overrides: vec![crate::executor::StateOverride {
+                    contract_address,
+                    nonce: None,
+                    class_hash: None,
+                    storage: vec![crate::executor::StorageOverride {
+                        key: test_key,
+                        value: overridden_value,
+                    }],
+                    eth_balance: None,
+                    strk_balance: None,
+                }],
+            };
+            let result = call(context, input).await.unwrap();
+            assert_eq!(result, Output(vec![CallResultValue(overridden_value.0)]));
+        }
+
         #[tokio::test]
         async fn contract_deployed_in_pending() {
             let (context, last_block_header, _contract_address, test_key, _test_value) =
@@ -359,6 +437,7 @@ mod tests {
                     calldata: vec![CallParam(*test_key.get())],
                 },
                 block_id: BlockId::Pending,
+                state_overrides: vec![],
             };
             let result = call(context.clone(), input).await.unwrap();
             assert_eq!(result, Output(vec![CallResultValue(new_value.0)]));
@@ -404,6 +483,7 @@ mod tests {
                     calldata: vec![],
                 },
                 block_id: BlockId::Pending,
+                state_overrides: vec![],
             };
             let result = call(context.clone(), input).await.unwrap();
             assert_eq!(result, Output(vec![CallResultValue(storage_value.0)]));
@@ -478,6 +558,7 @@ mod tests {
                     calldata: vec![],
                 },
                 block_id: BlockId::Latest,
+                state_overrides: vec![],
             };
             let result = call(context, input).await.unwrap();
             assert_eq!(result, Output(vec![CallResultValue(storage_value.0)]));
@@ -541,6 +622,7 @@ mod tests {
             let input = Input {
                 request: valid_mainnet_call(),
                 block_id: BlockId::Hash(block_hash_bytes!(b"nonexistent")),
+                state_overrides: vec![],
             };
             let error = call(context, input).await;
             assert_matches::assert_matches!(error, Err(CallError::BlockNotFound));
@@ -556,6 +638,7 @@ mod tests {
                     ..valid_mainnet_call()
                 },
                 block_id: BLOCK_5,
+                state_overrides: vec![],
             };
             let error = call(context, input).await;
             assert_matches::assert_matches!(error, Err(CallError::ContractNotFound));
@@ -571,6 +654,7 @@ mod tests {
                     ..valid_mainnet_call()
                 },
                 block_id: BLOCK_5,
+                state_overrides: vec![],
             };
             let error = call(context, input).await;
             assert_matches::assert_matches!(error, Err(CallError::Custom(_)));
@@ -583,6 +667,7 @@ mod tests {
             let input = Input {
                 request: valid_mainnet_call(),
                 block_id: BLOCK_5,
+                state_overrides: vec![],
             };
 
             let result = call(context, input).await.unwrap();
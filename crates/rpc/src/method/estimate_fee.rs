@@ -4,6 +4,7 @@ use pathfinder_executor::{ExecutionState, L1BlobDataAvailability};
 
 use crate::context::RpcContext;
 use crate::error::ApplicationError;
+use crate::executor::{map_state_overrides, BlockOverrides, StateOverride};
 use crate::v02::types::request::BroadcastedTransaction;
 use crate::v06::method::estimate_fee::{SimulationFlag, SimulationFlags};
 
@@ -13,6 +14,10 @@ pub struct Input {
     pub request: Vec<BroadcastedTransaction>,
     pub simulation_flags: SimulationFlags,
     pub block_id: BlockId,
+    #[serde(default)]
+    pub state_overrides: Vec<StateOverride>,
+    #[serde(default)]
+    pub block_overrides: BlockOverrides,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -57,7 +62,9 @@ pub async fn estimate_fee(context: RpcContext, input: Input) -> Result<Output, E
             // TODO Disabled for v06
             L1BlobDataAvailability::Enabled,
             context.config.custom_versioned_constants,
-        );
+        )
+        .with_state_overrides(map_state_overrides(input.state_overrides))
+        .with_block_overrides(input.block_overrides.into());
 
         let skip_validate = input
             .simulation_flags
@@ -347,6 +354,8 @@ mod tests {
             ],
             simulation_flags: SimulationFlags(vec![]),
             block_id: BlockId::Number(last_block_header.number),
+            state_overrides: vec![],
+            block_overrides: Default::default(),
         };
         let result = estimate_fee(context, input).await.unwrap();
         let declare_expected = pathfinder_executor::types::FeeEstimate {
@@ -432,6 +441,8 @@ mod tests {
             ],
             simulation_flags: SimulationFlags(vec![]),
             block_id: BlockId::Number(last_block_header.number),
+            state_overrides: vec![],
+            block_overrides: Default::default(),
         };
         let result = estimate_fee(context, input).await.unwrap();
         let declare_expected = pathfinder_executor::types::FeeEstimate {
@@ -0,0 +1,263 @@
+use anyhow::Context;
+use pathfinder_common::receipt::ExecutionStatus;
+use pathfinder_common::TransactionHash;
+use primitive_types::H256;
+
+use crate::context::RpcContext;
+use crate::dto::{TxnExecutionStatus, TxnStatus};
+
+#[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct Input {
+    /// The hash of the L1 transaction which sent the messages.
+    transaction_hash: H256,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Output(Vec<MessageStatus>);
+
+#[derive(Debug, PartialEq)]
+pub struct MessageStatus {
+    /// The L1 handler transaction which executes the message on L2.
+    transaction_hash: TransactionHash,
+    finality_status: FinalityStatus,
+    execution_status: Option<ExecutionStatus>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum FinalityStatus {
+    Received,
+    AcceptedOnL2,
+    AcceptedOnL1,
+}
+
+crate::error::generate_rpc_error_subset!(Error: TxnHashNotFound);
+
+/// Returns the status of the L1 handler transactions for all messages sent to
+/// L2 by an L1 transaction.
+///
+/// Messages are known as soon as Pathfinder has read their L1 block, also
+/// before it is finalized. Messages sent before Pathfinder started tracking the
+/// core contract's logs are only known if they were backfilled. Messages which
+/// have not yet been executed on L2 are reported as received.
+pub async fn get_messages_status(context: RpcContext, input: Input) -> Result<Output, Error> {
+    let span = tracing::Span::current();
+
+    tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let db_tx = db.transaction().context("Creating database transaction")?;
+
+        let l1_handlers = db_tx
+            .l1_handler_transactions(input.transaction_hash)
+            .context("Querying L1 handler transactions")?;
+        if l1_handlers.is_empty() {
+            return Err(Error::TxnHashNotFound);
+        }
+
+        let pending = context
+            .pending_data
+            .get(&db_tx)
+            .context("Querying pending data")?;
+
+        l1_handlers
+            .into_iter()
+            .map(|transaction_hash| {
+                if let Some((receipt, _)) = pending
+                    .block
+                    .transaction_receipts
+                    .iter()
+                    .find(|(rx, _)| rx.transaction_hash == transaction_hash)
+                {
+                    return Ok(MessageStatus {
+                        transaction_hash,
+                        finality_status: FinalityStatus::AcceptedOnL2,
+                        execution_status: Some(receipt.execution_status.clone()),
+                    });
+                }
+
+                let Some((_, receipt, _, block_hash)) = db_tx
+                    .transaction_with_receipt(transaction_hash)
+                    .context("Fetching receipt from database")?
+                else {
+                    return Ok(MessageStatus {
+                        transaction_hash,
+                        finality_status: FinalityStatus::Received,
+                        execution_status: None,
+                    });
+                };
+
+                let l1_accepted = db_tx
+                    .block_is_l1_accepted(block_hash.into())
+                    .context("Querying block's status")?;
+
+                Ok(MessageStatus {
+                    transaction_hash,
+                    finality_status: if l1_accepted {
+                        FinalityStatus::AcceptedOnL1
+                    } else {
+                        FinalityStatus::AcceptedOnL2
+                    },
+                    execution_status: Some(receipt.execution_status),
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()
+            .map(Output)
+    })
+    .await
+    .context("Joining database task")?
+}
+
+impl crate::dto::serialize::SerializeForVersion for Output {
+    fn serialize(
+        &self,
+        serializer: crate::dto::serialize::Serializer,
+    ) -> Result<crate::dto::serialize::Ok, crate::dto::serialize::Error> {
+        serializer.serialize_iter(self.0.len(), &mut self.0.iter())
+    }
+}
+
+impl crate::dto::serialize::SerializeForVersion for &MessageStatus {
+    fn serialize(
+        &self,
+        serializer: crate::dto::serialize::Serializer,
+    ) -> Result<crate::dto::serialize::Ok, crate::dto::serialize::Error> {
+        let finality_status = match self.finality_status {
+            FinalityStatus::Received => TxnStatus::Received,
+            FinalityStatus::AcceptedOnL2 => TxnStatus::AcceptedOnL2,
+            FinalityStatus::AcceptedOnL1 => TxnStatus::AcceptedOnL1,
+        };
+
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field(
+            "transaction_hash",
+            &crate::dto::TxnHash(&self.transaction_hash),
+        )?;
+        serializer.serialize_field("finality_status", &finality_status)?;
+        match &self.execution_status {
+            None => {}
+            Some(ExecutionStatus::Succeeded) => {
+                serializer.serialize_field("execution_status", &TxnExecutionStatus::Succeeded)?;
+            }
+            Some(ExecutionStatus::Reverted { reason }) => {
+                serializer.serialize_field("execution_status", &TxnExecutionStatus::Reverted)?;
+                serializer.serialize_field("failure_reason", reason)?;
+            }
+        }
+        serializer.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::{EthereumAddress, L1ToL2MessageNonce};
+    use pathfinder_crypto::Felt;
+    use pathfinder_ethereum::{L1ToL2Message, MessageLog, StarknetMessage};
+    use primitive_types::H160;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn output_serialization() {
+        use crate::dto::serialize::SerializeForVersion;
+
+        let output = Output(vec![
+            MessageStatus {
+                transaction_hash: transaction_hash!("0x1"),
+                finality_status: FinalityStatus::Received,
+                execution_status: None,
+            },
+            MessageStatus {
+                transaction_hash: transaction_hash!("0x2"),
+                finality_status: FinalityStatus::AcceptedOnL1,
+                execution_status: Some(ExecutionStatus::Succeeded),
+            },
+            MessageStatus {
+                transaction_hash: transaction_hash!("0x3"),
+                finality_status: FinalityStatus::AcceptedOnL2,
+                execution_status: Some(ExecutionStatus::Reverted {
+                    reason: "out of gas".to_owned(),
+                }),
+            },
+        ]);
+
+        let encoded = output.serialize(Default::default()).unwrap();
+        let expected = json!([
+            {"transaction_hash": "0x1", "finality_status": "RECEIVED"},
+            {
+                "transaction_hash": "0x2",
+                "finality_status": "ACCEPTED_ON_L1",
+                "execution_status": "SUCCEEDED"
+            },
+            {
+                "transaction_hash": "0x3",
+                "finality_status": "ACCEPTED_ON_L2",
+                "execution_status": "REVERTED",
+                "failure_reason": "out of gas"
+            },
+        ]);
+        assert_eq!(encoded, expected);
+    }
+
+    #[tokio::test]
+    async fn received() {
+        let context = RpcContext::for_tests();
+        let l1_transaction_hash = H256::from_low_u64_be(0xabcd);
+
+        let message = L1ToL2Message {
+            from_address: EthereumAddress(H160::from_low_u64_be(0x1)),
+            to_address: contract_address!("0x2"),
+            selector: entry_point!("0x3"),
+            payload: vec![],
+            nonce: L1ToL2MessageNonce(Felt::from_u64(4)),
+        };
+        let expected = message
+            .l1_handler_transaction()
+            .calculate_hash(context.chain_id);
+
+        let mut db = context.storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+        tx.insert_l1_messages(
+            &[MessageLog {
+                l1_block_number: 1,
+                l1_transaction_hash,
+                log_index: 0,
+                message: StarknetMessage::L1ToL2(message),
+            }],
+            context.chain_id,
+        )
+        .unwrap();
+        tx.commit().unwrap();
+
+        let input = Input {
+            transaction_hash: l1_transaction_hash,
+        };
+        let output = get_messages_status(context, input).await.unwrap();
+
+        assert_eq!(
+            output,
+            Output(vec![MessageStatus {
+                transaction_hash: expected,
+                finality_status: FinalityStatus::Received,
+                execution_status: None,
+            }])
+        );
+    }
+
+    #[tokio::test]
+    async fn unknown_l1_transaction() {
+        let context = RpcContext::for_tests();
+        let input = Input {
+            transaction_hash: H256::from_low_u64_be(0xabcd),
+        };
+        let err = get_messages_status(context, input).await.unwrap_err();
+
+        assert_matches!(err, Error::TxnHashNotFound);
+    }
+}
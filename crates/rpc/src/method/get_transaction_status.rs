@@ -1,5 +1,6 @@
 use anyhow::Context;
 use pathfinder_common::TransactionHash;
+use pathfinder_ethereum::L1Depth;
 
 use crate::context::RpcContext;
 use crate::dto::TxnExecutionStatus;
@@ -15,6 +16,9 @@ pub enum Output {
     Rejected,
     AcceptedOnL1(TxnExecutionStatus),
     AcceptedOnL2(TxnExecutionStatus),
+    /// Accepted on L2, and part of the Starknet state on L1 at a depth which
+    /// is not finalized yet.
+    SeenOnL1(TxnExecutionStatus, L1Depth),
 }
 
 crate::error::generate_rpc_error_subset!(Error: TxnHashNotFound);
@@ -53,14 +57,15 @@ pub async fn get_transaction_status(context: RpcContext, input: Input) -> Result
             return anyhow::Ok(None);
         };
 
-        let l1_accepted = db_tx
-            .block_is_l1_accepted(block_hash.into())
+        let l1_depth = db_tx
+            .block_l1_depth(block_hash.into())
             .context("Querying block's status")?;
 
-        Ok(Some(if l1_accepted {
-            Output::AcceptedOnL1((&receipt.execution_status).into())
-        } else {
-            Output::AcceptedOnL2((&receipt.execution_status).into())
+        let execution_status = (&receipt.execution_status).into();
+        Ok(Some(match l1_depth {
+            Some(L1Depth::Finalized) => Output::AcceptedOnL1(execution_status),
+            Some(depth) => Output::SeenOnL1(execution_status, depth),
+            None => Output::AcceptedOnL2(execution_status),
         }))
     })
     .await
@@ -111,7 +116,7 @@ impl Output {
             Output::Received => TxnStatus::Received,
             Output::Rejected => TxnStatus::Rejected,
             Output::AcceptedOnL1(_) => TxnStatus::AcceptedOnL1,
-            Output::AcceptedOnL2(_) => TxnStatus::AcceptedOnL2,
+            Output::AcceptedOnL2(_) | Output::SeenOnL1(..) => TxnStatus::AcceptedOnL2,
         }
     }
 
@@ -120,6 +125,18 @@ impl Output {
             Output::Received | Output::Rejected => None,
             Output::AcceptedOnL1(x) => Some(*x),
             Output::AcceptedOnL2(x) => Some(*x),
+            Output::SeenOnL1(x, _) => Some(*x),
+        }
+    }
+
+    /// The L1 depth of transactions which are not finalized on L1 yet. This
+    /// is an extension of the specification.
+    fn l1_depth(&self) -> Option<(&'static str, Option<u64>)> {
+        match self {
+            Output::SeenOnL1(_, L1Depth::Latest) => Some(("LATEST", None)),
+            Output::SeenOnL1(_, L1Depth::Confirmations(n)) => Some(("CONFIRMED", Some(*n))),
+            Output::SeenOnL1(_, L1Depth::Safe) => Some(("SAFE", None)),
+            _ => None,
         }
     }
 }
@@ -132,6 +149,10 @@ impl crate::dto::serialize::SerializeForVersion for Output {
         let mut serializer = serializer.serialize_struct()?;
         serializer.serialize_field("finality_status", &self.finality_status())?;
         serializer.serialize_optional("execution_status", self.execution_status())?;
+        if let Some((status, confirmations)) = self.l1_depth() {
+            serializer.serialize_field("l1_finality_status", &status)?;
+            serializer.serialize_optional("l1_confirmations", confirmations)?;
+        }
         serializer.end()
     }
 }
@@ -156,6 +177,19 @@ mod tests {
         Output::AcceptedOnL2(TxnExecutionStatus::Reverted),
         json!({"finality_status":"ACCEPTED_ON_L2","execution_status":"REVERTED"})
     )]
+    #[case::seen_on_l1_confirmed(
+        Output::SeenOnL1(TxnExecutionStatus::Succeeded, L1Depth::Confirmations(12)),
+        json!({
+            "finality_status":"ACCEPTED_ON_L2",
+            "execution_status":"SUCCEEDED",
+            "l1_finality_status":"CONFIRMED",
+            "l1_confirmations":12
+        })
+    )]
+    #[case::seen_on_l1_safe(
+        Output::SeenOnL1(TxnExecutionStatus::Reverted, L1Depth::Safe),
+        json!({"finality_status":"ACCEPTED_ON_L2","execution_status":"REVERTED","l1_finality_status":"SAFE"})
+    )]
     fn output_serialization(#[case] output: Output, #[case] expected: serde_json::Value) {
         use crate::dto::serialize::SerializeForVersion;
         let encoded = output.serialize(Default::default()).unwrap();
@@ -188,6 +222,38 @@ mod tests {
         assert_eq!(status, Output::AcceptedOnL2(TxnExecutionStatus::Succeeded));
     }
 
+    #[tokio::test]
+    async fn seen_on_l1() {
+        let context = RpcContext::for_tests();
+        {
+            let mut db = context.storage.connection().unwrap();
+            let db = db.transaction().unwrap();
+            let (block_number, block_hash) = db
+                .block_id(pathfinder_common::BlockNumber::new_or_panic(1).into())
+                .unwrap()
+                .unwrap();
+            let state = pathfinder_ethereum::EthereumStateUpdate {
+                state_root: Default::default(),
+                block_number,
+                block_hash,
+            };
+            db.upsert_l1_state_at_depth(L1Depth::Latest, 1, &state)
+                .unwrap();
+            db.commit().unwrap();
+        }
+
+        // This transaction is in block 1 which is not L1 accepted.
+        let input = Input {
+            transaction_hash: transaction_hash_bytes!(b"txn 1"),
+        };
+        let status = get_transaction_status(context, input).await.unwrap();
+
+        assert_eq!(
+            status,
+            Output::SeenOnL1(TxnExecutionStatus::Succeeded, L1Depth::Latest)
+        );
+    }
+
     #[tokio::test]
     async fn pending() {
         let context = RpcContext::for_tests_with_pending().await;
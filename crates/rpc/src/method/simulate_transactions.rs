@@ -3,14 +3,41 @@ use pathfinder_common::BlockId;
 use pathfinder_executor::TransactionExecutionError;
 
 use crate::context::RpcContext;
-use crate::executor::ExecutionStateError;
-use crate::v06::method::simulate_transactions as v06;
+use crate::executor::{map_state_overrides, BlockOverrides, ExecutionStateError, StateOverride};
+use crate::v02::types::request::BroadcastedTransaction;
+
+#[derive(serde::Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Input {
+    pub block_id: BlockId,
+    pub transactions: Vec<BroadcastedTransaction>,
+    pub simulation_flags: SimulationFlags,
+    #[serde(default)]
+    pub state_overrides: Vec<StateOverride>,
+    #[serde(default)]
+    pub block_overrides: BlockOverrides,
+}
+
+#[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct SimulationFlags(pub Vec<SimulationFlag>);
+
+#[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+pub enum SimulationFlag {
+    #[serde(rename = "SKIP_FEE_CHARGE")]
+    SkipFeeCharge,
+    #[serde(rename = "SKIP_VALIDATE")]
+    SkipValidate,
+    /// Includes the state read and written by each transaction and each of
+    /// its calls in the output.
+    #[serde(rename = "RETURN_ACCESS_LIST")]
+    ReturnAccessList,
+}
 
 pub struct Output(Vec<pathfinder_executor::types::TransactionSimulation>);
 
 pub async fn simulate_transactions(
     context: RpcContext,
-    input: v06::SimulateTransactionInput,
+    input: Input,
 ) -> Result<Output, SimulateTransactionError> {
     let span = tracing::Span::current();
     tokio::task::spawn_blocking(move || {
@@ -20,13 +47,19 @@ pub async fn simulate_transactions(
             .simulation_flags
             .0
             .iter()
-            .any(|flag| flag == &v06::dto::SimulationFlag::SkipValidate);
+            .any(|flag| flag == &SimulationFlag::SkipValidate);
 
         let skip_fee_charge = input
             .simulation_flags
             .0
             .iter()
-            .any(|flag| flag == &v06::dto::SimulationFlag::SkipFeeCharge);
+            .any(|flag| flag == &SimulationFlag::SkipFeeCharge);
+
+        let return_access_lists = input
+            .simulation_flags
+            .0
+            .iter()
+            .any(|flag| flag == &SimulationFlag::ReturnAccessList);
 
         let mut db = context
             .execution_storage
@@ -63,7 +96,9 @@ pub async fn simulate_transactions(
             // TODO This is disabled for v06
             pathfinder_executor::L1BlobDataAvailability::Enabled,
             context.config.custom_versioned_constants,
-        );
+        )
+        .with_state_overrides(map_state_overrides(input.state_overrides))
+        .with_block_overrides(input.block_overrides.into());
 
         let transactions = input
             .transactions
@@ -71,8 +106,13 @@ pub async fn simulate_transactions(
             .map(|tx| crate::executor::map_broadcasted_transaction(&tx, context.chain_id))
             .collect::<Result<Vec<_>, _>>()?;
 
-        let txs =
-            pathfinder_executor::simulate(state, transactions, skip_validate, skip_fee_charge)?;
+        let txs = pathfinder_executor::simulate(
+            state,
+            transactions,
+            skip_validate,
+            skip_fee_charge,
+            return_access_lists,
+        )?;
         Ok(Output(txs))
     })
     .await
@@ -107,6 +147,10 @@ impl crate::dto::serialize::SerializeForVersion for TransactionSimulation<'_> {
                 include_state_diff: true,
             },
         )?;
+        serializer.serialize_optional(
+            "access_list",
+            self.0.access_list.as_ref().map(crate::dto::AccessList),
+        )?;
         serializer.end()
     }
 }
@@ -191,7 +235,7 @@ pub(crate) mod tests {
         ERC20_CONTRACT_DEFINITION_CLASS_HASH,
     };
 
-    use super::simulate_transactions;
+    use super::{simulate_transactions, Input, SimulationFlag, SimulationFlags};
     use crate::context::RpcContext;
     use crate::dto::serialize::{SerializeForVersion, Serializer};
     use crate::v02::types::request::{
@@ -202,8 +246,8 @@ pub(crate) mod tests {
     use crate::v02::types::ContractClass;
     use crate::v03::method::get_state_update::types::{DeployedContract, Nonce, StateDiff};
     use crate::v06::method::call::FunctionCall;
+    use crate::v06::method::simulate_transactions::dto;
     use crate::v06::method::simulate_transactions::tests::setup_storage_with_starknet_version;
-    use crate::v06::method::simulate_transactions::{dto, SimulateTransactionInput};
     use crate::v06::types::PriceUnit;
     use crate::RpcVersion;
 
@@ -227,7 +271,7 @@ pub(crate) mod tests {
             ],
             "simulation_flags": ["SKIP_FEE_CHARGE"]
         });
-        let input = SimulateTransactionInput::deserialize(&input_json).unwrap();
+        let input = Input::deserialize(&input_json).unwrap();
 
         let expected: Vec<dto::SimulatedTransaction> = {
             use dto::*;
@@ -328,6 +372,119 @@ pub(crate) mod tests {
         pretty_assertions_sorted::assert_eq!(result, expected);
     }
 
+    #[tokio::test]
+    async fn block_overrides() {
+        let (context, _, _, _) = crate::test_setup::test_context().await;
+
+        let input_json = serde_json::json!({
+            "block_id": {"block_number": 1},
+            "transactions": [
+                {
+                    "contract_address_salt": "0x46c0d4abf0192a788aca261e58d7031576f7d8ea5229f452b0f23e691dd5971",
+                    "max_fee": "0x0",
+                    "signature": [],
+                    "class_hash": DUMMY_ACCOUNT_CLASS_HASH,
+                    "nonce": "0x0",
+                    "version": TransactionVersion::ONE_WITH_QUERY_VERSION,
+                    "constructor_calldata": [],
+                    "type": "DEPLOY_ACCOUNT"
+                }
+            ],
+            "simulation_flags": ["SKIP_FEE_CHARGE"],
+            "block_overrides": {
+                "timestamp": 1000,
+                "l1_gas_price": {"price_in_wei": "0x3"},
+                "l1_data_gas_price": {"price_in_wei": "0x5"}
+            }
+        });
+        let input = Input::deserialize(&input_json).unwrap();
+
+        let result = simulate_transactions(context, input).await.unwrap();
+
+        let fee_estimation = &result.0[0].fee_estimation;
+        assert_eq!(fee_estimation.gas_price, 3.into());
+        assert_eq!(fee_estimation.data_gas_price, 5.into());
+        assert_eq!(
+            fee_estimation.overall_fee,
+            fee_estimation.gas_consumed * 3 + fee_estimation.data_gas_consumed * 5
+        );
+    }
+
+    #[test]
+    fn block_overrides_reject_invalid_sequencer_address() {
+        let input_json = |sequencer_address: &str| {
+            serde_json::json!({
+                "block_id": {"block_number": 1},
+                "transactions": [],
+                "simulation_flags": [],
+                "block_overrides": {"sequencer_address": sequencer_address}
+            })
+        };
+
+        let input = Input::deserialize(&input_json(
+            "0x7ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+        ))
+        .unwrap();
+        assert_eq!(
+            input.block_overrides.sequencer_address,
+            Some(sequencer_address!(
+                "0x7ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff"
+            ))
+        );
+
+        let too_large = "0x800000000000000000000000000000000000000000000000000000000000000";
+        Input::deserialize(&input_json(too_large)).unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn access_list() {
+        let (context, _, _, _) = crate::test_setup::test_context().await;
+
+        let input_json = serde_json::json!({
+            "block_id": {"block_number": 1},
+            "transactions": [
+                {
+                    "contract_address_salt": "0x46c0d4abf0192a788aca261e58d7031576f7d8ea5229f452b0f23e691dd5971",
+                    "max_fee": "0x0",
+                    "signature": [],
+                    "class_hash": DUMMY_ACCOUNT_CLASS_HASH,
+                    "nonce": "0x0",
+                    "version": TransactionVersion::ONE_WITH_QUERY_VERSION,
+                    "constructor_calldata": [],
+                    "type": "DEPLOY_ACCOUNT"
+                }
+            ],
+            "simulation_flags": ["SKIP_FEE_CHARGE", "RETURN_ACCESS_LIST"]
+        });
+        let input = Input::deserialize(&input_json).unwrap();
+
+        let result = simulate_transactions(context.clone(), input).await.unwrap();
+        let access_list = result.0[0].access_list.as_ref().unwrap();
+
+        // The account was deployed, after checking that it did not exist yet.
+        assert_eq!(access_list.writes.class_hashes.len(), 1);
+        let account = access_list.writes.class_hashes.first().unwrap();
+        assert!(access_list.reads.class_hashes.contains(account));
+        assert!(access_list.writes.nonces.contains(account));
+        assert!(!access_list.calls.is_empty());
+        assert!(access_list
+            .calls
+            .iter()
+            .all(|call| call.contract_address == *account));
+        // The deployment is attributed to the constructor.
+        assert!(access_list
+            .calls
+            .iter()
+            .any(|call| call.class_hash_writes.contains(account)));
+
+        let mut input_json = input_json;
+        input_json["simulation_flags"] = serde_json::json!(["SKIP_FEE_CHARGE"]);
+        let input = Input::deserialize(&input_json).unwrap();
+
+        let result = simulate_transactions(context, input).await.unwrap();
+        assert_eq!(result.0[0].access_list, None);
+    }
+
     #[tokio::test]
     async fn declare_cairo_v0_class() {
         pub const CAIRO0_DEFINITION: &[u8] =
@@ -358,10 +515,12 @@ pub(crate) mod tests {
             },
         ));
 
-        let input = SimulateTransactionInput {
+        let input = Input {
             block_id: last_block_header.number.into(),
             transactions: vec![declare],
-            simulation_flags: dto::SimulationFlags(vec![]),
+            simulation_flags: SimulationFlags(vec![]),
+            state_overrides: vec![],
+            block_overrides: Default::default(),
         };
 
         let result = simulate_transactions(context, input).await.unwrap();
@@ -1586,7 +1745,7 @@ pub(crate) mod tests {
         ) = setup_storage_with_starknet_version(StarknetVersion::new(0, 13, 1, 1)).await;
         let context = RpcContext::for_tests().with_storage(storage);
 
-        let input = SimulateTransactionInput {
+        let input = Input {
             transactions: vec![
                 fixtures::input::declare(account_contract_address),
                 fixtures::input::universal_deployer(
@@ -1597,7 +1756,9 @@ pub(crate) mod tests {
                 fixtures::input::invoke_v3(account_contract_address),
             ],
             block_id: BlockId::Number(last_block_header.number),
-            simulation_flags: dto::SimulationFlags(vec![]),
+            simulation_flags: SimulationFlags(vec![]),
+            state_overrides: vec![],
+            block_overrides: Default::default(),
         };
         let result = simulate_transactions(context, input).await.unwrap();
 
@@ -1643,7 +1804,7 @@ pub(crate) mod tests {
         ) = setup_storage_with_starknet_version(StarknetVersion::new(0, 13, 1, 1)).await;
         let context = RpcContext::for_tests().with_storage(storage);
 
-        let input = SimulateTransactionInput {
+        let input = Input {
             transactions: vec![
                 fixtures::input::declare(account_contract_address),
                 fixtures::input::universal_deployer(
@@ -1654,7 +1815,9 @@ pub(crate) mod tests {
                 fixtures::input::invoke_v3(account_contract_address),
             ],
             block_id: BlockId::Number(last_block_header.number),
-            simulation_flags: dto::SimulationFlags(vec![dto::SimulationFlag::SkipFeeCharge]),
+            simulation_flags: SimulationFlags(vec![SimulationFlag::SkipFeeCharge]),
+            state_overrides: vec![],
+            block_overrides: Default::default(),
         };
         let result = simulate_transactions(context, input).await.unwrap();
 
@@ -1696,7 +1859,7 @@ pub(crate) mod tests {
         ) = setup_storage_with_starknet_version(StarknetVersion::new(0, 13, 1, 1)).await;
         let context = RpcContext::for_tests().with_storage(storage);
 
-        let input = SimulateTransactionInput {
+        let input = Input {
             transactions: vec![
                 fixtures::input::declare(account_contract_address),
                 fixtures::input::universal_deployer(
@@ -1707,7 +1870,9 @@ pub(crate) mod tests {
                 fixtures::input::invoke_v3(account_contract_address),
             ],
             block_id: BlockId::Number(last_block_header.number),
-            simulation_flags: dto::SimulationFlags(vec![dto::SimulationFlag::SkipValidate]),
+            simulation_flags: SimulationFlags(vec![SimulationFlag::SkipValidate]),
+            state_overrides: vec![],
+            block_overrides: Default::default(),
         };
         let result = simulate_transactions(context, input).await.unwrap();
 
@@ -1741,4 +1906,103 @@ pub(crate) mod tests {
             .unwrap()
         );
     }
+
+    #[test_log::test(tokio::test)]
+    async fn access_list_of_declare_and_nested_calls() {
+        use std::collections::{BTreeMap, BTreeSet};
+
+        use pathfinder_common::{SierraHash, StorageAddress};
+
+        let (storage, last_block_header, account_contract_address, universal_deployer_address, _) =
+            setup_storage_with_starknet_version(StarknetVersion::new(0, 13, 1, 1)).await;
+        let context = RpcContext::for_tests().with_storage(storage);
+
+        let input = Input {
+            transactions: vec![
+                fixtures::input::declare(account_contract_address),
+                fixtures::input::universal_deployer(
+                    account_contract_address,
+                    universal_deployer_address,
+                ),
+                fixtures::input::invoke(account_contract_address),
+            ],
+            block_id: BlockId::Number(last_block_header.number),
+            simulation_flags: SimulationFlags(vec![SimulationFlag::ReturnAccessList]),
+            state_overrides: vec![],
+            block_overrides: Default::default(),
+        };
+        let result = simulate_transactions(context, input).await.unwrap();
+        let access_lists = result
+            .0
+            .iter()
+            .map(|simulation| simulation.access_list.as_ref().unwrap())
+            .collect::<Vec<_>>();
+        let account = BTreeSet::from([account_contract_address]);
+
+        // The declared class is written, and the account's nonce is attributed to
+        // its validation.
+        let declare = access_lists[0];
+        assert_eq!(
+            declare.writes.declared_classes,
+            BTreeMap::from([(SierraHash(fixtures::SIERRA_HASH.0), fixtures::CASM_HASH)])
+        );
+        assert!(declare.writes.deprecated_declared_classes.is_empty());
+        let [validate, fee_transfer] = declare.calls.as_slice() else {
+            panic!(
+                "Expected validate and fee transfer calls: {:?}",
+                declare.calls
+            );
+        };
+        assert_eq!(validate.contract_address, account_contract_address);
+        assert_eq!(validate.nonce_reads, account);
+        assert_eq!(validate.nonce_writes, account);
+        assert_eq!(validate.class_hash_reads, account);
+        assert!(validate.class_hash_writes.is_empty());
+        // The balances are read before they are changed.
+        assert_eq!(
+            fee_transfer.contract_address,
+            pathfinder_executor::ETH_FEE_TOKEN_ADDRESS
+        );
+        assert!(fee_transfer.nonce_writes.is_empty());
+        assert_eq!(
+            fee_transfer.storage_writes,
+            declare.writes.storage[&pathfinder_executor::ETH_FEE_TOKEN_ADDRESS]
+        );
+        assert!(fee_transfer
+            .storage_writes
+            .is_subset(&fee_transfer.storage_reads));
+
+        // The deployment is attributed to the deploying call, not to the
+        // constructor.
+        let deploy = &access_lists[1].calls[1].inner_calls[0];
+        assert_eq!(deploy.contract_address, universal_deployer_address);
+        let deployed = BTreeSet::from([fixtures::DEPLOYED_CONTRACT_ADDRESS]);
+        assert_eq!(deploy.class_hash_writes, deployed);
+        assert!(deploy.class_hash_reads.is_superset(&deployed));
+        let constructor = &deploy.inner_calls[0];
+        assert_eq!(
+            constructor.contract_address,
+            fixtures::DEPLOYED_CONTRACT_ADDRESS
+        );
+        assert!(constructor.class_hash_writes.is_empty());
+
+        // Only the inner call reads the contract's storage.
+        let execute = &access_lists[2].calls[1];
+        assert_eq!(execute.contract_address, account_contract_address);
+        assert!(execute.nonce_reads.is_empty());
+        assert!(execute.nonce_writes.is_empty());
+        let [get_data] = execute.inner_calls.as_slice() else {
+            panic!("Expected a single inner call: {:?}", execute.inner_calls);
+        };
+        assert_eq!(
+            get_data.contract_address,
+            fixtures::DEPLOYED_CONTRACT_ADDRESS
+        );
+        assert_eq!(
+            get_data.storage_reads,
+            BTreeSet::from([StorageAddress::from_name(b"my_storage_var")])
+        );
+        assert!(get_data.storage_writes.is_empty());
+        assert_eq!(get_data.class_hash_reads, deployed);
+    }
 }
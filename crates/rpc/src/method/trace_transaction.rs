@@ -1,4 +1,5 @@
 use anyhow::Context;
+use pathfinder_common::TransactionHash;
 use pathfinder_executor::TransactionExecutionError;
 use starknet_gateway_client::GatewayApi;
 
@@ -22,6 +23,20 @@ pub async fn trace_transaction(
     context: RpcContext,
     input: v06::TraceTransactionInput,
 ) -> Result<Output, TraceTransactionError> {
+    let (trace, include_state_diff) = trace(context, input.transaction_hash).await?;
+
+    Ok(Output {
+        trace,
+        include_state_diff,
+    })
+}
+
+/// Returns the trace and whether it includes the state diff, which traces
+/// fetched from the feeder gateway lack.
+pub(crate) async fn trace(
+    context: RpcContext,
+    transaction_hash: TransactionHash,
+) -> Result<(pathfinder_executor::types::TransactionTrace, bool), TraceTransactionError> {
     #[allow(clippy::large_enum_variant)]
     enum LocalExecution {
         Success(pathfinder_executor::types::TransactionTrace),
@@ -49,7 +64,7 @@ pub async fn trace_transaction(
                 .block
                 .transactions
                 .iter()
-                .find(|tx| tx.hash == input.transaction_hash)
+                .find(|tx| tx.hash == transaction_hash)
             {
                 let header = pending.header();
 
@@ -67,7 +82,7 @@ pub async fn trace_transaction(
                 )
             } else {
                 let block_hash = db
-                    .transaction_block_hash(input.transaction_hash)?
+                    .transaction_block_hash(transaction_hash)?
                     .ok_or(TraceTransactionError::TxnHashNotFound)?;
 
                 let header = db
@@ -79,7 +94,7 @@ pub async fn trace_transaction(
                     < VERSIONS_LOWER_THAN_THIS_SHOULD_FALL_BACK_TO_FETCHING_TRACE_FROM_GATEWAY
                 {
                     let transaction = db
-                        .transaction(input.transaction_hash)
+                        .transaction(transaction_hash)
                         .context("Fetching transaction data")?
                         .context("Transaction data missing")?;
 
@@ -116,7 +131,7 @@ pub async fn trace_transaction(
                     let trace = txs
                         .into_iter()
                         .find_map(|(tx_hash, trace)| {
-                            if tx_hash == input.transaction_hash {
+                            if tx_hash == transaction_hash {
                                 Some(trace)
                             } else {
                                 None
@@ -125,7 +140,7 @@ pub async fn trace_transaction(
                         .ok_or_else(|| {
                             TraceTransactionError::Internal(anyhow::anyhow!(
                                 "Transaction trace missing from block: {}",
-                                input.transaction_hash
+                                transaction_hash
                             ))
                         })?;
                     Ok(LocalExecution::Success(trace))
@@ -134,7 +149,7 @@ pub async fn trace_transaction(
                     Ok(LocalExecution::Unsupported(
                         transactions
                             .into_iter()
-                            .find(|tx| tx.hash == input.transaction_hash)
+                            .find(|tx| tx.hash == transaction_hash)
                             .unwrap()
                             .clone(),
                     ))
@@ -146,28 +161,20 @@ pub async fn trace_transaction(
         .context("trace_transaction: execution")??;
 
     let transaction = match local {
-        LocalExecution::Success(trace) => {
-            return Ok(Output {
-                trace,
-                include_state_diff: true,
-            })
-        }
+        LocalExecution::Success(trace) => return Ok((trace, true)),
         LocalExecution::Unsupported(tx) => tx,
     };
 
     let trace = context
         .sequencer
-        .transaction_trace(input.transaction_hash)
+        .transaction_trace(transaction_hash)
         .await
         .context("Proxying call to feeder gateway")?;
 
     let trace = map_gateway_trace(transaction, trace)?;
 
-    Ok(Output {
-        trace,
-        // State diffs are not available for traces fetched from the gateway.
-        include_state_diff: false,
-    })
+    // State diffs are not available for traces fetched from the gateway.
+    Ok((trace, false))
 }
 
 impl crate::dto::serialize::SerializeForVersion for Output {
@@ -175,11 +182,12 @@ impl crate::dto::serialize::SerializeForVersion for Output {
         &self,
         serializer: crate::dto::serialize::Serializer,
     ) -> Result<crate::dto::serialize::Ok, crate::dto::serialize::Error> {
-        crate::dto::TransactionTrace {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.flatten(&crate::dto::TransactionTrace {
             trace: &self.trace,
             include_state_diff: self.include_state_diff,
-        }
-        .serialize(serializer)
+        })?;
+        serializer.end()
     }
 }
 
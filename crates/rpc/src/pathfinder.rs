@@ -8,4 +8,6 @@ pub fn register_routes() -> RpcRouterBuilder {
         .register("pathfinder_version",              || { pathfinder_common::consts::VERGEN_GIT_DESCRIBE })
         .register("pathfinder_getProof",             methods::get_proof)
         .register("pathfinder_getTransactionStatus", methods::get_transaction_status)
+        .register("pathfinder_getConsumedMessages",  methods::get_consumed_messages)
+        .register("pathfinder_getPeerReputations",   methods::get_peer_reputations)
 }
@@ -1,5 +1,9 @@
+mod get_consumed_messages;
+mod get_peer_reputations;
 mod get_proof;
 mod get_transaction_status;
 
+pub(crate) use get_consumed_messages::get_consumed_messages;
+pub(crate) use get_peer_reputations::get_peer_reputations;
 pub(crate) use get_proof::get_proof;
 pub(crate) use get_transaction_status::get_transaction_status;
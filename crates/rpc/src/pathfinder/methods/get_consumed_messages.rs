@@ -0,0 +1,102 @@
+use anyhow::Context;
+use primitive_types::H256;
+
+use crate::context::RpcContext;
+
+#[derive(serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct GetConsumedMessagesInput {
+    /// The hash of the L1 transaction which consumed the messages.
+    transaction_hash: H256,
+}
+
+crate::error::generate_rpc_error_subset!(GetConsumedMessagesError: TxnHashNotFound);
+
+/// Returns the hashes of the L2 to L1 messages consumed on L1 by an L1
+/// transaction, in the order they were consumed.
+///
+/// Messages are known as soon as Pathfinder has read their L1 block, also
+/// before it is finalized. Messages consumed before Pathfinder started tracking
+/// the core contract's logs are only known if they were backfilled.
+pub async fn get_consumed_messages(
+    context: RpcContext,
+    input: GetConsumedMessagesInput,
+) -> Result<Vec<H256>, GetConsumedMessagesError> {
+    let span = tracing::Span::current();
+
+    tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let db_tx = db.transaction().context("Creating database transaction")?;
+
+        let messages = db_tx
+            .consumed_l2_to_l1_messages(input.transaction_hash)
+            .context("Querying consumed messages")?;
+        if messages.is_empty() {
+            return Err(GetConsumedMessagesError::TxnHashNotFound);
+        }
+
+        Ok(messages)
+    })
+    .await
+    .context("Joining database task")?
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::EthereumAddress;
+    use pathfinder_ethereum::{ConsumedL2ToL1Message, MessageLog, StarknetMessage};
+    use primitive_types::H160;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn consumed() {
+        let context = RpcContext::for_tests();
+        let l1_transaction_hash = H256::from_low_u64_be(0xabcd);
+
+        let message = StarknetMessage::ConsumedL2ToL1(ConsumedL2ToL1Message {
+            from_address: contract_address!("0x1"),
+            to_address: EthereumAddress(H160::from_low_u64_be(0x2)),
+            payload: vec![],
+        });
+        let expected = message.message_hash();
+
+        let mut db = context.storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+        tx.insert_l1_messages(
+            &[MessageLog {
+                l1_block_number: 1,
+                l1_transaction_hash,
+                log_index: 0,
+                message,
+            }],
+            context.chain_id,
+        )
+        .unwrap();
+        tx.commit().unwrap();
+
+        let input = GetConsumedMessagesInput {
+            transaction_hash: l1_transaction_hash,
+        };
+        let output = get_consumed_messages(context, input).await.unwrap();
+
+        assert_eq!(output, vec![expected]);
+    }
+
+    #[tokio::test]
+    async fn unknown_l1_transaction() {
+        let context = RpcContext::for_tests();
+        let input = GetConsumedMessagesInput {
+            transaction_hash: H256::from_low_u64_be(0xabcd),
+        };
+        let err = get_consumed_messages(context, input).await.unwrap_err();
+
+        assert_matches!(err, GetConsumedMessagesError::TxnHashNotFound);
+    }
+}
@@ -0,0 +1,62 @@
+use anyhow::Context;
+use tokio::sync::oneshot;
+
+use crate::context::RpcContext;
+use crate::peers::PeerReputation;
+
+crate::error::generate_rpc_error_subset!(GetPeerReputationsError);
+
+/// Returns the reputations of all p2p peers which were penalized for sending
+/// invalid data and have not recovered yet, lowest score first.
+///
+/// Without p2p there are no penalized peers.
+pub async fn get_peer_reputations(
+    context: RpcContext,
+) -> Result<Vec<PeerReputation>, GetPeerReputationsError> {
+    let Some(peer_reputations) = context.peer_reputations else {
+        return Ok(vec![]);
+    };
+
+    let (sender, receiver) = oneshot::channel();
+    peer_reputations
+        .send(sender)
+        .await
+        .context("Requesting peer reputations")?;
+    let reputations = receiver.await.context("Receiving peer reputations")?;
+
+    Ok(reputations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn without_p2p() {
+        let context = RpcContext::for_tests();
+        let reputations = get_peer_reputations(context).await.unwrap();
+        assert!(reputations.is_empty());
+    }
+
+    #[tokio::test]
+    async fn reported_by_p2p() {
+        let reputation = PeerReputation {
+            peer_id: "12D3KooWDJryKaxjwNCk6yTtZ4GbtbLrH7JrEUTngvStaDttLtid".to_owned(),
+            score: -50,
+            banned: false,
+        };
+
+        let (sender, mut receiver) = tokio::sync::mpsc::channel(1);
+        let context = RpcContext::for_tests().with_peer_reputations(sender);
+        tokio::spawn({
+            let reputation = reputation.clone();
+            async move {
+                let request: oneshot::Sender<_> = receiver.recv().await.unwrap();
+                request.send(vec![reputation]).unwrap();
+            }
+        });
+
+        let reputations = get_peer_reputations(context).await.unwrap();
+        assert_eq!(reputations, vec![reputation]);
+    }
+}
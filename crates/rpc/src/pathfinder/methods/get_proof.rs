@@ -3,7 +3,7 @@ use pathfinder_common::prelude::*;
 use pathfinder_common::trie::TrieNode;
 use pathfinder_common::BlockId;
 use pathfinder_crypto::Felt;
-use pathfinder_merkle_tree::{ContractsStorageTree, StorageCommitmentTree};
+use pathfinder_merkle_tree::{ClassCommitmentTree, ContractsStorageTree, StorageCommitmentTree};
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 
@@ -14,6 +14,9 @@ pub struct GetProofInput {
     pub block_id: BlockId,
     pub contract_address: ContractAddress,
     pub keys: Vec<StorageAddress>,
+    /// Sierra classes for which to generate class commitment tree proofs.
+    #[serde(default)]
+    pub class_hashes: Vec<SierraHash>,
 }
 
 // FIXME: allow `generate_rpc_error_subset!` to work with enum struct variants.
@@ -128,6 +131,19 @@ pub struct ContractData {
     storage_proofs: Vec<ProofNodes>,
 }
 
+/// Holds the membership/non-membership proof of a class in the class
+/// commitment tree.
+#[skip_serializing_none]
+#[derive(Debug, Serialize)]
+pub struct ClassProof {
+    /// The compiled class hash of the class, if it is declared at this block.
+    /// Required to verify the class commitment leaf hash.
+    compiled_class_hash: Option<CasmHash>,
+
+    /// Membership / Non-membership proof for the queried class
+    proof: ProofNodes,
+}
+
 /// Holds the membership/non-membership of a contract and its associated
 /// contract contract if the contract exists.
 #[derive(Debug, Serialize)]
@@ -149,6 +165,10 @@ pub struct GetProofOutput {
 
     /// Additional contract data if it exists.
     contract_data: Option<ContractData>,
+
+    /// Membership / Non-membership proofs for the queried classes, in the
+    /// order they were requested. Present only if classes were requested.
+    class_proofs: Option<Vec<ClassProof>>,
 }
 
 /// Returns all the necessary data to trustlessly verify storage slots for a
@@ -158,10 +178,11 @@ pub async fn get_proof(
     input: GetProofInput,
 ) -> Result<GetProofOutput, GetProofError> {
     const MAX_KEYS: usize = 100;
-    if input.keys.len() > MAX_KEYS {
+    let requested = input.keys.len() + input.class_hashes.len();
+    if requested > MAX_KEYS {
         return Err(GetProofError::ProofLimitExceeded {
             limit: MAX_KEYS as u32,
-            requested: input.keys.len() as u32,
+            requested: requested as u32,
         });
     }
 
@@ -210,6 +231,35 @@ pub async fn get_proof(
                 .ok_or(GetProofError::ProofMissing)?;
         let contract_proof = ProofNodes(contract_proof);
 
+        let class_proofs = if input.class_hashes.is_empty() {
+            None
+        } else {
+            let mut class_proofs = Vec::with_capacity(input.class_hashes.len());
+            for class_hash in &input.class_hashes {
+                // Generate a proof for this class. If the class does not exist, this will
+                // be a "non membership" proof. An empty class trie, including that of
+                // blocks before Starknet 0.11.0, has no root and is proven by an empty
+                // proof.
+                let proof = if header.class_commitment == ClassCommitment::ZERO {
+                    Vec::new()
+                } else {
+                    ClassCommitmentTree::get_proof(&tx, header.number, *class_hash)
+                        .context("Creating class proof")?
+                        .ok_or(GetProofError::ProofMissing)?
+                };
+
+                let compiled_class_hash = tx
+                    .casm_hash_at(header.number.into(), ClassHash(class_hash.0))
+                    .context("Querying class's compiled class hash")?;
+
+                class_proofs.push(ClassProof {
+                    compiled_class_hash,
+                    proof: ProofNodes(proof),
+                });
+            }
+            Some(class_proofs)
+        };
+
         let contract_state_hash = tx
             .contract_state_hash(header.number, input.contract_address)
             .context("Fetching contract's state hash")?;
@@ -220,6 +270,7 @@ pub async fn get_proof(
                 class_commitment,
                 contract_proof,
                 contract_data: None,
+                class_proofs,
             });
         };
 
@@ -272,6 +323,7 @@ pub async fn get_proof(
             class_commitment,
             contract_proof,
             contract_data: Some(contract_data),
+            class_proofs,
         })
     });
 
@@ -293,12 +345,37 @@ mod tests {
             keys: (0..10_000)
                 .map(|idx| StorageAddress::new_or_panic(Felt::from_u64(idx)))
                 .collect(),
+            class_hashes: vec![],
         };
 
         let err = get_proof(context, input).await.unwrap_err();
         assert_matches::assert_matches!(err, GetProofError::ProofLimitExceeded { .. });
     }
 
+    #[tokio::test]
+    async fn limit_exceeded_with_class_hashes() {
+        let context = RpcContext::for_tests();
+        let input = GetProofInput {
+            block_id: BlockId::Latest,
+            contract_address: contract_address!("0xdeadbeef"),
+            keys: (0..50)
+                .map(|idx| StorageAddress::new_or_panic(Felt::from_u64(idx)))
+                .collect(),
+            class_hashes: (0..51)
+                .map(|idx| SierraHash::new_or_panic(Felt::from_u64(idx)))
+                .collect(),
+        };
+
+        let err = get_proof(context, input).await.unwrap_err();
+        assert_matches::assert_matches!(
+            err,
+            GetProofError::ProofLimitExceeded {
+                limit: 100,
+                requested: 101
+            }
+        );
+    }
+
     #[tokio::test]
     async fn proof_pruned() {
         let context =
@@ -336,8 +413,121 @@ mod tests {
             block_id: BlockId::Latest,
             contract_address: contract_address!("0xdeadbeef"),
             keys: vec![storage_address_bytes!(b"storage addr 0")],
+            class_hashes: vec![],
         };
         let err = get_proof(context, input).await.unwrap_err();
         assert_matches::assert_matches!(err, GetProofError::ProofMissing);
     }
+
+    mod class_proofs {
+        use pathfinder_common::hash::PoseidonHash;
+        use pathfinder_common::{calculate_class_commitment_leaf_hash, StateUpdate};
+        use pathfinder_merkle_tree::{verify_proof, Membership};
+        use pathfinder_storage::StorageBuilder;
+
+        use super::*;
+
+        const DECLARED: SierraHash = sierra_hash!("0x1234");
+        const CASM: CasmHash = casm_hash!("0xabcd");
+        const UNDECLARED: SierraHash = sierra_hash!("0x5678");
+
+        /// Returns a context with a single block, which declares [DECLARED] if
+        /// `declare` is set and has an empty class trie otherwise.
+        fn setup(declare: bool) -> RpcContext {
+            let storage = StorageBuilder::in_memory().unwrap();
+            let mut db = storage.connection().unwrap();
+            let tx = db.transaction().unwrap();
+
+            let mut class_commitment = ClassCommitment::ZERO;
+            let mut state_update = StateUpdate::default();
+            if declare {
+                tx.insert_sierra_class(&DECLARED, b"sierra", &CASM, b"casm")
+                    .unwrap();
+                state_update = state_update.with_declared_sierra_class(DECLARED, CASM);
+
+                let mut tree = ClassCommitmentTree::empty(&tx);
+                tree.set(DECLARED, calculate_class_commitment_leaf_hash(CASM))
+                    .unwrap();
+                let (commitment, update) = tree.commit().unwrap();
+                let root = tx.insert_class_trie(&update, BlockNumber::GENESIS).unwrap();
+                tx.insert_class_root(BlockNumber::GENESIS, root).unwrap();
+                class_commitment = commitment;
+            }
+
+            let header = BlockHeader::builder()
+                .with_class_commitment(class_commitment)
+                .finalize_with_hash(block_hash!("0xb10c"));
+            tx.insert_block_header(&header).unwrap();
+            tx.insert_state_update(header.number, &state_update)
+                .unwrap();
+            tx.commit().unwrap();
+
+            RpcContext::for_tests().with_storage(storage)
+        }
+
+        fn input() -> GetProofInput {
+            GetProofInput {
+                block_id: BlockId::Latest,
+                contract_address: contract_address!("0xdeadbeef"),
+                keys: vec![],
+                class_hashes: vec![DECLARED, UNDECLARED],
+            }
+        }
+
+        #[tokio::test]
+        async fn verify_against_class_commitment() {
+            let context = setup(true);
+
+            let output = get_proof(context, input()).await.unwrap();
+            let class_commitment = output.class_commitment.unwrap();
+            let class_proofs = output.class_proofs.unwrap();
+
+            let declared = &class_proofs[0];
+            assert_eq!(declared.compiled_class_hash, Some(CASM));
+            let membership = verify_proof::<PoseidonHash>(
+                class_commitment.0,
+                DECLARED.view_bits(),
+                &declared.proof.0,
+            )
+            .unwrap();
+            assert_eq!(
+                membership,
+                Membership::Member(calculate_class_commitment_leaf_hash(CASM).0)
+            );
+
+            let undeclared = &class_proofs[1];
+            assert_eq!(undeclared.compiled_class_hash, None);
+            let membership = verify_proof::<PoseidonHash>(
+                class_commitment.0,
+                UNDECLARED.view_bits(),
+                &undeclared.proof.0,
+            )
+            .unwrap();
+            assert_eq!(membership, Membership::NonMember);
+        }
+
+        #[tokio::test]
+        async fn empty_class_trie() {
+            let context = setup(false);
+
+            let output = get_proof(context, input()).await.unwrap();
+            assert_eq!(output.class_commitment, None);
+
+            for (class_proof, class_hash) in output
+                .class_proofs
+                .unwrap()
+                .iter()
+                .zip(input().class_hashes)
+            {
+                assert_eq!(class_proof.compiled_class_hash, None);
+                let membership = verify_proof::<PoseidonHash>(
+                    Felt::ZERO,
+                    class_hash.view_bits(),
+                    &class_proof.proof.0,
+                )
+                .unwrap();
+                assert_eq!(membership, Membership::NonMember);
+            }
+        }
+    }
 }
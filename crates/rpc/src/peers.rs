@@ -0,0 +1,14 @@
+//! Reputations of p2p peers, which the p2p network reports on request.
+
+use tokio::sync::{mpsc, oneshot};
+
+/// The reputation of a p2p peer which was penalized for sending invalid data.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct PeerReputation {
+    pub peer_id: String,
+    pub score: i32,
+    pub banned: bool,
+}
+
+/// Requests the reputations of all penalized peers from the p2p network.
+pub type PeerReputationsSender = mpsc::Sender<oneshot::Sender<Vec<PeerReputation>>>;
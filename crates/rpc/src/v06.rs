@@ -41,4 +41,6 @@ pub fn register_routes() -> RpcRouterBuilder {
         .register("starknet_traceTransaction"                , method::trace_transaction)
 
         .register("pathfinder_getProof"                      , crate::pathfinder::methods::get_proof)
+        .register("pathfinder_getConsumedMessages"           , crate::pathfinder::methods::get_consumed_messages)
+        .register("pathfinder_getPeerReputations"            , crate::pathfinder::methods::get_peer_reputations)
 }
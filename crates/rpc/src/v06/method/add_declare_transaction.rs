@@ -9,7 +9,7 @@ use starknet_gateway_types::request::add_transaction::{
 
 use crate::context::RpcContext;
 use crate::felt::RpcFelt;
-use crate::v02::types::request::BroadcastedDeclareTransaction;
+use crate::v02::types::request::{BroadcastedDeclareTransaction, BroadcastedTransaction};
 
 #[derive(Debug)]
 pub enum AddDeclareTransactionError {
@@ -126,6 +126,8 @@ impl From<SequencerError> for AddDeclareTransactionError {
     }
 }
 
+crate::validation::impl_from_validation_error!(AddDeclareTransactionError);
+
 #[derive(serde::Deserialize, Debug, PartialEq, Eq)]
 #[serde(tag = "type")]
 pub enum Transaction {
@@ -158,6 +160,15 @@ pub async fn add_declare_transaction(
 ) -> Result<AddDeclareTransactionOutput, AddDeclareTransactionError> {
     use starknet_gateway_types::request::add_transaction;
 
+    let Transaction::Declare(declare) = &input.declare_transaction;
+    if !matches!(declare, BroadcastedDeclareTransaction::V0(_)) {
+        crate::validation::validate_submitted(
+            &context,
+            BroadcastedTransaction::Declare(declare.clone()),
+        )
+        .await?;
+    }
+
     match input.declare_transaction {
         Transaction::Declare(BroadcastedDeclareTransaction::V0(_)) => {
             Err(AddDeclareTransactionError::UnsupportedTransactionVersion)
@@ -190,6 +201,7 @@ pub async fn add_declare_transaction(
             })
         }
         Transaction::Declare(BroadcastedDeclareTransaction::V2(tx)) => {
+            let gossiped = BroadcastedDeclareTransaction::V2(tx.clone());
             let contract_definition: SierraContractDefinition = tx
                 .contract_class
                 .try_into()
@@ -209,14 +221,25 @@ pub async fn add_declare_transaction(
                     }),
                     input.token,
                 )
-                .await?;
+                .await
+                .map(|response| AddDeclareTransactionOutput {
+                    transaction_hash: response.transaction_hash,
+                    class_hash: response.class_hash,
+                });
+            let output = crate::gossip::gossip_submitted(
+                &context,
+                BroadcastedTransaction::Declare(gossiped),
+                response,
+                |transaction| AddDeclareTransactionOutput {
+                    transaction_hash: transaction.hash,
+                    class_hash: crate::gossip::declared_class_hash(transaction),
+                },
+            )?;
 
-            Ok(AddDeclareTransactionOutput {
-                transaction_hash: response.transaction_hash,
-                class_hash: response.class_hash,
-            })
+            Ok(output)
         }
         Transaction::Declare(BroadcastedDeclareTransaction::V3(tx)) => {
+            let gossiped = BroadcastedDeclareTransaction::V3(tx.clone());
             let contract_definition: SierraContractDefinition = tx
                 .contract_class
                 .try_into()
@@ -251,12 +274,22 @@ pub async fn add_declare_transaction(
                     }),
                     input.token,
                 )
-                .await?;
+                .await
+                .map(|response| AddDeclareTransactionOutput {
+                    transaction_hash: response.transaction_hash,
+                    class_hash: response.class_hash,
+                });
+            let output = crate::gossip::gossip_submitted(
+                &context,
+                BroadcastedTransaction::Declare(gossiped),
+                response,
+                |transaction| AddDeclareTransactionOutput {
+                    transaction_hash: transaction.hash,
+                    class_hash: crate::gossip::declared_class_hash(transaction),
+                },
+            )?;
 
-            Ok(AddDeclareTransactionOutput {
-                transaction_hash: response.transaction_hash,
-                class_hash: response.class_hash,
-            })
+            Ok(output)
         }
     }
 }
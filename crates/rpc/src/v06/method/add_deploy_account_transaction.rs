@@ -7,6 +7,7 @@ use crate::felt::{RpcFelt, RpcFelt251};
 use crate::v02::types::request::{
     BroadcastedDeployAccountTransaction,
     BroadcastedDeployAccountTransactionV1,
+    BroadcastedTransaction,
 };
 
 #[derive(serde::Deserialize, Debug, PartialEq, Eq)]
@@ -103,6 +104,8 @@ impl From<SequencerError> for AddDeployAccountTransactionError {
     }
 }
 
+crate::validation::impl_from_validation_error!(AddDeployAccountTransactionError);
+
 pub async fn add_deploy_account_transaction(
     context: RpcContext,
     input: AddDeployAccountTransactionInput,
@@ -111,10 +114,23 @@ pub async fn add_deploy_account_transaction(
         Transaction::DeployAccount(tx) => tx.deployed_contract_address(),
     };
     let Transaction::DeployAccount(tx) = input.deploy_account_transaction;
-    let response = add_deploy_account_transaction_impl(&context, tx).await?;
+    crate::validation::validate_submitted(
+        &context,
+        BroadcastedTransaction::DeployAccount(tx.clone()),
+    )
+    .await?;
+    let response = add_deploy_account_transaction_impl(&context, tx.clone())
+        .await
+        .map(|response| response.transaction_hash);
+    let transaction_hash = crate::gossip::gossip_submitted(
+        &context,
+        BroadcastedTransaction::DeployAccount(tx),
+        response,
+        |transaction| transaction.hash,
+    )?;
 
     Ok(AddDeployAccountTransactionOutput {
-        transaction_hash: response.transaction_hash,
+        transaction_hash,
         contract_address,
     })
 }
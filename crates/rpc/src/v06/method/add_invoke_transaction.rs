@@ -4,7 +4,7 @@ use starknet_gateway_types::error::SequencerError;
 
 use crate::context::RpcContext;
 use crate::felt::RpcFelt;
-use crate::v02::types::request::BroadcastedInvokeTransaction;
+use crate::v02::types::request::{BroadcastedInvokeTransaction, BroadcastedTransaction};
 
 #[derive(serde::Deserialize, Debug, PartialEq, Eq)]
 #[serde(tag = "type")]
@@ -95,16 +95,26 @@ impl From<SequencerError> for AddInvokeTransactionError {
     }
 }
 
+crate::validation::impl_from_validation_error!(AddInvokeTransactionError);
+
 pub async fn add_invoke_transaction(
     context: RpcContext,
     input: AddInvokeTransactionInput,
 ) -> Result<AddInvokeTransactionOutput, AddInvokeTransactionError> {
     let Transaction::Invoke(tx) = input.invoke_transaction;
-    let response = add_invoke_transaction_impl(&context, tx).await?;
-
-    Ok(AddInvokeTransactionOutput {
-        transaction_hash: response.transaction_hash,
-    })
+    crate::validation::validate_submitted(&context, BroadcastedTransaction::Invoke(tx.clone()))
+        .await?;
+    let response = add_invoke_transaction_impl(&context, tx.clone())
+        .await
+        .map(|response| response.transaction_hash);
+    let transaction_hash = crate::gossip::gossip_submitted(
+        &context,
+        BroadcastedTransaction::Invoke(tx),
+        response,
+        |transaction| transaction.hash,
+    )?;
+
+    Ok(AddInvokeTransactionOutput { transaction_hash })
 }
 
 pub(crate) async fn add_invoke_transaction_impl(
@@ -156,8 +156,13 @@ pub async fn simulate_transactions_impl(
             .map(|tx| crate::executor::map_broadcasted_transaction(&tx, context.chain_id))
             .collect::<Result<Vec<_>, _>>()?;
 
-        let txs =
-            pathfinder_executor::simulate(state, transactions, skip_validate, skip_fee_charge)?;
+        let txs = pathfinder_executor::simulate(
+            state,
+            transactions,
+            skip_validate,
+            skip_fee_charge,
+            false,
+        )?;
         let txs = txs
             .into_iter()
             .map(TryInto::try_into)
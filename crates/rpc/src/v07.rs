@@ -34,5 +34,7 @@ pub fn register_routes() -> RpcRouterBuilder {
         .register("starknet_traceTransaction",                    crate::method::trace_transaction)
         .register("starknet_getBlockWithReceipts",                crate::method::get_block_with_receipts)
         .register("pathfinder_getProof",                          crate::pathfinder::methods::get_proof)
+        .register("pathfinder_getConsumedMessages",               crate::pathfinder::methods::get_consumed_messages)
+        .register("pathfinder_getPeerReputations",                crate::pathfinder::methods::get_peer_reputations)
         .register("starknet_specVersion",                         || "0.7.1")
 }
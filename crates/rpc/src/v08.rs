@@ -0,0 +1,8 @@
+use crate::jsonrpc::{RpcRouter, RpcRouterBuilder};
+
+/// The methods of the v0.8 specification which are implemented so far.
+#[rustfmt::skip]
+pub fn register_routes() -> RpcRouterBuilder {
+    RpcRouter::builder(crate::RpcVersion::V08)
+        .register("starknet_getMessagesStatus",                   crate::method::get_messages_status)
+}
@@ -0,0 +1,142 @@
+//! Local validation of transactions submitted via the RPC API, before they are
+//! forwarded to the gateway.
+
+use anyhow::Context;
+use pathfinder_executor::{ExecutionState, L1BlobDataAvailability, TransactionValidationError};
+
+use crate::context::RpcContext;
+use crate::v02::types::request::BroadcastedTransaction;
+
+/// Validates a submitted transaction against the pending state, if enabled
+/// in the [RpcConfig](crate::context::RpcConfig).
+///
+/// Only invalid transactions are rejected. If the validation itself fails,
+/// e.g. because the pending state is not available, the transaction is left
+/// for the gateway to validate.
+pub(crate) async fn validate_submitted(
+    context: &RpcContext,
+    transaction: BroadcastedTransaction,
+) -> Result<(), TransactionValidationError> {
+    if !context.config.validate_submitted_transactions {
+        return Ok(());
+    }
+
+    let context = context.clone();
+    let span = tracing::Span::current();
+
+    let result = tokio::task::spawn_blocking(move || {
+        let _g = span.enter();
+        let mut db = context
+            .execution_storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let pending = context
+            .pending_data
+            .get(&db)
+            .context("Querying pending data")?;
+
+        let state = ExecutionState::simulation(
+            &db,
+            context.chain_id,
+            pending.header(),
+            Some(pending.state_update.clone()),
+            L1BlobDataAvailability::Enabled,
+            context.config.custom_versioned_constants,
+        );
+
+        let transaction =
+            crate::executor::map_broadcasted_transaction(&transaction, context.chain_id)?;
+
+        pathfinder_executor::validate(state, transaction)
+    })
+    .await
+    .unwrap_or_else(|error| Err(anyhow::Error::from(error).into()));
+
+    match result {
+        Err(TransactionValidationError::Internal(error))
+        | Err(TransactionValidationError::Custom(error)) => {
+            tracing::debug!(%error, "Local transaction validation failed, leaving it to the gateway");
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+/// Implements `From<TransactionValidationError>` for the error type of an
+/// `add_*_transaction` method, all of which share the validation variants.
+macro_rules! impl_from_validation_error {
+    ($error:ty) => {
+        impl From<pathfinder_executor::TransactionValidationError> for $error {
+            fn from(e: pathfinder_executor::TransactionValidationError) -> Self {
+                use pathfinder_executor::TransactionValidationError::*;
+                match e {
+                    InvalidTransactionNonce => Self::InvalidTransactionNonce,
+                    InsufficientMaxFee => Self::InsufficientMaxFee,
+                    InsufficientAccountBalance => Self::InsufficientAccountBalance,
+                    ValidationFailure(error) => Self::ValidationFailure(error),
+                    Internal(e) | Custom(e) => Self::UnexpectedError(e.to_string()),
+                }
+            }
+        }
+    };
+}
+
+pub(crate) use impl_from_validation_error;
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::{ContractAddress, TransactionNonce, TransactionVersion};
+
+    use super::*;
+    use crate::v02::types::request::{
+        BroadcastedInvokeTransaction,
+        BroadcastedInvokeTransactionV1,
+    };
+
+    fn invoke(account: ContractAddress, nonce: TransactionNonce) -> BroadcastedTransaction {
+        BroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V1(
+            BroadcastedInvokeTransactionV1 {
+                version: TransactionVersion::ONE,
+                max_fee: fee!("0x1"),
+                signature: vec![],
+                nonce,
+                sender_address: account,
+                calldata: vec![],
+            },
+        ))
+    }
+
+    async fn context(enabled: bool) -> (RpcContext, ContractAddress) {
+        let (mut context, _, account, _) = crate::test_setup::test_context().await;
+        context.config.validate_submitted_transactions = enabled;
+        (context, account)
+    }
+
+    #[tokio::test]
+    async fn disabled() {
+        let (context, account) = context(false).await;
+
+        let result = validate_submitted(&context, invoke(account, transaction_nonce!("0x0"))).await;
+        assert_matches!(result, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn insufficient_max_fee() {
+        let (context, account) = context(true).await;
+
+        let result = validate_submitted(&context, invoke(account, transaction_nonce!("0x0"))).await;
+        assert_matches!(result, Err(TransactionValidationError::InsufficientMaxFee));
+    }
+
+    #[tokio::test]
+    async fn nonce_ahead_of_account_nonce_is_left_to_the_gateway() {
+        let (context, account) = context(true).await;
+
+        let result = validate_submitted(&context, invoke(account, transaction_nonce!("0x5"))).await;
+        assert_matches!(result, Ok(()));
+    }
+}
@@ -7,10 +7,13 @@ mod event;
 mod reference;
 mod reorg_counter;
 mod signature;
+mod snapshot;
 mod state_update;
+mod trace;
 pub(crate) mod transaction;
 mod trie;
 
+pub(crate) use event::build_event_address_index;
 pub use event::{
     EmittedEvent,
     EventFilter,
@@ -26,6 +29,7 @@ use pathfinder_common::{BlockNumber, TransactionHash};
 pub(crate) use reorg_counter::ReorgCounter;
 // Re-export this so users don't require rusqlite as a direct dep.
 pub use rusqlite::TransactionBehavior;
+pub use snapshot::SnapshotProgress;
 pub use trie::{Node, NodeRef, RootIndexUpdate, StoredNode, TrieUpdate};
 
 type PooledConnection = r2d2::PooledConnection<r2d2_sqlite::SqliteConnectionManager>;
@@ -34,6 +38,7 @@ pub struct Connection {
     connection: PooledConnection,
     bloom_filter_cache: Arc<crate::bloom::Cache>,
     trie_prune_mode: TriePruneMode,
+    event_address_index: bool,
 }
 
 impl Connection {
@@ -41,11 +46,13 @@ impl Connection {
         connection: PooledConnection,
         bloom_filter_cache: Arc<crate::bloom::Cache>,
         trie_prune_mode: TriePruneMode,
+        event_address_index: bool,
     ) -> Self {
         Self {
             connection,
             bloom_filter_cache,
             trie_prune_mode,
+            event_address_index,
         }
     }
 
@@ -55,6 +62,7 @@ impl Connection {
             transaction: tx,
             bloom_filter_cache: self.bloom_filter_cache.clone(),
             trie_prune_mode: self.trie_prune_mode,
+            event_address_index: self.event_address_index,
         })
     }
 
@@ -67,6 +75,7 @@ impl Connection {
             transaction: tx,
             bloom_filter_cache: self.bloom_filter_cache.clone(),
             trie_prune_mode: self.trie_prune_mode,
+            event_address_index: self.event_address_index,
         })
     }
 }
@@ -75,6 +84,8 @@ pub struct Transaction<'inner> {
     transaction: rusqlite::Transaction<'inner>,
     bloom_filter_cache: Arc<crate::bloom::Cache>,
     trie_prune_mode: TriePruneMode,
+    /// Whether the event address index is maintained and used.
+    event_address_index: bool,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -84,6 +95,32 @@ pub enum TriePruneMode {
     /// Prune merkle trie history. Only keep the last few blocks, as well as the
     /// latest block.
     Prune { num_blocks_kept: u64 },
+    /// Prune merkle trie history like [TriePruneMode::Prune], but additionally
+    /// keep the tries of every block that is a multiple of `interval`.
+    Checkpoints { num_blocks_kept: u64, interval: u64 },
+}
+
+impl TriePruneMode {
+    /// The number of blocks (in addition to the latest block) for which
+    /// the full trie history is kept, or `None` if nothing is pruned.
+    pub fn num_blocks_kept(&self) -> Option<u64> {
+        match self {
+            TriePruneMode::Archive => None,
+            TriePruneMode::Prune { num_blocks_kept }
+            | TriePruneMode::Checkpoints {
+                num_blocks_kept, ..
+            } => Some(*num_blocks_kept),
+        }
+    }
+
+    /// The interval between blocks whose tries are kept regardless of their
+    /// age, or `None` if no such checkpoints are kept.
+    pub fn checkpoint_interval(&self) -> Option<u64> {
+        match self {
+            TriePruneMode::Checkpoints { interval, .. } if *interval > 0 => Some(*interval),
+            _ => None,
+        }
+    }
 }
 
 type TransactionWithReceipt = (StarknetTransaction, Receipt, Vec<Event>, BlockNumber);
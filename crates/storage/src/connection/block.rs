@@ -121,6 +121,9 @@ impl Transaction<'_> {
             )
             .context("Deleting bloom filter")?;
 
+        self.purge_event_address_index(block)
+            .context("Deleting block from event address index")?;
+
         self.inner()
             .execute(
                 "DELETE FROM transactions WHERE block_number = ?",
@@ -128,6 +131,13 @@ impl Transaction<'_> {
             )
             .context("Deleting transactions")?;
 
+        self.inner()
+            .execute(
+                "DELETE FROM block_traces WHERE block_number = ?",
+                params![&block],
+            )
+            .context("Deleting block traces")?;
+
         self.inner()
             .execute(
                 "DELETE FROM canonical_blocks WHERE number = ?",
@@ -177,26 +187,9 @@ impl Transaction<'_> {
             )
             .context("Deleting block from storage_roots table")?;
 
-        self.inner()
-            .execute(
-                "DELETE FROM trie_contracts_removals WHERE block_number = ?",
-                params![&block],
-            )
-            .context("Deleting block from trie_contracts_removals table")?;
-
-        self.inner()
-            .execute(
-                "DELETE FROM trie_storage_removals WHERE block_number = ?",
-                params![&block],
-            )
-            .context("Deleting block from trie_storage_removals table")?;
-
-        self.inner()
-            .execute(
-                "DELETE FROM trie_class_removals WHERE block_number = ?",
-                params![&block],
-            )
-            .context("Deleting block from trie_class_removals table")?;
+        // Trie node removals are kept, since a reorg coalesces the removals of all
+        // reorged-away blocks into the first of them. See
+        // [coalesce_trie_nodes](Self::coalesce_trie_nodes).
 
         Ok(())
     }
@@ -136,6 +136,21 @@ impl Transaction<'_> {
             .collect::<Result<Vec<_>, _>>()?)
     }
 
+    /// Returns the classes declared at `block` whose definition has not been
+    /// stored yet.
+    pub fn missing_class_definitions(&self, block: BlockNumber) -> anyhow::Result<Vec<ClassHash>> {
+        let mut stmt = self.inner().prepare_cached(
+            "SELECT hash FROM class_definitions WHERE block_number = ? AND definition IS NULL",
+        )?;
+
+        let classes = stmt
+            .query_map(params![&block], |row| row.get_class_hash(0))
+            .context("Querying classes with missing definitions")?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(classes)
+    }
+
     /// Returns the uncompressed class definition.
     pub fn class_definition(&self, class_hash: ClassHash) -> anyhow::Result<Option<Vec<u8>>> {
         self.class_definition_with_block_number(class_hash)
@@ -556,6 +571,33 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn missing_class_definitions() {
+        let mut connection = crate::StorageBuilder::in_memory()
+            .unwrap()
+            .connection()
+            .unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let (stored, _, _) = setup_class(&tx);
+        let missing = class_hash!("0x456");
+        let block = BlockNumber::GENESIS;
+
+        tx.insert_block_header(&pathfinder_common::BlockHeader::default())
+            .unwrap();
+        tx.insert_state_update_data(
+            block,
+            &pathfinder_common::state_update::StateUpdateData {
+                declared_cairo_classes: [stored, missing].into_iter().collect(),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let result = tx.missing_class_definitions(block).unwrap();
+        assert_eq!(result, vec![missing]);
+    }
+
     #[test]
     fn insert_cairo() {
         let mut connection = crate::StorageBuilder::in_memory()
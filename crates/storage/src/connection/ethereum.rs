@@ -1,5 +1,7 @@
-use pathfinder_common::BlockNumber;
-use pathfinder_ethereum::EthereumStateUpdate;
+use anyhow::Context;
+use pathfinder_common::{BlockId, BlockNumber, ChainId, TransactionHash};
+use pathfinder_ethereum::{EthereumStateUpdate, L1Depth, MessageLog, StarknetMessage};
+use primitive_types::H256;
 
 use crate::prelude::*;
 
@@ -72,6 +74,280 @@ impl Transaction<'_> {
         .optional()
         .map_err(|e| e.into())
     }
+
+    /// Stores the Starknet state seen at an L1 depth other than
+    /// [finalized](L1Depth::Finalized) as of the L1 block `l1_block_number`,
+    /// replacing the previous state at that depth. The finalized state is
+    /// stored using [upsert_l1_state](Self::upsert_l1_state).
+    pub fn upsert_l1_state_at_depth(
+        &self,
+        depth: L1Depth,
+        l1_block_number: u64,
+        update: &EthereumStateUpdate,
+    ) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            depth != L1Depth::Finalized,
+            "Finalized L1 state is stored in l1_state"
+        );
+
+        self.inner().execute(
+            r"INSERT OR REPLACE INTO l1_state_at_depth (
+                    depth,
+                    confirmations,
+                    l1_block_number,
+                    starknet_block_number,
+                    starknet_block_hash,
+                    starknet_state_root
+                ) VALUES (
+                    :depth,
+                    :confirmations,
+                    :l1_block_number,
+                    :starknet_block_number,
+                    :starknet_block_hash,
+                    :starknet_state_root
+                )",
+            named_params! {
+                ":depth": &depth.kind(),
+                ":confirmations": &confirmations(depth),
+                ":l1_block_number": &l1_block_number,
+                ":starknet_block_number": &update.block_number,
+                ":starknet_block_hash": &update.block_hash,
+                ":starknet_state_root": &update.state_root,
+            },
+        )?;
+
+        Ok(())
+    }
+
+    /// Returns the Starknet state stored at each L1 depth other than
+    /// finalized, ordered from the deepest L1 block at which it was read.
+    pub fn l1_states_at_depth(&self) -> anyhow::Result<Vec<(L1Depth, EthereumStateUpdate)>> {
+        let mut stmt = self
+            .inner()
+            .prepare_cached(
+                r"SELECT depth, confirmations, starknet_block_number, starknet_block_hash, starknet_state_root
+                FROM l1_state_at_depth
+                ORDER BY l1_block_number ASC",
+            )
+            .context("Preparing statement")?;
+
+        let mut rows = stmt.query([]).context("Querying L1 states")?;
+
+        let mut states = Vec::new();
+        while let Some(row) = rows.next()? {
+            let depth: String = row.get(0)?;
+            let depth = match depth.as_str() {
+                "latest" => L1Depth::Latest,
+                "safe" => L1Depth::Safe,
+                "confirmations" => L1Depth::Confirmations(row.get_i64(1)? as u64),
+                other => anyhow::bail!("Unknown L1 depth {other}"),
+            };
+            let update = EthereumStateUpdate {
+                block_number: row.get_block_number(2)?,
+                block_hash: row.get_block_hash(3)?,
+                state_root: row.get_state_commitment(4)?,
+            };
+            states.push((depth, update));
+        }
+
+        Ok(states)
+    }
+
+    /// Removes the state stored at L1 depths which are no longer tracked.
+    pub fn retain_l1_states_at_depth(&self, depths: &[L1Depth]) -> anyhow::Result<()> {
+        for (depth, _) in self.l1_states_at_depth()? {
+            if !depths.contains(&depth) {
+                self.inner()
+                    .execute(
+                        "DELETE FROM l1_state_at_depth WHERE depth = ? AND confirmations = ?",
+                        params![&depth.kind(), &confirmations(depth)],
+                    )
+                    .context("Deleting L1 state")?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the deepest L1 depth at which the block is part of the Starknet
+    /// state on L1, or [None] if it has not reached L1 at any tracked depth.
+    ///
+    /// A block is only considered to be at a depth other than finalized if it
+    /// is part of the same chain as the state at that depth.
+    pub fn block_l1_depth(&self, block: BlockId) -> anyhow::Result<Option<L1Depth>> {
+        let Some((block_number, _)) = self.block_id(block).context("Fetching block number")? else {
+            return Ok(None);
+        };
+
+        if self.block_is_l1_accepted(block_number.into())? {
+            return Ok(Some(L1Depth::Finalized));
+        }
+
+        for (depth, state) in self.l1_states_at_depth()? {
+            if block_number > state.block_number {
+                continue;
+            }
+
+            let hash = self
+                .block_hash(state.block_number.into())
+                .context("Fetching block hash")?;
+            if hash == Some(state.block_hash) {
+                return Ok(Some(depth));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Stores the Starknet messages logged by the core contract. L1 to L2
+    /// messages are linked to the hash of the L1 handler transaction which
+    /// executes them on the chain identified by `chain_id`.
+    pub fn insert_l1_messages(
+        &self,
+        messages: &[MessageLog],
+        chain_id: ChainId,
+    ) -> anyhow::Result<()> {
+        let mut stmt = self
+            .inner()
+            .prepare_cached(
+                r"INSERT OR IGNORE INTO l1_messages (
+                    l1_transaction_hash,
+                    log_index,
+                    l1_block_number,
+                    message_hash,
+                    l1_handler_transaction_hash
+                ) VALUES (
+                    :l1_transaction_hash,
+                    :log_index,
+                    :l1_block_number,
+                    :message_hash,
+                    :l1_handler_transaction_hash
+                )",
+            )
+            .context("Preparing insert statement")?;
+
+        for log in messages {
+            let l1_handler_transaction_hash = match &log.message {
+                StarknetMessage::L1ToL2(message) => {
+                    Some(message.l1_handler_transaction().calculate_hash(chain_id))
+                }
+                StarknetMessage::ConsumedL2ToL1(_) => None,
+            };
+
+            stmt.execute(named_params! {
+                ":l1_transaction_hash": &log.l1_transaction_hash.as_bytes(),
+                ":log_index": &log.log_index,
+                ":l1_block_number": &log.l1_block_number,
+                ":message_hash": &log.message.message_hash().as_bytes(),
+                ":l1_handler_transaction_hash": &l1_handler_transaction_hash,
+            })
+            .context("Inserting L1 message")?;
+        }
+
+        Ok(())
+    }
+
+    /// Replaces all stored messages logged after the L1 block `after`, or all
+    /// stored messages if it is [None], by `messages`. Messages of L1 blocks
+    /// which are not finalized may be reorged away, so they are replaced as a
+    /// whole whenever those blocks are read again.
+    pub fn replace_l1_messages_after(
+        &self,
+        after: Option<u64>,
+        messages: &[MessageLog],
+        chain_id: ChainId,
+    ) -> anyhow::Result<()> {
+        self.inner()
+            .execute(
+                "DELETE FROM l1_messages WHERE l1_block_number > IFNULL(?, -1)",
+                params![&after],
+            )
+            .context("Deleting replaced L1 messages")?;
+
+        self.insert_l1_messages(messages, chain_id)
+    }
+
+    /// Returns the hashes of the L1 handler transactions for the messages sent
+    /// to L2 by the given L1 transaction, in the order they were sent.
+    pub fn l1_handler_transactions(
+        &self,
+        l1_transaction_hash: H256,
+    ) -> anyhow::Result<Vec<TransactionHash>> {
+        let mut stmt = self
+            .inner()
+            .prepare_cached(
+                r"SELECT l1_handler_transaction_hash FROM l1_messages
+                WHERE l1_transaction_hash = ? AND l1_handler_transaction_hash IS NOT NULL
+                ORDER BY log_index",
+            )
+            .context("Preparing statement")?;
+
+        let hashes = stmt
+            .query_map(params![&l1_transaction_hash.as_bytes()], |row| {
+                row.get_transaction_hash(0)
+            })
+            .context("Querying L1 handler transactions")?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(hashes)
+    }
+
+    /// Returns the hashes of the L2 to L1 messages consumed by the given L1
+    /// transaction, in the order they were consumed.
+    pub fn consumed_l2_to_l1_messages(
+        &self,
+        l1_transaction_hash: H256,
+    ) -> anyhow::Result<Vec<H256>> {
+        let mut stmt = self
+            .inner()
+            .prepare_cached(
+                r"SELECT message_hash FROM l1_messages
+                WHERE l1_transaction_hash = ? AND l1_handler_transaction_hash IS NULL
+                ORDER BY log_index",
+            )
+            .context("Preparing statement")?;
+
+        let hashes = stmt
+            .query_map(params![&l1_transaction_hash.as_bytes()], |row| {
+                row.get_blob(0).map(H256::from_slice)
+            })
+            .context("Querying consumed messages")?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(hashes)
+    }
+
+    /// Sets the L1 block up to which the core contract's message logs have
+    /// been stored.
+    pub fn update_l1_messages_head(&self, l1_block_number: u64) -> anyhow::Result<()> {
+        self.inner().execute(
+            "UPDATE refs SET l1_messages_head = ? WHERE idx = 1",
+            params![&l1_block_number],
+        )?;
+
+        Ok(())
+    }
+
+    pub fn l1_messages_head(&self) -> anyhow::Result<Option<u64>> {
+        // This table always contains exactly one row.
+        self.inner()
+            .query_row(
+                "SELECT l1_messages_head FROM refs WHERE idx = 1",
+                [],
+                |row| row.get_optional_i64(0),
+            )
+            .map(|head| head.map(|head| head as u64))
+            .map_err(|e| e.into())
+    }
+}
+
+/// The number of confirmations stored for the depth, which is zero for depths
+/// that are not a number of confirmations.
+fn confirmations(depth: L1Depth) -> u64 {
+    match depth {
+        L1Depth::Confirmations(confirmations) => confirmations,
+        _ => 0,
+    }
 }
 
 #[cfg(test)]
@@ -170,4 +446,202 @@ mod tests {
             .unwrap();
         assert_eq!(result, new_value);
     }
+
+    #[test]
+    fn block_l1_depth() {
+        let storage = crate::StorageBuilder::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let headers = [0, 1, 2].map(|i| {
+            pathfinder_common::BlockHeader::builder()
+                .with_number(BlockNumber::GENESIS + i)
+                .finalize_with_hash(BlockHash(Felt::from_u64(i + 1)))
+        });
+        for header in &headers {
+            tx.insert_block_header(header).unwrap();
+        }
+        let state = |header: &pathfinder_common::BlockHeader| EthereumStateUpdate {
+            state_root: header.state_commitment,
+            block_number: header.number,
+            block_hash: header.hash,
+        };
+
+        tx.update_l1_l2_pointer(Some(headers[0].number)).unwrap();
+        tx.upsert_l1_state_at_depth(L1Depth::Safe, 100, &state(&headers[1]))
+            .unwrap();
+        tx.upsert_l1_state_at_depth(L1Depth::Latest, 110, &state(&headers[2]))
+            .unwrap();
+        tx.upsert_l1_state_at_depth(L1Depth::Finalized, 90, &state(&headers[2]))
+            .unwrap_err();
+
+        let depth = |i: usize| tx.block_l1_depth(headers[i].number.into()).unwrap();
+        assert_eq!(depth(0), Some(L1Depth::Finalized));
+        assert_eq!(depth(1), Some(L1Depth::Safe));
+        assert_eq!(depth(2), Some(L1Depth::Latest));
+
+        // Each number of confirmations is stored separately, and the depth read
+        // at the deepest L1 block wins regardless of its kind.
+        tx.upsert_l1_state_at_depth(L1Depth::Confirmations(5), 105, &state(&headers[2]))
+            .unwrap();
+        tx.upsert_l1_state_at_depth(L1Depth::Confirmations(20), 90, &state(&headers[1]))
+            .unwrap();
+        assert_eq!(depth(1), Some(L1Depth::Confirmations(20)));
+        assert_eq!(depth(2), Some(L1Depth::Confirmations(5)));
+
+        // A state from another chain does not apply.
+        let mut reorged = state(&headers[2]);
+        reorged.block_hash = block_hash!("0xdead");
+        tx.upsert_l1_state_at_depth(L1Depth::Latest, 111, &reorged)
+            .unwrap();
+        tx.retain_l1_states_at_depth(&[L1Depth::Safe, L1Depth::Latest])
+            .unwrap();
+        assert_eq!(depth(2), None);
+
+        tx.retain_l1_states_at_depth(&[L1Depth::Latest]).unwrap();
+        assert_eq!(depth(1), None);
+        assert_eq!(
+            tx.l1_states_at_depth().unwrap(),
+            vec![(L1Depth::Latest, reorged)]
+        );
+    }
+
+    mod messages {
+        use pathfinder_common::{EthereumAddress, L1ToL2MessageNonce};
+        use pathfinder_ethereum::{ConsumedL2ToL1Message, L1ToL2Message};
+        use primitive_types::H160;
+
+        use super::*;
+
+        fn l1_to_l2(nonce: u64) -> StarknetMessage {
+            StarknetMessage::L1ToL2(L1ToL2Message {
+                from_address: EthereumAddress(H160::from_low_u64_be(0xabcd)),
+                to_address: contract_address!("0x1234"),
+                selector: entry_point!("0x5678"),
+                payload: vec![l1_to_l2_message_payload_elem!("0x1")],
+                nonce: L1ToL2MessageNonce(Felt::from_u64(nonce)),
+            })
+        }
+
+        fn consumed() -> StarknetMessage {
+            StarknetMessage::ConsumedL2ToL1(ConsumedL2ToL1Message {
+                from_address: contract_address!("0x1234"),
+                to_address: EthereumAddress(H160::from_low_u64_be(0xabcd)),
+                payload: vec![l2_to_l1_message_payload_elem!("0x2")],
+            })
+        }
+
+        #[test]
+        fn insert_and_query() {
+            let storage = crate::StorageBuilder::in_memory().unwrap();
+            let mut connection = storage.connection().unwrap();
+            let tx = connection.transaction().unwrap();
+
+            let deposit = H256::from_low_u64_be(1);
+            let withdrawal = H256::from_low_u64_be(2);
+            let logs = vec![
+                MessageLog {
+                    l1_block_number: 10,
+                    l1_transaction_hash: deposit,
+                    log_index: 3,
+                    message: l1_to_l2(1),
+                },
+                MessageLog {
+                    l1_block_number: 10,
+                    l1_transaction_hash: deposit,
+                    log_index: 1,
+                    message: l1_to_l2(0),
+                },
+                MessageLog {
+                    l1_block_number: 11,
+                    l1_transaction_hash: withdrawal,
+                    log_index: 0,
+                    message: consumed(),
+                },
+            ];
+            tx.insert_l1_messages(&logs, ChainId::SEPOLIA_TESTNET)
+                .unwrap();
+            // Inserting the same logs again is a no-op.
+            tx.insert_l1_messages(&logs, ChainId::SEPOLIA_TESTNET)
+                .unwrap();
+
+            let expected = [&logs[1], &logs[0]]
+                .map(|log| match &log.message {
+                    StarknetMessage::L1ToL2(message) => message
+                        .l1_handler_transaction()
+                        .calculate_hash(ChainId::SEPOLIA_TESTNET),
+                    StarknetMessage::ConsumedL2ToL1(_) => unreachable!(),
+                })
+                .to_vec();
+            assert_eq!(tx.l1_handler_transactions(deposit).unwrap(), expected);
+            assert!(tx.consumed_l2_to_l1_messages(deposit).unwrap().is_empty());
+
+            assert!(tx.l1_handler_transactions(withdrawal).unwrap().is_empty());
+            assert_eq!(
+                tx.consumed_l2_to_l1_messages(withdrawal).unwrap(),
+                vec![consumed().message_hash()]
+            );
+
+            let unknown = H256::from_low_u64_be(3);
+            assert!(tx.l1_handler_transactions(unknown).unwrap().is_empty());
+        }
+
+        #[test]
+        fn replace_after() {
+            let storage = crate::StorageBuilder::in_memory().unwrap();
+            let mut connection = storage.connection().unwrap();
+            let tx = connection.transaction().unwrap();
+
+            let log = |l1_block_number, l1_transaction_hash, nonce| MessageLog {
+                l1_block_number,
+                l1_transaction_hash: H256::from_low_u64_be(l1_transaction_hash),
+                log_index: 0,
+                message: l1_to_l2(nonce),
+            };
+            let handler = |nonce| match l1_to_l2(nonce) {
+                StarknetMessage::L1ToL2(message) => message
+                    .l1_handler_transaction()
+                    .calculate_hash(ChainId::SEPOLIA_TESTNET),
+                StarknetMessage::ConsumedL2ToL1(_) => unreachable!(),
+            };
+            let handlers = |l1_transaction_hash| {
+                tx.l1_handler_transactions(H256::from_low_u64_be(l1_transaction_hash))
+                    .unwrap()
+            };
+
+            tx.insert_l1_messages(&[log(10, 1, 0)], ChainId::SEPOLIA_TESTNET)
+                .unwrap();
+            tx.replace_l1_messages_after(
+                Some(10),
+                &[log(11, 2, 1), log(12, 3, 2)],
+                ChainId::SEPOLIA_TESTNET,
+            )
+            .unwrap();
+            assert_eq!(handlers(2), vec![handler(1)]);
+            assert_eq!(handlers(3), vec![handler(2)]);
+
+            // An L1 reorg dropped the transaction of block 11 and moved the one of
+            // block 12.
+            tx.replace_l1_messages_after(Some(10), &[log(11, 3, 2)], ChainId::SEPOLIA_TESTNET)
+                .unwrap();
+            assert_eq!(handlers(1), vec![handler(0)]);
+            assert!(handlers(2).is_empty());
+            assert_eq!(handlers(3), vec![handler(2)]);
+        }
+
+        #[test]
+        fn head() {
+            let storage = crate::StorageBuilder::in_memory().unwrap();
+            let mut connection = storage.connection().unwrap();
+            let tx = connection.transaction().unwrap();
+
+            assert_eq!(tx.l1_messages_head().unwrap(), None);
+
+            tx.update_l1_messages_head(100).unwrap();
+            assert_eq!(tx.l1_messages_head().unwrap(), Some(100));
+
+            tx.update_l1_messages_head(200).unwrap();
+            assert_eq!(tx.l1_messages_head().unwrap(), Some(200));
+        }
+    }
 }
@@ -1,5 +1,7 @@
+use std::collections::{HashSet, VecDeque};
 use std::num::NonZeroUsize;
 
+use anyhow::Context;
 use pathfinder_common::event::Event;
 use pathfinder_common::{
     BlockHash,
@@ -16,6 +18,10 @@ use crate::ReorgCounter;
 
 pub const PAGE_SIZE_LIMIT: usize = 1_024;
 pub const KEY_FILTER_LIMIT: usize = 16;
+/// The number of blocks covered by a single row of the event address index.
+const ADDRESS_INDEX_BUCKET_SIZE: u64 = 256;
+/// The number of blocks read from the event address index at once.
+const ADDRESS_INDEX_BATCH_SIZE: usize = 1_024;
 
 #[derive(Debug)]
 pub struct EventFilter {
@@ -79,16 +85,139 @@ impl Transaction<'_> {
         )?;
 
         let mut bloom = BloomFilter::new();
+        let mut addresses = HashSet::new();
         for event in events {
             bloom.set_keys(&event.keys);
             bloom.set_address(&event.from_address);
+            addresses.insert(event.from_address);
         }
 
         stmt.execute(params![&block_number, &bloom.to_compressed_bytes()])?;
 
+        if self.event_address_index {
+            for address in addresses {
+                self.update_event_address_index(address, block_number, true)
+                    .context("Updating event address index")?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Removes the block's currently stored events from the event address
+    /// index. Must be called before the events are replaced or deleted.
+    pub(super) fn purge_event_address_index(
+        &self,
+        block_number: BlockNumber,
+    ) -> anyhow::Result<()> {
+        if !self.event_address_index {
+            return Ok(());
+        }
+
+        let Some(events) = self.events_for_block(block_number.into())? else {
+            return Ok(());
+        };
+
+        let addresses: HashSet<_> = events
+            .iter()
+            .flat_map(|(_, events)| events.iter().map(|event| event.from_address))
+            .collect();
+        for address in addresses {
+            self.update_event_address_index(address, block_number, false)
+                .context("Updating event address index")?;
+        }
+
         Ok(())
     }
 
+    /// Marks whether `address` emitted events in the block.
+    fn update_event_address_index(
+        &self,
+        address: ContractAddress,
+        block_number: BlockNumber,
+        emitted: bool,
+    ) -> anyhow::Result<()> {
+        let bucket = block_number.get() / ADDRESS_INDEX_BUCKET_SIZE;
+        let offset = (block_number.get() % ADDRESS_INDEX_BUCKET_SIZE) as usize;
+
+        let mut blocks = self
+            .inner()
+            .prepare_cached(
+                "SELECT blocks FROM event_address_blocks WHERE contract_address = ? AND bucket = ?",
+            )?
+            .query_row(params![&address, &bucket], |row| {
+                Ok(row.get_blob(0)?.to_vec())
+            })
+            .optional()?
+            .unwrap_or_else(|| vec![0; ADDRESS_INDEX_BUCKET_SIZE as usize / 8]);
+
+        if emitted {
+            blocks[offset / 8] |= 1 << (offset % 8);
+        } else {
+            blocks[offset / 8] &= !(1 << (offset % 8));
+        }
+
+        if blocks.iter().all(|byte| *byte == 0) {
+            self.inner()
+                .prepare_cached(
+                    "DELETE FROM event_address_blocks WHERE contract_address = ? AND bucket = ?",
+                )?
+                .execute(params![&address, &bucket])?;
+        } else {
+            self.inner()
+                .prepare_cached(
+                    "INSERT OR REPLACE INTO event_address_blocks (contract_address, bucket, \
+                     blocks) VALUES (?, ?, ?)",
+                )?
+                .execute(params![&address, &bucket, &blocks])?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns up to `limit` blocks in `from..=to` in which `address` emitted
+    /// events, in ascending order.
+    fn event_address_blocks(
+        &self,
+        address: ContractAddress,
+        from: BlockNumber,
+        to: BlockNumber,
+        limit: usize,
+    ) -> anyhow::Result<Vec<BlockNumber>> {
+        let mut stmt = self.inner().prepare_cached(
+            r"
+            SELECT bucket, blocks
+            FROM event_address_blocks
+            WHERE contract_address = ? AND bucket >= ? AND bucket <= ?
+            ORDER BY bucket
+            ",
+        )?;
+        let mut rows = stmt.query(params![
+            &address,
+            &(from.get() / ADDRESS_INDEX_BUCKET_SIZE),
+            &(to.get() / ADDRESS_INDEX_BUCKET_SIZE)
+        ])?;
+
+        let mut block_numbers = Vec::new();
+        while let Some(row) = rows.next()? {
+            let bucket = row.get_i64(0)? as u64;
+            let blocks = row.get_blob(1)?;
+
+            let block_range = (0..blocks.len() * 8)
+                .filter(|offset| blocks[offset / 8] & (1 << (offset % 8)) != 0)
+                .map(|offset| bucket * ADDRESS_INDEX_BUCKET_SIZE + offset as u64)
+                .filter(|number| (from.get()..=to.get()).contains(number));
+            for number in block_range {
+                block_numbers.push(BlockNumber::new_or_panic(number));
+                if block_numbers.len() == limit {
+                    return Ok(block_numbers);
+                }
+            }
+        }
+
+        Ok(block_numbers)
+    }
+
     #[tracing::instrument(skip(self))]
     pub fn events(
         &self,
@@ -116,6 +245,13 @@ impl Transaction<'_> {
         let mut block_number = from_block;
         let mut offset = filter.offset;
 
+        // Queries for a single contract only visit the blocks in which the contract
+        // emitted events, as recorded by the event address index.
+        let mut indexed_blocks = filter
+            .contract_address
+            .filter(|_| self.event_address_index)
+            .map(|_| VecDeque::new());
+
         enum ScanResult {
             Done,
             PageFull,
@@ -123,6 +259,23 @@ impl Transaction<'_> {
         }
 
         let result = loop {
+            if let (Some(indexed_blocks), Some(address)) =
+                (indexed_blocks.as_mut(), filter.contract_address)
+            {
+                if indexed_blocks.is_empty() && block_number <= to_block {
+                    indexed_blocks.extend(self.event_address_blocks(
+                        address,
+                        block_number,
+                        to_block,
+                        ADDRESS_INDEX_BATCH_SIZE,
+                    )?);
+                }
+                match indexed_blocks.pop_front() {
+                    Some(next) => block_number = next,
+                    None => break ScanResult::Done,
+                }
+            }
+
             // Stop if we're past the last block.
             if block_number > to_block {
                 break ScanResult::Done;
@@ -165,6 +318,11 @@ impl Transaction<'_> {
                 offset,
                 &mut emitted_events,
             )? {
+                // The index may refer to blocks which are not yet visible, skip them.
+                BlockScanResult::NoSuchBlock if indexed_blocks.is_some() => {
+                    block_number += 1;
+                    continue;
+                }
                 BlockScanResult::NoSuchBlock => break ScanResult::Done,
                 BlockScanResult::Done { new_offset } => {
                     offset = new_offset;
@@ -377,6 +535,79 @@ enum Filter {
     Loaded(BloomFilter),
 }
 
+/// Rebuilds the event address index from the stored events of all blocks.
+pub(crate) fn build_event_address_index(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    use std::collections::HashMap;
+    use std::time::{Duration, Instant};
+
+    use super::transaction::{compression, dto};
+
+    tx.execute("DELETE FROM event_address_blocks", [])
+        .context("Clearing event address index")?;
+
+    let mut query_statement = tx.prepare(
+        r"SELECT block_number, events
+        FROM transactions
+        WHERE events IS NOT NULL
+        ORDER BY block_number",
+    )?;
+    let mut insert_statement = tx.prepare(
+        r"INSERT INTO event_address_blocks (contract_address, bucket, blocks) VALUES (?, ?, ?)",
+    )?;
+
+    let mut rows = query_statement.query([])?;
+
+    let mut current_bucket = 0;
+    let mut bucket_blocks: HashMap<ContractAddress, [u8; ADDRESS_INDEX_BUCKET_SIZE as usize / 8]> =
+        HashMap::new();
+    let mut progress_logged = Instant::now();
+    const LOG_RATE: Duration = Duration::from_secs(10);
+
+    while let Some(row) = rows.next().context("Fetching next block's events")? {
+        let block_number = row.get_block_number(0)?.get();
+
+        let bucket = block_number / ADDRESS_INDEX_BUCKET_SIZE;
+        if bucket != current_bucket {
+            if progress_logged.elapsed() > LOG_RATE {
+                tracing::info!(%block_number, "Indexing event addresses");
+                progress_logged = Instant::now();
+            }
+
+            for (contract_address, blocks) in bucket_blocks.drain() {
+                insert_statement.execute(params![
+                    &contract_address,
+                    &current_bucket,
+                    &blocks.as_slice()
+                ])?;
+            }
+            current_bucket = bucket;
+        }
+
+        let events =
+            compression::decompress_events(row.get_blob(1)?).context("Decompressing events")?;
+        let (events, _): (dto::EventsForBlock, _) =
+            bincode::serde::decode_from_slice(&events, bincode::config::standard())
+                .context("Deserializing events")?;
+
+        let offset = (block_number % ADDRESS_INDEX_BUCKET_SIZE) as usize;
+        for event in events.events().into_iter().flatten() {
+            let event = Event::from(event);
+            let blocks = bucket_blocks.entry(event.from_address).or_default();
+            blocks[offset / 8] |= 1 << (offset % 8);
+        }
+    }
+
+    for (contract_address, blocks) in bucket_blocks.drain() {
+        insert_statement.execute(params![
+            &contract_address,
+            &current_bucket,
+            &blocks.as_slice()
+        ])?;
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::LazyLock;
@@ -1092,6 +1323,128 @@ mod tests {
         );
     }
 
+    #[test]
+    fn contract_filter_only_scans_indexed_blocks() {
+        let (storage, test_data) = test_utils::setup_test_storage_with_event_address_index();
+        let emitted_events = test_data.events;
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        // The event is in the last block, so a linear scan would stop at the scan limit
+        // well before reaching it.
+        let expected_event = emitted_events.last().unwrap();
+        let filter = EventFilter {
+            from_block: None,
+            to_block: None,
+            contract_address: Some(expected_event.from_address),
+            keys: vec![],
+            page_size: 20,
+            offset: 0,
+        };
+        let events = tx
+            .events(&filter, 1.try_into().unwrap(), *MAX_BLOOM_FILTERS_TO_LOAD)
+            .unwrap();
+        assert_eq!(
+            events,
+            PageOfEvents {
+                events: vec![expected_event.clone()],
+                continuation_token: None,
+            }
+        );
+    }
+
+    #[test]
+    fn purged_block_is_removed_from_address_index() {
+        let (storage, test_data) = test_utils::setup_test_storage_with_event_address_index();
+        let emitted_events = test_data.events;
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let event = emitted_events.last().unwrap();
+        let blocks = tx
+            .event_address_blocks(
+                event.from_address,
+                BlockNumber::GENESIS,
+                BlockNumber::MAX,
+                10,
+            )
+            .unwrap();
+        assert_eq!(blocks, vec![event.block_number]);
+
+        tx.purge_block(event.block_number).unwrap();
+
+        let blocks = tx
+            .event_address_blocks(
+                event.from_address,
+                BlockNumber::GENESIS,
+                BlockNumber::MAX,
+                10,
+            )
+            .unwrap();
+        assert_eq!(blocks, vec![]);
+    }
+
+    #[test]
+    fn updated_events_are_rewritten_in_address_index() {
+        let (storage, test_data) = test_utils::setup_test_storage_with_event_address_index();
+        let emitted_events = test_data.events;
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let event = emitted_events.last().unwrap();
+        let replacement = Event {
+            data: vec![],
+            from_address: contract_address_bytes!(b"replacement"),
+            keys: vec![],
+        };
+        tx.update_events(event.block_number, vec![vec![replacement.clone()]])
+            .unwrap();
+
+        let blocks = |address| {
+            tx.event_address_blocks(address, BlockNumber::GENESIS, BlockNumber::MAX, 10)
+                .unwrap()
+        };
+        assert_eq!(blocks(event.from_address), vec![]);
+        assert_eq!(blocks(replacement.from_address), vec![event.block_number]);
+    }
+
+    #[test]
+    fn contract_filter_without_address_index() {
+        let (storage, test_data) = test_utils::setup_test_storage();
+        let emitted_events = test_data.events;
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let expected_event = &emitted_events[1];
+        let filter = EventFilter {
+            from_block: None,
+            to_block: None,
+            contract_address: Some(expected_event.from_address),
+            keys: vec![],
+            page_size: 20,
+            offset: 0,
+        };
+        let events = tx
+            .events(&filter, *MAX_BLOCKS_TO_SCAN, *MAX_BLOOM_FILTERS_TO_LOAD)
+            .unwrap();
+        assert_eq!(
+            events,
+            PageOfEvents {
+                events: vec![expected_event.clone()],
+                continuation_token: None,
+            }
+        );
+        assert!(tx
+            .event_address_blocks(
+                expected_event.from_address,
+                BlockNumber::GENESIS,
+                BlockNumber::MAX,
+                10
+            )
+            .unwrap()
+            .is_empty());
+    }
+
     #[test]
     fn bloom_filter_load_limit() {
         let (storage, test_data) = test_utils::setup_test_storage();
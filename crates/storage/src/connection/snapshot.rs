@@ -0,0 +1,212 @@
+use pathfinder_common::{
+    BlockNumber,
+    ClassCommitment,
+    ClassHash,
+    ContractAddress,
+    StateCommitment,
+    StorageAddress,
+};
+
+use crate::prelude::*;
+
+/// Progress of an in-flight snapshot sync.
+///
+/// `next_class` and `next_contract` are the start of the next range still to
+/// be downloaded, with `None` meaning that all ranges have been stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnapshotProgress {
+    pub block_number: BlockNumber,
+    pub state_commitment: StateCommitment,
+    /// Only known once the first contract range proof has been verified.
+    pub class_commitment: Option<ClassCommitment>,
+    pub next_class: Option<ClassHash>,
+    pub next_contract: Option<ContractAddress>,
+}
+
+impl Transaction<'_> {
+    pub fn snapshot_progress(&self) -> anyhow::Result<Option<SnapshotProgress>> {
+        self.inner()
+            .query_row(
+                "SELECT block_number, state_commitment, class_commitment, next_class, \
+                 next_contract FROM snapshot_sync_progress WHERE id = 1",
+                [],
+                |row| {
+                    Ok(SnapshotProgress {
+                        block_number: row.get_block_number(0)?,
+                        state_commitment: row.get_state_commitment(1)?,
+                        class_commitment: row.get_optional_class_commitment(2)?,
+                        next_class: row.get_optional_class_hash(3)?,
+                        next_contract: row.get_optional_felt(4)?.map(ContractAddress),
+                    })
+                },
+            )
+            .optional()
+            .map_err(|e| e.into())
+    }
+
+    pub fn upsert_snapshot_progress(&self, progress: &SnapshotProgress) -> anyhow::Result<()> {
+        self.inner().execute(
+            r"INSERT OR REPLACE INTO snapshot_sync_progress
+                (id, block_number, state_commitment, class_commitment, next_class, next_contract)
+            VALUES
+                (1, :block_number, :state_commitment, :class_commitment, :next_class,
+                 :next_contract)",
+            named_params! {
+                ":block_number": &progress.block_number,
+                ":state_commitment": &progress.state_commitment,
+                ":class_commitment": &progress.class_commitment,
+                ":next_class": &progress.next_class,
+                ":next_contract": &progress.next_contract,
+            },
+        )?;
+
+        Ok(())
+    }
+
+    /// Removes all snapshot sync progress, including the per-contract storage
+    /// progress.
+    pub fn delete_snapshot_progress(&self) -> anyhow::Result<()> {
+        self.inner().execute_batch(
+            r"DELETE FROM snapshot_sync_progress;
+            DELETE FROM snapshot_sync_storage_progress;",
+        )?;
+
+        Ok(())
+    }
+
+    /// Returns the start of the next storage range to download for a contract
+    /// whose storage has been stored before the contract itself. The start is
+    /// `None` if all of the contract's storage has been stored.
+    pub fn snapshot_storage_progress(
+        &self,
+        contract: ContractAddress,
+    ) -> anyhow::Result<Option<Option<StorageAddress>>> {
+        self.inner()
+            .query_row(
+                "SELECT next_key FROM snapshot_sync_storage_progress WHERE contract_address = ?",
+                params![&contract],
+                |row| row.get_optional_felt(0).map(|key| key.map(StorageAddress)),
+            )
+            .optional()
+            .map_err(|e| e.into())
+    }
+
+    pub fn upsert_snapshot_storage_progress(
+        &self,
+        contract: ContractAddress,
+        next_key: Option<StorageAddress>,
+    ) -> anyhow::Result<()> {
+        self.inner().execute(
+            "INSERT OR REPLACE INTO snapshot_sync_storage_progress (contract_address, next_key) \
+             VALUES (?, ?)",
+            params![&contract, &next_key],
+        )?;
+
+        Ok(())
+    }
+
+    pub fn delete_snapshot_storage_progress(
+        &self,
+        contract: ContractAddress,
+    ) -> anyhow::Result<()> {
+        self.inner().execute(
+            "DELETE FROM snapshot_sync_storage_progress WHERE contract_address = ?",
+            params![&contract],
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    fn progress() -> SnapshotProgress {
+        SnapshotProgress {
+            block_number: BlockNumber::new_or_panic(10),
+            state_commitment: state_commitment!("0x1"),
+            class_commitment: None,
+            next_class: Some(ClassHash::ZERO),
+            next_contract: Some(ContractAddress::ZERO),
+        }
+    }
+
+    #[test]
+    fn empty_is_none() {
+        let storage = crate::StorageBuilder::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        assert_eq!(tx.snapshot_progress().unwrap(), None);
+        assert_eq!(
+            tx.snapshot_storage_progress(contract_address!("0x1"))
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn upsert_replaces_progress() {
+        let storage = crate::StorageBuilder::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let mut expected = progress();
+        tx.upsert_snapshot_progress(&expected).unwrap();
+        assert_eq!(tx.snapshot_progress().unwrap(), Some(expected));
+
+        expected.class_commitment = Some(class_commitment!("0x2"));
+        expected.next_class = None;
+        expected.next_contract = Some(contract_address!("0x3"));
+        tx.upsert_snapshot_progress(&expected).unwrap();
+        assert_eq!(tx.snapshot_progress().unwrap(), Some(expected));
+    }
+
+    #[test]
+    fn storage_progress() {
+        let storage = crate::StorageBuilder::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let contract = contract_address!("0x1");
+        tx.upsert_snapshot_storage_progress(contract, Some(storage_address!("0x10")))
+            .unwrap();
+        tx.upsert_snapshot_storage_progress(contract, Some(storage_address!("0x20")))
+            .unwrap();
+        assert_eq!(
+            tx.snapshot_storage_progress(contract).unwrap(),
+            Some(Some(storage_address!("0x20")))
+        );
+
+        tx.upsert_snapshot_storage_progress(contract, None).unwrap();
+        assert_eq!(tx.snapshot_storage_progress(contract).unwrap(), Some(None));
+
+        tx.delete_snapshot_storage_progress(contract).unwrap();
+        assert_eq!(tx.snapshot_storage_progress(contract).unwrap(), None);
+    }
+
+    #[test]
+    fn delete_clears_all_progress() {
+        let storage = crate::StorageBuilder::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        tx.upsert_snapshot_progress(&progress()).unwrap();
+        tx.upsert_snapshot_storage_progress(
+            contract_address!("0x1"),
+            Some(storage_address!("0x10")),
+        )
+        .unwrap();
+
+        tx.delete_snapshot_progress().unwrap();
+        assert_eq!(tx.snapshot_progress().unwrap(), None);
+        assert_eq!(
+            tx.snapshot_storage_progress(contract_address!("0x1"))
+                .unwrap(),
+            None
+        );
+    }
+}
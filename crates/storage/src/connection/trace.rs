@@ -0,0 +1,167 @@
+use anyhow::Context;
+use pathfinder_common::BlockHash;
+
+use crate::prelude::*;
+
+impl Transaction<'_> {
+    /// Stores the traces of a block, serialized in the given format version,
+    /// replacing any previous ones.
+    ///
+    /// Nothing is stored if the block is not in the canonical chain, e.g.
+    /// because it was reorged away while it was being traced.
+    pub fn insert_block_traces(
+        &self,
+        block_hash: BlockHash,
+        format_version: u32,
+        traces: &[u8],
+    ) -> anyhow::Result<()> {
+        let mut compressor = zstd::bulk::Compressor::new(10).context("Creating zstd compressor")?;
+        let traces = compressor
+            .compress(traces)
+            .context("Compressing block traces")?;
+
+        self.inner()
+            .execute(
+                r"INSERT OR REPLACE INTO block_traces (block_hash, block_number, format_version, traces)
+                SELECT hash, number, :format_version, :traces FROM block_headers WHERE hash = :block_hash",
+                named_params! {
+                    ":block_hash": &block_hash,
+                    ":format_version": &format_version,
+                    ":traces": &traces,
+                },
+            )
+            .context("Inserting block traces")?;
+
+        Ok(())
+    }
+
+    /// Returns the serialized traces of a block and their format version.
+    pub fn block_traces(&self, block_hash: BlockHash) -> anyhow::Result<Option<(u32, Vec<u8>)>> {
+        let traces = self
+            .inner()
+            .query_row(
+                "SELECT format_version, traces FROM block_traces WHERE block_hash = ?",
+                params![&block_hash],
+                |row| Ok((row.get::<_, u32>(0)?, row.get::<_, Vec<u8>>(1)?)),
+            )
+            .optional()
+            .context("Querying block traces")?;
+
+        traces
+            .map(|(format_version, traces)| {
+                let traces =
+                    zstd::decode_all(traces.as_slice()).context("Decompressing block traces")?;
+                Ok((format_version, traces))
+            })
+            .transpose()
+    }
+
+    /// Deletes all but the `max_blocks` most recently stored block traces.
+    pub fn prune_block_traces(&self, max_blocks: u64) -> anyhow::Result<()> {
+        self.inner()
+            .execute(
+                r"DELETE FROM block_traces WHERE rowid NOT IN (
+                    SELECT rowid FROM block_traces ORDER BY rowid DESC LIMIT ?
+                )",
+                params![&max_blocks],
+            )
+            .context("Pruning block traces")?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::prelude::*;
+
+    use super::*;
+    use crate::Connection;
+
+    fn setup() -> (Connection, Vec<BlockHeader>) {
+        let storage = crate::StorageBuilder::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let genesis = BlockHeader::builder()
+            .with_number(BlockNumber::GENESIS)
+            .finalize_with_hash(block_hash_bytes!(b"genesis"));
+        let block1 = genesis
+            .child_builder()
+            .finalize_with_hash(block_hash_bytes!(b"block 1"));
+        let block2 = block1
+            .child_builder()
+            .finalize_with_hash(block_hash_bytes!(b"block 2"));
+
+        let headers = vec![genesis, block1, block2];
+        for header in &headers {
+            tx.insert_block_header(header).unwrap();
+        }
+        tx.commit().unwrap();
+
+        (connection, headers)
+    }
+
+    #[test]
+    fn insert_and_get() {
+        let (mut connection, headers) = setup();
+        let tx = connection.transaction().unwrap();
+
+        tx.insert_block_traces(headers[0].hash, 1, b"traces")
+            .unwrap();
+
+        let result = tx.block_traces(headers[0].hash).unwrap();
+        assert_eq!(result, Some((1, b"traces".to_vec())));
+
+        tx.insert_block_traces(headers[0].hash, 2, b"new traces")
+            .unwrap();
+
+        let result = tx.block_traces(headers[0].hash).unwrap();
+        assert_eq!(result, Some((2, b"new traces".to_vec())));
+
+        let result = tx.block_traces(headers[1].hash).unwrap();
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn unknown_block_is_not_stored() {
+        let (mut connection, _) = setup();
+        let tx = connection.transaction().unwrap();
+
+        let hash = block_hash_bytes!(b"unknown");
+        tx.insert_block_traces(hash, 1, b"traces").unwrap();
+
+        let result = tx.block_traces(hash).unwrap();
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn prune_keeps_most_recently_stored() {
+        let (mut connection, headers) = setup();
+        let tx = connection.transaction().unwrap();
+
+        tx.insert_block_traces(headers[2].hash, 1, b"2").unwrap();
+        tx.insert_block_traces(headers[0].hash, 1, b"0").unwrap();
+        tx.insert_block_traces(headers[1].hash, 1, b"1").unwrap();
+
+        tx.prune_block_traces(2).unwrap();
+
+        assert_eq!(tx.block_traces(headers[2].hash).unwrap(), None);
+        assert!(tx.block_traces(headers[0].hash).unwrap().is_some());
+        assert!(tx.block_traces(headers[1].hash).unwrap().is_some());
+    }
+
+    #[test]
+    fn purged_with_block() {
+        let (mut connection, headers) = setup();
+        let tx = connection.transaction().unwrap();
+
+        tx.insert_block_traces(headers[2].hash, 1, b"traces")
+            .unwrap();
+        tx.purge_block(headers[2].number).unwrap();
+
+        let result = tx.block_traces(headers[2].hash).unwrap();
+        assert_eq!(result, None);
+    }
+}
@@ -205,6 +205,11 @@ impl Transaction<'_> {
         let encoded_events =
             compression::compress_events(&encoded_events).context("Compressing events")?;
 
+        // Clear the index bits of the events being replaced, so that contracts which
+        // no longer emit events in this block are not reported for it.
+        self.purge_event_address_index(block_number)
+            .context("Deleting replaced events from event address index")?;
+
         stmt.execute(named_params![
             ":block_number": &block_number,
             ":events": &encoded_events,
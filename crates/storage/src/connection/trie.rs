@@ -7,7 +7,7 @@ use pathfinder_common::prelude::*;
 use pathfinder_crypto::Felt;
 
 use crate::prelude::*;
-use crate::{BlockId, TriePruneMode};
+use crate::BlockId;
 
 impl Transaction<'_> {
     pub fn class_root_index(&self, block_number: BlockNumber) -> anyhow::Result<Option<u64>> {
@@ -106,16 +106,20 @@ impl Transaction<'_> {
             params![&block_number, &new_root_index],
         )?;
 
-        if let TriePruneMode::Prune { num_blocks_kept } = self.trie_prune_mode {
-            if let Some(block_number) = block_number.checked_sub(num_blocks_kept) {
-                self.delete_class_roots(block_number)?;
+        if let Some(num_blocks_kept) = self.trie_prune_mode.num_blocks_kept() {
+            if let Some(before_block) = block_number.checked_sub(num_blocks_kept) {
+                self.delete_class_roots(block_number, before_block)?;
             }
         }
 
         Ok(())
     }
 
-    fn delete_class_roots(&self, before_block: BlockNumber) -> anyhow::Result<()> {
+    fn delete_class_roots(
+        &self,
+        block_number: BlockNumber,
+        before_block: BlockNumber,
+    ) -> anyhow::Result<()> {
         let mut stmt = self.inner().prepare_cached(
             "SELECT block_number
             FROM class_roots
@@ -129,10 +133,20 @@ impl Transaction<'_> {
 
         if let Some(last_block_with_root_index) = last_block_with_root_index {
             tracing::trace!(%last_block_with_root_index, "Removing class roots");
-            let mut stmt = self
-                .inner()
-                .prepare_cached("DELETE FROM class_roots WHERE block_number < ?")?;
-            stmt.execute(params![&last_block_with_root_index])?;
+            if let Some(interval) = self.trie_prune_mode.checkpoint_interval() {
+                self.delete_non_checkpoint_rows(
+                    "class_roots",
+                    None,
+                    block_number,
+                    last_block_with_root_index,
+                    interval,
+                )?;
+            } else {
+                let mut stmt = self
+                    .inner()
+                    .prepare_cached("DELETE FROM class_roots WHERE block_number < ?")?;
+                stmt.execute(params![&last_block_with_root_index])?;
+            }
         }
 
         Ok(())
@@ -150,9 +164,9 @@ impl Transaction<'_> {
             params![&block_number, &contract, &state_hash],
         )?;
 
-        if let TriePruneMode::Prune { num_blocks_kept } = self.trie_prune_mode {
-            if let Some(block_number) = block_number.checked_sub(num_blocks_kept) {
-                self.delete_contract_state_hashes(contract, block_number)?;
+        if let Some(num_blocks_kept) = self.trie_prune_mode.num_blocks_kept() {
+            if let Some(before_block) = block_number.checked_sub(num_blocks_kept) {
+                self.delete_contract_state_hashes(contract, block_number, before_block)?;
             }
         }
 
@@ -162,6 +176,7 @@ impl Transaction<'_> {
     fn delete_contract_state_hashes(
         &self,
         contract: ContractAddress,
+        block_number: BlockNumber,
         before_block: BlockNumber,
     ) -> anyhow::Result<()> {
         let mut stmt = self.inner().prepare_cached(
@@ -178,10 +193,21 @@ impl Transaction<'_> {
             .optional()?;
 
         if let Some(last_block_with_contract_state_hash) = last_block_with_contract_state_hash {
-            let mut stmt = self.inner().prepare_cached(
-                "DELETE FROM contract_state_hashes WHERE contract_address = ? AND block_number < ?",
-            )?;
-            stmt.execute(params![&contract, &last_block_with_contract_state_hash])?;
+            if let Some(interval) = self.trie_prune_mode.checkpoint_interval() {
+                self.delete_non_checkpoint_rows(
+                    "contract_state_hashes",
+                    Some(contract),
+                    block_number,
+                    last_block_with_contract_state_hash,
+                    interval,
+                )?;
+            } else {
+                let mut stmt = self.inner().prepare_cached(
+                    "DELETE FROM contract_state_hashes WHERE contract_address = ? AND \
+                     block_number < ?",
+                )?;
+                stmt.execute(params![&contract, &last_block_with_contract_state_hash])?;
+            }
         }
         Ok(())
     }
@@ -217,16 +243,20 @@ impl Transaction<'_> {
             params![&block_number, &new_root_index],
         )?;
 
-        if let TriePruneMode::Prune { num_blocks_kept } = self.trie_prune_mode {
-            if let Some(block_number) = block_number.checked_sub(num_blocks_kept) {
-                self.delete_storage_roots(block_number)?;
+        if let Some(num_blocks_kept) = self.trie_prune_mode.num_blocks_kept() {
+            if let Some(before_block) = block_number.checked_sub(num_blocks_kept) {
+                self.delete_storage_roots(block_number, before_block)?;
             }
         }
 
         Ok(())
     }
 
-    fn delete_storage_roots(&self, before_block: BlockNumber) -> anyhow::Result<()> {
+    fn delete_storage_roots(
+        &self,
+        block_number: BlockNumber,
+        before_block: BlockNumber,
+    ) -> anyhow::Result<()> {
         let mut stmt = self.inner().prepare_cached(
             "SELECT block_number
             FROM storage_roots
@@ -239,10 +269,20 @@ impl Transaction<'_> {
             .optional()?;
 
         if let Some(last_block_with_root_index) = last_block_with_root_index {
-            let mut stmt = self
-                .inner()
-                .prepare_cached("DELETE FROM storage_roots WHERE block_number < ?")?;
-            stmt.execute(params![&last_block_with_root_index])?;
+            if let Some(interval) = self.trie_prune_mode.checkpoint_interval() {
+                self.delete_non_checkpoint_rows(
+                    "storage_roots",
+                    None,
+                    block_number,
+                    last_block_with_root_index,
+                    interval,
+                )?;
+            } else {
+                let mut stmt = self
+                    .inner()
+                    .prepare_cached("DELETE FROM storage_roots WHERE block_number < ?")?;
+                stmt.execute(params![&last_block_with_root_index])?;
+            }
         }
 
         Ok(())
@@ -265,9 +305,9 @@ impl Transaction<'_> {
             params![&block_number, &contract, &new_root_index],
         )?;
 
-        if let TriePruneMode::Prune { num_blocks_kept } = self.trie_prune_mode {
-            if let Some(block_number) = block_number.checked_sub(num_blocks_kept) {
-                self.delete_contract_roots(contract, block_number)?;
+        if let Some(num_blocks_kept) = self.trie_prune_mode.num_blocks_kept() {
+            if let Some(before_block) = block_number.checked_sub(num_blocks_kept) {
+                self.delete_contract_roots(contract, block_number, before_block)?;
             }
         }
 
@@ -277,6 +317,7 @@ impl Transaction<'_> {
     fn delete_contract_roots(
         &self,
         contract: ContractAddress,
+        block_number: BlockNumber,
         before_block: BlockNumber,
     ) -> anyhow::Result<()> {
         let mut stmt = self.inner().prepare_cached(
@@ -293,15 +334,81 @@ impl Transaction<'_> {
             .optional()?;
 
         if let Some(last_block_with_root_index) = last_block_with_root_index {
-            let mut stmt = self.inner().prepare_cached(
-                "DELETE FROM contract_roots WHERE contract_address = ? AND block_number < ?",
-            )?;
-            stmt.execute(params![&contract, &last_block_with_root_index])?;
+            if let Some(interval) = self.trie_prune_mode.checkpoint_interval() {
+                self.delete_non_checkpoint_rows(
+                    "contract_roots",
+                    Some(contract),
+                    block_number,
+                    last_block_with_root_index,
+                    interval,
+                )?;
+            } else {
+                let mut stmt = self.inner().prepare_cached(
+                    "DELETE FROM contract_roots WHERE contract_address = ? AND block_number < ?",
+                )?;
+                stmt.execute(params![&contract, &last_block_with_root_index])?;
+            }
         }
 
         Ok(())
     }
 
+    /// Deletes the rows of a root index or state hash `table` up to
+    /// `last_block`, except for rows that are still in effect at a trie
+    /// checkpoint, i.e. the latest row at or before a multiple of `interval`.
+    ///
+    /// The row at `last_block` itself is always kept. Rows before the one that
+    /// was the last row when the row preceding `block_number` was inserted have
+    /// already been checked then, so only the rows from there on are checked.
+    fn delete_non_checkpoint_rows(
+        &self,
+        table: &'static str,
+        contract: Option<ContractAddress>,
+        block_number: BlockNumber,
+        last_block: BlockNumber,
+        interval: u64,
+    ) -> anyhow::Result<()> {
+        let contract_filter = match contract {
+            Some(_) => "contract_address = :contract AND",
+            None => "",
+        };
+        let num_blocks_kept = self.trie_prune_mode.num_blocks_kept().unwrap_or_default();
+        // A row is in effect from its block up to (excluding) the next row's block. It
+        // is only required if that range contains a checkpoint.
+        let mut stmt = self.inner().prepare_cached(&format!(
+            r"DELETE FROM {table} WHERE {contract_filter} block_number IN (
+                SELECT block_number FROM (
+                    SELECT block_number, LEAD(block_number) OVER (ORDER BY block_number) AS next_block
+                    FROM {table}
+                    WHERE {contract_filter} block_number <= :last_block AND block_number >= IFNULL((
+                        SELECT MAX(block_number) FROM {table} WHERE {contract_filter} block_number <= (
+                            SELECT MAX(block_number) FROM {table} WHERE {contract_filter} block_number < :block_number
+                        ) - :num_blocks_kept
+                    ), 0)
+                )
+                WHERE (next_block - 1) / :interval * :interval < block_number
+            )"
+        ))?;
+
+        match contract {
+            Some(contract) => stmt.execute(named_params![
+                ":contract": &contract,
+                ":block_number": &block_number,
+                ":last_block": &last_block,
+                ":num_blocks_kept": &num_blocks_kept,
+                ":interval": &interval,
+            ])?,
+            None => stmt.execute(named_params![
+                ":block_number": &block_number,
+                ":last_block": &last_block,
+                ":num_blocks_kept": &num_blocks_kept,
+                ":interval": &interval,
+            ])?,
+        };
+
+        Ok(())
+    }
+
     pub fn insert_contract_trie(
         &self,
         update: &TrieUpdate,
@@ -356,7 +463,7 @@ impl Transaction<'_> {
         let Some(block_number) = self.block_number(BlockId::Latest)? else {
             return Ok(());
         };
-        let TriePruneMode::Prune { num_blocks_kept } = self.trie_prune_mode else {
+        let Some(num_blocks_kept) = self.trie_prune_mode.num_blocks_kept() else {
             return Ok(());
         };
         tracing::info!("Cleaning up state trie");
@@ -369,7 +476,113 @@ impl Transaction<'_> {
     pub fn coalesce_trie_nodes(&self, target_block: BlockNumber) -> anyhow::Result<()> {
         self.coalesce_removed_trie_nodes(target_block, "trie_contracts")?;
         self.coalesce_removed_trie_nodes(target_block, "trie_storage")?;
-        self.coalesce_removed_trie_nodes(target_block, "trie_class")
+        self.coalesce_removed_trie_nodes(target_block, "trie_class")?;
+
+        // Checkpoints after the target block were reorged away. The checkpoint at the
+        // target block, if any, still protects the target's trie.
+        self.inner()
+            .execute(
+                "DELETE FROM trie_checkpoints WHERE block_number > ?",
+                params![&target_block],
+            )
+            .context("Deleting reorged trie checkpoints")?;
+
+        Ok(())
+    }
+
+    /// Returns the latest trie checkpoint strictly before `block_number`, if
+    /// checkpoints are kept.
+    fn latest_checkpoint_before(&self, block_number: BlockNumber) -> Option<BlockNumber> {
+        let interval = self.trie_prune_mode.checkpoint_interval()?;
+        let parent = block_number.parent()?;
+        Some(BlockNumber::new_or_panic(
+            parent.get() / interval * interval,
+        ))
+    }
+
+    /// Records the largest node index of `table` at the latest checkpoint
+    /// before `block_number`.
+    ///
+    /// Node indices are allocated in increasing order, so this must happen
+    /// before any nodes are inserted for blocks after the checkpoint. Every
+    /// node with an index up to and including the recorded one is then
+    /// potentially part of the checkpoint's trie.
+    fn mark_trie_checkpoint(
+        &self,
+        block_number: BlockNumber,
+        table: &'static str,
+    ) -> anyhow::Result<()> {
+        let Some(checkpoint) = self.latest_checkpoint_before(block_number) else {
+            return Ok(());
+        };
+
+        let mut stmt = self
+            .inner()
+            .prepare_cached(&format!(
+                "INSERT OR IGNORE INTO trie_checkpoints (trie, block_number, max_idx) SELECT ?, \
+                 ?, MAX(idx) FROM {table}"
+            ))
+            .context("Creating checkpoint statement")?;
+        stmt.execute(params![&table, &checkpoint])
+            .context("Inserting trie checkpoint")?;
+
+        Ok(())
+    }
+
+    /// Extends the checkpoint at `block_number` to all nodes of `table`
+    /// inserted so far.
+    ///
+    /// A checkpoint is only recorded once the block after it is inserted, so
+    /// this only applies if a reorg back to the checkpoint block inserts new
+    /// nodes for its trie.
+    fn extend_trie_checkpoint(
+        &self,
+        block_number: BlockNumber,
+        table: &'static str,
+    ) -> anyhow::Result<()> {
+        let Some(interval) = self.trie_prune_mode.checkpoint_interval() else {
+            return Ok(());
+        };
+        if block_number.get() % interval != 0 {
+            return Ok(());
+        }
+
+        let mut stmt = self
+            .inner()
+            .prepare_cached(&format!(
+                "UPDATE trie_checkpoints SET max_idx = (SELECT MAX(idx) FROM {table}) WHERE trie \
+                 = ? AND block_number = ?"
+            ))
+            .context("Creating checkpoint update statement")?;
+        stmt.execute(params![&table, &block_number])
+            .context("Updating trie checkpoint")?;
+
+        Ok(())
+    }
+
+    /// Returns the largest node index of `table` which is required by the
+    /// latest checkpoint before `block_number`, if any.
+    fn trie_checkpoint_max_index(
+        &self,
+        block_number: BlockNumber,
+        table: &'static str,
+    ) -> anyhow::Result<Option<u64>> {
+        let Some(checkpoint) = self.latest_checkpoint_before(block_number) else {
+            return Ok(None);
+        };
+
+        let mut stmt = self
+            .inner()
+            .prepare_cached(
+                "SELECT max_idx FROM trie_checkpoints WHERE trie = ? AND block_number = ?",
+            )
+            .context("Creating checkpoint query statement")?;
+        stmt.query_row(params![&table, &checkpoint], |row| {
+            row.get::<_, Option<u64>>(0)
+        })
+        .optional()
+        .map(Option::flatten)
+        .context("Querying trie checkpoint")
     }
 
     /// Mark the input nodes as ready for removal.
@@ -397,13 +610,16 @@ impl Transaction<'_> {
         Ok(())
     }
 
-    /// Coalesce removed trie nodes to the target block.
+    /// Coalesce removed trie nodes to the block after the target block.
     ///
     /// "Moves" all removed nodes from blocks _after_ the target block into
-    /// the target block.
+    /// the block following it.
     ///
     /// Used during a reorg to move deleted node data of all reorged-away blocks
-    /// to our reorg target.
+    /// to the first block after our reorg target. The nodes may still be part
+    /// of the target's trie, so they are only removed from the block after it
+    /// onwards, which also keeps them protected by the latest checkpoint at or
+    /// before the target.
     fn coalesce_removed_trie_nodes(
         &self,
         target_block: BlockNumber,
@@ -413,12 +629,12 @@ impl Transaction<'_> {
             .inner()
             .prepare_cached(&format!(
                 "UPDATE {table}_removals
-                SET block_number = ?1
+                SET block_number = ?1 + 1
                 WHERE block_number > ?1"
             ))
             .context("Creating update statement")?;
         stmt.execute(params![&target_block])
-            .context("Moving removed trie node data to target block")?;
+            .context("Moving removed trie node data to the block after the target")?;
 
         Ok(())
     }
@@ -435,7 +651,7 @@ impl Transaction<'_> {
             let mut select_stmt = self
                 .inner()
                 .prepare_cached(&format!(
-                    r"SELECT indices FROM {table}_removals WHERE block_number < ?"
+                    r"SELECT block_number, indices FROM {table}_removals WHERE block_number < ?"
                 ))
                 .context("Creating removal statement")?;
             let mut rows = select_stmt
@@ -446,11 +662,17 @@ impl Transaction<'_> {
                 .prepare_cached(&format!(r"DELETE FROM {table} WHERE idx = ?"))
                 .context("Creating delete statement")?;
             while let Some(row) = rows.next().context("Iterating over rows")? {
-                let (indices, _) = bincode::decode_from_slice::<Vec<u64>, _>(
-                    row.get_blob(0)?,
+                let removed_at = row.get_block_number(0)?;
+                let (mut indices, _) = bincode::decode_from_slice::<Vec<u64>, _>(
+                    row.get_blob(1)?,
                     bincode::config::standard(),
                 )
                 .context("Decoding indices")?;
+                // Nodes which already existed at the latest checkpoint are still part of
+                // its trie and must be kept.
+                if let Some(max_idx) = self.trie_checkpoint_max_index(removed_at, table)? {
+                    indices.retain(|idx| *idx > max_idx);
+                }
                 for idx in indices.iter() {
                     delete_stmt.execute(params![idx]).context("Deleting node")?;
                 }
@@ -479,7 +701,8 @@ impl Transaction<'_> {
         block_number: BlockNumber,
         table: &'static str,
     ) -> anyhow::Result<RootIndexUpdate> {
-        if let TriePruneMode::Prune { num_blocks_kept } = self.trie_prune_mode {
+        if let Some(num_blocks_kept) = self.trie_prune_mode.num_blocks_kept() {
+            self.mark_trie_checkpoint(block_number, table)?;
             self.prune_trie(block_number, num_blocks_kept, table)?;
             self.remove_trie(&update.nodes_removed, block_number, table)?;
         }
@@ -555,6 +778,8 @@ impl Transaction<'_> {
             metrics::increment_counter!(METRIC_TRIE_NODES_ADDED, "table" => table);
         }
 
+        self.extend_trie_checkpoint(block_number, table)?;
+
         Ok(RootIndexUpdate::Updated(
             *indices
                 .get(&(update.nodes_added.len() - 1))
@@ -782,6 +1007,7 @@ mod tests {
     use pathfinder_common::macro_prelude::*;
 
     use super::*;
+    use crate::TriePruneMode;
 
     #[test]
     fn class_roots() {
@@ -1710,4 +1936,212 @@ mod tests {
             Some(2)
         );
     }
+
+    #[test]
+    fn class_trie_pruning_keeps_checkpoints() {
+        let mut db =
+            crate::StorageBuilder::in_memory_with_trie_pruning(TriePruneMode::Checkpoints {
+                num_blocks_kept: 0,
+                interval: 2,
+            })
+            .unwrap()
+            .connection()
+            .unwrap();
+        let tx = db.transaction().unwrap();
+
+        let update = |removed: Vec<u64>| TrieUpdate {
+            nodes_added: vec![
+                (
+                    felt!("0"),
+                    Node::Binary {
+                        left: NodeRef::Index(1),
+                        right: NodeRef::Index(2),
+                    },
+                ),
+                (felt!("1"), Node::LeafBinary),
+                (felt!("2"), Node::LeafBinary),
+            ],
+            nodes_removed: removed,
+            root_commitment: Felt::ZERO,
+        };
+
+        // Index 1 is created at the checkpoint block and removed after it, index 4
+        // is both created and removed after the checkpoint.
+        tx.insert_class_trie(&update(vec![]), BlockNumber::GENESIS)
+            .unwrap();
+        tx.insert_class_trie(&update(vec![1]), BlockNumber::GENESIS + 1)
+            .unwrap();
+        tx.insert_class_trie(&update(vec![4]), BlockNumber::GENESIS + 2)
+            .unwrap();
+        tx.insert_class_trie(&update(vec![]), BlockNumber::GENESIS + 3)
+            .unwrap();
+
+        assert!(tx.class_trie_node(1).unwrap().is_some());
+        assert!(tx.class_trie_node(4).unwrap().is_none());
+    }
+
+    #[test]
+    fn class_root_insert_keeps_checkpoints() {
+        let mut db =
+            crate::StorageBuilder::in_memory_with_trie_pruning(TriePruneMode::Checkpoints {
+                num_blocks_kept: 0,
+                interval: 2,
+            })
+            .unwrap()
+            .connection()
+            .unwrap();
+        let tx = db.transaction().unwrap();
+
+        for block in 0..5 {
+            tx.insert_class_root(
+                BlockNumber::new_or_panic(block),
+                RootIndexUpdate::Updated(block + 1),
+            )
+            .unwrap();
+        }
+
+        for (block, exists) in [(0, true), (1, false), (2, true), (3, false), (4, true)] {
+            assert_eq!(
+                tx.class_root_exists(BlockNumber::new_or_panic(block))
+                    .unwrap(),
+                exists,
+                "block {block}"
+            );
+        }
+        assert_eq!(
+            tx.class_root_index(BlockNumber::new_or_panic(3)).unwrap(),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn contract_root_insert_keeps_checkpoints() {
+        let mut db =
+            crate::StorageBuilder::in_memory_with_trie_pruning(TriePruneMode::Checkpoints {
+                num_blocks_kept: 2,
+                interval: 10,
+            })
+            .unwrap()
+            .connection()
+            .unwrap();
+        let tx = db.transaction().unwrap();
+        let contract = contract_address!("0xc0");
+
+        // Rows at blocks 1 and 3 are only checked once the contract is updated again
+        // much later.
+        for (block, root_index) in [(1, 1), (3, 2), (25, 3), (40, 4)] {
+            tx.insert_contract_root(
+                BlockNumber::new_or_panic(block),
+                contract,
+                RootIndexUpdate::Updated(root_index),
+            )
+            .unwrap();
+        }
+
+        assert_eq!(
+            tx.contract_root_index(BlockNumber::new_or_panic(2), contract)
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            tx.contract_root_index(BlockNumber::new_or_panic(10), contract)
+                .unwrap(),
+            Some(2)
+        );
+        assert_eq!(
+            tx.contract_root_index(BlockNumber::new_or_panic(30), contract)
+                .unwrap(),
+            Some(3)
+        );
+    }
+
+    type InsertTrie =
+        fn(&Transaction<'_>, &TrieUpdate, BlockNumber) -> anyhow::Result<RootIndexUpdate>;
+    type GetTrieNode = fn(&Transaction<'_>, u64) -> anyhow::Result<Option<StoredNode>>;
+
+    const TRIES: [(InsertTrie, GetTrieNode); 3] = [
+        (Transaction::insert_class_trie, Transaction::class_trie_node),
+        (
+            Transaction::insert_contract_trie,
+            Transaction::contract_trie_node,
+        ),
+        (
+            Transaction::insert_storage_trie,
+            Transaction::storage_trie_node,
+        ),
+    ];
+
+    /// Inserts a trie consisting of a single node for each of the blocks, which
+    /// replaces the node `previous`, and returns the node indices.
+    fn insert_single_node_tries(
+        tx: &Transaction<'_>,
+        insert: InsertTrie,
+        blocks: std::ops::Range<u64>,
+        mut previous: Option<u64>,
+    ) -> Vec<u64> {
+        let mut indices = Vec::new();
+        for block in blocks {
+            let update = TrieUpdate {
+                nodes_added: vec![(Felt::from_u64(block), Node::LeafBinary)],
+                nodes_removed: previous.into_iter().collect(),
+                root_commitment: Felt::ZERO,
+            };
+            let RootIndexUpdate::Updated(index) =
+                insert(tx, &update, BlockNumber::new_or_panic(block)).unwrap()
+            else {
+                panic!("Expected a new root");
+            };
+            indices.push(index);
+            previous = Some(index);
+        }
+        indices
+    }
+
+    fn checkpoints_storage() -> crate::Storage {
+        crate::StorageBuilder::in_memory_with_trie_pruning(TriePruneMode::Checkpoints {
+            num_blocks_kept: 1,
+            interval: 2,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn reorg_to_checkpoint_keeps_its_trie() {
+        for (insert, node) in TRIES {
+            let mut db = checkpoints_storage().connection().unwrap();
+            let tx = db.transaction().unwrap();
+
+            let indices = insert_single_node_tries(&tx, insert, 0..5, None);
+            let checkpoint = indices[2];
+            let reorged = indices[3];
+
+            // Reorg back to the checkpoint at block 2. The nodes removed by the
+            // reorged-away blocks include the checkpoint's own node.
+            tx.coalesce_trie_nodes(BlockNumber::new_or_panic(2))
+                .unwrap();
+            insert_single_node_tries(&tx, insert, 3..6, Some(checkpoint));
+
+            assert!(node(&tx, checkpoint).unwrap().is_some());
+            assert!(node(&tx, reorged).unwrap().is_none());
+        }
+    }
+
+    #[test]
+    fn reorg_rewriting_checkpoint_trie_keeps_new_nodes() {
+        for (insert, node) in TRIES {
+            let mut db = checkpoints_storage().connection().unwrap();
+            let tx = db.transaction().unwrap();
+
+            let indices = insert_single_node_tries(&tx, insert, 0..5, None);
+
+            // Reverting to the checkpoint at block 2 stores a new trie for it.
+            let checkpoint = insert_single_node_tries(&tx, insert, 2..3, indices.last().copied());
+            let checkpoint = checkpoint[0];
+            tx.coalesce_trie_nodes(BlockNumber::new_or_panic(2))
+                .unwrap();
+            insert_single_node_tries(&tx, insert, 3..6, Some(checkpoint));
+
+            assert!(node(&tx, checkpoint).unwrap().is_some());
+        }
+    }
 }
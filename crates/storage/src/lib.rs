@@ -90,6 +90,7 @@ struct Inner {
     pool: Pool<SqliteConnectionManager>,
     bloom_filter_cache: Arc<bloom::Cache>,
     trie_prune_mode: TriePruneMode,
+    event_address_index: bool,
 }
 
 pub struct StorageManager {
@@ -97,6 +98,7 @@ pub struct StorageManager {
     journal_mode: JournalMode,
     bloom_filter_cache: Arc<bloom::Cache>,
     trie_prune_mode: TriePruneMode,
+    event_address_index: bool,
 }
 
 impl std::fmt::Debug for StorageManager {
@@ -105,6 +107,7 @@ impl std::fmt::Debug for StorageManager {
             .field("database_path", &self.database_path)
             .field("journal_mode", &self.journal_mode)
             .field("trie_prune_mode", &self.trie_prune_mode)
+            .field("event_address_index", &self.event_address_index)
             .finish()
     }
 }
@@ -128,6 +131,7 @@ impl StorageManager {
             pool,
             bloom_filter_cache: self.bloom_filter_cache.clone(),
             trie_prune_mode: self.trie_prune_mode,
+            event_address_index: self.event_address_index,
         }))
     }
 
@@ -148,6 +152,7 @@ pub struct StorageBuilder {
     journal_mode: JournalMode,
     bloom_filter_cache_size: usize,
     trie_prune_mode: Option<TriePruneMode>,
+    event_address_index: Option<bool>,
 }
 
 impl StorageBuilder {
@@ -157,6 +162,7 @@ impl StorageBuilder {
             journal_mode: JournalMode::WAL,
             bloom_filter_cache_size: 16,
             trie_prune_mode: None,
+            event_address_index: None,
         }
     }
 
@@ -175,6 +181,17 @@ impl StorageBuilder {
         self
     }
 
+    /// Enables or disables the index of the blocks in which each contract
+    /// emitted events. If not set, the index is kept as it is in the
+    /// database.
+    ///
+    /// The index is built from the stored events when it is first enabled on
+    /// an existing database, and dropped when it is disabled.
+    pub fn event_address_index(mut self, event_address_index: bool) -> Self {
+        self.event_address_index = Some(event_address_index);
+        self
+    }
+
     /// Convenience function for tests to create an in-memory database.
     pub fn in_memory() -> anyhow::Result<Storage> {
         Self::in_memory_with_trie_pruning(TriePruneMode::Archive)
@@ -183,6 +200,19 @@ impl StorageBuilder {
     /// Convenience function for tests to create an in-memory database with a
     /// specific trie prune mode.
     pub fn in_memory_with_trie_pruning(trie_prune_mode: TriePruneMode) -> anyhow::Result<Storage> {
+        Self::in_memory_impl(trie_prune_mode, false)
+    }
+
+    /// Convenience function for tests to create an in-memory database with the
+    /// [event address index](Self::event_address_index) enabled.
+    pub fn in_memory_with_event_address_index() -> anyhow::Result<Storage> {
+        Self::in_memory_impl(TriePruneMode::Archive, true)
+    }
+
+    fn in_memory_impl(
+        trie_prune_mode: TriePruneMode,
+        event_address_index: bool,
+    ) -> anyhow::Result<Storage> {
         // Create a unique database name so that they are not shared between
         // concurrent tests. i.e. Make every in-mem Storage unique.
         static COUNT: std::sync::Mutex<u64> = std::sync::Mutex::new(0);
@@ -202,9 +232,10 @@ impl StorageBuilder {
 
         let mut storage = Self::file(database_path)
             .journal_mode(JournalMode::Rollback)
+            .event_address_index(event_address_index)
             .migrate()?;
 
-        if let TriePruneMode::Prune { .. } = trie_prune_mode {
+        if trie_prune_mode.num_blocks_kept().is_some() {
             conn.execute(
                 "INSERT INTO storage_flags (flag) VALUES ('prune_tries')",
                 [],
@@ -253,10 +284,20 @@ impl StorageBuilder {
 
         // Validate that configuration matches database flags.
         let trie_prune_mode = self.determine_trie_prune_mode(&mut connection, is_new_database)?;
-        if let TriePruneMode::Prune { num_blocks_kept } = trie_prune_mode {
-            tracing::info!(history_kept=%num_blocks_kept, "Merkle trie pruning enabled");
-        } else {
-            tracing::info!("Merkle trie pruning disabled");
+        let event_address_index = self.apply_event_address_index(&mut connection)?;
+        match trie_prune_mode {
+            TriePruneMode::Archive => tracing::info!("Merkle trie pruning disabled"),
+            TriePruneMode::Prune { num_blocks_kept } => {
+                tracing::info!(history_kept=%num_blocks_kept, "Merkle trie pruning enabled")
+            }
+            TriePruneMode::Checkpoints {
+                num_blocks_kept,
+                interval,
+            } => tracing::info!(
+                history_kept=%num_blocks_kept,
+                checkpoint_interval=%interval,
+                "Merkle trie pruning enabled, keeping checkpoints"
+            ),
         }
 
         connection
@@ -269,6 +310,66 @@ impl StorageBuilder {
             journal_mode: self.journal_mode,
             bloom_filter_cache: Arc::new(bloom::Cache::with_size(self.bloom_filter_cache_size)),
             trie_prune_mode,
+            event_address_index,
+        })
+    }
+
+    /// Opens an existing database without migrating or otherwise modifying it
+    /// and returns a [storage manager](StorageManager).
+    ///
+    /// The database must already be at the latest schema revision. Only
+    /// [read-only pools](StorageManager::create_read_only_pool) should be
+    /// created from the returned manager.
+    pub fn open_read_only(self) -> anyhow::Result<StorageManager> {
+        let connection = rusqlite::Connection::open_with_flags(
+            &self.database_path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )
+        .context("Opening DB")?;
+
+        let current_revision = schema_version(&connection)?;
+        let latest_revision = schema::BASE_SCHEMA_REVISION + schema::migrations().len();
+        anyhow::ensure!(
+            current_revision == latest_revision,
+            "Database version {current_revision} does not match the expected version \
+             {latest_revision}, start the node once to migrate the database"
+        );
+
+        let prune_flag_is_set = connection
+            .query_row(
+                "SELECT 1 FROM storage_flags WHERE flag = 'prune_tries'",
+                [],
+                |_| Ok(()),
+            )
+            .optional()
+            .context("Querying trie pruning flag")?
+            .is_some();
+        let trie_prune_mode = match (self.trie_prune_mode, prune_flag_is_set) {
+            (Some(mode), _) => mode,
+            (None, true) => TriePruneMode::Prune {
+                num_blocks_kept: 20,
+            },
+            (None, false) => TriePruneMode::Archive,
+        };
+
+        // The index cannot be built or dropped here, so it is used as long as it is
+        // complete.
+        let event_address_index = connection
+            .query_row(
+                "SELECT 1 FROM storage_flags WHERE flag = 'event_address_index'",
+                [],
+                |_| Ok(()),
+            )
+            .optional()
+            .context("Querying event address index flag")?
+            .is_some();
+
+        Ok(StorageManager {
+            database_path: self.database_path,
+            journal_mode: self.journal_mode,
+            bloom_filter_cache: Arc::new(bloom::Cache::with_size(self.bloom_filter_cache_size)),
+            trie_prune_mode,
+            event_address_index,
         })
     }
 
@@ -310,7 +411,7 @@ impl StorageBuilder {
                     )
                 }
             }
-            TriePruneMode::Prune { num_blocks_kept: _ } => {
+            TriePruneMode::Prune { .. } | TriePruneMode::Checkpoints { .. } => {
                 if !is_new_database && !prune_flag_is_set {
                     anyhow::bail!(
                         "Cannot enable Merkle trie pruning on a database that was not created \
@@ -330,6 +431,53 @@ impl StorageBuilder {
 
         Ok(trie_prune_mode)
     }
+
+    /// Builds or drops the event address index if it was enabled or disabled
+    /// since the last start-up, and returns whether it is enabled. The
+    /// `event_address_index` flag is set in the database while the index is
+    /// complete.
+    fn apply_event_address_index(
+        &self,
+        connection: &mut rusqlite::Connection,
+    ) -> anyhow::Result<bool> {
+        let index_flag_is_set = connection
+            .query_row(
+                "SELECT 1 FROM storage_flags WHERE flag = 'event_address_index'",
+                [],
+                |_| Ok(()),
+            )
+            .optional()
+            .map(|x| x.is_some())?;
+
+        let event_address_index = self.event_address_index.unwrap_or(index_flag_is_set);
+        match (event_address_index, index_flag_is_set) {
+            (true, false) => {
+                tracing::info!("Building event address index, this may take a while");
+                let tx = connection.transaction()?;
+                connection::build_event_address_index(&tx)
+                    .context("Building event address index")?;
+                tx.execute(
+                    "INSERT INTO storage_flags (flag) VALUES ('event_address_index')",
+                    [],
+                )?;
+                tx.commit()?;
+            }
+            (false, true) => {
+                tracing::info!("Dropping event address index");
+                let tx = connection.transaction()?;
+                tx.execute("DELETE FROM event_address_blocks", [])?;
+                tx.execute(
+                    "DELETE FROM storage_flags WHERE flag = 'event_address_index'",
+                    [],
+                )?;
+                tx.commit()?;
+            }
+            (true, true) => tracing::info!("Event address index enabled"),
+            (false, false) => {}
+        }
+
+        Ok(event_address_index)
+    }
 }
 
 impl Storage {
@@ -340,6 +488,7 @@ impl Storage {
             conn,
             self.0.bloom_filter_cache.clone(),
             self.0.trie_prune_mode,
+            self.0.event_address_index,
         ))
     }
 
@@ -595,6 +744,44 @@ mod tests {
         (db_dir, db_path)
     }
 
+    #[test]
+    fn event_address_index_is_built_and_dropped() {
+        let db_dir = tempfile::TempDir::new().unwrap();
+        let db_path = db_dir.path().join("test.sqlite");
+
+        let index_rows = |event_address_index: bool| {
+            let storage = StorageBuilder::file(db_path.clone())
+                .event_address_index(event_address_index)
+                .migrate()
+                .unwrap()
+                .create_pool(NonZeroU32::new(1).unwrap())
+                .unwrap();
+            let mut connection = storage.connection().unwrap();
+            let tx = connection.transaction().unwrap();
+            tx.inner()
+                .query_row("SELECT COUNT(*) FROM event_address_blocks", [], |row| {
+                    row.get::<_, u64>(0)
+                })
+                .unwrap()
+        };
+
+        let storage = StorageBuilder::file(db_path.clone())
+            .migrate()
+            .unwrap()
+            .create_pool(NonZeroU32::new(1).unwrap())
+            .unwrap();
+        let (_, test_data) = test_utils::insert_test_data(storage);
+        let addresses: std::collections::HashSet<_> = test_data
+            .events
+            .iter()
+            .map(|event| event.from_address)
+            .collect();
+
+        // All test blocks are in the first bucket.
+        assert_eq!(index_rows(true), addresses.len() as u64);
+        assert_eq!(index_rows(false), 0);
+    }
+
     #[test]
     fn enabling_merkle_trie_pruning_fails_without_flag() {
         let (_db_dir, db_path) = rpc_test_db_fixture();
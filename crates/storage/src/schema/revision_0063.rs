@@ -0,0 +1,16 @@
+use anyhow::Context;
+
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute_batch(
+        r"
+        CREATE TABLE trie_checkpoints (
+            trie TEXT NOT NULL,
+            block_number INTEGER NOT NULL,
+            max_idx INTEGER,
+            PRIMARY KEY (trie, block_number)
+        );",
+    )
+    .context("Creating trie_checkpoints table")?;
+
+    Ok(())
+}
@@ -0,0 +1,20 @@
+use anyhow::Context;
+
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute_batch(
+        r"
+        CREATE TABLE l1_messages (
+            l1_transaction_hash BLOB NOT NULL,
+            log_index INTEGER NOT NULL,
+            l1_block_number INTEGER NOT NULL,
+            message_hash BLOB NOT NULL,
+            l1_handler_transaction_hash BLOB,
+            PRIMARY KEY (l1_transaction_hash, log_index)
+        );
+        CREATE INDEX l1_messages_l1_block_number ON l1_messages(l1_block_number);
+        ALTER TABLE refs ADD COLUMN l1_messages_head INTEGER;",
+    )
+    .context("Creating l1_messages table")?;
+
+    Ok(())
+}
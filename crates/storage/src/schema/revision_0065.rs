@@ -0,0 +1,19 @@
+use anyhow::Context;
+
+/// Creates the event address index, which is only populated if it is enabled.
+/// See [StorageBuilder::event_address_index](crate::StorageBuilder::event_address_index).
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute_batch(
+        r"
+        CREATE TABLE event_address_blocks (
+            contract_address BLOB NOT NULL,
+            bucket INTEGER NOT NULL,
+            blocks BLOB NOT NULL,
+            PRIMARY KEY (contract_address, bucket)
+        );
+    ",
+    )
+    .context("Creating event address index table")?;
+
+    Ok(())
+}
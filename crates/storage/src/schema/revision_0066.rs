@@ -0,0 +1,23 @@
+use anyhow::Context;
+
+/// Adds the Starknet state as seen by L1 at depths other than the finalized
+/// block, which remains in `l1_state`.
+///
+/// `confirmations` is zero for depths which are not a number of confirmations,
+/// so that each configured depth has its own row.
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute_batch(
+        r"
+        CREATE TABLE l1_state_at_depth (
+            depth TEXT NOT NULL,
+            confirmations INTEGER NOT NULL,
+            l1_block_number INTEGER NOT NULL,
+            starknet_block_number INTEGER NOT NULL,
+            starknet_block_hash BLOB NOT NULL,
+            starknet_state_root BLOB NOT NULL,
+            PRIMARY KEY (depth, confirmations)
+        );
+    ",
+    )
+    .context("Creating l1_state_at_depth table")
+}
@@ -0,0 +1,19 @@
+use anyhow::Context;
+
+/// Adds a persistent cache of block traces, which is populated by the RPC
+/// methods tracing blocks. The format version identifies the serialization of
+/// the traces.
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute_batch(
+        r"
+        CREATE TABLE block_traces (
+            block_hash BLOB PRIMARY KEY NOT NULL,
+            block_number INTEGER NOT NULL,
+            format_version INTEGER NOT NULL,
+            traces BLOB NOT NULL
+        );
+        CREATE INDEX block_traces_block_number ON block_traces(block_number);
+    ",
+    )
+    .context("Creating block_traces table")
+}
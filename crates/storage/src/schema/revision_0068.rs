@@ -0,0 +1,26 @@
+use anyhow::Context;
+
+/// Adds tables tracking the progress of an in-flight snapshot sync, so that an
+/// interrupted snapshot sync can resume instead of starting over.
+///
+/// A `NULL` storage `next_key` means that all of the contract's storage has
+/// been stored.
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute_batch(
+        r"
+        CREATE TABLE snapshot_sync_progress (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            block_number INTEGER NOT NULL,
+            state_commitment BLOB NOT NULL,
+            class_commitment BLOB,
+            next_class BLOB,
+            next_contract BLOB
+        );
+        CREATE TABLE snapshot_sync_storage_progress (
+            contract_address BLOB PRIMARY KEY NOT NULL,
+            next_key BLOB
+        );
+    ",
+    )
+    .context("Creating snapshot sync progress tables")
+}
@@ -188,7 +188,15 @@ pub struct TestData {
 
 // Creates a storage instance in memory with a set of expected emitted event
 pub fn setup_test_storage() -> (crate::Storage, TestData) {
-    let storage = crate::StorageBuilder::in_memory().unwrap();
+    insert_test_data(crate::StorageBuilder::in_memory().unwrap())
+}
+
+// Like [setup_test_storage], with the event address index enabled
+pub fn setup_test_storage_with_event_address_index() -> (crate::Storage, TestData) {
+    insert_test_data(crate::StorageBuilder::in_memory_with_event_address_index().unwrap())
+}
+
+pub(crate) fn insert_test_data(storage: crate::Storage) -> (crate::Storage, TestData) {
     let mut connection = storage.connection().unwrap();
     let tx = connection.transaction().unwrap();
 